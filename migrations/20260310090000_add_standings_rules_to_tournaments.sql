@@ -0,0 +1,9 @@
+-- Points and tiebreak configuration used to compute tournament standings
+
+ALTER TABLE tournaments ADD COLUMN IF NOT EXISTS points_for_win INT DEFAULT 2 NOT NULL;
+ALTER TABLE tournaments ADD COLUMN IF NOT EXISTS points_for_draw INT DEFAULT 1 NOT NULL;
+ALTER TABLE tournaments ADD COLUMN IF NOT EXISTS points_for_loss INT DEFAULT 0 NOT NULL;
+ALTER TABLE tournaments ADD COLUMN IF NOT EXISTS tiebreakers VARCHAR(255)
+    DEFAULT 'head_to_head,head_to_head_goal_difference,goal_difference,goals_for' NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_matches_tournament_id ON matches(tournament_id);
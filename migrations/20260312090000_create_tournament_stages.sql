@@ -0,0 +1,33 @@
+-- Tournament formats, stages and groups, and bracket placement of matches
+
+ALTER TABLE tournaments ADD COLUMN IF NOT EXISTS format VARCHAR(30) DEFAULT 'league' NOT NULL
+    CHECK (format IN ('league', 'groups_knockout', 'single_elimination'));
+
+CREATE TABLE IF NOT EXISTS tournament_stages (
+    id BIGSERIAL PRIMARY KEY,
+    tournament_id BIGINT NOT NULL REFERENCES tournaments(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    stage_type VARCHAR(20) NOT NULL CHECK (stage_type IN ('group', 'knockout')),
+    position INT DEFAULT 1 NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tournament_groups (
+    id BIGSERIAL PRIMARY KEY,
+    stage_id BIGINT NOT NULL REFERENCES tournament_stages(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    UNIQUE (stage_id, name)
+);
+
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS stage_id BIGINT REFERENCES tournament_stages(id) ON DELETE SET NULL;
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS group_id BIGINT REFERENCES tournament_groups(id) ON DELETE SET NULL;
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS round INT;
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS bracket_position INT;
+-- 7-metre shootout goals, used to decide drawn knockout matches
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS home_shootout INT;
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS away_shootout INT;
+
+CREATE INDEX IF NOT EXISTS idx_tournament_stages_tournament_id ON tournament_stages(tournament_id);
+CREATE INDEX IF NOT EXISTS idx_matches_stage_id ON matches(stage_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_matches_bracket_slot ON matches(stage_id, round, bracket_position)
+    WHERE bracket_position IS NOT NULL;
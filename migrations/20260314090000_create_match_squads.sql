@@ -0,0 +1,51 @@
+-- Match squads (team sheets), player availability, suspensions and notifications
+
+CREATE TABLE IF NOT EXISTS match_squads (
+    id BIGSERIAL PRIMARY KEY,
+    match_id BIGINT NOT NULL REFERENCES matches(id) ON DELETE CASCADE,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    shirt_number INT NOT NULL CHECK (shirt_number BETWEEN 1 AND 99),
+    is_goalkeeper BOOLEAN DEFAULT FALSE NOT NULL,
+    is_starting BOOLEAN DEFAULT FALSE NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    UNIQUE (match_id, player_id),
+    UNIQUE (match_id, shirt_number)
+);
+
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS squad_published_at TIMESTAMP;
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS squad_published_by BIGINT REFERENCES users(id);
+
+CREATE TABLE IF NOT EXISTS match_availability (
+    id BIGSERIAL PRIMARY KEY,
+    match_id BIGINT NOT NULL REFERENCES matches(id) ON DELETE CASCADE,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    available BOOLEAN NOT NULL,
+    reason TEXT,
+    submitted_by BIGINT NOT NULL REFERENCES users(id),
+    updated_at TIMESTAMP DEFAULT now() NOT NULL,
+    UNIQUE (match_id, player_id)
+);
+
+CREATE TABLE IF NOT EXISTS player_suspensions (
+    id BIGSERIAL PRIMARY KEY,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    starts_on DATE NOT NULL,
+    ends_on DATE, -- inclusive; NULL until lifted
+    created_by BIGINT NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    title VARCHAR(200) NOT NULL,
+    body TEXT NOT NULL,
+    link VARCHAR(255),
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    read_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_match_squads_match_id ON match_squads(match_id);
+CREATE INDEX IF NOT EXISTS idx_player_suspensions_player_id ON player_suspensions(player_id);
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id);
@@ -0,0 +1,12 @@
+-- Soft deletion: rows stay in place with deleted_at set until the purge job
+-- removes them after the retention window.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE seasons ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE tournaments ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at ON users(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_matches_deleted_at ON matches(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_seasons_deleted_at ON seasons(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_tournaments_deleted_at ON tournaments(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,21 @@
+-- Registration modes: accounts can wait for admin approval, and admins can
+-- invite people with a fixed role instead of open self-registration.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'active'
+    CHECK (status IN ('active', 'pending', 'rejected'));
+
+CREATE INDEX IF NOT EXISTS idx_users_pending ON users(created_at) WHERE status = 'pending';
+
+-- Single-use invitations. Only a hash of the code is stored.
+CREATE TABLE IF NOT EXISTS invites (
+    id BIGSERIAL PRIMARY KEY,
+    code_hash VARCHAR(64) NOT NULL UNIQUE,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('player', 'coach')),
+    email VARCHAR(255),
+    expires_at TIMESTAMP NOT NULL,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    used_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    used_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
@@ -0,0 +1,33 @@
+-- Guardian accounts for minor players, and consents given on their behalf.
+
+INSERT INTO roles (name) VALUES ('guardian') ON CONFLICT (name) DO NOTHING;
+
+-- Admins invite guardians like any other role
+ALTER TABLE invites DROP CONSTRAINT IF EXISTS invites_role_check;
+ALTER TABLE invites ADD CONSTRAINT invites_role_check CHECK (role IN ('player', 'coach', 'guardian'));
+
+-- A player can have several guardians and a guardian several players.
+CREATE TABLE IF NOT EXISTS player_guardians (
+    id BIGSERIAL PRIMARY KEY,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    guardian_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    relationship VARCHAR(50),
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (player_id, guardian_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_player_guardians_guardian ON player_guardians(guardian_id);
+
+-- The current answer per player and consent type; history is in the audit log.
+CREATE TABLE IF NOT EXISTS player_consents (
+    id BIGSERIAL PRIMARY KEY,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    consent_type VARCHAR(30) NOT NULL
+        CHECK (consent_type IN ('photos', 'medical_treatment', 'travel', 'data_processing')),
+    granted BOOLEAN NOT NULL,
+    notes TEXT,
+    given_by BIGINT NOT NULL REFERENCES users(id),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (player_id, consent_type)
+);
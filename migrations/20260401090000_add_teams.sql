@@ -0,0 +1,41 @@
+-- Teams and age groups within the club. Players and coaches belong to one or
+-- more teams; matches and training sessions belong to one team. A match or
+-- training session without a team is club-wide.
+
+CREATE TABLE IF NOT EXISTS teams (
+    id BIGSERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    age_group VARCHAR(30),
+    gender VARCHAR(10) CHECK (gender IN ('men', 'women', 'mixed')),
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS team_members (
+    id BIGSERIAL PRIMARY KEY,
+    team_id BIGINT NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (team_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_team_members_user ON team_members(user_id);
+
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS team_id BIGINT REFERENCES teams(id);
+ALTER TABLE attendance ADD COLUMN IF NOT EXISTS team_id BIGINT REFERENCES teams(id);
+CREATE INDEX IF NOT EXISTS idx_matches_team_id ON matches(team_id);
+
+-- Everything so far belonged to the one team the club had.
+INSERT INTO teams (name, age_group) VALUES ('First Team', 'senior') ON CONFLICT (name) DO NOTHING;
+
+UPDATE matches SET team_id = (SELECT id FROM teams WHERE name = 'First Team')
+WHERE team_id IS NULL AND stage_id IS NULL;
+
+UPDATE attendance a SET team_id = COALESCE(
+    (SELECT m.team_id FROM matches m WHERE m.id = a.match_id),
+    (SELECT id FROM teams WHERE name = 'First Team'))
+WHERE a.team_id IS NULL AND (a.match_id IS NULL OR EXISTS (SELECT 1 FROM matches m WHERE m.id = a.match_id AND m.team_id IS NOT NULL));
+
+INSERT INTO team_members (team_id, user_id)
+SELECT t.id, u.id FROM users u JOIN roles r ON u.role_id = r.id, teams t
+WHERE t.name = 'First Team' AND r.name IN ('player', 'coach') AND u.deleted_at IS NULL
+ON CONFLICT (team_id, user_id) DO NOTHING;
@@ -0,0 +1,76 @@
+-- Several clubs share one installation. Top-level records carry the club
+-- they belong to; child rows (players, squads, events, stages, members...)
+-- belong to a club through their parent.
+
+CREATE TABLE IF NOT EXISTS clubs (
+    id BIGSERIAL PRIMARY KEY,
+    slug VARCHAR(50) NOT NULL UNIQUE CHECK (slug ~ '^[a-z0-9][a-z0-9-]*$'),
+    name VARCHAR(100) NOT NULL,
+    logo_url VARCHAR(255),
+    primary_color VARCHAR(7) CHECK (primary_color ~ '^#[0-9a-fA-F]{6}$'),
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+-- The club that had the installation to itself until now.
+INSERT INTO clubs (id, slug, name, logo_url) VALUES (1, 'tornadoes', 'Tornadoes', '/static/FirstTeamLogo.svg')
+ON CONFLICT (id) DO NOTHING;
+SELECT setval('clubs_id_seq', (SELECT MAX(id) FROM clubs));
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+ALTER TABLE teams ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+ALTER TABLE seasons ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+ALTER TABLE tournaments ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+ALTER TABLE matches ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+ALTER TABLE announcements ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+ALTER TABLE invites ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+ALTER TABLE user_identities ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+-- Null for system actions such as the purge job
+ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id);
+
+UPDATE users SET club_id = 1 WHERE club_id IS NULL;
+UPDATE teams SET club_id = 1 WHERE club_id IS NULL;
+UPDATE seasons SET club_id = 1 WHERE club_id IS NULL;
+UPDATE tournaments SET club_id = 1 WHERE club_id IS NULL;
+UPDATE matches SET club_id = 1 WHERE club_id IS NULL;
+UPDATE announcements SET club_id = 1 WHERE club_id IS NULL;
+UPDATE invites SET club_id = 1 WHERE club_id IS NULL;
+UPDATE user_identities SET club_id = 1 WHERE club_id IS NULL;
+UPDATE audit_log SET club_id = 1 WHERE club_id IS NULL;
+
+-- No default: every insert has to say which club it is for.
+ALTER TABLE users ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE teams ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE seasons ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE tournaments ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE matches ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE announcements ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE invites ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE user_identities ALTER COLUMN club_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_club_id ON users(club_id);
+CREATE INDEX IF NOT EXISTS idx_teams_club_id ON teams(club_id);
+CREATE INDEX IF NOT EXISTS idx_seasons_club_id ON seasons(club_id);
+CREATE INDEX IF NOT EXISTS idx_tournaments_club_id ON tournaments(club_id);
+CREATE INDEX IF NOT EXISTS idx_matches_club_id ON matches(club_id);
+CREATE INDEX IF NOT EXISTS idx_announcements_club_id ON announcements(club_id);
+CREATE INDEX IF NOT EXISTS idx_invites_club_id ON invites(club_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_club_id ON audit_log(club_id);
+
+-- Names and addresses only need to be unique within a club.
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+ALTER TABLE users ADD CONSTRAINT users_club_email_key UNIQUE (club_id, email);
+ALTER TABLE teams DROP CONSTRAINT IF EXISTS teams_name_key;
+ALTER TABLE teams ADD CONSTRAINT teams_club_name_key UNIQUE (club_id, name);
+ALTER TABLE user_identities DROP CONSTRAINT IF EXISTS user_identities_provider_subject_key;
+ALTER TABLE user_identities ADD CONSTRAINT user_identities_club_provider_subject_key UNIQUE (club_id, provider, subject);
+
+-- Registration mode, 2FA policy and other settings are chosen per club.
+ALTER TABLE app_settings ADD COLUMN IF NOT EXISTS club_id BIGINT REFERENCES clubs(id) ON DELETE CASCADE;
+UPDATE app_settings SET club_id = 1 WHERE club_id IS NULL;
+ALTER TABLE app_settings ALTER COLUMN club_id SET NOT NULL;
+ALTER TABLE app_settings DROP CONSTRAINT IF EXISTS app_settings_pkey;
+ALTER TABLE app_settings ADD PRIMARY KEY (club_id, key);
+
+-- A login started on one club's address finishes there.
+DELETE FROM oidc_login_states;
+ALTER TABLE oidc_login_states ADD COLUMN IF NOT EXISTS club_id BIGINT NOT NULL REFERENCES clubs(id) ON DELETE CASCADE;
@@ -0,0 +1,23 @@
+-- Injuries and medical clearance. A player with an injury that has not been
+-- cleared cannot be selected; the notes are medical and hidden from coaches.
+
+CREATE TABLE IF NOT EXISTS player_injuries (
+    id BIGSERIAL PRIMARY KEY,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    injury_type VARCHAR(30) NOT NULL
+        CHECK (injury_type IN ('sprain', 'strain', 'fracture', 'dislocation', 'contusion', 'concussion', 'ligament', 'tendon', 'illness', 'other')),
+    body_part VARCHAR(50) NOT NULL,
+    injured_on DATE NOT NULL,
+    expected_return DATE,
+    notes TEXT,
+    cleared_on DATE,
+    cleared_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    reported_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    CHECK (expected_return IS NULL OR expected_return >= injured_on),
+    CHECK (cleared_on IS NULL OR cleared_on >= injured_on)
+);
+
+CREATE INDEX IF NOT EXISTS idx_player_injuries_player ON player_injuries(player_id);
+CREATE INDEX IF NOT EXISTS idx_player_injuries_open ON player_injuries(player_id) WHERE cleared_on IS NULL;
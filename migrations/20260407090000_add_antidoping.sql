@@ -0,0 +1,49 @@
+-- Anti-doping: medication and supplement declarations and Therapeutic Use
+-- Exemptions (TUEs). Only the player, their guardians and the club's
+-- medical officers can read them; admins designate medical officers.
+
+INSERT INTO roles (name) VALUES ('medical_officer') ON CONFLICT (name) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS medication_declarations (
+    id BIGSERIAL PRIMARY KEY,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('medication', 'supplement')),
+    name VARCHAR(200) NOT NULL,
+    dosage VARCHAR(100),
+    started_on DATE NOT NULL,
+    ended_on DATE,
+    reason TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'reviewed', 'flagged')),
+    review_notes TEXT,
+    reviewed_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMP,
+    declared_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    CHECK (ended_on IS NULL OR ended_on >= started_on)
+);
+
+CREATE INDEX IF NOT EXISTS idx_medication_declarations_player ON medication_declarations(player_id);
+CREATE INDEX IF NOT EXISTS idx_medication_declarations_pending ON medication_declarations(status) WHERE status = 'pending';
+
+-- A TUE is either an uploaded certificate or a reference to one held by the
+-- anti-doping organisation. reminded_at marks the expiry reminder as sent.
+CREATE TABLE IF NOT EXISTS therapeutic_use_exemptions (
+    id BIGSERIAL PRIMARY KEY,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    substance VARCHAR(200) NOT NULL,
+    reference VARCHAR(100),
+    document BYTEA,
+    document_name VARCHAR(200),
+    document_type VARCHAR(100),
+    granted_on DATE,
+    expires_on DATE NOT NULL,
+    reminded_at TIMESTAMP,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    CHECK (reference IS NOT NULL OR document IS NOT NULL),
+    CHECK (granted_on IS NULL OR expires_on >= granted_on)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tues_player ON therapeutic_use_exemptions(player_id);
+CREATE INDEX IF NOT EXISTS idx_tues_unreminded ON therapeutic_use_exemptions(expires_on) WHERE reminded_at IS NULL;
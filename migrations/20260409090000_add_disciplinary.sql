@@ -0,0 +1,29 @@
+-- Disciplinary record: cards are match events; rules per tournament turn
+-- them into match bans, which are served by the team's next fixtures.
+
+-- Every `threshold`-th event of `event_type` in a tournament bans the player
+-- for `ban_matches` fixtures. A tournament without rules uses the defaults
+-- in services/disciplinary.rs.
+CREATE TABLE IF NOT EXISTS disciplinary_rules (
+    id BIGSERIAL PRIMARY KEY,
+    tournament_id BIGINT NOT NULL REFERENCES tournaments(id) ON DELETE CASCADE,
+    event_type VARCHAR(20) NOT NULL CHECK (event_type IN ('yellow_card', 'suspension', 'red_card', 'blue_card')),
+    threshold INT NOT NULL CHECK (threshold >= 1),
+    ban_matches INT NOT NULL CHECK (ban_matches >= 1),
+    UNIQUE (tournament_id, event_type)
+);
+
+-- One ban per triggering event; removing the event lifts the ban.
+CREATE TABLE IF NOT EXISTS match_bans (
+    id BIGSERIAL PRIMARY KEY,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    tournament_id BIGINT NOT NULL REFERENCES tournaments(id) ON DELETE CASCADE,
+    match_id BIGINT NOT NULL REFERENCES matches(id) ON DELETE CASCADE,
+    event_id BIGINT NOT NULL UNIQUE REFERENCES match_events(id) ON DELETE CASCADE,
+    matches INT NOT NULL CHECK (matches >= 1),
+    reason TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_match_bans_player ON match_bans(player_id);
+CREATE INDEX IF NOT EXISTS idx_match_bans_tournament ON match_bans(tournament_id);
@@ -0,0 +1,38 @@
+-- Fitness testing: each club configures its test types; results are
+-- recorded per player and test date.
+
+CREATE TABLE IF NOT EXISTS fitness_test_types (
+    id BIGSERIAL PRIMARY KEY,
+    club_id BIGINT NOT NULL REFERENCES clubs(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    unit VARCHAR(20) NOT NULL,
+    -- false for timed tests such as sprints, where a lower value is better
+    higher_is_better BOOLEAN NOT NULL DEFAULT TRUE,
+    description TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (club_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS fitness_results (
+    id BIGSERIAL PRIMARY KEY,
+    test_type_id BIGINT NOT NULL REFERENCES fitness_test_types(id) ON DELETE CASCADE,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    tested_on DATE NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    notes TEXT,
+    recorded_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (test_type_id, player_id, tested_on)
+);
+
+CREATE INDEX IF NOT EXISTS idx_fitness_results_player ON fitness_results(player_id);
+
+-- The tests most clubs start with
+INSERT INTO fitness_test_types (club_id, name, unit, higher_is_better, description)
+SELECT c.id, t.name, t.unit, t.higher_is_better, t.description FROM clubs c CROSS JOIN (VALUES
+    ('Beep test', 'level', TRUE, 'Multi-stage 20 m shuttle run'),
+    ('20 m sprint', 's', FALSE, 'Timed from a standing start'),
+    ('Countermovement jump', 'cm', TRUE, 'Best of three attempts')
+) AS t(name, unit, higher_is_better, description)
+ON CONFLICT (club_id, name) DO NOTHING;
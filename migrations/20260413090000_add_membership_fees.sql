@@ -0,0 +1,73 @@
+-- Membership fees: fee plans per season and member category, invoices per
+-- player (billed to the player or a guardian), payments recorded by the
+-- treasurer or confirmed by an online payment provider.
+
+INSERT INTO roles (name) VALUES ('treasurer') ON CONFLICT (name) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS fee_plans (
+    id BIGSERIAL PRIMARY KEY,
+    club_id BIGINT NOT NULL REFERENCES clubs(id) ON DELETE CASCADE,
+    season_id BIGINT NOT NULL REFERENCES seasons(id) ON DELETE CASCADE,
+    -- e.g. "senior", "youth", "student", "family"
+    category VARCHAR(50) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    -- amounts are in the currency's minor unit (cents)
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    currency CHAR(3) NOT NULL,
+    due_date DATE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (club_id, season_id, category)
+);
+
+CREATE TABLE IF NOT EXISTS invoices (
+    id BIGSERIAL PRIMARY KEY,
+    club_id BIGINT NOT NULL REFERENCES clubs(id) ON DELETE CASCADE,
+    fee_plan_id BIGINT NOT NULL REFERENCES fee_plans(id) ON DELETE RESTRICT,
+    player_id BIGINT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    -- the player, or the guardian who pays for them
+    billed_to BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    description VARCHAR(200) NOT NULL,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    currency CHAR(3) NOT NULL,
+    issued_on DATE NOT NULL DEFAULT CURRENT_DATE,
+    due_date DATE NOT NULL,
+    cancelled_at TIMESTAMP,
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (fee_plan_id, player_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoices_club_due ON invoices(club_id, due_date);
+CREATE INDEX IF NOT EXISTS idx_invoices_billed_to ON invoices(billed_to);
+
+CREATE TABLE IF NOT EXISTS invoice_payments (
+    id BIGSERIAL PRIMARY KEY,
+    invoice_id BIGINT NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    paid_on DATE NOT NULL,
+    method VARCHAR(20) NOT NULL CHECK (method IN ('cash', 'bank_transfer', 'card', 'mobile_money', 'online')),
+    reference VARCHAR(100),
+    notes TEXT,
+    recorded_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoice_payments_invoice ON invoice_payments(invoice_id);
+
+-- Online payments started with a provider, until it confirms or rejects them
+CREATE TABLE IF NOT EXISTS payment_intents (
+    id BIGSERIAL PRIMARY KEY,
+    invoice_id BIGINT NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    provider VARCHAR(30) NOT NULL,
+    reference VARCHAR(100) NOT NULL,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'succeeded', 'failed')),
+    checkout_url TEXT,
+    payment_id BIGINT REFERENCES invoice_payments(id) ON DELETE SET NULL,
+    started_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (provider, reference)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_intents_pending ON payment_intents(status) WHERE status = 'pending';
@@ -0,0 +1,8 @@
+-- Linking an external identity by email is only automatic for ordinary
+-- accounts. Privileged accounts, and accounts whose email changed recently,
+-- link from a signed-in session instead.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_changed_at TIMESTAMP;
+
+-- Set when a signed-in user starts linking a provider to their own account.
+ALTER TABLE oidc_login_states ADD COLUMN IF NOT EXISTS link_user_id BIGINT REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1,6 @@
+-- Online payments the provider confirmed for an invoice that was cancelled or
+-- settled in the meantime: the part not recorded is owed back to the payer.
+
+ALTER TABLE payment_intents ADD COLUMN IF NOT EXISTS refund_cents BIGINT NOT NULL DEFAULT 0 CHECK (refund_cents >= 0);
+
+CREATE INDEX IF NOT EXISTS idx_payment_intents_refund ON payment_intents(invoice_id) WHERE refund_cents > 0;
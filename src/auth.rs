@@ -4,22 +4,29 @@ use axum::{
     http,
     middleware::Next,
     response::{IntoResponse, Response},
-    Json,
+    Extension, Json,
 };
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
-use argon2::password_hash::{rand_core::OsRng, SaltString};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use std::sync::{Arc, OnceLock};
 
+use crate::config;
 use crate::errors::AppError;
-use crate::models::{AuthResponse, LoginRequest, RegisterRequest};
+use crate::models::{ApiResponse, AuthResponse, ChangePasswordRequest, LoginRequest, RegisterRequest};
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs::Club;
+use crate::services::password;
+use crate::services::rate_limit::{self, RateLimiter};
+use crate::services::registration::{self, RegistrationMode};
+use crate::services::two_factor::{self, LoginStep};
+use crate::telemetry::{self, CurrentUser};
 
 // ─── JWT Configuration ──────────────────────────────────────────────
 
-/// Secret key for JWT signing. In production, use an env variable.
-fn jwt_secret() -> String {
-    std::env::var("JWT_SECRET").unwrap_or_else(|_| "tornadoes-handball-secret-key-2026".to_string())
+/// Secret key for JWT signing, from `auth.jwt_secret` in the config.
+fn jwt_secret() -> &'static str {
+    &config::get().auth.jwt_secret
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,11 +35,12 @@ pub struct Claims {
     pub email: String,
     pub role: String,
     pub name: String,
+    pub club_id: i64,
     pub exp: usize,     // expiry timestamp
 }
 
-/// Generate a JWT token for a user.
-pub fn create_token(user_id: i64, email: &str, role: &str, name: &str) -> Result<String, AppError> {
+/// Generate a JWT token for a user of a club.
+pub fn create_token(user_id: i64, email: &str, role: &str, name: &str, club_id: i64) -> Result<String, AppError> {
     let expiration = chrono::Utc::now()
         .checked_add_signed(chrono::Duration::hours(24))
         .ok_or_else(|| AppError::Internal("Failed to compute token expiry".into()))?
@@ -43,6 +51,7 @@ pub fn create_token(user_id: i64, email: &str, role: &str, name: &str) -> Result
         email: email.to_string(),
         role: role.to_string(),
         name: name.to_string(),
+        club_id,
         exp: expiration,
     };
 
@@ -55,6 +64,41 @@ pub fn create_token(user_id: i64, email: &str, role: &str, name: &str) -> Result
     Ok(token)
 }
 
+/// Claims of the short-lived token that links the two login steps. It lacks
+/// the access token's fields, so neither can be used as the other.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChallengeClaims {
+    sub: i64,
+    purpose: String,
+    exp: usize,
+}
+
+const CHALLENGE_PURPOSE: &str = "two_factor";
+
+/// Minutes the user has to enter the second factor.
+const CHALLENGE_MINUTES: i64 = 5;
+
+/// Token proving the password step passed, exchanged for a JWT at `/api/login/2fa`.
+pub fn create_challenge_token(user_id: i64) -> Result<String, AppError> {
+    let claims = ChallengeClaims {
+        sub: user_id,
+        purpose: CHALLENGE_PURPOSE.to_string(),
+        exp: (chrono::Utc::now() + chrono::Duration::minutes(CHALLENGE_MINUTES)).timestamp() as usize,
+    };
+    Ok(encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_bytes()))?)
+}
+
+/// The user id of a valid, unexpired challenge token.
+pub fn decode_challenge_token(token: &str) -> Result<i64, AppError> {
+    let claims = decode::<ChallengeClaims>(token, &DecodingKey::from_secret(jwt_secret().as_bytes()), &Validation::default())
+        .map_err(|_| AppError::Unauthorized("Login challenge is invalid or has expired. Sign in again.".into()))?
+        .claims;
+    if claims.purpose != CHALLENGE_PURPOSE {
+        return Err(AppError::Unauthorized("Login challenge is invalid or has expired. Sign in again.".into()));
+    }
+    Ok(claims.sub)
+}
+
 /// Decode and validate a JWT token.
 pub fn decode_token(token: &str) -> Result<Claims, AppError> {
     let token_data = decode::<Claims>(
@@ -68,7 +112,10 @@ pub fn decode_token(token: &str) -> Result<Claims, AppError> {
 // ─── Auth Middleware ─────────────────────────────────────────────────
 
 /// Middleware that extracts JWT from Authorization header and injects Claims.
+/// The token must belong to the club the request is for, and stops working
+/// as soon as its user is deleted, no longer active or given another role.
 pub async fn auth_middleware(
+    State(pool): State<PgPool>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, AppError> {
@@ -83,9 +130,30 @@ pub async fn auth_middleware(
         .ok_or_else(|| AppError::Unauthorized("Missing or invalid Authorization header".into()))?;
 
     let claims = decode_token(&token)?;
+    if req.extensions().get::<Club>().map(|club| club.id) != Some(claims.club_id) {
+        return Err(AppError::Unauthorized("This token belongs to another club".into()));
+    }
+    let account: Option<(bool, String)> = sqlx::query_as(
+        "SELECT u.deleted_at IS NULL AND u.status = 'active', r.name FROM users u JOIN roles r ON u.role_id = r.id WHERE u.id = $1",
+    )
+    .bind(claims.sub)
+    .fetch_optional(&pool)
+    .await?;
+    if let Some((active, role)) = account {
+        if !active {
+            return Err(AppError::Unauthorized("This account is no longer active".into()));
+        }
+        // Logging in again applies the new role's two-factor policy
+        if role != claims.role {
+            return Err(AppError::Unauthorized("Your role has changed. Please log in again.".into()));
+        }
+    }
+    let user_id = claims.sub;
     req.extensions_mut().insert(claims);
 
-    Ok(next.run(req).await)
+    let mut response = next.run(req).await;
+    response.extensions_mut().insert(CurrentUser(user_id));
+    Ok(response)
 }
 
 // (require_role is not used and has been removed to resolve dead code warning)
@@ -95,54 +163,60 @@ pub fn require_coach_or_admin(claims: &Claims) -> Result<(), AppError> {
     if claims.role == "coach" || claims.role == "admin" {
         Ok(())
     } else {
-        Err(AppError::Unauthorized(
+        Err(AppError::Forbidden(
             "Coach or Admin role required".into(),
         ))
     }
 }
 
+/// Check if user is admin.
+pub fn require_admin(claims: &Claims) -> Result<(), AppError> {
+    if claims.role == "admin" {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("Admin role required".into()))
+    }
+}
+
 // ─── Auth Handlers ──────────────────────────────────────────────────
 
-/// POST /api/register — Create a new user account
-pub async fn register_handler(
-    State(pool): State<PgPool>,
-    Json(payload): Json<RegisterRequest>,
-) -> Result<impl IntoResponse, AppError> {
-    // Validate input
+/// Validate a registration request without touching the database.
+/// Shared by the register endpoint and the roster import.
+pub fn validate_registration(payload: &RegisterRequest) -> Result<(), AppError> {
     if payload.email.is_empty() || payload.password.is_empty() || payload.first_name.is_empty() || payload.last_name.is_empty() {
         return Err(AppError::BadRequest("All fields are required".into()));
     }
-    if payload.password.len() < 6 {
-        return Err(AppError::BadRequest("Password must be at least 6 characters".into()));
+    password::validate(&payload.password, &payload.email)?;
+    // Only allow coach, player or guardian roles
+    if !["coach", "player", "guardian"].contains(&payload.role.as_str()) {
+        return Err(AppError::BadRequest("Role must be 'coach', 'player' or 'guardian'".into()));
     }
+    if payload.role == "player" && payload.player_details.is_none() {
+        return Err(AppError::BadRequest("Player details required for role 'player'".into()));
+    }
+    Ok(())
+}
 
-    // Check if email already exists
-    let exists: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE email = $1")
+/// Create the user and its player or coach profile for a validated request,
+/// in a club with the given `users.status`. Returns the new user id.
+pub async fn create_account(conn: &mut PgConnection, club_id: i64, payload: &RegisterRequest, status: &str) -> Result<i64, AppError> {
+    // Check if email already exists in the club
+    let exists: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE email = $1 AND club_id = $2")
         .bind(&payload.email)
-        .fetch_one(&pool)
+        .bind(club_id)
+        .fetch_one(&mut *conn)
         .await?;
 
     if exists.0 > 0 {
         return Err(AppError::Conflict("Email already registered".into()));
     }
 
-    // Hash password with argon2
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(payload.password.as_bytes(), &salt)
-        .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))?
-        .to_string();
-
-    // Only allow coach or player roles
-    if payload.role != "coach" && payload.role != "player" {
-        return Err(AppError::BadRequest("Role must be 'coach' or 'player'".into()));
-    }
+    let password_hash = password::hash(&config::get().password, &payload.password)?;
 
     // Find role id
     let role_row: Option<(i64,)> = sqlx::query_as("SELECT id FROM roles WHERE name = $1")
         .bind(&payload.role)
-        .fetch_optional(&pool)
+        .fetch_optional(&mut *conn)
         .await?;
     let role_id = role_row
         .ok_or_else(|| AppError::BadRequest(format!("Role '{}' does not exist", payload.role)))?
@@ -150,30 +224,31 @@ pub async fn register_handler(
 
     let full_name = format!("{} {}", payload.first_name, payload.last_name);
     let result = sqlx::query(
-        "INSERT INTO users (email, password_hash, name, role_id, created_at) VALUES ($1, $2, $3, $4, NOW()) RETURNING id",
+        "INSERT INTO users (email, password_hash, name, role_id, status, club_id, created_at) VALUES ($1, $2, $3, $4, $5, $6, NOW()) RETURNING id",
     )
     .bind(&payload.email)
     .bind(&password_hash)
     .bind(&full_name)
     .bind(role_id)
-    .fetch_one(&pool)
+    .bind(status)
+    .bind(club_id)
+    .fetch_one(&mut *conn)
     .await?;
 
     let user_id: i64 = result.get(0);
 
     // If player, insert player details
-    if payload.role == "player" {
-        let details = payload.player_details.as_ref().ok_or_else(|| AppError::BadRequest("Player details required for role 'player'".into()))?;
+    if let (Some(details), "player") = (&payload.player_details, payload.role.as_str()) {
         sqlx::query(
             "INSERT INTO players (user_id, first_name, last_name, date_of_birth, position, jersey_number, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW())"
         )
         .bind(user_id)
         .bind(&payload.first_name)
         .bind(&payload.last_name)
-        .bind(&details.date_of_birth)
+        .bind(details.date_of_birth)
         .bind(&details.position)
         .bind(details.jersey_number)
-        .execute(&pool)
+        .execute(&mut *conn)
         .await?;
     }
     // If coach, insert into coaches table
@@ -184,12 +259,68 @@ pub async fn register_handler(
         .bind(user_id)
         .bind(&payload.first_name)
         .bind(&payload.last_name)
-        .execute(&pool)
+        .execute(&mut *conn)
         .await?;
     }
+    Ok(user_id)
+}
+
+/// POST /api/register — Create a new user account. Depending on the
+/// registration mode the account may need an invite or admin approval.
+pub async fn register_handler(
+    State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+    audit: AuditContext,
+    Json(mut payload): Json<RegisterRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // An invite decides the role
+    let invite = match payload.invite_code.as_deref().filter(|c| !c.trim().is_empty()) {
+        Some(code) => {
+            let (invite_id, role) = registration::claim_invite(&mut tx, club.id, code, &payload.email)
+                .await?
+                .ok_or_else(|| AppError::BadRequest("Invite code is invalid, used or expired".into()))?;
+            payload.role = role;
+            Some(invite_id)
+        }
+        None => None,
+    };
+    validate_registration(&payload)?;
+
+    let mode = registration::mode(&mut *tx, club.id).await?;
+    if mode == RegistrationMode::InviteOnly && invite.is_none() {
+        return Err(AppError::Forbidden("Registration is by invitation only".into()));
+    }
+    let status = registration::initial_status(mode, &payload.role, invite.is_some());
+
+    let user_id = create_account(&mut tx, club.id, &payload, status).await?;
+    if let Some(invite_id) = invite {
+        registration::mark_invite_used(&mut tx, invite_id, user_id).await?;
+    }
+    let after = audit::snapshot(&mut tx, "users", user_id).await?;
+    audit::record(&mut tx, &audit.with_actor(user_id), "register", "user", Some(user_id), None, after).await?;
+
+    let full_name = format!("{} {}", payload.first_name, payload.last_name);
+    if status == "pending" {
+        registration::notify_admins(&mut tx, club.id, &full_name, &payload.role).await?;
+        tx.commit().await?;
+        return Ok(Json(AuthResponse {
+            success: true,
+            message: "Registration received. An admin will review your account before you can log in.".into(),
+            token: None,
+            role: Some(payload.role.clone()),
+            user_id: Some(user_id),
+            name: Some(full_name),
+            two_factor: None,
+            challenge: None,
+            recovery_codes: None,
+        }));
+    }
+    tx.commit().await?;
 
     // Issue JWT
-    let token = create_token(user_id, &payload.email, &payload.role, &full_name)?;
+    let token = create_token(user_id, &payload.email, &payload.role, &full_name, club.id)?;
 
     Ok(Json(AuthResponse {
         success: true,
@@ -198,33 +329,77 @@ pub async fn register_handler(
         role: Some(payload.role.clone()),
         user_id: Some(user_id),
         name: Some(full_name),
+        two_factor: None,
+        challenge: None,
+        recovery_codes: None,
     }))
 }
 
+/// Check a password against a throwaway hash so that an unknown email takes
+/// as long to reject as a wrong password.
+fn verify_dummy_password(attempt: &str) {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = DUMMY_HASH.get_or_init(|| password::hash(&config::get().password, "no-such-account").unwrap_or_default());
+    let _ = password::verify(attempt, hash);
+}
+
 /// POST /api/login — Authenticate and receive JWT
 pub async fn login_handler(
     State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+    Extension(limiter): Extension<Arc<RateLimiter>>,
     Json(payload): Json<LoginRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Find user by email
-    let user = sqlx::query_as::<_, (i64, String, String, i64)>(
-        "SELECT id, password_hash, name, role_id FROM users WHERE email = $1",
+    // Counted for every email, registered or not, so the limit reveals nothing
+    limiter.check_account(club.id, &payload.email).await?;
+
+    // Find user by email; `locked_for` is the remaining lockout in seconds
+    let user = sqlx::query_as::<_, (i64, String, String, i64, String, Option<f64>)>(
+        "SELECT id, password_hash, name, role_id, status, EXTRACT(EPOCH FROM locked_until - NOW())::FLOAT8 \
+         FROM users WHERE email = $1 AND club_id = $2 AND deleted_at IS NULL",
     )
     .bind(&payload.email)
+    .bind(club.id)
     .fetch_optional(&pool)
-    .await?
-    .ok_or_else(|| AppError::Unauthorized("Invalid email or password".into()))?;
+    .await?;
 
-    let (user_id, stored_hash, name, role_id) = user;
+    let Some((user_id, stored_hash, name, role_id, status, locked_for)) = user else {
+        verify_dummy_password(&payload.password);
+        telemetry::record_login_failure("unknown_email");
+        return Err(AppError::Unauthorized("Invalid email or password".into()));
+    };
 
-    // Verify password
-    let parsed_hash = PasswordHash::new(&stored_hash)
-        .map_err(|e| AppError::Internal(format!("Hash parse error: {}", e)))?;
+    // Hash before looking at the lockout and answer a locked account like a
+    // wrong password, so neither the status nor the timing shows the email exists
+    let password_ok = password::verify(&payload.password, &stored_hash)?;
+    if locked_for.is_some_and(|secs| secs > 0.0) {
+        telemetry::record_login_failure("locked");
+        return Err(AppError::Unauthorized("Invalid email or password".into()));
+    }
+    if !password_ok {
+        telemetry::record_login_failure("wrong_password");
+        rate_limit::record_failed_login(&pool, limiter.config(), user_id).await?;
+        return Err(AppError::Unauthorized("Invalid email or password".into()));
+    }
+    rate_limit::clear_failed_logins(&pool, user_id).await?;
 
-    let argon2 = Argon2::default();
-    argon2
-        .verify_password(payload.password.as_bytes(), &parsed_hash)
-        .map_err(|_| AppError::Unauthorized("Invalid email or password".into()))?;
+    // Only after the password, so the status is not revealed to strangers
+    match status.as_str() {
+        "pending" => return Err(AppError::Forbidden("Your account is awaiting approval by an admin".into())),
+        "rejected" => return Err(AppError::Forbidden("Your registration was not approved".into())),
+        _ => {}
+    }
+
+    // Upgrade hashes made with older argon2 parameters while we have the password
+    let policy = &config::get().password;
+    if password::needs_rehash(policy, &stored_hash) {
+        sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(password::hash(policy, &payload.password)?)
+            .execute(&pool)
+            .await?;
+        tracing::info!("Upgraded password hash parameters for user {}", user_id);
+    }
 
     // Get role name
     let role_name: (String,) = sqlx::query_as("SELECT name FROM roles WHERE id = $1")
@@ -232,8 +407,29 @@ pub async fn login_handler(
         .fetch_one(&pool)
         .await?;
 
+    // Second factor: answer with a challenge instead of a token
+    let mut conn = pool.acquire().await?;
+    let step = two_factor::login_step(&mut conn, club.id, user_id, &role_name.0).await?;
+    if step != LoginStep::Done {
+        let message = match step {
+            LoginStep::Setup => "Two-factor authentication is required for your role. Set it up to continue.",
+            _ => "Enter the code from your authenticator app.",
+        };
+        return Ok(Json(AuthResponse {
+            success: true,
+            message: message.into(),
+            token: None,
+            role: None,
+            user_id: None,
+            name: None,
+            two_factor: Some(step.as_str().to_string()),
+            challenge: Some(create_challenge_token(user_id)?),
+            recovery_codes: None,
+        }));
+    }
+
     // Issue JWT
-    let token = create_token(user_id, &payload.email, &role_name.0, &name)?;
+    let token = create_token(user_id, &payload.email, &role_name.0, &name, club.id)?;
 
     Ok(Json(AuthResponse {
         success: true,
@@ -242,5 +438,47 @@ pub async fn login_handler(
         role: Some(role_name.0),
         user_id: Some(user_id),
         name: Some(name),
+        two_factor: None,
+        challenge: None,
+        recovery_codes: None,
+    }))
+}
+
+/// POST /api/password/change — Any user: change their own password
+pub async fn change_password_handler(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
+    let (email, stored_hash): (String, String) =
+        sqlx::query_as("SELECT email, password_hash FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+            .bind(claims.sub)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    if !password::verify(&payload.current_password, &stored_hash)? {
+        return Err(AppError::Unauthorized("Current password is incorrect".into()));
+    }
+    if payload.new_password == payload.current_password {
+        return Err(AppError::BadRequest("The new password must differ from the current one".into()));
+    }
+    password::validate(&payload.new_password, &email)?;
+
+    let before = audit::snapshot(&mut tx, "users", claims.sub).await?;
+    sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
+        .bind(claims.sub)
+        .bind(password::hash(&config::get().password, &payload.new_password)?)
+        .execute(&mut *tx)
+        .await?;
+    let after = audit::snapshot(&mut tx, "users", claims.sub).await?;
+    audit::record(&mut tx, &audit, "change_password", "user", Some(claims.sub), before, after).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Password changed".into(),
     }))
 }
@@ -17,9 +17,9 @@ pub async fn run_migrations(pool: &PgPool) {
     tracing::info!("Database migrations complete.");
 }
 
-/// Seed default roles (player, coach, admin) if not already present.
+/// Seed default roles (player, coach, admin, guardian) if not already present.
 pub async fn seed_roles(pool: &PgPool) {
-    let roles = ["player", "coach", "admin"];
+    let roles = ["player", "coach", "admin", "guardian"];
     for role in roles.iter() {
         let _ = sqlx::query("INSERT INTO roles (name) VALUES ($1) ON CONFLICT (name) DO NOTHING")
             .bind(role)
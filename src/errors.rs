@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -14,6 +14,9 @@ pub enum AppError {
     Forbidden(String),
     BadRequest(String),
     Conflict(String),
+    /// Rate limited or locked out: the message and the seconds until the
+    /// caller may try again (sent as `Retry-After`).
+    TooManyRequests(String, u64),
     Database(sqlx::Error),
     Internal(String),
 }
@@ -26,6 +29,7 @@ impl std::fmt::Display for AppError {
             AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
             AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::TooManyRequests(msg, secs) => write!(f, "Too many requests: {} (retry after {}s)", msg, secs),
             AppError::Database(e) => write!(f, "Database error: {}", e),
             AppError::Internal(msg) => write!(f, "Internal error: {}", msg),
         }
@@ -40,6 +44,7 @@ impl IntoResponse for AppError {
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.clone()),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg.clone()),
+            AppError::TooManyRequests(msg, _) => (StatusCode::TOO_MANY_REQUESTS, msg.clone()),
             AppError::Database(e) => {
                 tracing::error!("Database error: {}", e);
                 (StatusCode::INTERNAL_SERVER_ERROR, "A database error occurred.".to_string())
@@ -55,7 +60,11 @@ impl IntoResponse for AppError {
             "message": message,
         });
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if let AppError::TooManyRequests(_, secs) = self {
+            response.headers_mut().insert(header::RETRY_AFTER, secs.max(1).into());
+        }
+        response
     }
 }
 
@@ -1,6 +1,28 @@
-use axum::{response::IntoResponse, Extension, Json};
-use crate::auth::Claims;
+use axum::{extract::{Path, Query, State}, http::header, response::IntoResponse, Extension, Json};
+use sqlx::PgPool;
+use crate::auth::{require_admin, Claims};
+use crate::config;
 use crate::errors::AppError;
+use crate::models::{ApiResponse, AuditEntryResponse, AuditQuery, JobsQuery, TrashQuery};
+use crate::services::audit::{self, AuditContext};
+use crate::services::backup;
+use crate::services::clubs::{self, Club};
+use crate::services::jobs;
+use crate::services::rate_limit;
+use crate::services::trash::{self, TrashEntity};
+
+type AuditRow = (
+    i64,
+    Option<i64>,
+    Option<String>,
+    String,
+    String,
+    Option<i64>,
+    Option<serde_json::Value>,
+    Option<serde_json::Value>,
+    Option<String>,
+    chrono::NaiveDateTime,
+);
 
 /// GET /api/admin/protected — Only accessible by admin
 pub async fn protected_admin_route(
@@ -12,3 +34,184 @@ pub async fn protected_admin_route(
         Err(AppError::Forbidden("Forbidden: Admins only".into()))
     }
 }
+
+/// GET /api/admin/backup — Host club admin: download a full backup archive (zip) of every club
+pub async fn download_backup(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Extension(club): Extension<Club>,
+) -> Result<impl IntoResponse, AppError> {
+    clubs::require_host_admin(&claims, &club)?;
+    let archive = backup::create_backup(&pool).await?;
+    let filename = format!("tornadoes-backup-{}.zip", chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        archive,
+    ))
+}
+
+/// GET /api/audit — Admin: the club's audit log, newest first.
+/// Filters: `actor_id`, `entity_type`, `entity_id`, `from`/`to` (YYYY-MM-DD, inclusive), `limit` (default 100, max 1000).
+pub async fn list_audit_log(
+    Query(query): Query<AuditQuery>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let parse_date = |value: &Option<String>| -> Result<Option<chrono::NaiveDate>, AppError> {
+        value
+            .as_deref()
+            .map(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d"))
+            .transpose()
+            .map_err(|_| AppError::BadRequest("Invalid date format. Use YYYY-MM-DD.".into()))
+    };
+    let from = parse_date(&query.from)?;
+    let to = parse_date(&query.to)?;
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    let rows = sqlx::query_as::<_, AuditRow>(
+        "SELECT a.id, a.actor_id, u.name, a.action, a.entity_type, a.entity_id, a.before_data, a.after_data, \
+                a.request_id, a.created_at \
+         FROM audit_log a LEFT JOIN users u ON a.actor_id = u.id \
+         WHERE ($1::BIGINT IS NULL OR a.actor_id = $1) \
+           AND ($2::TEXT IS NULL OR a.entity_type = $2) \
+           AND ($3::BIGINT IS NULL OR a.entity_id = $3) \
+           AND ($4::DATE IS NULL OR a.created_at >= $4) \
+           AND ($5::DATE IS NULL OR a.created_at < $5 + 1) \
+           AND a.club_id = $7 \
+         ORDER BY a.created_at DESC, a.id DESC LIMIT $6",
+    )
+    .bind(query.actor_id)
+    .bind(&query.entity_type)
+    .bind(query.entity_id)
+    .bind(from)
+    .bind(to)
+    .bind(limit)
+    .bind(claims.club_id)
+    .fetch_all(&pool)
+    .await?;
+
+    let entries: Vec<AuditEntryResponse> = rows
+        .into_iter()
+        .map(|(id, actor_id, actor_name, action, entity_type, entity_id, before, after, request_id, created_at)| {
+            AuditEntryResponse {
+                id,
+                actor_id,
+                actor_name,
+                action,
+                entity_type,
+                entity_id,
+                before,
+                after,
+                request_id,
+                created_at: created_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            }
+        })
+        .collect();
+    Ok(Json(entries))
+}
+
+fn parse_trash_entity(name: &str) -> Result<TrashEntity, AppError> {
+    TrashEntity::parse(name)
+        .ok_or_else(|| AppError::BadRequest("Entity must be 'users', 'matches', 'seasons' or 'tournaments'".into()))
+}
+
+/// GET /api/admin/trash — Admin: soft-deleted users, matches, seasons and tournaments.
+/// `?entity=matches` limits the list to one kind.
+pub async fn list_trash(
+    Query(query): Query<TrashQuery>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let entity = query.entity.as_deref().map(parse_trash_entity).transpose()?;
+    let items = trash::list(&pool, claims.club_id, entity, config::get().trash.retention_days).await?;
+    Ok(Json(items))
+}
+
+/// POST /api/admin/trash/:entity/:id/restore — Admin: undo a delete
+pub async fn restore_from_trash(
+    Path((entity, id)): Path<(String, i64)>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let entity = parse_trash_entity(&entity)?;
+    let mut tx = pool.begin().await?;
+    if !trash::restore(&mut tx, &audit, claims.club_id, entity, id).await? {
+        return Err(AppError::NotFound("Item not found in the trash".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Restored {} {}.", entity.entity_type(), id),
+    }))
+}
+
+/// GET /api/admin/jobs — Host club admin: background jobs, newest first.
+/// Filters: `status`, `name`, `limit` (default 100, max 1000).
+pub async fn list_jobs(
+    Query(query): Query<JobsQuery>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Extension(club): Extension<Club>,
+) -> Result<impl IntoResponse, AppError> {
+    clubs::require_host_admin(&claims, &club)?;
+    if let Some(status) = query.status.as_deref() {
+        if !["pending", "running", "succeeded", "failed"].contains(&status) {
+            return Err(AppError::BadRequest(
+                "Status must be 'pending', 'running', 'succeeded' or 'failed'".into(),
+            ));
+        }
+    }
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let items = jobs::list(&pool, query.status.as_deref(), query.name.as_deref(), limit).await?;
+    Ok(Json(items))
+}
+
+/// POST /api/admin/jobs/:id/retry — Host club admin: run a failed job again
+pub async fn retry_job(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Extension(club): Extension<Club>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    clubs::require_host_admin(&claims, &club)?;
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "jobs", id).await?;
+    if !jobs::retry(&mut *tx, id).await? {
+        return Err(AppError::NotFound("No failed job with that id".into()));
+    }
+    let after = audit::snapshot(&mut tx, "jobs", id).await?;
+    audit::record(&mut tx, &audit, "retry_job", "job", Some(id), before, after).await?;
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Job {} queued again.", id),
+    }))
+}
+
+/// POST /api/admin/users/:id/unlock — Admin: lift a login lockout
+pub async fn unlock_user(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    clubs::require_owned(&mut *tx, "users", id, claims.club_id, "User").await?;
+    if !rate_limit::unlock(&mut tx, &audit, id).await? {
+        return Err(AppError::NotFound("User not found".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("User {} unlocked.", id),
+    }))
+}
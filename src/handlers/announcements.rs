@@ -2,16 +2,23 @@
 pub async fn reject_announcement(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<ApproveRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     require_coach_or_admin(&claims)?;
-    let result = sqlx::query("UPDATE announcements SET status = 'rejected' WHERE id = ? AND status = 'pending'")
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "announcements", payload.id).await?;
+    let result = sqlx::query("UPDATE announcements SET status = 'rejected' WHERE id = $1 AND club_id = $2 AND status = 'pending'")
         .bind(payload.id)
-        .execute(&pool)
+        .bind(claims.club_id)
+        .execute(&mut *tx)
         .await?;
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Announcement not found or already processed".into()));
     }
+    let after = audit::snapshot(&mut tx, "announcements", payload.id).await?;
+    audit::record(&mut tx, &audit, "reject", "announcement", Some(payload.id), before, after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "Announcement rejected.".into(),
@@ -22,6 +29,8 @@ use sqlx::PgPool;
 
 use crate::auth::{require_coach_or_admin, Claims};
 use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs::Club;
 use crate::models::{
     AnnouncementCreateRequest, AnnouncementResponse, ApiResponse, ApproveRequest,
 };
@@ -30,6 +39,7 @@ use crate::models::{
 pub async fn create_announcement(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<AnnouncementCreateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     if payload.title.is_empty() || payload.content.is_empty() {
@@ -39,16 +49,21 @@ pub async fn create_announcement(
     if claims.role != "user" {
         return Err(AppError::Unauthorized("Only users can post announcements.".into()));
     }
-    sqlx::query(
-        "INSERT INTO announcements (title, content, external_link, author_id, created_at, status) \
-         VALUES ($1, $2, $3, $4, NOW(), 'pending')"
+    let mut tx = pool.begin().await?;
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO announcements (title, content, external_link, author_id, club_id, created_at, status) \
+         VALUES ($1, $2, $3, $4, $5, NOW(), 'pending') RETURNING id"
     )
     .bind(&payload.title)
     .bind(&payload.content)
     .bind(&payload.external_link)
     .bind(claims.sub)
-    .execute(&pool)
+    .bind(claims.club_id)
+    .fetch_one(&mut *tx)
     .await?;
+    let after = audit::snapshot(&mut tx, "announcements", id).await?;
+    audit::record(&mut tx, &audit, "create", "announcement", Some(id), None, after).await?;
+    tx.commit().await?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -60,18 +75,25 @@ pub async fn create_announcement(
 pub async fn approve_announcement(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<ApproveRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     require_coach_or_admin(&claims)?;
 
-    let result = sqlx::query("UPDATE announcements SET status = 'approved' WHERE id = ? AND status = 'pending'")
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "announcements", payload.id).await?;
+    let result = sqlx::query("UPDATE announcements SET status = 'approved' WHERE id = $1 AND club_id = $2 AND status = 'pending'")
         .bind(payload.id)
-        .execute(&pool)
+        .bind(claims.club_id)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Announcement not found or already approved".into()));
     }
+    let after = audit::snapshot(&mut tx, "announcements", payload.id).await?;
+    audit::record(&mut tx, &audit, "approve", "announcement", Some(payload.id), before, after).await?;
+    tx.commit().await?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -79,14 +101,16 @@ pub async fn approve_announcement(
     }))
 }
 
-/// GET /api/announcements — Public: list approved announcements
+/// GET /api/announcements — Public: list the club's approved announcements
 pub async fn list_announcements(
     State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
 ) -> Result<impl IntoResponse, AppError> {
     let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, i64, String, String)>(
         "SELECT a.id, a.title, a.content, a.external_link, a.author_id, a.created_at, a.status \
-         FROM announcements a WHERE a.status = 'approved' ORDER BY a.created_at DESC",
+         FROM announcements a WHERE a.status = 'approved' AND a.club_id = $1 ORDER BY a.created_at DESC",
     )
+    .bind(club.id)
     .fetch_all(&pool)
     .await?;
 
@@ -118,8 +142,9 @@ pub async fn list_pending_announcements(
 
     let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, i64, String, String)>(
         "SELECT a.id, a.title, a.content, a.external_link, a.author_id, a.created_at, a.status \
-         FROM announcements a WHERE a.status = 'pending' ORDER BY a.created_at DESC",
+         FROM announcements a WHERE a.status = 'pending' AND a.club_id = $1 ORDER BY a.created_at DESC",
     )
+    .bind(claims.club_id)
     .fetch_all(&pool)
     .await?;
 
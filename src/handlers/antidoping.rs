@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    Extension, Json,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::auth::Claims;
+use crate::errors::AppError;
+use crate::models::{
+    AntidopingPlayer, ApiResponse, DeclarationQuery, DeclarationRequest, DeclarationReviewRequest, TueQuery, TueRequest,
+};
+use crate::services::antidoping::{self, DeclarationDetails, TueDetails, TueDocument};
+use crate::services::audit::AuditContext;
+
+fn parse_date(value: &str, what: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| AppError::BadRequest(format!("Invalid {} format", what)))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn declaration_details(payload: &DeclarationRequest) -> Result<DeclarationDetails<'_>, AppError> {
+    Ok(DeclarationDetails {
+        kind: &payload.kind,
+        name: &payload.name,
+        dosage: non_empty(&payload.dosage),
+        started_on: parse_date(&payload.started_on, "start date")?,
+        ended_on: payload.ended_on.as_deref().map(|d| parse_date(d, "end date")).transpose()?,
+        reason: non_empty(&payload.reason),
+    })
+}
+
+/// GET /api/antidoping/players — Players and guardians: the players whose
+/// declarations they keep (themselves, or their linked players)
+pub async fn my_players(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let players: Vec<AntidopingPlayer> = antidoping::own_players(&pool, claims.sub)
+        .await?
+        .into_iter()
+        .map(|(player_id, player_name)| AntidopingPlayer { player_id, player_name })
+        .collect();
+    Ok(Json(players))
+}
+
+/// GET /api/players/:id/medications — The player, their guardians or the medical officer
+pub async fn list_declarations(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    antidoping::require_access(&mut conn, &claims, player_id).await?;
+    Ok(Json(antidoping::declarations(&mut *conn, player_id).await?))
+}
+
+/// POST /api/players/:id/medications — The player, their guardians or the medical
+/// officer: declare a medication or supplement
+pub async fn create_declaration(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<DeclarationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let details = declaration_details(&payload)?;
+    let mut tx = pool.begin().await?;
+    antidoping::require_access(&mut tx, &claims, player_id).await?;
+    antidoping::declare(&mut tx, &audit, player_id, &details).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Declaration recorded. The medical officer will review it.".into(),
+    }))
+}
+
+/// PUT /api/medications/:id — The player, their guardians or the medical officer:
+/// change a declaration, e.g. when the player stopped taking it; it is reviewed again
+pub async fn update_declaration(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<DeclarationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let details = declaration_details(&payload)?;
+    let mut tx = pool.begin().await?;
+    let player_id = antidoping::declaration_player(&mut *tx, claims.club_id, id).await?;
+    antidoping::require_access(&mut tx, &claims, player_id).await?;
+    antidoping::update_declaration(&mut tx, &audit, id, &details).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Declaration updated".into(),
+    }))
+}
+
+/// GET /api/medical/declarations?status= — Medical officer: the club's declarations,
+/// oldest first; `status=pending` is the review queue
+pub async fn declaration_queue(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<DeclarationQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    antidoping::require_medical_officer(&claims)?;
+    Ok(Json(antidoping::declaration_queue(&pool, claims.club_id, query.status.as_deref()).await?))
+}
+
+/// POST /api/medical/declarations/:id/review — Medical officer: mark a declaration
+/// reviewed, or flagged for the player to act on
+pub async fn review_declaration(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<DeclarationReviewRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    antidoping::require_medical_officer(&claims)?;
+    let mut tx = pool.begin().await?;
+    antidoping::declaration_player(&mut *tx, claims.club_id, id).await?;
+    antidoping::review(&mut tx, &audit, id, &payload.status, non_empty(&payload.notes)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Declaration {}", payload.status),
+    }))
+}
+
+/// GET /api/players/:id/tues — The player, their guardians or the medical officer
+pub async fn list_tues(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    antidoping::require_access(&mut conn, &claims, player_id).await?;
+    Ok(Json(antidoping::tues(&mut *conn, player_id).await?))
+}
+
+/// POST /api/players/:id/tues — The player, their guardians or the medical officer:
+/// add a TUE by uploading the certificate (base64) or giving its reference
+pub async fn create_tue(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<TueRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let document = match &payload.document {
+        Some(upload) => Some(TueDocument {
+            name: upload.file_name.clone(),
+            content_type: upload.content_type.trim().to_ascii_lowercase(),
+            bytes: STANDARD
+                .decode(upload.data.trim())
+                .map_err(|_| AppError::BadRequest("The certificate is not valid base64".into()))?,
+        }),
+        None => None,
+    };
+    let details = TueDetails {
+        substance: &payload.substance,
+        reference: non_empty(&payload.reference),
+        granted_on: payload.granted_on.as_deref().map(|d| parse_date(d, "grant date")).transpose()?,
+        expires_on: parse_date(&payload.expires_on, "expiry date")?,
+        document,
+    };
+    let mut tx = pool.begin().await?;
+    antidoping::require_access(&mut tx, &claims, player_id).await?;
+    antidoping::add_tue(&mut tx, &audit, player_id, &details).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "TUE recorded. You will be reminded before it expires.".into(),
+    }))
+}
+
+/// GET /api/tues/:id/document — The player, their guardians or the medical officer:
+/// download the uploaded TUE certificate
+pub async fn tue_document(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    let (player_id, name, content_type, bytes) = antidoping::tue_document(&mut *conn, claims.club_id, id).await?;
+    antidoping::require_access(&mut conn, &claims, player_id).await?;
+    let name: String = name.chars().filter(|c| !c.is_control() && *c != '"' && *c != '\\').collect();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name)),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        bytes,
+    ))
+}
+
+/// GET /api/medical/tues?within_days= — Medical officer: the club's TUEs expiring
+/// within the given days (default 60), expired ones included
+pub async fn expiring_tues(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<TueQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    antidoping::require_medical_officer(&claims)?;
+    let days = query.within_days.unwrap_or(60).clamp(0, 3650);
+    Ok(Json(antidoping::expiring_tues(&pool, claims.club_id, days).await?))
+}
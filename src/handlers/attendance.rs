@@ -1,14 +1,53 @@
 use axum::{extract::State, response::IntoResponse, Extension, Json};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
 use crate::auth::{require_coach_or_admin, Claims};
 use crate::errors::AppError;
 use crate::models::{ApiResponse, AttendanceBulkRequest, AttendanceMarkRequest, AttendanceResponse};
+use crate::services::audit::{self, AuditContext};
+use crate::services::teams;
+
+/// Insert or update one attendance row and record the change in the audit log.
+async fn upsert_attendance(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    player_id: i64,
+    match_id: Option<i64>,
+    team_id: Option<i64>,
+    present: bool,
+    date: Option<chrono::NaiveDate>,
+) -> Result<(), sqlx::Error> {
+    let existing: Option<(i64,)> = sqlx::query_as("SELECT id FROM attendance WHERE player_id = $1 AND match_id = $2")
+        .bind(player_id)
+        .bind(match_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let before = match existing {
+        Some((id,)) => audit::snapshot(conn, "attendance", id).await?,
+        None => None,
+    };
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO attendance (player_id, match_id, team_id, attended, date) VALUES ($1, $2, $3, $4, $5) \
+         ON CONFLICT (player_id, match_id) DO UPDATE SET attended = EXCLUDED.attended, date = EXCLUDED.date \
+         RETURNING id"
+    )
+    .bind(player_id)
+    .bind(match_id)
+    .bind(team_id)
+    .bind(present)
+    .bind(date)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(conn, "attendance", id).await?;
+    let action = if before.is_some() { "update" } else { "create" };
+    audit::record(conn, ctx, action, "attendance", Some(id), before, after).await
+}
 
 /// POST /api/attendance — Coach/Admin marks a single player's attendance
 pub async fn mark_attendance(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<AttendanceMarkRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     require_coach_or_admin(&claims)?;
@@ -19,21 +58,18 @@ pub async fn mark_attendance(
             .map_err(|_| AppError::BadRequest("Invalid date format".into()))?),
         None => None,
     };
+    let mut tx = pool.begin().await?;
+    let team_id = teams::require_match_access(&mut *tx, &claims, payload.match_id).await?;
     // Find player_id from user_id
     let rec = sqlx::query!("SELECT id FROM players WHERE user_id = $1", payload.user_id)
-        .fetch_one(&pool)
+        .fetch_one(&mut *tx)
         .await?;
     let player_id = rec.id;
-    sqlx::query(
-        "INSERT INTO attendance (player_id, match_id, attended, date) VALUES ($1, $2, $3, $4) \
-         ON CONFLICT (player_id, match_id) DO UPDATE SET attended = EXCLUDED.attended, date = EXCLUDED.date"
-    )
-    .bind(player_id)
-    .bind(payload.match_id)
-    .bind(payload.present)
-    .bind(date)
-    .execute(&pool)
-    .await?;
+    if !teams::players_outside_match_team(&mut *tx, payload.match_id, &[player_id]).await?.is_empty() {
+        return Err(AppError::BadRequest("The player is not in this match's team".into()));
+    }
+    upsert_attendance(&mut tx, &audit, player_id, Some(payload.match_id), team_id, payload.present, date).await?;
+    tx.commit().await?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -45,19 +81,35 @@ pub async fn mark_attendance(
 pub async fn mark_attendance_bulk(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<AttendanceBulkRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     require_coach_or_admin(&claims)?;
 
+    let mut tx = pool.begin().await?;
+    // Match attendance belongs to the match's team; training to the given team
+    let team_id = match payload.match_id {
+        Some(match_id) => teams::require_match_access(&mut *tx, &claims, match_id).await?,
+        None => teams::team_for_new(&mut tx, &claims, payload.team_id).await?,
+    };
+    let members = match team_id {
+        Some(team_id) => Some(teams::members(&mut *tx, team_id).await?),
+        None => None,
+    };
+
     for record in &payload.records {
         let date = match record.date.clone().or_else(|| payload.date.clone()) {
             Some(d) => Some(chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
                 .map_err(|_| AppError::BadRequest("Invalid date format".into()))?),
             None => None,
         };
-        // Find player_id from user_id
-        let rec = sqlx::query!("SELECT id FROM players WHERE user_id = $1", record.user_id)
-            .fetch_optional(&pool)
+        // Find player_id from user_id, within the club
+        let rec = sqlx::query!(
+            "SELECT p.id FROM players p JOIN users u ON p.user_id = u.id WHERE p.user_id = $1 AND u.club_id = $2",
+            record.user_id,
+            claims.club_id
+        )
+            .fetch_optional(&mut *tx)
             .await?;
         let player_id = match rec {
             Some(r) => r.id,
@@ -66,18 +118,13 @@ pub async fn mark_attendance_bulk(
                 continue; // Skip this record
             }
         };
-        let match_id = payload.match_id;
-        sqlx::query(
-            "INSERT INTO attendance (player_id, match_id, attended, date) VALUES ($1, $2, $3, $4) \
-             ON CONFLICT (player_id, match_id) DO UPDATE SET attended = EXCLUDED.attended, date = EXCLUDED.date"
-        )
-        .bind(player_id)
-        .bind(match_id)
-        .bind(record.present)
-        .bind(date)
-        .execute(&pool)
-        .await?;
+        if members.as_ref().is_some_and(|m| !m.iter().any(|m| m.user_id == record.user_id)) {
+            tracing::warn!("User {} is not in team {:?}", record.user_id, team_id);
+            continue;
+        }
+        upsert_attendance(&mut tx, &audit, player_id, payload.match_id, team_id, record.present, date).await?;
     }
+    tx.commit().await?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -86,24 +133,31 @@ pub async fn mark_attendance_bulk(
 }
 
 /// GET /api/attendance — Returns attendance records.
-/// Coach/Admin: all records. Player: only their own.
+/// Admin: all of the club's records. Coach: their teams' records. Player: only their own. Guardian: their linked players'.
 pub async fn list_attendance(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
 ) -> Result<impl IntoResponse, AppError> {
     let rows = if claims.role == "coach" || claims.role == "admin" {
         sqlx::query_as::<_, (i64, i64, Option<String>, i64, bool, Option<String>)>(
-            "SELECT a.id, a.player_id, u.name, a.match_id, a.attended, a.date \
+            "SELECT a.id, a.player_id, u.name, a.match_id, a.attended, a.date::text \
              FROM attendance a LEFT JOIN players p ON a.player_id = p.id LEFT JOIN users u ON p.user_id = u.id \
+             LEFT JOIN matches m ON a.match_id = m.id \
+             WHERE u.deleted_at IS NULL AND m.deleted_at IS NULL AND u.club_id = $2 \
+             AND ($1::bigint[] IS NULL OR a.team_id IS NULL OR a.team_id = ANY($1)) \
              ORDER BY a.match_id DESC",
         )
+        .bind(teams::scope(&pool, &claims).await?.ids())
+        .bind(claims.club_id)
         .fetch_all(&pool)
         .await?
     } else {
         sqlx::query_as::<_, (i64, i64, Option<String>, i64, bool, Option<String>)>(
-            "SELECT a.id, a.player_id, u.name, a.match_id, a.attended, a.date \
+            "SELECT a.id, a.player_id, u.name, a.match_id, a.attended, a.date::text \
              FROM attendance a LEFT JOIN players p ON a.player_id = p.id LEFT JOIN users u ON p.user_id = u.id \
-             WHERE a.player_id = $1 ORDER BY a.match_id DESC",
+             LEFT JOIN matches m ON a.match_id = m.id \
+             WHERE (p.user_id = $1 OR a.player_id IN (SELECT player_id FROM player_guardians WHERE guardian_id = $1)) \
+             AND m.deleted_at IS NULL ORDER BY a.match_id DESC",
         )
         .bind(claims.sub)
         .fetch_all(&pool)
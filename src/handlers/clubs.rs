@@ -0,0 +1,63 @@
+use axum::{extract::State, response::IntoResponse, Extension, Json};
+use sqlx::PgPool;
+
+use crate::auth::{require_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{ClubBrandingRequest, ClubCreateRequest, ClubCreatedResponse};
+use crate::services::audit::AuditContext;
+use crate::services::clubs::{self, Club};
+
+/// GET /api/club — Public: name, logo and colour of the club this address belongs to
+pub async fn get_club(Extension(club): Extension<Club>) -> impl IntoResponse {
+    Json(club)
+}
+
+/// PUT /api/club — Admin: change the club's name, logo and colour
+pub async fn update_branding(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<ClubBrandingRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let logo_url = payload.logo_url.as_deref().map(str::trim).filter(|u| !u.is_empty());
+    let primary_color = payload.primary_color.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    let mut tx = pool.begin().await?;
+    clubs::update_branding(&mut tx, &audit, claims.club_id, &payload.name, logo_url, primary_color).await?;
+    let club = clubs::get(&mut *tx, claims.club_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Club not found".into()))?;
+    tx.commit().await?;
+    Ok(Json(club))
+}
+
+/// GET /api/admin/clubs — Host club admin: every club of the installation
+pub async fn list_clubs(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Extension(club): Extension<Club>,
+) -> Result<impl IntoResponse, AppError> {
+    clubs::require_host_admin(&claims, &club)?;
+    Ok(Json(clubs::list(&pool).await?))
+}
+
+/// POST /api/admin/clubs — Host club admin: add a club and its first admin;
+/// returns the admin's temporary password once
+pub async fn create_club(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Extension(club): Extension<Club>,
+    audit: AuditContext,
+    Json(payload): Json<ClubCreateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    clubs::require_host_admin(&claims, &club)?;
+    let slug = payload.slug.trim().to_ascii_lowercase();
+    let mut tx = pool.begin().await?;
+    let id = clubs::create(&mut tx, &audit, &slug, &payload.name).await?;
+    let (admin_id, temporary_password) = clubs::add_admin(&mut tx, &audit, id, &payload.admin_email, &payload.admin_name).await?;
+    let created = clubs::get(&mut *tx, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Club not found".into()))?;
+    tx.commit().await?;
+    Ok(Json(ClubCreatedResponse { club: created, admin_id, temporary_password }))
+}
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use sqlx::PgPool;
+
+use crate::auth::{require_admin, require_coach_or_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{ApiResponse, DisciplinaryReport, DisciplinaryRulesRequest, PlayerDisciplinaryRecord};
+use crate::services::audit::AuditContext;
+use crate::services::{clubs, disciplinary, guardians, teams};
+
+/// GET /api/tournaments/:id/disciplinary — Coach/Admin: the tournament's rules, card
+/// counts per player and bans with the fixtures served
+pub async fn tournament_report(
+    Path(tournament_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    clubs::require_owned(&pool, "tournaments", tournament_id, claims.club_id, "Tournament").await?;
+    let scope = teams::scope(&pool, &claims).await?;
+
+    Ok(Json(DisciplinaryReport {
+        rules: disciplinary::rules(&pool, tournament_id).await?,
+        players: disciplinary::tournament_tallies(&pool, tournament_id, &scope).await?,
+        bans: disciplinary::tournament_bans(&pool, tournament_id, &scope).await?,
+    }))
+}
+
+/// PUT /api/tournaments/:id/disciplinary/rules — Admin: set which cards lead to a
+/// ban and for how many matches; applies to cards recorded from now on
+pub async fn update_rules(
+    Path(tournament_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<DisciplinaryRulesRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    disciplinary::validate_rules(&payload.rules).map_err(|errors| AppError::BadRequest(errors.join("; ")))?;
+    let mut tx = pool.begin().await?;
+    clubs::require_owned(&mut *tx, "tournaments", tournament_id, claims.club_id, "Tournament").await?;
+    disciplinary::set_rules(&mut tx, &audit, tournament_id, &payload.rules).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Disciplinary rules updated".into(),
+    }))
+}
+
+/// GET /api/players/:id/disciplinary — The player, their guardians, their coaches and
+/// admins: cards per tournament and bans
+pub async fn player_record(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    guardians::require_player_access(&mut conn, &claims, player_id).await?;
+
+    Ok(Json(PlayerDisciplinaryRecord {
+        tournaments: disciplinary::player_tallies(&mut *conn, player_id).await?,
+        bans: disciplinary::player_bans(&mut *conn, player_id).await?,
+    }))
+}
@@ -0,0 +1,100 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::auth::{require_coach_or_admin, Claims};
+use crate::errors::AppError;
+use crate::services::export::{self, ExportDataset, ExportFilter, ExportFormat};
+use crate::services::teams;
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+    pub season_id: Option<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub team_id: Option<i64>,
+}
+
+fn parse_date(value: &Option<String>, name: &str) -> Result<Option<chrono::NaiveDate>, AppError> {
+    value
+        .as_deref()
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            chrono::NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                .map_err(|_| AppError::BadRequest(format!("Invalid '{}' date. Use YYYY-MM-DD.", name)))
+        })
+        .transpose()
+}
+
+/// GET /api/export/:dataset — Coach/Admin: export matches, events, attendance, players or fitness results.
+/// The format comes from `?format=csv|jsonl|xlsx` or the Accept header (CSV by default);
+/// `season_id`, `from` and `to` filter by the match season and date (the test date for
+/// fitness results), `team_id` by team.
+pub async fn export_dataset(
+    Path(dataset): Path<String>,
+    Query(query): Query<ExportQuery>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    require_coach_or_admin(&claims)?;
+    let dataset = ExportDataset::parse(&dataset).ok_or_else(|| {
+        AppError::NotFound("Unknown export. Use matches, events, attendance, players or fitness.".into())
+    })?;
+    let format = match &query.format {
+        Some(name) => ExportFormat::parse(name)
+            .ok_or_else(|| AppError::BadRequest("Format must be 'csv', 'jsonl' or 'xlsx'".into()))?,
+        None => ExportFormat::from_accept(
+            headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()).unwrap_or(""),
+        ),
+    };
+    // Coaches export their own teams only
+    let teams = match query.team_id {
+        Some(team_id) => {
+            teams::require_team_access(&pool, &claims, Some(team_id)).await?;
+            Some(vec![team_id])
+        }
+        None => teams::scope(&pool, &claims).await?.ids(),
+    };
+    let filter = ExportFilter {
+        club_id: claims.club_id,
+        season_id: query.season_id,
+        from: parse_date(&query.from, "from")?,
+        to: parse_date(&query.to, "to")?,
+        teams,
+    };
+
+    let body = match format {
+        ExportFormat::Xlsx => {
+            let rows = export::fetch_rows(&pool, dataset, &filter).await?;
+            let workbook = export::xlsx_workbook(dataset.as_str(), dataset.columns(), &rows)
+                .map_err(|e| AppError::Internal(format!("XLSX export failed: {}", e)))?;
+            Body::from(workbook)
+        }
+        _ => {
+            let receiver = export::stream_rows(pool, dataset, filter, format);
+            Body::from_stream(futures_util::stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|chunk| (chunk, receiver))
+            }))
+        }
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.{}\"", dataset.as_str(), format.extension()),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
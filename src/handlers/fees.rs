@@ -0,0 +1,258 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::auth::Claims;
+use crate::config;
+use crate::errors::AppError;
+use crate::models::{
+    ApiResponse, CheckoutRequest, FeePlanQuery, FeePlanRequest, InvoiceDetail, InvoiceIssueRequest, InvoiceQuery,
+    PaymentRecordRequest,
+};
+use crate::services::audit::AuditContext;
+use crate::services::fees::{self, FeePlanDetails, InvoiceFilter, PaymentDetails};
+use crate::services::payments::Payments;
+use crate::services::teams;
+
+fn parse_date(value: &str, what: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| AppError::BadRequest(format!("Invalid {} format", what)))
+}
+
+fn plan_details(payload: &FeePlanRequest) -> Result<FeePlanDetails<'_>, AppError> {
+    Ok(FeePlanDetails {
+        season_id: payload.season_id,
+        category: &payload.category,
+        name: &payload.name,
+        amount_cents: payload.amount_cents,
+        currency: payload.currency.as_deref().unwrap_or(&config::get().fees.currency),
+        due_date: parse_date(&payload.due_date, "due date")?,
+    })
+}
+
+/// GET /api/fees/plans?season_id= — Treasurer/Admin: the club's fee plans
+pub async fn list_plans(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FeePlanQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    Ok(Json(fees::plans(&pool, claims.club_id, query.season_id).await?))
+}
+
+/// POST /api/fees/plans — Treasurer/Admin sets the fee of a member category for a season
+pub async fn create_plan(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<FeePlanRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    let details = plan_details(&payload)?;
+    let mut tx = pool.begin().await?;
+    fees::create_plan(&mut tx, &audit, claims.club_id, &details).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Fee plan created".into(),
+    }))
+}
+
+/// PUT /api/fees/plans/:id — Treasurer/Admin: change a fee plan; issued invoices are kept as they are
+pub async fn update_plan(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<FeePlanRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    let details = plan_details(&payload)?;
+    let mut tx = pool.begin().await?;
+    fees::update_plan(&mut tx, &audit, claims.club_id, id, &details).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Fee plan updated".into(),
+    }))
+}
+
+/// POST /api/fees/plans/:id/invoices — Treasurer/Admin issues invoices from a plan
+/// to the given players or every player of a team, billed to their guardian
+/// when they have one
+pub async fn issue_invoices(
+    Path(plan_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<InvoiceIssueRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    let mut tx = pool.begin().await?;
+    let player_ids: Vec<i64> = match (&payload.player_ids, payload.team_id) {
+        (Some(ids), None) if !ids.is_empty() => ids.clone(),
+        (None, Some(team_id)) => {
+            teams::get(&mut *tx, claims.club_id, team_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Team not found".into()))?;
+            teams::members(&mut *tx, team_id).await?.into_iter().filter_map(|m| m.player_id).collect()
+        }
+        _ => return Err(AppError::BadRequest("Give either player_ids or a team_id".into())),
+    };
+    let summary =
+        fees::issue_invoices(&mut tx, &audit, claims.club_id, plan_id, &player_ids, payload.bill_guardians.unwrap_or(true)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("{} invoice(s) issued, {} player(s) already invoiced", summary.issued, summary.skipped),
+    }))
+}
+
+/// GET /api/invoices?status=&season_id=&player_id= — Treasurer/Admin: the club's
+/// invoices; everyone else: the invoices they pay or that are for them or their children
+pub async fn list_invoices(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<InvoiceQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let viewer = (!fees::is_treasurer(&claims)).then_some(claims.sub);
+    let filter = InvoiceFilter {
+        status: query.status,
+        season_id: query.season_id,
+        player_id: query.player_id,
+    };
+    Ok(Json(fees::list(&pool, claims.club_id, viewer, &filter).await?))
+}
+
+/// GET /api/fees/overdue?season_id= — Treasurer/Admin: unpaid invoices past their
+/// due date, longest overdue first
+pub async fn overdue_invoices(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FeePlanQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    let filter = InvoiceFilter {
+        status: Some("overdue".into()),
+        season_id: query.season_id,
+        player_id: None,
+    };
+    Ok(Json(fees::list(&pool, claims.club_id, None, &filter).await?))
+}
+
+/// GET /api/fees/balances?season_id= — Treasurer/Admin: invoiced, paid and
+/// outstanding amounts per payer
+pub async fn balances(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FeePlanQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    Ok(Json(fees::balances(&pool, claims.club_id, query.season_id).await?))
+}
+
+/// GET /api/invoices/:id — The payer, the player, their guardians and the treasurer:
+/// an invoice with its payments
+pub async fn get_invoice(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    let invoice = fees::require_invoice_access(&mut conn, &claims, id).await?;
+    let payments = fees::payments(&mut *conn, id).await?;
+    Ok(Json(InvoiceDetail { invoice, payments }))
+}
+
+/// POST /api/invoices/:id/payments — Treasurer/Admin records a payment received
+/// outside the app; partial payments are allowed up to the balance
+pub async fn record_payment(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<PaymentRecordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    let details = PaymentDetails {
+        amount_cents: payload.amount_cents,
+        paid_on: match &payload.paid_on {
+            Some(d) => parse_date(d, "payment date")?,
+            None => chrono::Utc::now().date_naive(),
+        },
+        method: &payload.method,
+        reference: payload.reference.as_deref().map(str::trim).filter(|r| !r.is_empty()),
+        notes: payload.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()),
+    };
+    let mut tx = pool.begin().await?;
+    fees::require_invoice_access(&mut tx, &claims, id).await?;
+    fees::record_payment(&mut tx, &audit, id, &details).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Payment recorded".into(),
+    }))
+}
+
+/// POST /api/invoices/:id/cancel — Treasurer/Admin cancels an invoice issued in error
+pub async fn cancel_invoice(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    fees::require_treasurer(&claims)?;
+    let mut tx = pool.begin().await?;
+    fees::cancel_invoice(&mut tx, &audit, claims.club_id, id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Invoice cancelled".into(),
+    }))
+}
+
+/// GET /api/payments/:id/receipt.pdf — Whoever may see the invoice: a PDF receipt for a payment
+pub async fn payment_receipt(
+    Path(payment_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    let invoice_id = fees::invoice_of_payment(&mut *conn, claims.club_id, payment_id).await?;
+    let invoice = fees::require_invoice_access(&mut conn, &claims, invoice_id).await?;
+    let pdf = fees::receipt_pdf(&mut conn, &invoice, payment_id).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, format!("inline; filename=\"receipt-{}.pdf\"", payment_id)),
+        ],
+        pdf,
+    ))
+}
+
+/// POST /api/invoices/:id/checkout — The payer, the player or their guardians: pay
+/// an invoice online. The payment is recorded once the provider confirms it.
+pub async fn checkout(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Extension(payments): Extension<Payments>,
+    audit: AuditContext,
+    Json(payload): Json<CheckoutRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let provider = payments.require()?;
+    let mut tx = pool.begin().await?;
+    let invoice = fees::require_invoice_access(&mut tx, &claims, id).await?;
+    let checkout = fees::start_checkout(&mut tx, &audit, provider, &invoice, payload.amount_cents).await?;
+    tx.commit().await?;
+    Ok(Json(checkout))
+}
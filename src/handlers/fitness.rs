@@ -0,0 +1,144 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::auth::{require_coach_or_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{ApiResponse, FitnessPercentileQuery, FitnessResultsRequest, FitnessTestRequest};
+use crate::services::audit::AuditContext;
+use crate::services::fitness::{self, TestTypeDetails};
+use crate::services::guardians;
+use crate::services::teams;
+
+fn parse_date(value: &str, what: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| AppError::BadRequest(format!("Invalid {} format", what)))
+}
+
+fn details(payload: &FitnessTestRequest) -> TestTypeDetails<'_> {
+    TestTypeDetails {
+        name: &payload.name,
+        unit: &payload.unit,
+        higher_is_better: payload.higher_is_better.unwrap_or(true),
+        description: payload.description.as_deref().map(str::trim).filter(|d| !d.is_empty()),
+    }
+}
+
+/// GET /api/fitness/tests — Club members: the fitness tests the club runs
+pub async fn list_tests(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(fitness::test_types(&pool, claims.club_id).await?))
+}
+
+/// POST /api/fitness/tests — Coach/Admin adds a fitness test with its unit
+pub async fn create_test(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<FitnessTestRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    fitness::create_test_type(&mut tx, &audit, claims.club_id, &details(&payload)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Fitness test added".into(),
+    }))
+}
+
+/// PUT /api/fitness/tests/:id — Coach/Admin renames a test or changes its unit
+pub async fn update_test(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<FitnessTestRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    fitness::update_test_type(&mut tx, &audit, claims.club_id, id, &details(&payload)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Fitness test updated".into(),
+    }))
+}
+
+/// POST /api/fitness/results — Coach/Admin records one test session: a result
+/// per player of their teams. A player's earlier result that day is replaced.
+pub async fn record_results(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<FitnessResultsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let tested_on = parse_date(&payload.tested_on, "test date")?;
+    if tested_on > chrono::Utc::now().date_naive() {
+        return Err(AppError::BadRequest("Results cannot be recorded for a future date".into()));
+    }
+    if payload.results.is_empty() {
+        return Err(AppError::BadRequest("No results to record".into()));
+    }
+
+    let mut tx = pool.begin().await?;
+    fitness::test_type(&mut *tx, claims.club_id, payload.test_type_id).await?;
+    for entry in &payload.results {
+        if !teams::can_see_player(&mut *tx, &claims, entry.player_id).await? {
+            return Err(AppError::Forbidden(format!("Player {} is not in one of your teams", entry.player_id)));
+        }
+        let notes = entry.notes.as_deref().map(str::trim).filter(|n| !n.is_empty());
+        fitness::record(&mut tx, &audit, payload.test_type_id, entry.player_id, tested_on, entry.value, notes).await?;
+    }
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("{} result(s) recorded", payload.results.len()),
+    }))
+}
+
+/// GET /api/players/:id/fitness — The player, their guardians, their coaches and
+/// admins: the player's results per test with the change between tests
+pub async fn player_fitness(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    guardians::require_player_access(&mut conn, &claims, player_id).await?;
+    Ok(Json(fitness::history(&mut *conn, player_id).await?))
+}
+
+/// GET /api/fitness/tests/:id/percentiles?team_id=&date= — Coach/Admin: each
+/// player's latest result up to the date ranked within the team (coaches'
+/// own teams, or the whole club for admins, without a team)
+pub async fn test_percentiles(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FitnessPercentileQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let date = match &query.date {
+        Some(d) => parse_date(d, "date")?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let mut conn = pool.acquire().await?;
+    let teams = match query.team_id {
+        Some(team_id) => {
+            teams::require_team_access(&mut *conn, &claims, Some(team_id)).await?;
+            Some(vec![team_id])
+        }
+        None => teams::scope(&mut *conn, &claims).await?.ids(),
+    };
+    Ok(Json(fitness::percentiles(&mut conn, claims.club_id, id, teams, date).await?))
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use axum::{extract::{Path, State}, response::IntoResponse, Extension, Json};
+use sqlx::PgPool;
+
+use crate::auth::{require_admin, require_coach_or_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{
+    ApiResponse, AttendanceResponse, ConsentUpdateRequest, GuardianLinkRequest, PlayerScheduleEntry, PlayerStatsResponse,
+};
+use crate::services::audit::AuditContext;
+use crate::services::guardians;
+
+/// POST /api/admin/guardians — Admin: link a guardian account to a player
+pub async fn link_guardian(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<GuardianLinkRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let relationship = payload.relationship.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    let mut tx = pool.begin().await?;
+    if !guardians::link(&mut tx, &audit, claims.club_id, payload.guardian_id, payload.player_id, relationship).await? {
+        return Err(AppError::Conflict("That guardian is already linked to this player".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Guardian linked".into(),
+    }))
+}
+
+/// DELETE /api/admin/guardians/:guardian_id/players/:player_id — Admin: remove a guardian link
+pub async fn unlink_guardian(
+    Path((guardian_id, player_id)): Path<(i64, i64)>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    if !guardians::unlink(&mut tx, &audit, claims.club_id, guardian_id, player_id).await? {
+        return Err(AppError::NotFound("That guardian is not linked to this player".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Guardian unlinked".into(),
+    }))
+}
+
+/// GET /api/players/:id/guardians — Coach/Admin: who looks after a player in their teams
+pub async fn list_guardians(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let mut conn = pool.acquire().await?;
+    guardians::require_player_access(&mut conn, &claims, player_id).await?;
+    Ok(Json(guardians::guardians_of(&mut *conn, player_id).await?))
+}
+
+/// GET /api/guardian/players — Guardian: the players linked to this account
+pub async fn my_players(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    if claims.role != "guardian" {
+        return Err(AppError::Forbidden("Guardian role required".into()));
+    }
+    Ok(Json(guardians::linked_players(&pool, claims.sub).await?))
+}
+
+/// GET /api/players/:id/schedule — Player/Guardian/Coach: upcoming matches of the
+/// player's teams with their availability and squad selection
+pub async fn player_schedule(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    guardians::require_player_access(&mut conn, &claims, player_id).await?;
+
+    let rows = sqlx::query_as::<_, (i64, chrono::NaiveDate, String, String, Option<String>, Option<bool>, Option<bool>)>(
+        "SELECT m.id, m.date, m.home_team, m.away_team, m.location, a.available, \
+         CASE WHEN m.squad_published_at IS NULL THEN NULL ELSE s.id IS NOT NULL END \
+         FROM matches m \
+         LEFT JOIN match_availability a ON a.match_id = m.id AND a.player_id = $1 \
+         LEFT JOIN match_squads s ON s.match_id = m.id AND s.player_id = $1 \
+         WHERE m.deleted_at IS NULL AND m.club_id = $2 AND m.date >= CURRENT_DATE \
+         AND (m.team_id IS NULL OR m.team_id IN ( \
+           SELECT tm.team_id FROM team_members tm JOIN players p ON p.user_id = tm.user_id WHERE p.id = $1)) \
+         ORDER BY m.date, m.id",
+    )
+    .bind(player_id)
+    .bind(claims.club_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let schedule: Vec<PlayerScheduleEntry> = rows
+        .into_iter()
+        .map(|(match_id, date, home_team, away_team, location, available, in_squad)| PlayerScheduleEntry {
+            match_id,
+            date: date.to_string(),
+            home_team,
+            away_team,
+            location,
+            available,
+            in_squad,
+        })
+        .collect();
+    Ok(Json(schedule))
+}
+
+/// GET /api/players/:id/attendance — Player/Guardian/Coach: one player's attendance
+pub async fn player_attendance(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    guardians::require_player_access(&mut conn, &claims, player_id).await?;
+
+    let rows = sqlx::query_as::<_, (i64, i64, Option<String>, i64, bool, Option<String>)>(
+        "SELECT a.id, a.player_id, u.name, a.match_id, a.attended, a.date::text \
+         FROM attendance a JOIN players p ON a.player_id = p.id JOIN users u ON p.user_id = u.id \
+         JOIN matches m ON a.match_id = m.id \
+         WHERE a.player_id = $1 AND m.deleted_at IS NULL ORDER BY m.date DESC, a.match_id DESC",
+    )
+    .bind(player_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let attendance: Vec<AttendanceResponse> = rows
+        .into_iter()
+        .map(|(id, user_id, user_name, match_id, present, date)| AttendanceResponse {
+            id,
+            user_id,
+            user_name,
+            match_id,
+            present,
+            date,
+        })
+        .collect();
+    Ok(Json(attendance))
+}
+
+/// GET /api/players/:id/stats — Player/Guardian/Coach: season totals for one player
+pub async fn player_stats(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    guardians::require_player_access(&mut conn, &claims, player_id).await?;
+
+    let matches_in_squad: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM match_squads s JOIN matches m ON s.match_id = m.id \
+         WHERE s.player_id = $1 AND m.squad_published_at IS NOT NULL AND m.deleted_at IS NULL",
+    )
+    .bind(player_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let (attended, attendance_recorded): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*) FILTER (WHERE a.attended), COUNT(*) FROM attendance a JOIN matches m ON a.match_id = m.id \
+         WHERE a.player_id = $1 AND m.deleted_at IS NULL",
+    )
+    .bind(player_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let events: BTreeMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+        "SELECT e.event_type, COUNT(*) FROM match_events e JOIN matches m ON e.match_id = m.id \
+         WHERE e.player_id = $1 AND m.deleted_at IS NULL GROUP BY e.event_type",
+    )
+    .bind(player_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .collect();
+
+    Ok(Json(PlayerStatsResponse {
+        player_id,
+        matches_in_squad,
+        attended,
+        attendance_recorded,
+        events,
+    }))
+}
+
+/// GET /api/players/:id/consents — Player/Guardian/Coach: consents on file for a player
+pub async fn player_consents(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    guardians::require_player_access(&mut conn, &claims, player_id).await?;
+    Ok(Json(guardians::consents(&mut *conn, player_id).await?))
+}
+
+/// PUT /api/players/:id/consents/:consent_type — Guardian/Admin (or a player without
+/// guardians): give or withdraw a consent
+pub async fn update_consent(
+    Path((player_id, consent_type)): Path<(i64, String)>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<ConsentUpdateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let notes = payload.notes.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let mut tx = pool.begin().await?;
+    guardians::set_consent(&mut tx, &audit, &claims, player_id, &consent_type, payload.granted, notes).await?;
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: if payload.granted { "Consent given".into() } else { "Consent withdrawn".into() },
+    }))
+}
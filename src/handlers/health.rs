@@ -0,0 +1,40 @@
+use axum::{extract::State, http::{header, StatusCode}, response::IntoResponse, Json};
+use serde_json::json;
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::telemetry;
+
+/// How long the readiness probe waits for the database.
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+async fn database_ok(pool: &PgPool) -> bool {
+    matches!(
+        tokio::time::timeout(DB_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// GET /healthz — Public: liveness. Always 200 while the process serves requests;
+/// the database state is reported but does not fail the probe.
+pub async fn healthz(State(pool): State<PgPool>) -> impl IntoResponse {
+    let database = if database_ok(&pool).await { "ok" } else { "unavailable" };
+    Json(json!({ "status": "ok", "database": database }))
+}
+
+/// GET /readyz — Public: readiness. 503 until the database answers.
+pub async fn readyz(State(pool): State<PgPool>) -> impl IntoResponse {
+    if database_ok(&pool).await {
+        (StatusCode::OK, Json(json!({ "status": "ready", "database": "ok" })))
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "status": "unavailable", "database": "unavailable" })))
+    }
+}
+
+/// GET /metrics — Public: Prometheus metrics in the text exposition format
+pub async fn metrics(State(pool): State<PgPool>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        telemetry::render_metrics(&pool),
+    )
+}
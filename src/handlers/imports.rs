@@ -0,0 +1,60 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::auth::{require_admin, Claims};
+use crate::errors::AppError;
+use crate::services::audit::AuditContext;
+use crate::services::import::{self, ImportFormat, ImportKind};
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    pub format: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// POST /api/import/:kind — Admin: bulk import players, fixtures or results into the club.
+/// The request body is the raw CSV or XLSX file. `?dry_run=true` validates
+/// without saving; `?format=xlsx` is implied by a spreadsheet content type.
+pub async fn import_file(
+    Path(kind): Path<String>,
+    Query(query): Query<ImportQuery>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let kind = ImportKind::parse(&kind)
+        .ok_or_else(|| AppError::BadRequest("Import type must be 'players', 'fixtures' or 'results'".into()))?;
+    let format = match &query.format {
+        Some(name) => ImportFormat::parse(name)
+            .ok_or_else(|| AppError::BadRequest("Format must be 'csv' or 'xlsx'".into()))?,
+        None => {
+            let content_type = headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("");
+            if content_type.contains("spreadsheetml") {
+                ImportFormat::Xlsx
+            } else {
+                ImportFormat::Csv
+            }
+        }
+    };
+
+    let report = match import::read_rows(&body, format) {
+        Ok(rows) => import::run_import(&pool, &audit, claims.club_id, kind, rows, query.dry_run).await?,
+        Err(errors) => import::report(kind, query.dry_run, 0, 0, errors),
+    };
+    let status = if report.success { StatusCode::OK } else { StatusCode::BAD_REQUEST };
+    Ok((status, Json(report)))
+}
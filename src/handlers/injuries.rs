@@ -0,0 +1,134 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::auth::{require_coach_or_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{ApiResponse, InjuryClearRequest, InjuryQuery, InjuryRequest};
+use crate::services::antidoping;
+use crate::services::audit::AuditContext;
+use crate::services::guardians;
+use crate::services::injuries::{self, InjuryDetails};
+use crate::services::teams;
+
+fn parse_date(value: &str, what: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| AppError::BadRequest(format!("Invalid {} format", what)))
+}
+
+fn details(payload: &InjuryRequest) -> Result<InjuryDetails<'_>, AppError> {
+    Ok(InjuryDetails {
+        injury_type: &payload.injury_type,
+        body_part: &payload.body_part,
+        injured_on: parse_date(&payload.injured_on, "injury date")?,
+        expected_return: payload.expected_return.as_deref().map(|d| parse_date(d, "expected return date")).transpose()?,
+        notes: payload.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()),
+    })
+}
+
+/// POST /api/injuries — Coach/Admin records an injury of a player in their teams
+pub async fn create_injury(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<InjuryRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let details = details(&payload)?;
+    if !teams::can_see_player(&pool, &claims, payload.player_id).await? {
+        return Err(AppError::Forbidden("The player is not in one of your teams".into()));
+    }
+
+    let mut tx = pool.begin().await?;
+    injuries::create(&mut tx, &audit, payload.player_id, &details).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Injury recorded. The player cannot be selected until medically cleared.".into(),
+    }))
+}
+
+/// GET /api/injuries?include_cleared= — Coach/Admin: open injuries of players in
+/// their teams with the matches they affect; coaches do not see the medical notes
+pub async fn list_injuries(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<InjuryQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let scope = teams::scope(&pool, &claims).await?;
+    let list = injuries::list(&pool, claims.club_id, &scope, query.include_cleared.unwrap_or(false)).await?;
+    Ok(Json(injuries::redact(list, &claims)))
+}
+
+/// PUT /api/injuries/:id — Coach/Admin: correct an injury or its expected return
+pub async fn update_injury(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<InjuryRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let details = details(&payload)?;
+    let mut tx = pool.begin().await?;
+    let player_id = injuries::player_of(&mut *tx, claims.club_id, id).await?;
+    if !teams::can_see_player(&mut *tx, &claims, player_id).await? {
+        return Err(AppError::Forbidden("The player is not in one of your teams".into()));
+    }
+    injuries::update(&mut tx, &audit, id, &details, injuries::can_read_notes(&claims)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Injury updated".into(),
+    }))
+}
+
+/// POST /api/injuries/:id/clear — Admin/Medical officer: record the player's medical clearance
+pub async fn clear_injury(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<InjuryClearRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    if claims.role != "admin" && !antidoping::is_medical_officer(&claims) {
+        return Err(AppError::Forbidden("Admin or medical officer role required".into()));
+    }
+    let cleared_on = match &payload.cleared_on {
+        Some(d) => parse_date(d, "clearance date")?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let mut tx = pool.begin().await?;
+    injuries::player_of(&mut *tx, claims.club_id, id).await?;
+    injuries::clear(&mut tx, &audit, id, cleared_on).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Medical clearance recorded".into(),
+    }))
+}
+
+/// GET /api/players/:id/injuries — The player, their guardians, their coaches,
+/// admins and medical officers: the player's injury history; coaches do not see
+/// the medical notes
+pub async fn player_injuries(
+    Path(player_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.acquire().await?;
+    if antidoping::is_medical_officer(&claims) {
+        antidoping::require_access(&mut conn, &claims, player_id).await?;
+    } else {
+        guardians::require_player_access(&mut conn, &claims, player_id).await?;
+    }
+    let list = injuries::for_player(&mut *conn, player_id).await?;
+    Ok(Json(injuries::redact(list, &claims)))
+}
@@ -1,4 +1,6 @@
-use handball_team_app::match_statistics;
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs::Club;
+use crate::services::{disciplinary, match_report, match_statistics, matches, teams};
 
 /// GET /api/matches/{id}/statistics — Returns match and player statistics
 pub async fn get_match_statistics(
@@ -7,6 +9,7 @@ pub async fn get_match_statistics(
     Extension(claims): Extension<Claims>,
 ) -> Result<impl IntoResponse, AppError> {
     require_coach_or_admin(&claims)?;
+    teams::require_match_access(&pool, &claims, match_id).await?;
     match match_statistics::compute_match_statistics(&pool, match_id).await {
         Ok(Some(stats)) => Ok(Json(stats)),
         Ok(None) => Err(AppError::NotFound("Match not found".into())),
@@ -14,18 +17,54 @@ pub async fn get_match_statistics(
     }
 }
 
+/// GET /api/matches/{id}/report.pdf — Coach/Admin: printable match report as PDF
+pub async fn get_match_report_pdf(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    teams::require_match_access(&pool, &claims, match_id).await?;
+    let report = match_report::build_report(&pool, match_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Match not found".into()))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, format!("inline; filename=\"match-{}-report.pdf\"", match_id)),
+        ],
+        match_report::render_pdf(&report),
+    ))
+}
+
+/// GET /api/matches/{id}/report.html — Coach/Admin: printable match report as HTML
+pub async fn get_match_report_html(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    teams::require_match_access(&pool, &claims, match_id).await?;
+    let report = match_report::build_report(&pool, match_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Match not found".into()))?;
+    Ok(Html(match_report::render_html(&report)))
+}
+
 // Only import once at the top
-use axum::{extract::{Path, State}, response::IntoResponse, Extension, Json};
+use axum::{extract::{Path, Query, State}, http::header, response::{Html, IntoResponse}, Extension, Json};
 use sqlx::PgPool;
 use crate::auth::{require_coach_or_admin, Claims};
 use crate::errors::AppError;
-use crate::models::{ApiResponse, MatchCreateRequest, MatchResponse, MatchUpdateRequest, MatchEventCreateRequest, MatchEventResponse, validate_event_type, validate_period};
+use crate::services::bracket;
+use crate::models::{ApiResponse, MatchCreateRequest, MatchResponse, MatchUpdateRequest, MatchEventCreateRequest, MatchEventResponse, TeamFilter, validate_event_type, validate_period};
 
-/// POST /api/matches/{match_id}/events — Insert match event
+/// POST /api/matches/{match_id}/events — Insert match event; cards may trigger a match ban
 pub async fn create_match_event(
     Path(match_id): Path<i64>,
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<MatchEventCreateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     require_coach_or_admin(&claims)?;
@@ -43,20 +82,19 @@ pub async fn create_match_event(
         return Err(AppError::BadRequest("Minute must be >= 0".into()));
     }
 
-    // Confirm match exists
-    let match_exists = sqlx::query_scalar::<_, i64>("SELECT id FROM matches WHERE id = $1")
-        .bind(match_id)
-        .fetch_optional(&pool)
-        .await?;
-    if match_exists.is_none() {
-        return Err(AppError::NotFound("Match not found".into()));
-    }
+    let mut tx = pool.begin().await?;
 
-    // Confirm player exists
-    let player_exists = sqlx::query_scalar::<_, i64>("SELECT id FROM players WHERE id = $1")
-        .bind(payload.player_id)
-        .fetch_optional(&pool)
-        .await?;
+    // Confirm match exists and belongs to one of the user's teams
+    teams::require_match_access(&mut *tx, &claims, match_id).await?;
+
+    // Confirm player exists in the club
+    let player_exists = sqlx::query_scalar::<_, i64>(
+        "SELECT p.id FROM players p JOIN users u ON p.user_id = u.id WHERE p.id = $1 AND u.club_id = $2",
+    )
+    .bind(payload.player_id)
+    .bind(claims.club_id)
+    .fetch_optional(&mut *tx)
+    .await?;
     if player_exists.is_none() {
         return Err(AppError::NotFound("Player not found".into()));
     }
@@ -78,8 +116,13 @@ pub async fn create_match_event(
         payload.is_penalty,
         claims.sub
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
     .await?;
+    let after = audit::snapshot(&mut tx, "match_events", rec.id).await?;
+    audit::record(&mut tx, &audit, "create", "match_event", Some(rec.id), None, after).await?;
+    // Cards count towards the tournament's disciplinary rules
+    disciplinary::apply_rules(&mut tx, &audit, rec.id).await?;
+    tx.commit().await?;
 
     Ok(Json(MatchEventResponse {
         id: rec.id,
@@ -94,20 +137,27 @@ pub async fn create_match_event(
         created_at: rec.created_at.unwrap_or_default(),
     }))
 }
-/// DELETE /api/matches/:id — Coach/Admin deletes a match
+/// DELETE /api/matches/:id — Coach/Admin moves a match to the trash
 pub async fn delete_match(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse, AppError> {
     require_coach_or_admin(&claims)?;
-    let result = sqlx::query("DELETE FROM matches WHERE id = $1")
+    let mut tx = pool.begin().await?;
+    teams::require_match_access(&mut *tx, &claims, id).await?;
+    let before = audit::snapshot(&mut tx, "matches", id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null())
+        .ok_or_else(|| AppError::NotFound("Match not found".into()))?;
+    sqlx::query("UPDATE matches SET deleted_at = NOW() WHERE id = $1")
         .bind(id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Match not found".into()));
-    }
+    let after = audit::snapshot(&mut tx, "matches", id).await?;
+    audit::record(&mut tx, &audit, "delete", "match", Some(id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "Match deleted.".into(),
@@ -118,27 +168,18 @@ pub async fn delete_match(
 pub async fn create_match(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
-    Json(payload): Json<MatchCreateRequest>,
+    audit: AuditContext,
+    Json(mut payload): Json<MatchCreateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Validation for new fields
-    if payload.match_date.is_empty() || payload.home_team.is_empty() || payload.away_team.is_empty() || payload.location.as_deref().unwrap_or("").is_empty() {
-        return Err(AppError::BadRequest("Date, home team, away team, and location are required".into()));
-    }
-        let date = chrono::NaiveDate::parse_from_str(&payload.match_date, "%Y-%m-%d")
-        .map_err(|_| AppError::BadRequest("Invalid date format. Use YYYY-MM-DD.".into()))?;
-    sqlx::query(
-        "INSERT INTO matches (date, home_team, away_team, location, tournament_id, home_score, away_score) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7)",
-    )
-        .bind(date)
-    .bind(&payload.home_team)
-    .bind(&payload.away_team)
-    .bind(&payload.location)
-    .bind(payload.tournament_id)
-    .bind(payload.home_score)
-    .bind(payload.away_score)
-    .execute(&pool)
-    .await?;
+    require_coach_or_admin(&claims)?;
+    let date = matches::validate(&payload)?;
+    let mut tx = pool.begin().await?;
+    payload.team_id = teams::team_for_new(&mut tx, &claims, payload.team_id).await?;
+    matches::check_references(&mut tx, claims.club_id, &payload).await?;
+    let id = matches::insert(&mut tx, claims.club_id, &payload, date).await?;
+    let after = audit::snapshot(&mut tx, "matches", id).await?;
+    audit::record(&mut tx, &audit, "create", "match", Some(id), None, after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "Match created.".into(),
@@ -149,39 +190,67 @@ pub async fn create_match(
 pub async fn update_match(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<MatchUpdateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
     // Only update home_score and away_score if present in struct
-    let result = sqlx::query(
-        "UPDATE matches SET home_score = $1, away_score = $2 WHERE id = $3",
+    let mut tx = pool.begin().await?;
+    teams::require_match_access(&mut *tx, &claims, payload.id).await?;
+    let before = audit::snapshot(&mut tx, "matches", payload.id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null())
+        .ok_or_else(|| AppError::NotFound("Match not found".into()))?;
+    // A score correction that leaves the shootout out keeps the recorded one
+    sqlx::query(
+        "UPDATE matches SET home_score = $1, away_score = $2, \
+                home_shootout = CASE WHEN $6 THEN NULL ELSE COALESCE($3, home_shootout) END, \
+                away_shootout = CASE WHEN $6 THEN NULL ELSE COALESCE($4, away_shootout) END \
+         WHERE id = $5",
     )
     .bind(payload.home_score)
     .bind(payload.away_score)
+    .bind(payload.home_shootout)
+    .bind(payload.away_shootout)
     .bind(payload.id)
-    .execute(&pool)
+    .bind(payload.clear_shootout)
+    .execute(&mut *tx)
     .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Match not found".into()));
-    }
+    // Knockout matches push their winner into the next round
+    let advanced = bracket::advance_winner(&mut tx, payload.id).await?;
+    let after = audit::snapshot(&mut tx, "matches", payload.id).await?;
+    audit::record(&mut tx, &audit, "update", "match", Some(payload.id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
-        message: "Match updated.".into(),
+        message: if advanced.is_some() {
+            "Match updated. Winner advanced to the next round.".into()
+        } else {
+            "Match updated.".into()
+        },
     }))
 }
 
-/// GET /api/matches — Public: list all matches (newest first)
+/// GET /api/matches — Public: list the club's matches (newest first); `?team_id=` for one team
 pub async fn list_matches(
     State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+    Query(filter): Query<TeamFilter>,
 ) -> Result<impl IntoResponse, AppError> {
-    let rows = sqlx::query_as::<_, (i64, chrono::NaiveDate, String, String, Option<String>, Option<i64>, Option<i32>, Option<i32>)>(
-        "SELECT id, date, home_team, away_team, location, tournament_id, home_score, away_score FROM matches ORDER BY date DESC",
+    #[allow(clippy::type_complexity)]
+    let rows = sqlx::query_as::<_, (i64, chrono::NaiveDate, String, String, Option<String>, Option<i64>, Option<i32>, Option<i32>, Option<i32>, Option<i32>, Option<i64>, Option<i64>, Option<i32>, Option<i32>, Option<i64>)>(
+        "SELECT id, date, home_team, away_team, location, tournament_id, home_score, away_score, \
+                home_shootout, away_shootout, stage_id, group_id, round, bracket_position, team_id \
+         FROM matches WHERE deleted_at IS NULL AND club_id = $2 AND ($1::bigint IS NULL OR team_id = $1) ORDER BY date DESC",
     )
+    .bind(filter.team_id)
+    .bind(club.id)
     .fetch_all(&pool)
     .await?;
 
     let matches: Vec<MatchResponse> = rows
         .into_iter()
-            .map(|(id, date, home_team, away_team, location, tournament_id, home_score, away_score)| {
+            .map(|(id, date, home_team, away_team, location, tournament_id, home_score, away_score, home_shootout, away_shootout, stage_id, group_id, round, bracket_position, team_id)| {
             MatchResponse {
                 id,
                     match_date: date.to_string(),
@@ -191,6 +260,13 @@ pub async fn list_matches(
                 tournament_id,
                 home_score,
                 away_score,
+                home_shootout,
+                away_shootout,
+                stage_id,
+                group_id,
+                round,
+                bracket_position,
+                team_id,
             }
         })
         .collect();
@@ -3,3 +3,20 @@ pub mod matches;
 pub mod attendance;
 pub mod seasons;
 pub mod admin;
+pub mod tournaments;
+pub mod squads;
+pub mod notifications;
+pub mod imports;
+pub mod exports;
+pub mod health;
+pub mod two_factor;
+pub mod registration;
+pub mod oidc;
+pub mod guardians;
+pub mod teams;
+pub mod clubs;
+pub mod injuries;
+pub mod antidoping;
+pub mod disciplinary;
+pub mod fitness;
+pub mod fees;
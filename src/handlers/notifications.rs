@@ -0,0 +1,54 @@
+use axum::{extract::{Path, State}, response::IntoResponse, Extension, Json};
+use sqlx::PgPool;
+
+use crate::auth::Claims;
+use crate::errors::AppError;
+use crate::models::{ApiResponse, NotificationResponse};
+
+/// GET /api/notifications — The current user's notifications, newest first
+pub async fn list_notifications(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let rows = sqlx::query_as::<_, (i64, String, String, Option<String>, chrono::NaiveDateTime, bool)>(
+        "SELECT id, title, body, link, created_at, read_at IS NOT NULL FROM notifications \
+         WHERE user_id = $1 ORDER BY created_at DESC LIMIT 100",
+    )
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    let notifications: Vec<NotificationResponse> = rows
+        .into_iter()
+        .map(|(id, title, body, link, created_at, read)| NotificationResponse {
+            id,
+            title,
+            body,
+            link,
+            created_at: created_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            read,
+        })
+        .collect();
+
+    Ok(Json(notifications))
+}
+
+/// POST /api/notifications/:id/read — Mark one of the current user's notifications as read
+pub async fn mark_notification_read(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let result = sqlx::query("UPDATE notifications SET read_at = COALESCE(read_at, NOW()) WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Notification not found".into()));
+    }
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Notification marked as read.".into(),
+    }))
+}
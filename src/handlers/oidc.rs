@@ -0,0 +1,205 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Redirect, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::auth::{create_challenge_token, create_token, Claims};
+use crate::config;
+use crate::errors::AppError;
+use crate::services::audit::AuditContext;
+use crate::services::clubs::Club;
+use crate::services::oidc::{self, Resolution};
+use crate::services::rate_limit;
+use crate::services::two_factor::{self, LoginStep};
+use crate::telemetry;
+
+/// Binds a login to the browser that started it.
+const STATE_COOKIE: &str = "oidc_state";
+
+#[derive(Serialize)]
+pub struct ProviderSummary {
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Serialize)]
+pub struct LinkStart {
+    pub authorization_url: String,
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+fn state_cookie(value: &str, max_age: u64) -> String {
+    let secure = if config::get().oidc.public_url.starts_with("https://") { "; Secure" } else { "" };
+    format!("{}={}; Path=/api/auth/oidc; HttpOnly; SameSite=Lax; Max-Age={}{}", STATE_COOKIE, value, max_age, secure)
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// The login page with the outcome in the fragment, which never reaches the server.
+fn login_page(params: &[(&str, String)]) -> Redirect {
+    let fragment = url::form_urlencoded::Serializer::new(String::new()).extend_pairs(params).finish();
+    Redirect::to(&format!("/login.html#{}", fragment))
+}
+
+/// GET /api/auth/oidc/providers — Public: sign-in providers for the login page
+pub async fn list_providers() -> impl IntoResponse {
+    let providers: Vec<ProviderSummary> = config::get()
+        .oidc
+        .providers
+        .iter()
+        .map(|p| ProviderSummary { name: p.name.clone(), display_name: p.display_name.clone() })
+        .collect();
+    Json(providers)
+}
+
+/// GET /api/auth/oidc/:provider/login — Public: redirect to the provider's sign-in page
+pub async fn login(
+    Path(provider): Path<String>,
+    State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+) -> Result<Response, AppError> {
+    let config = &config::get().oidc;
+    let provider = oidc::provider(config, &provider)?;
+    let start = oidc::begin(&pool, config, provider, &club, None).await?;
+    let cookie = state_cookie(&start.state, oidc::STATE_TTL.as_secs());
+    Ok(([(header::SET_COOKIE, cookie)], Redirect::to(&start.authorization_url)).into_response())
+}
+
+/// POST /api/auth/oidc/:provider/link — Signed-in user: start a sign-in that
+/// links the provider account to their own. The browser then opens the URL.
+pub async fn link(
+    Path(provider): Path<String>,
+    State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Response, AppError> {
+    let config = &config::get().oidc;
+    let provider = oidc::provider(config, &provider)?;
+    let start = oidc::begin(&pool, config, provider, &club, Some(claims.sub)).await?;
+    let cookie = state_cookie(&start.state, oidc::STATE_TTL.as_secs());
+    Ok(([(header::SET_COOKIE, cookie)], Json(LinkStart { authorization_url: start.authorization_url })).into_response())
+}
+
+/// GET /api/auth/oidc/:provider/callback — Public: the provider returns here; on to the login page
+pub async fn callback(
+    Path(provider): Path<String>,
+    State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+    Query(query): Query<CallbackQuery>,
+    headers: HeaderMap,
+    audit: AuditContext,
+) -> Response {
+    let clear = [(header::SET_COOKIE, state_cookie("", 0))];
+    let params = match complete_login(&pool, &club, &provider, &query, &headers, &audit).await {
+        Ok(params) => params,
+        Err(e) => {
+            let message = match e {
+                AppError::Database(_) | AppError::Internal(_) => {
+                    tracing::error!("OIDC login with {} failed: {}", provider, e);
+                    "Sign-in failed. Please try again.".to_string()
+                }
+                AppError::NotFound(msg)
+                | AppError::Unauthorized(msg)
+                | AppError::Forbidden(msg)
+                | AppError::BadRequest(msg)
+                | AppError::Conflict(msg)
+                | AppError::TooManyRequests(msg, _) => msg,
+            };
+            vec![("error", message)]
+        }
+    };
+    (clear, login_page(&params)).into_response()
+}
+
+/// Verify the callback and decide what the login page receives: a token,
+/// a 2FA challenge, or an error.
+async fn complete_login(
+    pool: &PgPool,
+    club: &Club,
+    provider: &str,
+    query: &CallbackQuery,
+    headers: &HeaderMap,
+    audit: &AuditContext,
+) -> Result<Vec<(&'static str, String)>, AppError> {
+    let config = &config::get().oidc;
+    let provider = oidc::provider(config, provider)?;
+    if let Some(error) = &query.error {
+        let detail = query.error_description.as_deref().unwrap_or(error);
+        return Err(AppError::Unauthorized(format!("{} sign-in was cancelled or refused: {}", provider.display_name, detail)));
+    }
+    let (Some(code), Some(state)) = (&query.code, &query.state) else {
+        return Err(AppError::BadRequest("The sign-in response is incomplete".into()));
+    };
+    if cookie_value(headers, STATE_COOKIE) != Some(state.as_str()) {
+        return Err(AppError::BadRequest("This sign-in was started in another browser. Please try again.".into()));
+    }
+
+    let identity = oidc::finish(pool, config, provider, club, state, code).await?;
+    let mut tx = pool.begin().await?;
+    let user_id = match oidc::resolve_user(&mut tx, audit, club.id, &provider.name, &identity).await? {
+        Resolution::User(user_id) => user_id,
+        Resolution::NeedsConfirmation => {
+            telemetry::record_login_failure("oidc_unconfirmed");
+            return Err(AppError::Forbidden(format!(
+                "Log in with your password and link your {} account from the dashboard first.",
+                provider.display_name
+            )));
+        }
+        Resolution::NoMatch => {
+            telemetry::record_login_failure("oidc_unlinked");
+            let reason = if identity.email.is_some() && !identity.email_verified {
+                format!("Your {} email address is not verified.", provider.display_name)
+            } else {
+                format!("No account matches your {} email address.", provider.display_name)
+            };
+            return Err(AppError::Forbidden(format!("{} Log in with your password or ask an admin for an invite.", reason)));
+        }
+    };
+    let (email, name, role, status): (String, String, String, String) = sqlx::query_as(
+        "SELECT u.email, u.name, r.name, u.status FROM users u JOIN roles r ON u.role_id = r.id WHERE u.id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    match status.as_str() {
+        "pending" => return Err(AppError::Forbidden("Your account is awaiting approval by an admin".into())),
+        "rejected" => return Err(AppError::Forbidden("Your registration was not approved".into())),
+        _ => {}
+    }
+    if let Some(secs) = rate_limit::lockout_remaining(pool, user_id).await? {
+        return Err(AppError::TooManyRequests("Account temporarily locked after repeated failed logins.".into(), secs));
+    }
+
+    // The provider replaces the password, not the second factor
+    let mut conn = pool.acquire().await?;
+    let step = two_factor::login_step(&mut conn, club.id, user_id, &role).await?;
+    if step != LoginStep::Done {
+        return Ok(vec![
+            ("two_factor", step.as_str().to_string()),
+            ("challenge", create_challenge_token(user_id)?),
+        ]);
+    }
+    let token = create_token(user_id, &email, &role, &name, club.id)?;
+    Ok(vec![("token", token), ("role", role), ("user_id", user_id.to_string()), ("name", name)])
+}
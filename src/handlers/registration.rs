@@ -0,0 +1,129 @@
+use axum::{extract::{Path, State}, response::IntoResponse, Extension, Json};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+
+use crate::auth::{require_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{ApiResponse, InviteCreateRequest, InviteCreatedResponse, RegistrationModeSetting};
+use crate::services::audit::AuditContext;
+use crate::services::registration;
+
+/// Longest an invite may stay valid.
+const MAX_INVITE_DAYS: i64 = 90;
+
+/// GET /api/admin/registration-mode — Admin: how new accounts are admitted
+pub async fn get_registration_mode(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    Ok(Json(RegistrationModeSetting { mode: registration::mode(&pool, claims.club_id).await? }))
+}
+
+/// PUT /api/admin/registration-mode — Admin: switch between open, approval and invite-only
+pub async fn update_registration_mode(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<RegistrationModeSetting>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    registration::set_mode(&mut tx, &audit, claims.club_id, payload.mode).await?;
+    tx.commit().await?;
+    Ok(Json(payload))
+}
+
+/// POST /api/admin/invites — Admin: invite someone with a role; returns the code once
+pub async fn create_invite(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<InviteCreateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    if !["coach", "player", "guardian"].contains(&payload.role.as_str()) {
+        return Err(AppError::BadRequest("Role must be 'coach', 'player' or 'guardian'".into()));
+    }
+    let days = payload.expires_in_days.unwrap_or(7);
+    if !(1..=MAX_INVITE_DAYS).contains(&days) {
+        return Err(AppError::BadRequest(format!("expires_in_days must be between 1 and {}", MAX_INVITE_DAYS)));
+    }
+    let email = payload.email.as_deref().map(str::trim).filter(|e| !e.is_empty());
+    let expires_at = (Utc::now() + Duration::days(days)).naive_utc();
+
+    let mut tx = pool.begin().await?;
+    let (invite, code) = registration::create_invite(&mut tx, &audit, claims.club_id, &payload.role, email, expires_at).await?;
+    tx.commit().await?;
+    Ok(Json(InviteCreatedResponse { link: format!("/register.html?invite={}", code), invite, code }))
+}
+
+/// GET /api/admin/invites — Admin: all invites with their state
+pub async fn list_invites(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    Ok(Json(registration::list_invites(&pool, claims.club_id).await?))
+}
+
+/// DELETE /api/admin/invites/:id — Admin: revoke an unused invite
+pub async fn revoke_invite(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    if !registration::revoke_invite(&mut tx, &audit, claims.club_id, id).await? {
+        return Err(AppError::NotFound("No unused invite with that id".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Invite revoked".into(),
+    }))
+}
+
+/// GET /api/admin/pending-users — Admin: sign-ups waiting for approval
+pub async fn pending_users(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    Ok(Json(registration::pending_users(&pool, claims.club_id).await?))
+}
+
+async fn review(pool: &PgPool, claims: &Claims, audit: &AuditContext, id: i64, approve: bool) -> Result<ApiResponse, AppError> {
+    require_admin(claims)?;
+    let mut tx = pool.begin().await?;
+    if !registration::review(&mut tx, audit, claims.club_id, id, approve).await? {
+        return Err(AppError::NotFound("No pending user with that id".into()));
+    }
+    tx.commit().await?;
+    Ok(ApiResponse {
+        success: true,
+        message: if approve { "User approved".into() } else { "User rejected".into() },
+    })
+}
+
+/// POST /api/admin/users/:id/approve — Admin: activate a pending account
+pub async fn approve_user(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(review(&pool, &claims, &audit, id, true).await?))
+}
+
+/// POST /api/admin/users/:id/reject — Admin: turn down a pending account
+pub async fn reject_user(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(review(&pool, &claims, &audit, id, false).await?))
+}
@@ -2,46 +2,62 @@
 use crate::models::UserUpdateRequest;
 pub async fn update_user(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Path(id): Path<i64>,
     Json(payload): Json<UserUpdateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to update users
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
     // Find role id
         let role: Option<(i64,)> = sqlx::query_as("SELECT id FROM roles WHERE name = $1")
         .bind(&payload.role)
-        .fetch_optional(&pool)
+        .fetch_optional(&mut *tx)
         .await?;
     let role_id = role.ok_or_else(|| AppError::BadRequest(format!("Role '{}' does not exist", payload.role)))?.0;
-        let result = sqlx::query("UPDATE users SET name = $1, email = $2, role_id = $3 WHERE id = $4")
-        .bind(&payload.name)
-        .bind(&payload.email)
-        .bind(role_id)
-        .bind(id)
-        .execute(&pool)
-        .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("User not found".into()));
-    }
+    let before = audit::snapshot(&mut tx, "users", id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null() && row["club_id"] == claims.club_id)
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+    // A changed email holds off linking sign-in providers by email for a while
+    sqlx::query(
+        "UPDATE users SET name = $1, email = $2, role_id = $3, \
+         email_changed_at = CASE WHEN LOWER(email) <> LOWER($2) THEN NOW() ELSE email_changed_at END WHERE id = $4",
+    )
+    .bind(&payload.name)
+    .bind(&payload.email)
+    .bind(role_id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    let after = audit::snapshot(&mut tx, "users", id).await?;
+    audit::record(&mut tx, &audit, "update", "user", Some(id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "User updated.".into(),
     }))
 }
-/// DELETE /api/users/:id — Admin deletes a user
+/// DELETE /api/users/:id — Admin moves a user to the trash
 pub async fn delete_user(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to delete users
-    let result = sqlx::query("DELETE FROM users WHERE id = $1")
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "users", id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null() && row["club_id"] == claims.club_id)
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+    sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1")
         .bind(id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("User not found".into()));
-    }
+    let after = audit::snapshot(&mut tx, "users", id).await?;
+    audit::record(&mut tx, &audit, "delete", "user", Some(id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "User deleted.".into(),
@@ -51,89 +67,141 @@ pub async fn delete_user(
 use crate::models::SeasonUpdateRequest;
 pub async fn update_season(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Path(id): Path<i64>,
     Json(payload): Json<SeasonUpdateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to update seasons
+    require_admin(&claims)?;
     let start_date = chrono::NaiveDate::parse_from_str(&payload.start_date, "%Y-%m-%d")
         .map_err(|_| AppError::BadRequest("Invalid start date format".into()))?;
     let end_date = chrono::NaiveDate::parse_from_str(&payload.end_date, "%Y-%m-%d")
         .map_err(|_| AppError::BadRequest("Invalid end date format".into()))?;
-    let result = sqlx::query("UPDATE seasons SET name = $1, start_date = $2, end_date = $3 WHERE id = $4")
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "seasons", id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null() && row["club_id"] == claims.club_id)
+        .ok_or_else(|| AppError::NotFound("Season not found".into()))?;
+    sqlx::query("UPDATE seasons SET name = $1, start_date = $2, end_date = $3 WHERE id = $4")
         .bind(&payload.name)
         .bind(start_date)
         .bind(end_date)
         .bind(id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Season not found".into()));
-    }
+    let after = audit::snapshot(&mut tx, "seasons", id).await?;
+    audit::record(&mut tx, &audit, "update", "season", Some(id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "Season updated.".into(),
     }))
 }
 
-/// PATCH /api/tournaments/:id — Admin updates a tournament
+/// PATCH /api/tournaments/:id — Coach/Admin updates a tournament
 use crate::models::TournamentUpdateRequest;
 pub async fn update_tournament(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Path(id): Path<i64>,
     Json(payload): Json<TournamentUpdateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to update tournaments
-    let result = sqlx::query("UPDATE tournaments SET name = $1, season_id = $2 WHERE id = $3")
+    require_coach_or_admin(&claims)?;
+    if let Some(list) = &payload.tiebreakers {
+        parse_tiebreakers(list)
+            .map_err(|name| AppError::BadRequest(format!("Unknown tiebreaker '{}'", name)))?;
+    }
+    if payload.format.as_deref().is_some_and(|f| !validate_format(f)) {
+        return Err(AppError::BadRequest("Invalid tournament format".into()));
+    }
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "tournaments", id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null() && row["club_id"] == claims.club_id)
+        .ok_or_else(|| AppError::NotFound("Tournament not found".into()))?;
+    // Points left out keep their stored value, so check the rules as they will be saved
+    let points = |given: Option<i32>, field: &str| given.unwrap_or_else(|| before[field].as_i64().unwrap_or_default() as i32);
+    if !validate_points(
+        points(payload.points_for_win, "points_for_win"),
+        points(payload.points_for_draw, "points_for_draw"),
+        points(payload.points_for_loss, "points_for_loss"),
+    ) {
+        return Err(AppError::BadRequest(POINTS_ERROR.into()));
+    }
+    clubs::require_owned(&mut *tx, "seasons", payload.season_id, claims.club_id, "Season").await?;
+    sqlx::query(
+        "UPDATE tournaments SET name = $1, season_id = $2, \
+         points_for_win = COALESCE($3, points_for_win), points_for_draw = COALESCE($4, points_for_draw), \
+         points_for_loss = COALESCE($5, points_for_loss), tiebreakers = COALESCE($6, tiebreakers), \
+         format = COALESCE($7, format) \
+         WHERE id = $8",
+    )
         .bind(&payload.name)
         .bind(payload.season_id)
+        .bind(payload.points_for_win)
+        .bind(payload.points_for_draw)
+        .bind(payload.points_for_loss)
+        .bind(&payload.tiebreakers)
+        .bind(&payload.format)
         .bind(id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Tournament not found".into()));
-    }
+    let after = audit::snapshot(&mut tx, "tournaments", id).await?;
+    audit::record(&mut tx, &audit, "update", "tournament", Some(id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "Tournament updated.".into(),
     }))
 }
-/// DELETE /api/seasons/:id — Admin deletes a season
+/// DELETE /api/seasons/:id — Admin moves a season to the trash
 use axum::extract::Path;
 pub async fn delete_season(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to delete seasons
-    let result = sqlx::query("DELETE FROM seasons WHERE id = $1")
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "seasons", id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null() && row["club_id"] == claims.club_id)
+        .ok_or_else(|| AppError::NotFound("Season not found".into()))?;
+    sqlx::query("UPDATE seasons SET deleted_at = NOW() WHERE id = $1")
         .bind(id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Season not found".into()));
-    }
+    let after = audit::snapshot(&mut tx, "seasons", id).await?;
+    audit::record(&mut tx, &audit, "delete", "season", Some(id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "Season deleted.".into(),
     }))
 }
 
-/// DELETE /api/tournaments/:id — Admin deletes a tournament
+/// DELETE /api/tournaments/:id — Coach/Admin moves a tournament to the trash
 pub async fn delete_tournament(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Path(id): Path<i64>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to delete tournaments
-    let result = sqlx::query("DELETE FROM tournaments WHERE id = $1")
+    require_coach_or_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "tournaments", id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null() && row["club_id"] == claims.club_id)
+        .ok_or_else(|| AppError::NotFound("Tournament not found".into()))?;
+    sqlx::query("UPDATE tournaments SET deleted_at = NOW() WHERE id = $1")
         .bind(id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Tournament not found".into()));
-    }
+    let after = audit::snapshot(&mut tx, "tournaments", id).await?;
+    audit::record(&mut tx, &audit, "delete", "tournament", Some(id), Some(before), after).await?;
+    tx.commit().await?;
     Ok(Json(ApiResponse {
         success: true,
         message: "Tournament deleted.".into(),
@@ -142,8 +210,13 @@ pub async fn delete_tournament(
 use axum::{extract::State, response::IntoResponse, Extension, Json};
 use sqlx::PgPool;
 
-use crate::auth::Claims;
+use crate::auth::{require_admin, require_coach_or_admin, Claims};
 use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::bracket::validate_format;
+use crate::services::clubs::{self, Club};
+use crate::services::standings::{parse_tiebreakers, validate_points};
+use crate::services::teams;
 use crate::models::{
     ApiResponse, SeasonCreateRequest, SeasonResponse, TournamentCreateRequest, TournamentResponse,
     RoleUpdateRequest, UserResponse,
@@ -154,10 +227,11 @@ use crate::models::{
 /// POST /api/seasons — Admin creates a season
 pub async fn create_season(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<SeasonCreateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to create seasons
+    require_admin(&claims)?;
 
     if payload.name.is_empty() {
         return Err(AppError::BadRequest("Season name is required".into()));
@@ -167,12 +241,17 @@ pub async fn create_season(
         .map_err(|_| AppError::BadRequest("Invalid start date format".into()))?;
     let end_date = chrono::NaiveDate::parse_from_str(&payload.end_date, "%Y-%m-%d")
         .map_err(|_| AppError::BadRequest("Invalid end date format".into()))?;
-    sqlx::query("INSERT INTO seasons (name, start_date, end_date) VALUES ($1, $2, $3)")
+    let mut tx = pool.begin().await?;
+    let (id,): (i64,) = sqlx::query_as("INSERT INTO seasons (name, start_date, end_date, club_id) VALUES ($1, $2, $3, $4) RETURNING id")
         .bind(&payload.name)
         .bind(start_date)
         .bind(end_date)
-        .execute(&pool)
+        .bind(claims.club_id)
+        .fetch_one(&mut *tx)
         .await?;
+    let after = audit::snapshot(&mut tx, "seasons", id).await?;
+    audit::record(&mut tx, &audit, "create", "season", Some(id), None, after).await?;
+    tx.commit().await?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -180,13 +259,15 @@ pub async fn create_season(
     }))
 }
 
-/// GET /api/seasons — Public: list all seasons
+/// GET /api/seasons — Public: list the club's seasons
 pub async fn list_seasons(
     State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
 ) -> Result<impl IntoResponse, AppError> {
     let rows = sqlx::query_as::<_, (i64, String, chrono::NaiveDate, chrono::NaiveDate)>(
-        "SELECT id, name, start_date, end_date FROM seasons ORDER BY start_date DESC"
+        "SELECT id, name, start_date, end_date FROM seasons WHERE deleted_at IS NULL AND club_id = $1 ORDER BY start_date DESC"
     )
+    .bind(club.id)
     .fetch_all(&pool)
     .await?;
 
@@ -205,23 +286,53 @@ pub async fn list_seasons(
 
 // ─── Tournaments ────────────────────────────────────────────────────
 
-/// POST /api/tournaments — Admin creates a tournament
+const POINTS_ERROR: &str = "Points must satisfy win >= draw >= loss >= 0";
+
+/// POST /api/tournaments — Coach/Admin creates a tournament
 pub async fn create_tournament(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<TournamentCreateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to create tournaments
+    require_coach_or_admin(&claims)?;
 
     if payload.name.is_empty() {
         return Err(AppError::BadRequest("Tournament name is required".into()));
     }
 
-    sqlx::query("INSERT INTO tournaments (name, season_id) VALUES ($1, $2)")
+    if let Some(list) = &payload.tiebreakers {
+        parse_tiebreakers(list)
+            .map_err(|name| AppError::BadRequest(format!("Unknown tiebreaker '{}'", name)))?;
+    }
+    if payload.format.as_deref().is_some_and(|f| !validate_format(f)) {
+        return Err(AppError::BadRequest("Invalid tournament format".into()));
+    }
+    if !validate_points(payload.points_for_win.unwrap_or(2), payload.points_for_draw.unwrap_or(1), payload.points_for_loss.unwrap_or(0)) {
+        return Err(AppError::BadRequest(POINTS_ERROR.into()));
+    }
+
+    let mut tx = pool.begin().await?;
+    clubs::require_owned(&mut *tx, "seasons", payload.season_id, claims.club_id, "Season").await?;
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO tournaments (name, season_id, points_for_win, points_for_draw, points_for_loss, tiebreakers, format, club_id) \
+         VALUES ($1, $2, COALESCE($3, 2), COALESCE($4, 1), COALESCE($5, 0), \
+                 COALESCE($6, 'head_to_head,head_to_head_goal_difference,goal_difference,goals_for'), COALESCE($7, 'league'), $8) \
+         RETURNING id",
+    )
         .bind(&payload.name)
         .bind(payload.season_id)
-        .execute(&pool)
+        .bind(payload.points_for_win)
+        .bind(payload.points_for_draw)
+        .bind(payload.points_for_loss)
+        .bind(&payload.tiebreakers)
+        .bind(&payload.format)
+        .bind(claims.club_id)
+        .fetch_one(&mut *tx)
         .await?;
+    let after = audit::snapshot(&mut tx, "tournaments", id).await?;
+    audit::record(&mut tx, &audit, "create", "tournament", Some(id), None, after).await?;
+    tx.commit().await?;
 
     Ok(Json(ApiResponse {
         success: true,
@@ -229,22 +340,30 @@ pub async fn create_tournament(
     }))
 }
 
-/// GET /api/tournaments — Public: list all tournaments
+/// GET /api/tournaments — Public: list the club's tournaments
 pub async fn list_tournaments(
     State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
 ) -> Result<impl IntoResponse, AppError> {
-    let rows = sqlx::query_as::<_, (i64, String, i64)>(
-        "SELECT id, name, season_id FROM tournaments ORDER BY id DESC",
+    let rows = sqlx::query_as::<_, (i64, String, i64, i32, i32, i32, String, String)>(
+        "SELECT id, name, season_id, points_for_win, points_for_draw, points_for_loss, tiebreakers, format \
+         FROM tournaments WHERE deleted_at IS NULL AND club_id = $1 ORDER BY id DESC",
     )
+    .bind(club.id)
     .fetch_all(&pool)
     .await?;
 
     let tournaments: Vec<TournamentResponse> = rows
         .into_iter()
-        .map(|(id, name, season_id)| TournamentResponse {
+        .map(|(id, name, season_id, points_for_win, points_for_draw, points_for_loss, tiebreakers, format)| TournamentResponse {
             id,
             name,
             season_id,
+            points_for_win,
+            points_for_draw,
+            points_for_loss,
+            tiebreakers,
+            format,
         })
         .collect();
 
@@ -258,11 +377,17 @@ pub async fn list_users(
     State(pool): State<PgPool>,
     Extension(claims): Extension<Claims>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Debug: print claims and role
-    tracing::info!("/api/users claims: {:?}, role: {}", claims, claims.role);
-    // Allow all logged-in users to fetch the user list
+    tracing::debug!("/api/users requested by user {} ({})", claims.sub, claims.role);
+    // Guardians are in no team, so the team scope below would show them every
+    // unassigned account; they see their own players at /api/guardian/players
+    if claims.role == "guardian" {
+        return Err(AppError::Forbidden("Guardians can only see their linked players".into()));
+    }
+    // Other logged-in users may fetch the user list; outside admins it is limited
+    // to people sharing one of their teams and people in no team
+    let scope = teams::scope(&pool, &claims).await?;
 
-    let rows = sqlx::query_as::<_, (i64, String, String, String, Option<String>, Option<String>)>(
+    let rows = sqlx::query_as::<_, (i64, String, String, String, Option<String>, Option<String>)>(&format!(
         r#"
         SELECT u.id, u.name, u.email, r.name,
                COALESCE(p.first_name, c.first_name) AS first_name,
@@ -271,9 +396,13 @@ pub async fn list_users(
         JOIN roles r ON u.role_id = r.id
         LEFT JOIN players p ON p.user_id = u.id
         LEFT JOIN coaches c ON c.user_id = u.id
+        WHERE u.deleted_at IS NULL AND u.club_id = $2 AND {}
         ORDER BY u.name
-        "#
-    )
+        "#,
+        teams::user_in_scope("u.id", 1)
+    ))
+    .bind(scope.ids())
+    .bind(claims.club_id)
     .fetch_all(&pool)
     .await?;
 
@@ -288,8 +417,6 @@ pub async fn list_users(
             last_name,
         })
         .collect();
-    
-        tracing::info!("/api/users returned users: {:?}", users);
 
     Ok(Json(users))
 }
@@ -297,30 +424,36 @@ pub async fn list_users(
 /// POST /api/users/role — Admin: change a user's role
 pub async fn update_user_role(
     State(pool): State<PgPool>,
-    Extension(_claims): Extension<Claims>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
     Json(payload): Json<RoleUpdateRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Allow all logged-in users to update user roles
+    // Roles grant access to medical records (medical_officer), so only admins assign them
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
 
     // Find role id
     let role: Option<(i64,)> = sqlx::query_as("SELECT id FROM roles WHERE name = $1")
         .bind(&payload.role_name)
-        .fetch_optional(&pool)
+        .fetch_optional(&mut *tx)
         .await?;
 
     let role_id = role
         .ok_or_else(|| AppError::BadRequest(format!("Role '{}' does not exist", payload.role_name)))?
         .0;
 
-    let result = sqlx::query("UPDATE users SET role_id = $1 WHERE id = $2")
+    let before = audit::snapshot(&mut tx, "users", payload.user_id)
+        .await?
+        .filter(|row| row["deleted_at"].is_null() && row["club_id"] == claims.club_id)
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+    sqlx::query("UPDATE users SET role_id = $1 WHERE id = $2")
         .bind(role_id)
         .bind(payload.user_id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
-
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("User not found".into()));
-    }
+    let after = audit::snapshot(&mut tx, "users", payload.user_id).await?;
+    audit::record(&mut tx, &audit, "update_role", "user", Some(payload.user_id), Some(before), after).await?;
+    tx.commit().await?;
 
     Ok(Json(ApiResponse {
         success: true,
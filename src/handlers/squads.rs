@@ -0,0 +1,450 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde_json::Value;
+use sqlx::{PgConnection, PgPool};
+
+use crate::auth::{require_coach_or_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{
+    ApiResponse, AvailabilityRequest, AvailabilityResponse, SquadPlayerResponse, SquadResponse,
+    SquadUpdateRequest, SuspensionCreateRequest, SuspensionResponse,
+};
+use crate::services::audit::{self, AuditContext};
+use crate::services::guardians;
+use crate::services::injuries;
+use crate::services::notifications::notify;
+use crate::services::squad::{self, SquadEntry};
+use crate::services::teams;
+
+type SquadRow = (i64, String, String, i32, bool, bool);
+
+async fn load_squad(pool: &PgPool, match_id: i64) -> Result<Vec<SquadRow>, sqlx::Error> {
+    sqlx::query_as::<_, SquadRow>(
+        "SELECT s.player_id, p.first_name, p.last_name, s.shirt_number, s.is_goalkeeper, s.is_starting \
+         FROM match_squads s JOIN players p ON s.player_id = p.id \
+         WHERE s.match_id = $1 ORDER BY s.is_starting DESC, s.is_goalkeeper DESC, s.shirt_number",
+    )
+    .bind(match_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// The saved team sheet of a match as JSON, for the audit log.
+async fn squad_snapshot(conn: &mut PgConnection, match_id: i64) -> Result<Value, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(jsonb_agg(to_jsonb(s) - 'id' ORDER BY s.shirt_number), '[]'::jsonb) \
+         FROM match_squads s WHERE s.match_id = $1",
+    )
+    .bind(match_id)
+    .fetch_one(conn)
+    .await
+}
+
+/// Fetch the squad publication time of a match, failing if the match does not exist.
+async fn match_published_at(pool: &PgPool, match_id: i64) -> Result<Option<chrono::NaiveDateTime>, AppError> {
+    sqlx::query_scalar::<_, Option<chrono::NaiveDateTime>>("SELECT squad_published_at FROM matches WHERE id = $1 AND deleted_at IS NULL")
+        .bind(match_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Match not found".into()))
+}
+
+/// GET /api/matches/:id/squad — Coach/Admin: current sheet. Players: only once published.
+pub async fn get_squad(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    teams::require_match_access(&pool, &claims, match_id).await?;
+    let published_at = match_published_at(&pool, match_id).await?;
+    if published_at.is_none() && require_coach_or_admin(&claims).is_err() {
+        return Err(AppError::Forbidden("The squad has not been published yet".into()));
+    }
+
+    let players = load_squad(&pool, match_id)
+        .await?
+        .into_iter()
+        .map(|(player_id, first_name, last_name, shirt_number, is_goalkeeper, is_starting)| SquadPlayerResponse {
+            player_id,
+            first_name,
+            last_name,
+            shirt_number,
+            is_goalkeeper,
+            is_starting,
+        })
+        .collect();
+
+    Ok(Json(SquadResponse {
+        match_id,
+        published: published_at.is_some(),
+        published_at: published_at.map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string()),
+        players,
+    }))
+}
+
+/// PUT /api/matches/:id/squad — Coach/Admin replaces the team sheet.
+/// Saving a sheet withdraws any earlier publication.
+pub async fn update_squad(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<SquadUpdateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    teams::require_match_access(&pool, &claims, match_id).await?;
+
+    let ids: Vec<i64> = payload.players.iter().map(|p| p.player_id).collect();
+    let known: Vec<i64> = sqlx::query_scalar(
+        "SELECT p.id FROM players p JOIN users u ON p.user_id = u.id WHERE p.id = ANY($1) AND u.club_id = $2",
+    )
+        .bind(&ids)
+        .bind(claims.club_id)
+        .fetch_all(&pool)
+        .await?;
+    if let Some(unknown) = ids.iter().find(|id| !known.contains(id)) {
+        return Err(AppError::BadRequest(format!("Player {} does not exist", unknown)));
+    }
+    let outsiders = teams::players_outside_match_team(&pool, match_id, &ids).await?;
+    if !outsiders.is_empty() {
+        let list: Vec<String> = outsiders.iter().map(i64::to_string).collect();
+        return Err(AppError::BadRequest(format!("Players {} are not in this match's team", list.join(", "))));
+    }
+
+    let ineligible = squad::ineligible_players(&pool, match_id).await?;
+    squad::validate_squad(&payload.players, &ineligible, false)
+        .map_err(|errors| AppError::BadRequest(errors.join("; ")))?;
+
+    let mut tx = pool.begin().await?;
+    let before = squad_snapshot(&mut tx, match_id).await?;
+    sqlx::query("DELETE FROM match_squads WHERE match_id = $1")
+        .bind(match_id)
+        .execute(&mut *tx)
+        .await?;
+    for entry in &payload.players {
+        sqlx::query(
+            "INSERT INTO match_squads (match_id, player_id, shirt_number, is_goalkeeper, is_starting) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(match_id)
+        .bind(entry.player_id)
+        .bind(entry.shirt_number)
+        .bind(entry.is_goalkeeper)
+        .bind(entry.is_starting)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("UPDATE matches SET squad_published_at = NULL, squad_published_by = NULL WHERE id = $1")
+        .bind(match_id)
+        .execute(&mut *tx)
+        .await?;
+    let after = squad_snapshot(&mut tx, match_id).await?;
+    audit::record(&mut tx, &audit, "update_squad", "match", Some(match_id), Some(before), Some(after)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Squad saved with {} players.", payload.players.len()),
+    }))
+}
+
+/// POST /api/matches/:id/squad/publish — Coach/Admin publishes the sheet and notifies selected players
+pub async fn publish_squad(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    teams::require_match_access(&pool, &claims, match_id).await?;
+
+    let (home_team, away_team, date) = sqlx::query_as::<_, (String, String, chrono::NaiveDate)>(
+        "SELECT home_team, away_team, date FROM matches WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(match_id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Match not found".into()))?;
+
+    let rows = load_squad(&pool, match_id).await?;
+    if rows.is_empty() {
+        return Err(AppError::BadRequest("The squad is empty".into()));
+    }
+    let entries: Vec<SquadEntry> = rows
+        .iter()
+        .map(|(player_id, _, _, shirt_number, is_goalkeeper, is_starting)| SquadEntry {
+            player_id: *player_id,
+            shirt_number: *shirt_number,
+            is_goalkeeper: *is_goalkeeper,
+            is_starting: *is_starting,
+        })
+        .collect();
+    // Availability or suspensions may have changed since the sheet was saved.
+    let ineligible = squad::ineligible_players(&pool, match_id).await?;
+    squad::validate_squad(&entries, &ineligible, true)
+        .map_err(|errors| AppError::BadRequest(errors.join("; ")))?;
+
+    let mut tx = pool.begin().await?;
+    let before = audit::snapshot(&mut tx, "matches", match_id).await?;
+    sqlx::query("UPDATE matches SET squad_published_at = NOW(), squad_published_by = $1 WHERE id = $2")
+        .bind(claims.sub)
+        .bind(match_id)
+        .execute(&mut *tx)
+        .await?;
+    let selected: Vec<(i64, i32)> = sqlx::query_as(
+        "SELECT p.user_id, s.shirt_number FROM match_squads s JOIN players p ON s.player_id = p.id WHERE s.match_id = $1",
+    )
+    .bind(match_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let link = format!("/api/matches/{}/squad", match_id);
+    for (user_id, shirt_number) in &selected {
+        let body = format!(
+            "You have been selected for {} vs {} on {}, wearing number {}.",
+            home_team, away_team, date, shirt_number
+        );
+        notify(&mut *tx, *user_id, "Squad selection", &body, Some(&link)).await?;
+    }
+    let after = audit::snapshot(&mut tx, "matches", match_id).await?;
+    audit::record(&mut tx, &audit, "publish_squad", "match", Some(match_id), before, after).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Squad published to {} players.", selected.len()),
+    }))
+}
+
+/// GET /api/matches/:id/squad/export — Coach/Admin downloads the team sheet as CSV
+pub async fn export_squad(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    teams::require_match_access(&pool, &claims, match_id).await?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| AppError::Internal(format!("CSV export failed: {}", e));
+    writer
+        .write_record(["shirt_number", "first_name", "last_name", "goalkeeper", "starting"])
+        .map_err(csv_error)?;
+    for (_, first_name, last_name, shirt_number, is_goalkeeper, is_starting) in load_squad(&pool, match_id).await? {
+        writer
+            .write_record([
+                shirt_number.to_string(),
+                first_name,
+                last_name,
+                is_goalkeeper.to_string(),
+                is_starting.to_string(),
+            ])
+            .map_err(csv_error)?;
+    }
+    let body = writer
+        .into_inner()
+        .map_err(|e| AppError::Internal(format!("CSV export failed: {}", e)))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"squad-match-{}.csv\"", match_id)),
+        ],
+        body,
+    ))
+}
+
+// ─── Availability ───────────────────────────────────────────────────
+
+/// POST /api/matches/:id/availability — Players declare their own availability;
+/// guardians for their linked players; Coach/Admin for any player
+pub async fn submit_availability(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<AvailabilityRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    match_published_at(&pool, match_id).await?;
+
+    let player_id = match payload.player_id {
+        Some(player_id) if claims.role == "guardian" => {
+            if !guardians::is_guardian_of(&pool, claims.sub, player_id).await? {
+                return Err(AppError::Forbidden("You can only declare availability for your linked players".into()));
+            }
+            player_id
+        }
+        Some(player_id) => {
+            require_coach_or_admin(&claims)?;
+            teams::require_match_access(&pool, &claims, match_id).await?;
+            player_id
+        }
+        None => sqlx::query_scalar::<_, i64>("SELECT id FROM players WHERE user_id = $1")
+            .bind(claims.sub)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::Forbidden("Only players can declare their own availability".into()))?,
+    };
+    if !teams::players_outside_match_team(&pool, match_id, &[player_id]).await?.is_empty() {
+        return Err(AppError::Forbidden("The player is not in this match's team".into()));
+    }
+
+    let mut tx = pool.begin().await?;
+    let existing: Option<i64> = sqlx::query_scalar("SELECT id FROM match_availability WHERE match_id = $1 AND player_id = $2")
+        .bind(match_id)
+        .bind(player_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let before = match existing {
+        Some(id) => audit::snapshot(&mut tx, "match_availability", id).await?,
+        None => None,
+    };
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO match_availability (match_id, player_id, available, reason, submitted_by, updated_at) \
+         VALUES ($1, $2, $3, $4, $5, NOW()) \
+         ON CONFLICT (match_id, player_id) DO UPDATE SET available = EXCLUDED.available, \
+         reason = EXCLUDED.reason, submitted_by = EXCLUDED.submitted_by, updated_at = NOW() \
+         RETURNING id",
+    )
+    .bind(match_id)
+    .bind(player_id)
+    .bind(payload.available)
+    .bind(&payload.reason)
+    .bind(claims.sub)
+    .fetch_one(&mut *tx)
+    .await?;
+    let after = audit::snapshot(&mut tx, "match_availability", id).await?;
+    let action = if before.is_some() { "update" } else { "create" };
+    audit::record(&mut tx, &audit, action, "availability", Some(id), before, after).await?;
+    tx.commit().await?;
+
+    let message = match injuries::uncleared_players(&pool, match_id).await?.remove(&player_id) {
+        Some(injury) if payload.available => format!("Availability recorded. The player cannot be selected: {}.", injury),
+        _ => "Availability recorded.".to_string(),
+    };
+    Ok(Json(ApiResponse { success: true, message }))
+}
+
+/// GET /api/matches/:id/availability — Coach/Admin: declared availability for a match;
+/// players injured and not medically cleared are flagged unavailable
+pub async fn list_availability(
+    Path(match_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    teams::require_match_access(&pool, &claims, match_id).await?;
+
+    let rows = sqlx::query_as::<_, (i64, String, bool, Option<String>, chrono::NaiveDateTime)>(
+        "SELECT a.player_id, p.first_name || ' ' || p.last_name, a.available, a.reason, a.updated_at \
+         FROM match_availability a JOIN players p ON a.player_id = p.id \
+         WHERE a.match_id = $1 ORDER BY p.last_name, p.first_name",
+    )
+    .bind(match_id)
+    .fetch_all(&pool)
+    .await?;
+    let mut uncleared = injuries::uncleared_players(&pool, match_id).await?;
+
+    let availability: Vec<AvailabilityResponse> = rows
+        .into_iter()
+        .map(|(player_id, player_name, available, reason, updated_at)| {
+            let injury = uncleared.remove(&player_id);
+            AvailabilityResponse {
+                player_id,
+                player_name,
+                available: available && injury.is_none(),
+                reason,
+                injury,
+                updated_at: updated_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            }
+        })
+        .collect();
+
+    Ok(Json(availability))
+}
+
+// ─── Suspensions ────────────────────────────────────────────────────
+
+/// POST /api/suspensions — Coach/Admin records a suspension
+pub async fn create_suspension(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<SuspensionCreateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+
+    if payload.reason.is_empty() {
+        return Err(AppError::BadRequest("A reason is required".into()));
+    }
+    let starts_on = chrono::NaiveDate::parse_from_str(&payload.starts_on, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("Invalid start date format".into()))?;
+    let ends_on = match &payload.ends_on {
+        Some(d) => Some(chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| AppError::BadRequest("Invalid end date format".into()))?),
+        None => None,
+    };
+    if ends_on.is_some_and(|end| end < starts_on) {
+        return Err(AppError::BadRequest("A suspension cannot end before it starts".into()));
+    }
+    if !teams::can_see_player(&pool, &claims, payload.player_id).await? {
+        return Err(AppError::Forbidden("The player is not in one of your teams".into()));
+    }
+
+    let mut tx = pool.begin().await?;
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO player_suspensions (player_id, reason, starts_on, ends_on, created_by) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(payload.player_id)
+    .bind(&payload.reason)
+    .bind(starts_on)
+    .bind(ends_on)
+    .bind(claims.sub)
+    .fetch_one(&mut *tx)
+    .await?;
+    let after = audit::snapshot(&mut tx, "player_suspensions", id).await?;
+    audit::record(&mut tx, &audit, "create", "suspension", Some(id), None, after).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Suspension recorded.".into(),
+    }))
+}
+
+/// GET /api/suspensions — Coach/Admin: suspensions of players in their teams, most recent first
+pub async fn list_suspensions(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+    let scope = teams::scope(&pool, &claims).await?;
+
+    let rows = sqlx::query_as::<_, (i64, i64, String, String, chrono::NaiveDate, Option<chrono::NaiveDate>)>(&format!(
+        "SELECT s.id, s.player_id, p.first_name || ' ' || p.last_name, s.reason, s.starts_on, s.ends_on \
+         FROM player_suspensions s JOIN players p ON s.player_id = p.id JOIN users u ON p.user_id = u.id \
+         WHERE u.club_id = $2 AND {} ORDER BY s.starts_on DESC",
+        teams::user_in_scope("p.user_id", 1)
+    ))
+    .bind(scope.ids())
+    .bind(claims.club_id)
+    .fetch_all(&pool)
+    .await?;
+
+    let suspensions: Vec<SuspensionResponse> = rows
+        .into_iter()
+        .map(|(id, player_id, player_name, reason, starts_on, ends_on)| SuspensionResponse {
+            id,
+            player_id,
+            player_name,
+            reason,
+            starts_on: starts_on.to_string(),
+            ends_on: ends_on.map(|d| d.to_string()),
+        })
+        .collect();
+
+    Ok(Json(suspensions))
+}
@@ -0,0 +1,121 @@
+use axum::{extract::{Path, State}, response::IntoResponse, Extension, Json};
+use sqlx::PgPool;
+
+use crate::auth::{require_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{ApiResponse, TeamMemberRequest, TeamRequest};
+use crate::services::audit::AuditContext;
+use crate::services::teams;
+
+fn optional(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// GET /api/teams — Admin: every team. Others: the teams they belong to
+pub async fn list_teams(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    let scope = teams::scope(&pool, &claims).await?;
+    Ok(Json(teams::list(&pool, claims.club_id, &scope).await?))
+}
+
+/// POST /api/teams — Admin: create a team or age group
+pub async fn create_team(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<TeamRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    let id = teams::create(&mut tx, &audit, claims.club_id, &payload.name, optional(&payload.age_group), optional(&payload.gender)).await?;
+    let team = teams::get(&mut *tx, claims.club_id, id).await?;
+    tx.commit().await?;
+    Ok(Json(team))
+}
+
+/// PUT /api/teams/:id — Admin: rename a team or change its age group
+pub async fn update_team(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<TeamRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    teams::update(&mut tx, &audit, claims.club_id, id, &payload.name, optional(&payload.age_group), optional(&payload.gender)).await?;
+    let team = teams::get(&mut *tx, claims.club_id, id).await?;
+    tx.commit().await?;
+    Ok(Json(team))
+}
+
+/// DELETE /api/teams/:id — Admin: delete a team that has no matches or training
+pub async fn delete_team(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    teams::delete(&mut tx, &audit, claims.club_id, id).await?;
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Team deleted".into(),
+    }))
+}
+
+/// GET /api/teams/:id/members — Admin or team member: players and coaches in a team
+pub async fn list_members(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    if teams::get(&pool, claims.club_id, id).await?.is_none() {
+        return Err(AppError::NotFound("Team not found".into()));
+    }
+    teams::require_team_access(&pool, &claims, Some(id)).await?;
+    Ok(Json(teams::members(&pool, id).await?))
+}
+
+/// POST /api/teams/:id/members — Admin: add a player or coach to a team
+pub async fn add_member(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<TeamMemberRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    if !teams::add_member(&mut tx, &audit, claims.club_id, id, payload.user_id).await? {
+        return Err(AppError::Conflict("That user is already in the team".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Member added".into(),
+    }))
+}
+
+/// DELETE /api/teams/:id/members/:user_id — Admin: take someone out of a team
+pub async fn remove_member(
+    Path((id, user_id)): Path<(i64, i64)>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    if !teams::remove_member(&mut tx, &audit, claims.club_id, id, user_id).await? {
+        return Err(AppError::NotFound("That user is not in the team".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Member removed".into(),
+    }))
+}
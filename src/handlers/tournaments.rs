@@ -0,0 +1,161 @@
+use axum::{extract::{Path, State}, response::IntoResponse, Extension, Json};
+use sqlx::PgPool;
+
+use crate::auth::{require_coach_or_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{ApiResponse, BracketGenerateRequest, StageCreateRequest};
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs::{self, Club};
+use crate::services::{bracket, standings};
+
+/// GET /api/tournaments/:id/standings — Public: league table for a tournament
+pub async fn get_tournament_standings(
+    Path(tournament_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+) -> Result<impl IntoResponse, AppError> {
+    clubs::require_owned(&pool, "tournaments", tournament_id, club.id, "Tournament").await?;
+    let table = standings::compute_tournament_standings(&pool, tournament_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tournament not found".into()))?;
+    Ok(Json(table))
+}
+
+/// GET /api/tournaments/:id/bracket — Public: stages, group tables and knockout rounds
+pub async fn get_tournament_bracket(
+    Path(tournament_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+) -> Result<impl IntoResponse, AppError> {
+    clubs::require_owned(&pool, "tournaments", tournament_id, club.id, "Tournament").await?;
+    let view = bracket::load_bracket(&pool, tournament_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tournament not found".into()))?;
+    Ok(Json(view))
+}
+
+/// POST /api/tournaments/:id/stages — Coach/Admin adds a group or knockout stage
+pub async fn create_stage(
+    Path(tournament_id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<StageCreateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+
+    if payload.name.is_empty() {
+        return Err(AppError::BadRequest("Stage name is required".into()));
+    }
+    if !bracket::validate_stage_type(&payload.stage_type) {
+        return Err(AppError::BadRequest("Stage type must be 'group' or 'knockout'".into()));
+    }
+    let groups = payload.groups.unwrap_or_default();
+    if payload.stage_type == "knockout" && !groups.is_empty() {
+        return Err(AppError::BadRequest("Knockout stages cannot have groups".into()));
+    }
+
+    let format = sqlx::query_scalar::<_, String>("SELECT format FROM tournaments WHERE id = $1 AND club_id = $2 AND deleted_at IS NULL")
+        .bind(tournament_id)
+        .bind(claims.club_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tournament not found".into()))?;
+    if !bracket::format_allows_stage(&format, &payload.stage_type) {
+        return Err(AppError::BadRequest(format!(
+            "A '{}' tournament cannot have a {} stage",
+            format, payload.stage_type
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    let stage_id: i64 = sqlx::query_scalar(
+        "INSERT INTO tournament_stages (tournament_id, name, stage_type, position) \
+         VALUES ($1, $2, $3, COALESCE($4, (SELECT COUNT(*) + 1 FROM tournament_stages WHERE tournament_id = $1)::INT)) \
+         RETURNING id",
+    )
+    .bind(tournament_id)
+    .bind(&payload.name)
+    .bind(&payload.stage_type)
+    .bind(payload.position)
+    .fetch_one(&mut *tx)
+    .await?;
+    for group in &groups {
+        sqlx::query("INSERT INTO tournament_groups (stage_id, name) VALUES ($1, $2)")
+            .bind(stage_id)
+            .bind(group)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let after = audit::snapshot(&mut tx, "tournament_stages", stage_id).await?;
+    audit::record(&mut tx, &audit, "create", "tournament_stage", Some(stage_id), None, after).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Stage created with {} group(s).", groups.len()),
+    }))
+}
+
+/// POST /api/tournaments/:id/stages/:stage_id/bracket — Coach/Admin draws a knockout bracket
+pub async fn generate_bracket(
+    Path((tournament_id, stage_id)): Path<(i64, i64)>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<BracketGenerateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_coach_or_admin(&claims)?;
+
+    let date = chrono::NaiveDate::parse_from_str(&payload.match_date, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("Invalid date format. Use YYYY-MM-DD.".into()))?;
+
+    let stage_type = sqlx::query_scalar::<_, String>(
+        "SELECT s.stage_type FROM tournament_stages s JOIN tournaments t ON s.tournament_id = t.id \
+         WHERE s.id = $1 AND s.tournament_id = $2 AND t.club_id = $3",
+    )
+    .bind(stage_id)
+    .bind(tournament_id)
+    .bind(claims.club_id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Stage not found".into()))?;
+    if stage_type != "knockout" {
+        return Err(AppError::BadRequest("Brackets can only be drawn for knockout stages".into()));
+    }
+
+    let teams = match (payload.teams, payload.qualifiers_per_group) {
+        (Some(teams), None) => teams,
+        (None, Some(per_group)) => bracket::group_qualifiers(&pool, tournament_id, per_group).await?,
+        _ => return Err(AppError::BadRequest("Provide either teams or qualifiers_per_group".into())),
+    };
+    if teams.len() < 2 {
+        return Err(AppError::BadRequest("A bracket needs at least two teams".into()));
+    }
+    if teams.iter().any(|t| t.is_empty() || t == bracket::TBD) {
+        return Err(AppError::BadRequest("Team names must not be empty or 'TBD'".into()));
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(team) = teams.iter().find(|t| !seen.insert(t.as_str())) {
+        return Err(AppError::BadRequest(format!("Team '{}' is listed more than once", team)));
+    }
+
+    let existing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM matches WHERE stage_id = $1 AND deleted_at IS NULL")
+        .bind(stage_id)
+        .fetch_one(&pool)
+        .await?;
+    if existing > 0 {
+        return Err(AppError::Conflict("This stage already has matches".into()));
+    }
+
+    let mut tx = pool.begin().await?;
+    let created = bracket::generate_knockout(&mut tx, tournament_id, stage_id, &teams, date, payload.location.as_deref()).await?;
+    let after = serde_json::json!({ "tournament_id": tournament_id, "teams": teams, "matches_created": created });
+    audit::record(&mut tx, &audit, "generate_bracket", "tournament_stage", Some(stage_id), None, Some(after)).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Bracket created with {} matches for {} teams.", created, teams.len()),
+    }))
+}
@@ -0,0 +1,243 @@
+use axum::{extract::{Path, State}, response::IntoResponse, Extension, Json};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::auth::{create_token, decode_challenge_token, require_admin, Claims};
+use crate::errors::AppError;
+use crate::models::{
+    ApiResponse, AuthResponse, LoginChallengeRequest, LoginChallengeVerifyRequest, RecoveryCodesResponse, SecurityPolicy,
+    TwoFactorCodeRequest, TwoFactorDisableRequest, TwoFactorSetupResponse,
+};
+use crate::services::audit::AuditContext;
+use crate::services::clubs::{self, Club};
+use crate::services::rate_limit::{self, Limit, RateLimiter};
+use crate::services::two_factor::{self, TwoFactorState};
+use crate::services::{password, totp};
+
+/// The user's 2FA state; 404 when the account is gone.
+async fn load_state(conn: &mut sqlx::PgConnection, user_id: i64) -> Result<TwoFactorState, AppError> {
+    two_factor::state(conn, user_id).await?.ok_or_else(|| AppError::NotFound("User not found".into()))
+}
+
+/// Start enrolment for `user_id`: a fresh secret and its otpauth URI.
+async fn start_enrolment(pool: &PgPool, audit: &AuditContext, user_id: i64) -> Result<TwoFactorSetupResponse, AppError> {
+    let mut tx = pool.begin().await?;
+    if load_state(&mut tx, user_id).await?.enabled {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".into()));
+    }
+    let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let secret = two_factor::begin_enrolment(&mut tx, audit, user_id).await?;
+    tx.commit().await?;
+    Ok(TwoFactorSetupResponse { otpauth_uri: totp::otpauth_uri(&email, &secret), secret })
+}
+
+/// POST /api/2fa/setup — Any user: start TOTP enrolment (secret and otpauth URI)
+pub async fn setup(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(start_enrolment(&pool, &audit, claims.sub).await?))
+}
+
+/// POST /api/2fa/enable — Any user: confirm enrolment with a code; returns recovery codes
+pub async fn enable(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
+    let state = load_state(&mut tx, claims.sub).await?;
+    if state.enabled {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".into()));
+    }
+    if state.secret.is_none() {
+        return Err(AppError::BadRequest("Start enrolment with /api/2fa/setup first".into()));
+    }
+    if !two_factor::check_code(&mut tx, claims.sub, &state, &payload.code).await? {
+        return Err(AppError::Unauthorized("Invalid two-factor code".into()));
+    }
+    let recovery_codes = two_factor::enable(&mut tx, &audit, claims.sub).await?;
+    tx.commit().await?;
+    Ok(Json(RecoveryCodesResponse {
+        success: true,
+        message: "Two-factor authentication enabled. Store the recovery codes somewhere safe.".into(),
+        recovery_codes,
+    }))
+}
+
+/// POST /api/2fa/disable — Any user: turn 2FA off (password and code required)
+pub async fn disable(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<TwoFactorDisableRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
+    if two_factor::required_roles(&mut *tx, claims.club_id).await?.contains(&claims.role) {
+        return Err(AppError::Forbidden("Two-factor authentication is required for your role".into()));
+    }
+    let state = load_state(&mut tx, claims.sub).await?;
+    if !state.enabled {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled".into()));
+    }
+    let stored_hash: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+        .bind(claims.sub)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !password::verify(&payload.password, &stored_hash)? || !two_factor::check_code(&mut tx, claims.sub, &state, &payload.code).await? {
+        return Err(AppError::Unauthorized("Invalid password or two-factor code".into()));
+    }
+    two_factor::disable(&mut tx, &audit, claims.sub, "disable_2fa").await?;
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: "Two-factor authentication disabled".into(),
+    }))
+}
+
+/// POST /api/2fa/recovery-codes — Any user: replace the recovery codes (TOTP code required)
+pub async fn regenerate_recovery_codes(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = pool.begin().await?;
+    let state = load_state(&mut tx, claims.sub).await?;
+    if !state.enabled {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled".into()));
+    }
+    let is_totp = payload.code.trim().len() == 6;
+    if !is_totp || !two_factor::check_code(&mut tx, claims.sub, &state, &payload.code).await? {
+        return Err(AppError::Unauthorized("Invalid two-factor code".into()));
+    }
+    let recovery_codes = two_factor::regenerate_recovery_codes(&mut tx, &audit, claims.sub).await?;
+    tx.commit().await?;
+    Ok(Json(RecoveryCodesResponse {
+        success: true,
+        message: "New recovery codes issued; the old ones no longer work.".into(),
+        recovery_codes,
+    }))
+}
+
+/// POST /api/login/2fa/setup — Login step 2 when the policy requires 2FA: start enrolment
+pub async fn login_setup(
+    State(pool): State<PgPool>,
+    audit: AuditContext,
+    Json(payload): Json<LoginChallengeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = decode_challenge_token(&payload.challenge)?;
+    Ok(Json(start_enrolment(&pool, &audit.with_actor(user_id), user_id).await?))
+}
+
+/// POST /api/login/2fa — Login step 2: exchange the challenge and a code
+/// (TOTP or recovery code) for a JWT. Completes enrolment when one is pending.
+pub async fn login_verify(
+    State(pool): State<PgPool>,
+    Extension(club): Extension<Club>,
+    Extension(limiter): Extension<Arc<RateLimiter>>,
+    audit: AuditContext,
+    Json(payload): Json<LoginChallengeVerifyRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = decode_challenge_token(&payload.challenge)?;
+    let limits = limiter.config();
+    limiter
+        .check(&format!("2fa:{}", user_id), Limit { burst: limits.account_burst, per_minute: limits.account_per_minute })
+        .await?;
+    if let Some(secs) = rate_limit::lockout_remaining(&pool, user_id).await? {
+        return Err(AppError::TooManyRequests("Account temporarily locked after repeated failed logins.".into(), secs));
+    }
+
+    let mut tx = pool.begin().await?;
+    let state = load_state(&mut tx, user_id).await?;
+    if state.secret.is_none() {
+        return Err(AppError::BadRequest("Set up two-factor authentication first".into()));
+    }
+    if !two_factor::check_code(&mut tx, user_id, &state, &payload.code).await? {
+        drop(tx);
+        rate_limit::record_failed_login(&pool, limits, user_id).await?;
+        return Err(AppError::Unauthorized("Invalid two-factor code".into()));
+    }
+    let recovery_codes = if state.enabled {
+        None
+    } else {
+        Some(two_factor::enable(&mut tx, &audit.with_actor(user_id), user_id).await?)
+    };
+    let (email, name, role): (String, String, String) = sqlx::query_as(
+        "SELECT u.email, u.name, r.name FROM users u JOIN roles r ON u.role_id = r.id WHERE u.id = $1 AND u.club_id = $2",
+    )
+    .bind(user_id)
+    .bind(club.id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Unauthorized("Login challenge is invalid or has expired. Sign in again.".into()))?;
+    tx.commit().await?;
+    rate_limit::clear_failed_logins(&pool, user_id).await?;
+
+    let token = create_token(user_id, &email, &role, &name, club.id)?;
+    Ok(Json(AuthResponse {
+        success: true,
+        message: "Login successful".into(),
+        token: Some(token),
+        role: Some(role),
+        user_id: Some(user_id),
+        name: Some(name),
+        two_factor: None,
+        challenge: None,
+        recovery_codes,
+    }))
+}
+
+/// GET /api/admin/security-policy — Admin: roles that must use 2FA
+pub async fn get_security_policy(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    Ok(Json(SecurityPolicy { require_2fa_roles: two_factor::required_roles(&pool, claims.club_id).await? }))
+}
+
+/// PUT /api/admin/security-policy — Admin: set the roles that must use 2FA
+pub async fn update_security_policy(
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+    Json(payload): Json<SecurityPolicy>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut roles = payload.require_2fa_roles;
+    if let Some(role) = roles.iter().find(|r| !two_factor::PRIVILEGED_ROLES.contains(&r.as_str())) {
+        return Err(AppError::BadRequest(format!("2FA can only be required for 'admin' and 'coach', not '{}'", role)));
+    }
+    roles.sort();
+    roles.dedup();
+    let mut tx = pool.begin().await?;
+    two_factor::set_required_roles(&mut tx, &audit, claims.club_id, &roles).await?;
+    tx.commit().await?;
+    Ok(Json(SecurityPolicy { require_2fa_roles: roles }))
+}
+
+/// POST /api/admin/users/:id/2fa/reset — Admin: remove 2FA from an account (lost device)
+pub async fn reset_two_factor(
+    Path(id): Path<i64>,
+    State(pool): State<PgPool>,
+    Extension(claims): Extension<Claims>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&claims)?;
+    let mut tx = pool.begin().await?;
+    clubs::require_owned(&mut *tx, "users", id, claims.club_id, "User").await?;
+    if !two_factor::disable(&mut tx, &audit, id, "reset_2fa").await? {
+        return Err(AppError::NotFound("User not found".into()));
+    }
+    tx.commit().await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Two-factor authentication reset for user {}.", id),
+    }))
+}
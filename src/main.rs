@@ -1,15 +1,23 @@
-mod auth;
-mod db;
-mod errors;
-mod handlers;
-mod models;
+use handball_team_app::{config, db, routes, telemetry};
+use handball_team_app::services::antidoping;
+use handball_team_app::services::backup;
+use handball_team_app::services::clubs;
+use handball_team_app::services::fees;
+use handball_team_app::services::audit::AuditContext;
+use handball_team_app::services::import::{self, ImportFormat, ImportKind};
+use handball_team_app::services::jobs::Scheduler;
+use handball_team_app::services::payments::Payments;
+use handball_team_app::services::rate_limit;
+use handball_team_app::services::trash;
 
-use axum::{middleware, routing::{get, post}, Router};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
 use std::env;
+use std::future::IntoFuture;
 use std::net::SocketAddr;
 use std::str::FromStr;
-use tower_http::services::ServeDir;
+use std::time::Duration;
+use tokio::sync::watch;
 
 #[tokio::main]
 async fn main() {
@@ -18,18 +26,38 @@ async fn main() {
     if let Err(e) = dotenvy::dotenv() {
         println!("Warning: failed to load .env file: {:?}", e);
     }
-    // Debug: print DATABASE_URL after loading .env
-    match std::env::var("DATABASE_URL") {
-        Ok(val) => println!("DEBUG: DATABASE_URL loaded: {}", val),
-        Err(e) => println!("DEBUG: DATABASE_URL not found: {:?}", e),
+    // Defaults, then config.toml (or CONFIG_FILE), then environment variables
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    telemetry::init_tracing(&config.logging);
+    telemetry::prometheus();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("config") => std::process::exit(run_config_cli(&config, &args[1..])),
+        // Restore targets a separate, empty database (Postgres or SQLite), so it runs before connecting.
+        Some("restore") => std::process::exit(run_restore_cli(&config, &args[1..]).await),
+        _ => {}
     }
-    tracing_subscriber::fmt::init();
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set in .env");
-    let connect_options = PgConnectOptions::from_str(&database_url)
-        .expect("Invalid DATABASE_URL");
+    if let Err(errors) = config.validate() {
+        for error in errors {
+            eprintln!("Configuration error: {}", error);
+        }
+        std::process::exit(1);
+    }
+    tracing::info!("Configuration: {:?}", config.redacted());
+    config::init(config.clone());
+
+    let connect_options = PgConnectOptions::from_str(&config.database.url)
+        .expect("Invalid database URL");
     let pool = PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(config.database.max_connections)
         .connect_with(connect_options)
         .await
         .expect("Failed to connect to PostgreSQL");
@@ -38,96 +66,251 @@ async fn main() {
     db::run_migrations(&pool).await;
     db::seed_roles(&pool).await;
 
-    // ── Public API routes (no auth required) ────────────────────────
-    let public_api = Router::new()
-        .route("/api/announcements", get(handlers::announcements::list_announcements))
-        .route("/api/matches", get(handlers::matches::list_matches))
-        .route("/api/seasons", get(handlers::seasons::list_seasons))
-        .route("/api/tournaments", get(handlers::seasons::list_tournaments));
-
-    // ── Auth routes (no auth required) ──────────────────────────────
-    let auth_routes = Router::new()
-        .route("/api/register", post(auth::register_handler))
-        .route("/api/login", post(auth::login_handler));
-
-    // ── Protected API routes (JWT auth required) ────────────────────
-    let protected_api = Router::new()
-        // Announcements
-        .route("/api/announcements", post(handlers::announcements::create_announcement))
-        .route("/api/announcements/approve", post(handlers::announcements::approve_announcement))
-        .route("/api/announcements/reject", post(handlers::announcements::reject_announcement))
-        .route("/api/announcements/pending", get(handlers::announcements::list_pending_announcements))
-        // Matches
-        .route("/api/matches", post(handlers::matches::create_match))
-        .route("/api/matches/update", post(handlers::matches::update_match))
-        .route("/api/matches/:id", axum::routing::delete(handlers::matches::delete_match))
-        .route("/api/matches/:match_id/events", post(handlers::matches::create_match_event))
-        .route("/api/matches/:id/statistics", get(handlers::matches::get_match_statistics))
-        // Attendance
-        .route("/api/attendance", post(handlers::attendance::mark_attendance))
-        .route("/api/attendance/bulk", post(handlers::attendance::mark_attendance_bulk))
-        .route("/api/attendance/list", get(handlers::attendance::list_attendance))
-        // Seasons & Tournaments
-        .route("/api/seasons", post(handlers::seasons::create_season))
-        .route("/api/seasons/:id", axum::routing::delete(handlers::seasons::delete_season))
-        .route("/api/seasons/:id", axum::routing::patch(handlers::seasons::update_season))
-        .route("/api/tournaments", post(handlers::seasons::create_tournament))
-        .route("/api/tournaments/:id", axum::routing::delete(handlers::seasons::delete_tournament))
-        .route("/api/tournaments/:id", axum::routing::patch(handlers::seasons::update_tournament))
-        // User management
-        .route("/api/users", get(handlers::seasons::list_users))
-        .route("/api/users/role", post(handlers::seasons::update_user_role))
-        .route("/api/users/:id", axum::routing::delete(handlers::seasons::delete_user))
-        .route("/api/users/:id", axum::routing::patch(handlers::seasons::update_user))
-        // Admin protected route
-        .route("/api/admin/protected", get(handlers::admin::protected_admin_route))
-        // Apply auth middleware to all protected routes
-        .layer(middleware::from_fn(auth::auth_middleware));
-
-    // ── Combine all routes ──────────────────────────────────────────
-    use axum::{response::IntoResponse, http::StatusCode};
-    use tokio::fs;
-    use axum::extract::Path;
-
-    async fn serve_index() -> impl IntoResponse {
-        match fs::read("static/index.html").await {
-            Ok(contents) => ([("content-type", "text/html")], contents).into_response(),
-            Err(_) => (StatusCode::NOT_FOUND, "index.html not found").into_response(),
-        }
+    // CLI modes: `import <players|fixtures|results> <file> [--dry-run] [--club <slug>]`,
+    // `backup <file.zip>` and `club create <slug> <name> <admin-email>`
+    match args.first().map(String::as_str) {
+        Some("import") => std::process::exit(run_import_cli(&pool, &args[1..]).await),
+        Some("club") => std::process::exit(run_club_cli(&pool, &args[1..]).await),
+        Some("backup") => std::process::exit(run_backup_cli(&pool, &args[1..]).await),
+        _ => {}
     }
 
-    async fn serve_html(Path(filename): Path<String>) -> impl IntoResponse {
-        let path = format!("static/{}", filename);
-        if !filename.ends_with(".html") {
-            return (StatusCode::NOT_FOUND, "Not found").into_response();
-        }
-        match fs::read(&path).await {
-            Ok(contents) => ([("content-type", "text/html")], contents).into_response(),
-            Err(_) => (StatusCode::NOT_FOUND, "HTML file not found").into_response(),
-        }
-    }
+    // Background jobs: register every recurring task before starting the runner
+    let mut scheduler = Scheduler::new(pool.clone(), Duration::from_secs(config.jobs.poll_interval_secs));
+    // Hard-delete trashed rows once they are past the retention window
+    trash::register_purge_job(&mut scheduler, config.trash.retention_days);
+    rate_limit::register_prune_job(&mut scheduler, &config.rate_limit);
+    antidoping::register_reminder_job(&mut scheduler, config.antidoping.tue_reminder_days);
+    // Online payments are confirmed by polling the provider
+    let payments = Payments::from_config(&config.fees);
+    fees::register_sync_job(&mut scheduler, &payments);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let scheduler = scheduler.spawn(shutdown_rx.clone());
+
+    let app = routes::app(pool.clone(), payments);
 
-    let app = Router::new()
-        .route("/", get(serve_index))
-        .route("/:filename", get(serve_html))
-        .merge(public_api)
-        .merge(auth_routes)
-        .merge(protected_api)
-        .nest_service("/static", ServeDir::new("static"))
-        .with_state(pool);
-
-    let port: u16 = std::env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(3000);
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let host: std::net::IpAddr = config.server.host.parse().expect("Invalid server host");
+    let addr = SocketAddr::from((host, config.server.port));
     tracing::info!("🏐 Tornadoes Team Management running at http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server error");
+    // On SIGTERM/Ctrl-C: stop accepting connections, let in-flight requests and
+    // the running job finish (bounded by the shutdown timeout), then close the pool.
+    let grace = Duration::from_secs(config.server.shutdown_timeout_secs);
+    let mut draining = shutdown_rx.clone();
+    // Connect info gives the rate limiter the peer address
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        tracing::info!("Shutdown signal received, draining in-flight requests");
+        let _ = shutdown_tx.send(true);
+    });
+    tokio::select! {
+        result = server.into_future() => result.expect("Server error"),
+        _ = async {
+            let _ = draining.changed().await;
+            tokio::time::sleep(grace).await;
+        } => tracing::warn!("Requests still running after {}s, shutting down anyway", grace.as_secs()),
+    }
+    if tokio::time::timeout(grace, scheduler).await.is_err() {
+        tracing::warn!("Background job still running after {}s, shutting down anyway", grace.as_secs());
+    }
+    pool.close().await;
+    tracing::info!("Shutdown complete");
+}
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM (what container runtimes send).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("Failed to listen for Ctrl-C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Import a CSV or XLSX file from the command line. Returns the process exit code.
+async fn run_import_cli(pool: &PgPool, args: &[String]) -> i32 {
+    let usage = "Usage: handball_team_app import <players|fixtures|results> <file.csv|file.xlsx> [--dry-run] [--club <slug>]";
+    let mut dry_run = false;
+    let mut slug = Some(config::get().tenancy.default_club.clone());
+    let mut positional = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--club" => slug = rest.next().cloned(),
+            _ if arg.starts_with("--") => {}
+            _ => positional.push(arg),
+        }
+    }
+    let (Some(kind), Some(path), Some(slug)) = (positional.first().and_then(|k| ImportKind::parse(k)), positional.get(1), slug) else {
+        eprintln!("{}", usage);
+        return 2;
+    };
+    let club = match clubs::by_slug(pool, &slug).await {
+        Ok(Some(club)) => club,
+        Ok(None) => {
+            eprintln!("Unknown club '{}'", slug);
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("Import failed: {}", e);
+            return 1;
+        }
+    };
+    let format = std::path::Path::new(path.as_str())
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(ImportFormat::parse)
+        .unwrap_or(ImportFormat::Csv);
+    let bytes = match std::fs::read(path.as_str()) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path, e);
+            return 1;
+        }
+    };
+
+    let report = match import::read_rows(&bytes, format) {
+        Ok(rows) => match import::run_import(pool, &AuditContext::default().with_club(club.id), club.id, kind, rows, dry_run).await {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Import failed: {}", e);
+                return 1;
+            }
+        },
+        Err(errors) => import::report(kind, dry_run, 0, 0, errors),
+    };
+    for error in &report.errors {
+        eprintln!("line {}: {}", error.line, error.message);
+    }
+    println!("{}", report.message);
+    if report.success { 0 } else { 1 }
+}
+
+/// Add a club with its first admin, whose temporary password is printed once.
+/// Returns the process exit code.
+async fn run_club_cli(pool: &PgPool, args: &[String]) -> i32 {
+    let usage = "Usage: handball_team_app club create <slug> <name> <admin-email>";
+    let [command, slug, name, email] = args else {
+        eprintln!("{}", usage);
+        return 2;
+    };
+    if command != "create" {
+        eprintln!("{}", usage);
+        return 2;
+    }
+    let ctx = AuditContext::default();
+    let created = async {
+        let mut tx = pool.begin().await?;
+        let id = clubs::create(&mut tx, &ctx, slug, name).await?;
+        let admin = clubs::add_admin(&mut tx, &ctx, id, email, &format!("{} Admin", name.trim())).await?;
+        tx.commit().await?;
+        Ok::<_, handball_team_app::errors::AppError>(admin)
+    };
+    match created.await {
+        Ok((_, temporary_password)) => {
+            println!("Club '{}' created. Sign in as {} with the temporary password: {}", slug, email, temporary_password);
+            println!("Change it after the first login.");
+            0
+        }
+        Err(e) => {
+            eprintln!("Could not create the club: {}", e);
+            1
+        }
+    }
+}
+
+/// Write a full backup archive to a file. Returns the process exit code.
+async fn run_backup_cli(pool: &PgPool, args: &[String]) -> i32 {
+    let Some(path) = args.first() else {
+        eprintln!("Usage: handball_team_app backup <file.zip>");
+        return 2;
+    };
+    let archive = match backup::create_backup(pool).await {
+        Ok(archive) => archive,
+        Err(e) => {
+            eprintln!("Backup failed: {}", e);
+            return 1;
+        }
+    };
+    if let Err(e) = std::fs::write(path, &archive) {
+        eprintln!("Could not write {}: {}", path, e);
+        return 1;
+    }
+    println!("Backup written to {} ({} bytes).", path, archive.len());
+    0
+}
+
+/// Print the effective configuration with secrets masked and validate it.
+/// Returns the process exit code.
+fn run_config_cli(config: &config::Config, args: &[String]) -> i32 {
+    if args.first().map(String::as_str) != Some("check") {
+        eprintln!("Usage: handball_team_app config check");
+        return 2;
+    }
+    print!("{}", config.to_toml());
+    match config.validate() {
+        Ok(()) => {
+            println!("# Configuration is valid.");
+            0
+        }
+        Err(errors) => {
+            for error in errors {
+                eprintln!("Configuration error: {}", error);
+            }
+            1
+        }
+    }
+}
+
+/// Restore a backup archive into an empty database. The target defaults to
+/// the configured database URL and can be overridden with `--database-url <url>`.
+async fn run_restore_cli(config: &config::Config, args: &[String]) -> i32 {
+    let usage = "Usage: handball_team_app restore <file.zip> [--database-url <postgres://…|sqlite:…>]";
+    let mut path = None;
+    let mut database_url = Some(config.database.url.clone()).filter(|url| !url.is_empty());
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--database-url" {
+            database_url = rest.next().cloned();
+        } else if path.is_none() {
+            path = Some(arg.clone());
+        }
+    }
+    let (Some(path), Some(database_url)) = (path, database_url) else {
+        eprintln!("{}", usage);
+        return 2;
+    };
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path, e);
+            return 1;
+        }
+    };
+    match backup::restore(&database_url, &bytes).await {
+        Ok(summary) => {
+            println!("Restored {} row(s) into {} table(s).", summary.rows, summary.tables);
+            0
+        }
+        Err(e) => {
+            eprintln!("Restore failed: {}", e);
+            1
+        }
+    }
 }
@@ -25,6 +25,7 @@ pub struct MatchEventResponse {
 pub fn validate_event_type(event_type: &str) -> bool {
     matches!(event_type,
         "goal" | "shot_missed" | "save" | "block" | "steal" | "foul" | "suspension" | "turnover"
+            | "yellow_card" | "red_card" | "blue_card"
     )
 }
 
@@ -48,6 +49,11 @@ pub struct SeasonUpdateRequest {
 pub struct TournamentUpdateRequest {
     pub name: String,
     pub season_id: i64,
+    pub points_for_win: Option<i32>,
+    pub points_for_draw: Option<i32>,
+    pub points_for_loss: Option<i32>,
+    pub tiebreakers: Option<String>,
+    pub format: Option<String>,
 }
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDate;
@@ -68,8 +74,9 @@ pub struct RegisterRequest {
     pub password: String,
     pub first_name: String,
     pub last_name: String,
-    pub role: String, // "coach" or "player"
+    pub role: String, // "coach", "player" or "guardian"
     pub player_details: Option<PlayerRegisterDetails>, // Only for player
+    pub invite_code: Option<String>, // From an admin invite link
 }
 
 #[derive(Deserialize)]
@@ -85,6 +92,73 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+// ─── Two-Factor Authentication ──────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String, // 6-digit TOTP code (or a recovery code where accepted)
+}
+
+#[derive(Deserialize)]
+pub struct TwoFactorDisableRequest {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoginChallengeRequest {
+    pub challenge: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoginChallengeVerifyRequest {
+    pub challenge: String,
+    pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct TwoFactorSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String, // encode as a QR code for authenticator apps
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodesResponse {
+    pub success: bool,
+    pub message: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SecurityPolicy {
+    pub require_2fa_roles: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RegistrationModeSetting {
+    pub mode: crate::services::registration::RegistrationMode,
+}
+
+#[derive(Deserialize)]
+pub struct InviteCreateRequest {
+    pub role: String, // "coach", "player" or "guardian"
+    pub email: Option<String>, // Restrict the invite to one address
+    pub expires_in_days: Option<i64>, // Default 7
+}
+
+#[derive(Serialize)]
+pub struct InviteCreatedResponse {
+    pub invite: crate::services::registration::Invite,
+    pub code: String, // Shown once; only a hash is stored
+    pub link: String,
+}
+
 #[derive(Serialize)]
 pub struct AuthResponse {
     pub success: bool,
@@ -93,6 +167,15 @@ pub struct AuthResponse {
     pub role: Option<String>,
     pub user_id: Option<i64>,
     pub name: Option<String>,
+    /// Set when a second step is needed: "verify" (enter a code) or "setup" (enrol first).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub two_factor: Option<String>,
+    /// Short-lived token for the second login step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<String>,
+    /// Shown once, when 2FA was enabled during this login.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
 }
 
 // ─── Announcements ──────────────────────────────────────────────────
@@ -132,6 +215,11 @@ pub struct MatchCreateRequest {
     pub tournament_id: Option<i64>,
     pub home_score: Option<i32>,
     pub away_score: Option<i32>,
+    pub stage_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub round: Option<i32>,
+    pub bracket_position: Option<i32>,
+    pub team_id: Option<i64>, // Defaults to the creator's only team
 }
 
 #[derive(Deserialize)]
@@ -139,6 +227,10 @@ pub struct MatchUpdateRequest {
     pub id: i64,
     pub home_score: Option<i32>,
     pub away_score: Option<i32>,
+    pub home_shootout: Option<i32>, // 7-metre shootout, knockout draws only; omitted keeps the recorded one
+    pub away_shootout: Option<i32>,
+    #[serde(default)]
+    pub clear_shootout: bool, // Removes a recorded shootout
 }
 
 #[derive(Serialize)]
@@ -151,6 +243,336 @@ pub struct MatchResponse {
     pub tournament_id: Option<i64>,
     pub home_score: Option<i32>,
     pub away_score: Option<i32>,
+    pub home_shootout: Option<i32>,
+    pub away_shootout: Option<i32>,
+    pub stage_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub round: Option<i32>,
+    pub bracket_position: Option<i32>,
+    pub team_id: Option<i64>,
+}
+
+// ─── Match Squads ───────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct SquadUpdateRequest {
+    pub players: Vec<crate::services::squad::SquadEntry>,
+}
+
+#[derive(Serialize)]
+pub struct SquadPlayerResponse {
+    pub player_id: i64,
+    pub first_name: String,
+    pub last_name: String,
+    pub shirt_number: i32,
+    pub is_goalkeeper: bool,
+    pub is_starting: bool,
+}
+
+#[derive(Serialize)]
+pub struct SquadResponse {
+    pub match_id: i64,
+    pub published: bool,
+    pub published_at: Option<String>,
+    pub players: Vec<SquadPlayerResponse>,
+}
+
+#[derive(Deserialize)]
+pub struct AvailabilityRequest {
+    pub player_id: Option<i64>, // Coaches may submit for a player; players submit for themselves
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AvailabilityResponse {
+    pub player_id: i64,
+    pub player_name: String,
+    pub available: bool,
+    pub reason: Option<String>,
+    pub injury: Option<String>, // Set while the player is not medically cleared
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct SuspensionCreateRequest {
+    pub player_id: i64,
+    pub reason: String,
+    pub starts_on: String,
+    pub ends_on: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SuspensionResponse {
+    pub id: i64,
+    pub player_id: i64,
+    pub player_name: String,
+    pub reason: String,
+    pub starts_on: String,
+    pub ends_on: Option<String>,
+}
+
+// ─── Discipline ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct DisciplinaryRulesRequest {
+    pub rules: Vec<crate::services::disciplinary::Rule>, // Empty restores the defaults
+}
+
+#[derive(Serialize)]
+pub struct DisciplinaryReport {
+    pub rules: Vec<crate::services::disciplinary::Rule>,
+    pub players: Vec<crate::services::disciplinary::Tally>,
+    pub bans: Vec<crate::services::disciplinary::Ban>,
+}
+
+#[derive(Serialize)]
+pub struct PlayerDisciplinaryRecord {
+    pub tournaments: Vec<crate::services::disciplinary::Tally>,
+    pub bans: Vec<crate::services::disciplinary::Ban>,
+}
+
+// ─── Injuries ───────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct InjuryRequest {
+    pub player_id: i64, // Ignored on update
+    pub injury_type: String,
+    pub body_part: String,
+    pub injured_on: String,
+    pub expected_return: Option<String>,
+    pub notes: Option<String>, // Medical; hidden from coaches
+}
+
+#[derive(Deserialize)]
+pub struct InjuryClearRequest {
+    pub cleared_on: Option<String>, // Defaults to today
+}
+
+#[derive(Deserialize)]
+pub struct InjuryQuery {
+    pub include_cleared: Option<bool>,
+}
+
+// ─── Anti-doping ────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct DeclarationRequest {
+    pub kind: String, // "medication" or "supplement"
+    pub name: String,
+    pub dosage: Option<String>,
+    pub started_on: String,
+    pub ended_on: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DeclarationReviewRequest {
+    pub status: String, // "reviewed" or "flagged"
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DeclarationQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TueRequest {
+    pub substance: String,
+    pub reference: Option<String>, // Certificate number when not uploaded
+    pub granted_on: Option<String>,
+    pub expires_on: String,
+    pub document: Option<TueDocumentUpload>,
+}
+
+#[derive(Deserialize)]
+pub struct TueDocumentUpload {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: String, // base64
+}
+
+#[derive(Deserialize)]
+pub struct TueQuery {
+    pub within_days: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct AntidopingPlayer {
+    pub player_id: i64,
+    pub player_name: String,
+}
+
+// ─── Fitness ────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct FitnessTestRequest {
+    pub name: String,
+    pub unit: String,
+    pub higher_is_better: Option<bool>, // Defaults to true; false for timed tests
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct FitnessResultsRequest {
+    pub test_type_id: i64,
+    pub tested_on: String,
+    pub results: Vec<FitnessResultEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct FitnessResultEntry {
+    pub player_id: i64,
+    pub value: f64,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct FitnessPercentileQuery {
+    pub team_id: Option<i64>,
+    pub date: Option<String>, // Defaults to today
+}
+
+// ─── Membership fees ────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct FeePlanRequest {
+    pub season_id: i64,
+    pub category: String, // e.g. "senior", "youth", "family"
+    pub name: String,
+    pub amount_cents: i64,
+    pub currency: Option<String>, // Defaults to fees.currency
+    pub due_date: String,
+}
+
+#[derive(Deserialize)]
+pub struct FeePlanQuery {
+    pub season_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct InvoiceIssueRequest {
+    pub player_ids: Option<Vec<i64>>,
+    pub team_id: Option<i64>, // Every player of the team
+    pub bill_guardians: Option<bool>, // Defaults to true
+}
+
+#[derive(Deserialize)]
+pub struct InvoiceQuery {
+    pub status: Option<String>,
+    pub season_id: Option<i64>,
+    pub player_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct InvoiceDetail {
+    pub invoice: crate::services::fees::Invoice,
+    pub payments: Vec<crate::services::fees::Payment>,
+}
+
+#[derive(Deserialize)]
+pub struct PaymentRecordRequest {
+    pub amount_cents: i64,
+    pub paid_on: Option<String>, // Defaults to today
+    pub method: String,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CheckoutRequest {
+    pub amount_cents: Option<i64>, // Defaults to the balance
+}
+
+// ─── Clubs ──────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ClubBrandingRequest {
+    pub name: String,
+    pub logo_url: Option<String>, // A path on this site or an https:// URL
+    pub primary_color: Option<String>, // e.g. "#f97316"
+}
+
+#[derive(Deserialize)]
+pub struct ClubCreateRequest {
+    pub slug: String, // Subdomain and X-Club value, e.g. "hawks"
+    pub name: String,
+    pub admin_email: String,
+    pub admin_name: String,
+}
+
+#[derive(Serialize)]
+pub struct ClubCreatedResponse {
+    pub club: crate::services::clubs::Club,
+    pub admin_id: i64,
+    pub temporary_password: String, // Shown once
+}
+
+// ─── Teams ──────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct TeamRequest {
+    pub name: String,
+    pub age_group: Option<String>, // e.g. "senior", "U17"
+    pub gender: Option<String>, // "men", "women" or "mixed"
+}
+
+#[derive(Deserialize)]
+pub struct TeamMemberRequest {
+    pub user_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct TeamFilter {
+    pub team_id: Option<i64>,
+}
+
+// ─── Guardians ──────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct GuardianLinkRequest {
+    pub guardian_id: i64,
+    pub player_id: i64,
+    pub relationship: Option<String>, // e.g. "mother", "legal guardian"
+}
+
+#[derive(Deserialize)]
+pub struct ConsentUpdateRequest {
+    pub granted: bool,
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PlayerScheduleEntry {
+    pub match_id: i64,
+    pub date: String,
+    pub home_team: String,
+    pub away_team: String,
+    pub location: Option<String>,
+    pub available: Option<bool>, // None until declared
+    pub in_squad: Option<bool>, // None until the squad is published
+}
+
+#[derive(Serialize)]
+pub struct PlayerStatsResponse {
+    pub player_id: i64,
+    pub matches_in_squad: i64,
+    pub attended: i64,
+    pub attendance_recorded: i64,
+    pub events: std::collections::BTreeMap<String, i64>,
+}
+
+// ─── Notifications ──────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct NotificationResponse {
+    pub id: i64,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+    pub created_at: String,
+    pub read: bool,
 }
 
 // ─── Attendance ─────────────────────────────────────────────────────
@@ -166,6 +588,7 @@ pub struct AttendanceMarkRequest {
 #[derive(Deserialize)]
 pub struct AttendanceBulkRequest {
     pub match_id: Option<i64>,
+    pub team_id: Option<i64>, // Training without a match belongs to a team
     pub records: Vec<AttendanceRecord>,
     pub date: Option<String>,
 }
@@ -208,6 +631,11 @@ pub struct SeasonResponse {
 pub struct TournamentCreateRequest {
     pub name: String,
     pub season_id: i64,
+    pub points_for_win: Option<i32>,
+    pub points_for_draw: Option<i32>,
+    pub points_for_loss: Option<i32>,
+    pub tiebreakers: Option<String>, // e.g. "head_to_head,goal_difference"
+    pub format: Option<String>,      // "league", "groups_knockout" or "single_elimination"
 }
 
 #[derive(Serialize)]
@@ -215,6 +643,27 @@ pub struct TournamentResponse {
     pub id: i64,
     pub name: String,
     pub season_id: i64,
+    pub points_for_win: i32,
+    pub points_for_draw: i32,
+    pub points_for_loss: i32,
+    pub tiebreakers: String,
+    pub format: String,
+}
+
+#[derive(Deserialize)]
+pub struct StageCreateRequest {
+    pub name: String,
+    pub stage_type: String, // "group" or "knockout"
+    pub position: Option<i32>,
+    pub groups: Option<Vec<String>>, // Group names, only for group stages
+}
+
+#[derive(Deserialize)]
+pub struct BracketGenerateRequest {
+    pub teams: Option<Vec<String>>, // Seeded, best first
+    pub qualifiers_per_group: Option<usize>, // Take teams from group tables instead
+    pub match_date: String,
+    pub location: Option<String>,
 }
 
 // ─── User Management ────────────────────────────────────────────────
@@ -235,3 +684,45 @@ pub struct RoleUpdateRequest {
     pub user_id: i64,
     pub role_name: String,
 }
+
+// ─── Trash ──────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct TrashQuery {
+    pub entity: Option<String>, // "users", "matches", "seasons" or "tournaments"
+}
+
+// ─── Background Jobs ────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct JobsQuery {
+    pub status: Option<String>, // "pending", "running", "succeeded" or "failed"
+    pub name: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// ─── Audit Log ──────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<i64>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i64>,
+    pub from: Option<String>, // YYYY-MM-DD, inclusive
+    pub to: Option<String>,   // YYYY-MM-DD, inclusive
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct AuditEntryResponse {
+    pub id: i64,
+    pub actor_id: Option<i64>,
+    pub actor_name: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i64>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+    pub created_at: String,
+}
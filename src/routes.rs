@@ -0,0 +1,212 @@
+//! The HTTP routes of the app, shared by the server and the integration tests.
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    middleware,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Router,
+};
+use sqlx::PgPool;
+use tokio::fs;
+use tower_http::services::ServeDir;
+
+use crate::services::clubs;
+use crate::services::payments::Payments;
+use crate::services::rate_limit::{self, RateLimiter};
+use crate::{auth, config, handlers, telemetry};
+
+async fn serve_index() -> impl IntoResponse {
+    match fs::read(config::get().server.static_dir.join("index.html")).await {
+        Ok(contents) => ([("content-type", "text/html")], contents).into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "index.html not found").into_response(),
+    }
+}
+
+async fn serve_html(Path(filename): Path<String>) -> impl IntoResponse {
+    let path = config::get().server.static_dir.join(&filename);
+    if !filename.ends_with(".html") {
+        return (StatusCode::NOT_FOUND, "Not found").into_response();
+    }
+    match fs::read(&path).await {
+        Ok(contents) => ([("content-type", "text/html")], contents).into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "HTML file not found").into_response(),
+    }
+}
+
+/// Every route with its middleware. `payments` is the online payment provider
+/// the fee handlers use.
+pub fn app(pool: PgPool, payments: Payments) -> Router {
+    // ── Public API routes (no auth required) ────────────────────────
+    let public_api = Router::new()
+        .route("/api/announcements", get(handlers::announcements::list_announcements))
+        .route("/api/matches", get(handlers::matches::list_matches))
+        .route("/api/seasons", get(handlers::seasons::list_seasons))
+        .route("/api/tournaments", get(handlers::seasons::list_tournaments))
+        .route("/api/tournaments/:id/standings", get(handlers::tournaments::get_tournament_standings))
+        .route("/api/tournaments/:id/bracket", get(handlers::tournaments::get_tournament_bracket))
+        .route("/api/club", get(handlers::clubs::get_club))
+        // Health checks and metrics
+        .route("/healthz", get(handlers::health::healthz))
+        .route("/readyz", get(handlers::health::readyz))
+        .route("/metrics", get(handlers::health::metrics));
+
+    // ── Auth routes (no auth required, rate limited) ────────────────
+    let limiter = RateLimiter::new(&config::get().rate_limit, &pool);
+    let auth_routes = Router::new()
+        .route("/api/register", post(auth::register_handler))
+        .route("/api/login", post(auth::login_handler))
+        .route("/api/login/2fa", post(handlers::two_factor::login_verify))
+        .route("/api/login/2fa/setup", post(handlers::two_factor::login_setup))
+        // Single sign-on with OpenID Connect providers
+        .route("/api/auth/oidc/providers", get(handlers::oidc::list_providers))
+        .route("/api/auth/oidc/:provider/login", get(handlers::oidc::login))
+        .route("/api/auth/oidc/:provider/callback", get(handlers::oidc::callback))
+        .layer(middleware::from_fn_with_state(limiter.clone(), rate_limit::limit_by_ip))
+        .layer(Extension(limiter));
+
+    // ── Protected API routes (JWT auth required) ────────────────────
+    let protected_api = Router::new()
+        // Account
+        .route("/api/password/change", post(auth::change_password_handler))
+        .route("/api/2fa/setup", post(handlers::two_factor::setup))
+        .route("/api/2fa/enable", post(handlers::two_factor::enable))
+        .route("/api/2fa/disable", post(handlers::two_factor::disable))
+        .route("/api/2fa/recovery-codes", post(handlers::two_factor::regenerate_recovery_codes))
+        .route("/api/auth/oidc/:provider/link", post(handlers::oidc::link))
+        // Announcements
+        .route("/api/announcements", post(handlers::announcements::create_announcement))
+        .route("/api/announcements/approve", post(handlers::announcements::approve_announcement))
+        .route("/api/announcements/reject", post(handlers::announcements::reject_announcement))
+        .route("/api/announcements/pending", get(handlers::announcements::list_pending_announcements))
+        // Matches
+        .route("/api/matches", post(handlers::matches::create_match))
+        .route("/api/matches/update", post(handlers::matches::update_match))
+        .route("/api/matches/:id", axum::routing::delete(handlers::matches::delete_match))
+        .route("/api/matches/:match_id/events", post(handlers::matches::create_match_event))
+        .route("/api/matches/:id/statistics", get(handlers::matches::get_match_statistics))
+        .route("/api/matches/:id/report.pdf", get(handlers::matches::get_match_report_pdf))
+        .route("/api/matches/:id/report.html", get(handlers::matches::get_match_report_html))
+        .route("/api/matches/:id/squad", get(handlers::squads::get_squad).put(handlers::squads::update_squad))
+        .route("/api/matches/:id/squad/publish", post(handlers::squads::publish_squad))
+        .route("/api/matches/:id/squad/export", get(handlers::squads::export_squad))
+        .route("/api/matches/:id/availability", get(handlers::squads::list_availability).post(handlers::squads::submit_availability))
+        .route("/api/suspensions", get(handlers::squads::list_suspensions).post(handlers::squads::create_suspension))
+        .route("/api/tournaments/:id/disciplinary", get(handlers::disciplinary::tournament_report))
+        .route("/api/tournaments/:id/disciplinary/rules", axum::routing::put(handlers::disciplinary::update_rules))
+        .route("/api/players/:id/disciplinary", get(handlers::disciplinary::player_record))
+        // Notifications
+        .route("/api/notifications", get(handlers::notifications::list_notifications))
+        .route("/api/notifications/:id/read", post(handlers::notifications::mark_notification_read))
+        // Attendance
+        .route("/api/attendance", post(handlers::attendance::mark_attendance))
+        .route("/api/attendance/bulk", post(handlers::attendance::mark_attendance_bulk))
+        .route("/api/attendance/list", get(handlers::attendance::list_attendance))
+        // Teams
+        .route("/api/teams", get(handlers::teams::list_teams).post(handlers::teams::create_team))
+        .route("/api/teams/:id", axum::routing::put(handlers::teams::update_team).delete(handlers::teams::delete_team))
+        .route("/api/teams/:id/members", get(handlers::teams::list_members).post(handlers::teams::add_member))
+        .route("/api/teams/:id/members/:user_id", axum::routing::delete(handlers::teams::remove_member))
+        // Guardians
+        .route("/api/admin/guardians", post(handlers::guardians::link_guardian))
+        .route("/api/admin/guardians/:guardian_id/players/:player_id", axum::routing::delete(handlers::guardians::unlink_guardian))
+        .route("/api/guardian/players", get(handlers::guardians::my_players))
+        .route("/api/players/:id/guardians", get(handlers::guardians::list_guardians))
+        .route("/api/players/:id/schedule", get(handlers::guardians::player_schedule))
+        .route("/api/players/:id/attendance", get(handlers::guardians::player_attendance))
+        .route("/api/players/:id/stats", get(handlers::guardians::player_stats))
+        .route("/api/players/:id/consents", get(handlers::guardians::player_consents))
+        .route("/api/players/:id/consents/:consent_type", axum::routing::put(handlers::guardians::update_consent))
+        // Injuries
+        .route("/api/injuries", get(handlers::injuries::list_injuries).post(handlers::injuries::create_injury))
+        .route("/api/injuries/:id", axum::routing::put(handlers::injuries::update_injury))
+        .route("/api/injuries/:id/clear", post(handlers::injuries::clear_injury))
+        .route("/api/players/:id/injuries", get(handlers::injuries::player_injuries))
+        // Anti-doping
+        .route("/api/antidoping/players", get(handlers::antidoping::my_players))
+        .route("/api/players/:id/medications", get(handlers::antidoping::list_declarations).post(handlers::antidoping::create_declaration))
+        .route("/api/medications/:id", axum::routing::put(handlers::antidoping::update_declaration))
+        .route("/api/players/:id/tues", get(handlers::antidoping::list_tues).post(handlers::antidoping::create_tue))
+        .route("/api/tues/:id/document", get(handlers::antidoping::tue_document))
+        .route("/api/medical/declarations", get(handlers::antidoping::declaration_queue))
+        .route("/api/medical/declarations/:id/review", post(handlers::antidoping::review_declaration))
+        .route("/api/medical/tues", get(handlers::antidoping::expiring_tues))
+        // Fitness
+        .route("/api/fitness/tests", get(handlers::fitness::list_tests).post(handlers::fitness::create_test))
+        .route("/api/fitness/tests/:id", axum::routing::put(handlers::fitness::update_test))
+        .route("/api/fitness/tests/:id/percentiles", get(handlers::fitness::test_percentiles))
+        .route("/api/fitness/results", post(handlers::fitness::record_results))
+        .route("/api/players/:id/fitness", get(handlers::fitness::player_fitness))
+        // Membership fees
+        .route("/api/fees/plans", get(handlers::fees::list_plans).post(handlers::fees::create_plan))
+        .route("/api/fees/plans/:id", axum::routing::put(handlers::fees::update_plan))
+        .route("/api/fees/plans/:id/invoices", post(handlers::fees::issue_invoices))
+        .route("/api/fees/overdue", get(handlers::fees::overdue_invoices))
+        .route("/api/fees/balances", get(handlers::fees::balances))
+        .route("/api/invoices", get(handlers::fees::list_invoices))
+        .route("/api/invoices/:id", get(handlers::fees::get_invoice))
+        .route("/api/invoices/:id/payments", post(handlers::fees::record_payment))
+        .route("/api/invoices/:id/cancel", post(handlers::fees::cancel_invoice))
+        .route("/api/invoices/:id/checkout", post(handlers::fees::checkout))
+        .route("/api/payments/:id/receipt.pdf", get(handlers::fees::payment_receipt))
+        // Seasons & Tournaments
+        .route("/api/seasons", post(handlers::seasons::create_season))
+        .route("/api/seasons/:id", axum::routing::delete(handlers::seasons::delete_season))
+        .route("/api/seasons/:id", axum::routing::patch(handlers::seasons::update_season))
+        .route("/api/tournaments", post(handlers::seasons::create_tournament))
+        .route("/api/tournaments/:id", axum::routing::delete(handlers::seasons::delete_tournament))
+        .route("/api/tournaments/:id", axum::routing::patch(handlers::seasons::update_tournament))
+        .route("/api/tournaments/:id/stages", post(handlers::tournaments::create_stage))
+        .route("/api/tournaments/:id/stages/:stage_id/bracket", post(handlers::tournaments::generate_bracket))
+        // User management
+        .route("/api/users", get(handlers::seasons::list_users))
+        .route("/api/users/role", post(handlers::seasons::update_user_role))
+        .route("/api/users/:id", axum::routing::delete(handlers::seasons::delete_user))
+        .route("/api/users/:id", axum::routing::patch(handlers::seasons::update_user))
+        .route("/api/admin/users/:id/unlock", post(handlers::admin::unlock_user))
+        .route("/api/admin/users/:id/2fa/reset", post(handlers::two_factor::reset_two_factor))
+        .route("/api/admin/security-policy", get(handlers::two_factor::get_security_policy).put(handlers::two_factor::update_security_policy))
+        .route("/api/admin/users/:id/approve", post(handlers::registration::approve_user))
+        .route("/api/admin/users/:id/reject", post(handlers::registration::reject_user))
+        .route("/api/admin/pending-users", get(handlers::registration::pending_users))
+        .route("/api/admin/registration-mode", get(handlers::registration::get_registration_mode).put(handlers::registration::update_registration_mode))
+        .route("/api/admin/invites", get(handlers::registration::list_invites).post(handlers::registration::create_invite))
+        .route("/api/admin/invites/:id", axum::routing::delete(handlers::registration::revoke_invite))
+        // Bulk import
+        .route("/api/import/:kind", post(handlers::imports::import_file))
+        // Data export
+        .route("/api/export/:dataset", get(handlers::exports::export_dataset))
+        // Admin protected route
+        .route("/api/admin/protected", get(handlers::admin::protected_admin_route))
+        .route("/api/admin/backup", get(handlers::admin::download_backup))
+        // Trash (soft-deleted rows)
+        .route("/api/admin/trash", get(handlers::admin::list_trash))
+        .route("/api/admin/trash/:entity/:id/restore", post(handlers::admin::restore_from_trash))
+        // Background jobs
+        .route("/api/admin/jobs", get(handlers::admin::list_jobs))
+        .route("/api/admin/jobs/:id/retry", post(handlers::admin::retry_job))
+        // Audit log
+        .route("/api/audit", get(handlers::admin::list_audit_log))
+        // Clubs and branding
+        .route("/api/club", axum::routing::put(handlers::clubs::update_branding))
+        .route("/api/admin/clubs", get(handlers::clubs::list_clubs).post(handlers::clubs::create_club))
+        // Apply auth middleware to all protected routes
+        .layer(Extension(payments))
+        .layer(middleware::from_fn_with_state(pool.clone(), auth::auth_middleware));
+
+    // ── Combine all routes ──────────────────────────────────────────
+    Router::new()
+        .route("/", get(serve_index))
+        .route("/:filename", get(serve_html))
+        .merge(public_api)
+        .merge(auth_routes)
+        .merge(protected_api)
+        .nest_service("/static", ServeDir::new(&config::get().server.static_dir))
+        // Every request is for one club: from the X-Club header, the subdomain or the default
+        .layer(middleware::from_fn_with_state(pool.clone(), clubs::resolve_tenant))
+        // Request ids wrap everything so the request log and audit entries share them
+        .layer(middleware::from_fn(telemetry::track_requests))
+        .layer(middleware::from_fn(telemetry::request_id))
+        .with_state(pool)
+}
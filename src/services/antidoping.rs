@@ -0,0 +1,427 @@
+//! Anti-doping: medication and supplement declarations and Therapeutic Use
+//! Exemptions (TUEs).
+//!
+//! This is health data. Only the player, their guardians and the club's
+//! medical officers can read it — not coaches, and not admins, who only
+//! designate the medical officers. Audit entries keep ids, dates and review
+//! status but none of the declared substances.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{Connection, PgConnection, PgExecutor, PgPool};
+use std::time::Duration;
+
+use crate::auth::Claims;
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::guardians;
+use crate::services::jobs::Scheduler;
+use crate::services::notifications::notify;
+
+pub const MEDICAL_OFFICER: &str = "medical_officer";
+pub const KINDS: &[&str] = &["medication", "supplement"];
+pub const REVIEW_STATUSES: &[&str] = &["reviewed", "flagged"];
+
+/// Largest TUE certificate accepted, after base64 decoding.
+pub const MAX_DOCUMENT_BYTES: usize = 1024 * 1024;
+pub const DOCUMENT_TYPES: &[&str] = &["application/pdf", "image/png", "image/jpeg"];
+
+/// Job name of the TUE expiry reminders in the `jobs` table.
+pub const REMINDER_JOB: &str = "antidoping.tue_reminders";
+
+/// How often the reminder job looks for expiring TUEs.
+const REMINDER_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Columns of these tables that may appear in the audit log.
+const AUDIT_FIELDS: &[&str] = &["id", "player_id", "kind", "status", "reviewed_by", "reviewed_at", "granted_on", "expires_on"];
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Declaration {
+    pub id: i64,
+    pub player_id: i64,
+    pub player_name: String,
+    pub kind: String,
+    pub name: String,
+    pub dosage: Option<String>,
+    pub started_on: NaiveDate,
+    pub ended_on: Option<NaiveDate>,
+    pub reason: Option<String>,
+    pub status: String,
+    pub review_notes: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Tue {
+    pub id: i64,
+    pub player_id: i64,
+    pub player_name: String,
+    pub substance: String,
+    pub reference: Option<String>,
+    pub document_name: Option<String>,
+    pub has_document: bool,
+    pub granted_on: Option<NaiveDate>,
+    pub expires_on: NaiveDate,
+    /// Negative once expired.
+    pub days_left: i32,
+}
+
+/// What a player declares taking.
+pub struct DeclarationDetails<'a> {
+    pub kind: &'a str,
+    pub name: &'a str,
+    pub dosage: Option<&'a str>,
+    pub started_on: NaiveDate,
+    pub ended_on: Option<NaiveDate>,
+    pub reason: Option<&'a str>,
+}
+
+impl DeclarationDetails<'_> {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !KINDS.contains(&self.kind) {
+            return Err(AppError::BadRequest("Kind must be 'medication' or 'supplement'".into()));
+        }
+        if self.name.trim().is_empty() || self.name.len() > 200 {
+            return Err(AppError::BadRequest("Name is required (at most 200 characters)".into()));
+        }
+        if self.dosage.is_some_and(|d| d.len() > 100) {
+            return Err(AppError::BadRequest("Dosage must be at most 100 characters".into()));
+        }
+        if self.ended_on.is_some_and(|d| d < self.started_on) {
+            return Err(AppError::BadRequest("A declaration cannot end before it starts".into()));
+        }
+        Ok(())
+    }
+}
+
+/// An uploaded TUE certificate.
+pub struct TueDocument {
+    pub name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+pub struct TueDetails<'a> {
+    pub substance: &'a str,
+    pub reference: Option<&'a str>,
+    pub granted_on: Option<NaiveDate>,
+    pub expires_on: NaiveDate,
+    pub document: Option<TueDocument>,
+}
+
+impl TueDetails<'_> {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.substance.trim().is_empty() || self.substance.len() > 200 {
+            return Err(AppError::BadRequest("Substance is required (at most 200 characters)".into()));
+        }
+        if self.reference.is_some_and(|r| r.len() > 100) {
+            return Err(AppError::BadRequest("Reference must be at most 100 characters".into()));
+        }
+        if self.reference.is_none() && self.document.is_none() {
+            return Err(AppError::BadRequest("Upload the TUE certificate or give its reference".into()));
+        }
+        if self.granted_on.is_some_and(|d| self.expires_on < d) {
+            return Err(AppError::BadRequest("A TUE cannot expire before it is granted".into()));
+        }
+        if let Some(document) = &self.document {
+            if !DOCUMENT_TYPES.contains(&document.content_type.as_str()) {
+                return Err(AppError::BadRequest(format!("The certificate must be one of: {}", DOCUMENT_TYPES.join(", "))));
+            }
+            if document.bytes.is_empty() || document.bytes.len() > MAX_DOCUMENT_BYTES {
+                return Err(AppError::BadRequest(format!("The certificate must be at most {} KiB", MAX_DOCUMENT_BYTES / 1024)));
+            }
+            if document.name.trim().is_empty() || document.name.len() > 200 {
+                return Err(AppError::BadRequest("The certificate needs a file name (at most 200 characters)".into()));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn is_medical_officer(claims: &Claims) -> bool {
+    claims.role == MEDICAL_OFFICER
+}
+
+pub fn require_medical_officer(claims: &Claims) -> Result<(), AppError> {
+    if is_medical_officer(claims) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("Medical officer access required".into()))
+    }
+}
+
+/// Allow the player themselves, their guardians and the club's medical
+/// officers. 404 for players of other clubs; coaches and admins are refused.
+pub async fn require_access(conn: &mut PgConnection, claims: &Claims, player_id: i64) -> Result<(), AppError> {
+    let owner: i64 = sqlx::query_scalar(
+        "SELECT p.user_id FROM players p JOIN users u ON p.user_id = u.id WHERE p.id = $1 AND u.club_id = $2 AND u.deleted_at IS NULL",
+    )
+    .bind(player_id)
+    .bind(claims.club_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Player not found".into()))?;
+    if is_medical_officer(claims) || owner == claims.sub || guardians::is_guardian_of(&mut *conn, claims.sub, player_id).await? {
+        return Ok(());
+    }
+    Err(AppError::Forbidden("Anti-doping records are only open to the player, their guardians and the medical officer".into()))
+}
+
+/// The players whose records the user keeps: themselves, or their linked players.
+pub async fn own_players<'e>(executor: impl PgExecutor<'e>, user_id: i64) -> Result<Vec<(i64, String)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT p.id, p.first_name || ' ' || p.last_name FROM players p \
+         WHERE p.user_id = $1 OR p.id IN (SELECT g.player_id FROM player_guardians g WHERE g.guardian_id = $1) \
+         ORDER BY p.last_name, p.first_name",
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Keep only the audit-safe fields of a row snapshot.
+fn audit_view(snapshot: Option<Value>) -> Option<Value> {
+    snapshot.map(|mut row| {
+        if let Value::Object(map) = &mut row {
+            map.retain(|key, _| AUDIT_FIELDS.contains(&key.as_str()));
+        }
+        row
+    })
+}
+
+// ─── Declarations ───────────────────────────────────────────────────
+
+const DECLARATION_SELECT: &str = "SELECT d.id, d.player_id, p.first_name || ' ' || p.last_name AS player_name, d.kind, d.name, \
+     d.dosage, d.started_on, d.ended_on, d.reason, d.status, d.review_notes, d.reviewed_at, d.created_at \
+     FROM medication_declarations d JOIN players p ON d.player_id = p.id JOIN users u ON p.user_id = u.id";
+
+/// A player's declarations, most recent first.
+pub async fn declarations<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Declaration>, sqlx::Error> {
+    sqlx::query_as(&format!("{} WHERE d.player_id = $1 ORDER BY d.started_on DESC, d.id DESC", DECLARATION_SELECT))
+        .bind(player_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// The club's declarations with the given status (all when None), oldest first.
+pub async fn declaration_queue<'e>(executor: impl PgExecutor<'e>, club_id: i64, status: Option<&str>) -> Result<Vec<Declaration>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "{} WHERE u.club_id = $1 AND u.deleted_at IS NULL AND ($2::text IS NULL OR d.status = $2) ORDER BY d.created_at, d.id",
+        DECLARATION_SELECT
+    ))
+    .bind(club_id)
+    .bind(status)
+    .fetch_all(executor)
+    .await
+}
+
+/// The player a declaration of the club belongs to. 404 for other clubs'.
+pub async fn declaration_player<'e>(executor: impl PgExecutor<'e>, club_id: i64, id: i64) -> Result<i64, AppError> {
+    sqlx::query_scalar(
+        "SELECT d.player_id FROM medication_declarations d JOIN players p ON d.player_id = p.id JOIN users u ON p.user_id = u.id \
+         WHERE d.id = $1 AND u.club_id = $2",
+    )
+    .bind(id)
+    .bind(club_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Declaration not found".into()))
+}
+
+pub async fn declare(conn: &mut PgConnection, ctx: &AuditContext, player_id: i64, details: &DeclarationDetails<'_>) -> Result<i64, AppError> {
+    details.validate()?;
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO medication_declarations (player_id, kind, name, dosage, started_on, ended_on, reason, declared_by) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+    )
+    .bind(player_id)
+    .bind(details.kind)
+    .bind(details.name.trim())
+    .bind(details.dosage)
+    .bind(details.started_on)
+    .bind(details.ended_on)
+    .bind(details.reason)
+    .bind(ctx.actor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "medication_declarations", id).await?;
+    audit::record(conn, ctx, "create", "medication_declaration", Some(id), None, audit_view(after)).await?;
+    Ok(id)
+}
+
+/// Change a declaration, e.g. to record when the player stopped taking it.
+/// The change goes back to the medical officer for review.
+pub async fn update_declaration(conn: &mut PgConnection, ctx: &AuditContext, id: i64, details: &DeclarationDetails<'_>) -> Result<(), AppError> {
+    details.validate()?;
+    let before = audit::snapshot(&mut *conn, "medication_declarations", id).await?;
+    sqlx::query(
+        "UPDATE medication_declarations SET kind = $2, name = $3, dosage = $4, started_on = $5, ended_on = $6, reason = $7, \
+         status = 'pending', review_notes = NULL, reviewed_by = NULL, reviewed_at = NULL, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(id)
+    .bind(details.kind)
+    .bind(details.name.trim())
+    .bind(details.dosage)
+    .bind(details.started_on)
+    .bind(details.ended_on)
+    .bind(details.reason)
+    .execute(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "medication_declarations", id).await?;
+    audit::record(conn, ctx, "update", "medication_declaration", Some(id), audit_view(before), audit_view(after)).await?;
+    Ok(())
+}
+
+/// Medical officer's review: 'reviewed' when nothing is wrong, 'flagged' when
+/// the player must act (e.g. apply for a TUE). The player is notified.
+pub async fn review(conn: &mut PgConnection, ctx: &AuditContext, id: i64, status: &str, notes: Option<&str>) -> Result<(), AppError> {
+    if !REVIEW_STATUSES.contains(&status) {
+        return Err(AppError::BadRequest("Status must be 'reviewed' or 'flagged'".into()));
+    }
+    let before = audit::snapshot(&mut *conn, "medication_declarations", id).await?;
+    let user_id: i64 = sqlx::query_scalar(
+        "UPDATE medication_declarations d SET status = $2, review_notes = $3, reviewed_by = $4, reviewed_at = NOW(), updated_at = NOW() \
+         FROM players p WHERE d.id = $1 AND p.id = d.player_id RETURNING p.user_id",
+    )
+    .bind(id)
+    .bind(status)
+    .bind(notes)
+    .bind(ctx.actor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "medication_declarations", id).await?;
+    audit::record(conn, ctx, "review", "medication_declaration", Some(id), audit_view(before), audit_view(after)).await?;
+    if status == "flagged" {
+        notify(
+            &mut *conn,
+            user_id,
+            "Declaration needs attention",
+            "The medical officer flagged one of your anti-doping declarations. Please check the notes.",
+            Some("/antidoping.html"),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// ─── TUEs ───────────────────────────────────────────────────────────
+
+const TUE_SELECT: &str = "SELECT t.id, t.player_id, p.first_name || ' ' || p.last_name AS player_name, t.substance, t.reference, \
+     t.document_name, t.document IS NOT NULL AS has_document, t.granted_on, t.expires_on, (t.expires_on - CURRENT_DATE) AS days_left \
+     FROM therapeutic_use_exemptions t JOIN players p ON t.player_id = p.id JOIN users u ON p.user_id = u.id";
+
+/// A player's TUEs, latest expiry first.
+pub async fn tues<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Tue>, sqlx::Error> {
+    sqlx::query_as(&format!("{} WHERE t.player_id = $1 ORDER BY t.expires_on DESC, t.id DESC", TUE_SELECT))
+        .bind(player_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// The club's TUEs expiring within `days` (including expired ones), soonest first.
+pub async fn expiring_tues<'e>(executor: impl PgExecutor<'e>, club_id: i64, days: i32) -> Result<Vec<Tue>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "{} WHERE u.club_id = $1 AND u.deleted_at IS NULL AND t.expires_on <= CURRENT_DATE + $2 ORDER BY t.expires_on, t.id",
+        TUE_SELECT
+    ))
+    .bind(club_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn add_tue(conn: &mut PgConnection, ctx: &AuditContext, player_id: i64, details: &TueDetails<'_>) -> Result<i64, AppError> {
+    details.validate()?;
+    let document = details.document.as_ref();
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO therapeutic_use_exemptions \
+         (player_id, substance, reference, document, document_name, document_type, granted_on, expires_on, created_by) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+    )
+    .bind(player_id)
+    .bind(details.substance.trim())
+    .bind(details.reference)
+    .bind(document.map(|d| d.bytes.as_slice()))
+    .bind(document.map(|d| d.name.trim()))
+    .bind(document.map(|d| d.content_type.as_str()))
+    .bind(details.granted_on)
+    .bind(details.expires_on)
+    .bind(ctx.actor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "therapeutic_use_exemptions", id).await?;
+    audit::record(conn, ctx, "create", "tue", Some(id), None, audit_view(after)).await?;
+    Ok(id)
+}
+
+/// A club's TUE certificate: (player id, file name, content type, bytes).
+/// 404 for other clubs' TUEs and for TUEs given by reference only.
+pub async fn tue_document<'e>(executor: impl PgExecutor<'e>, club_id: i64, id: i64) -> Result<(i64, String, String, Vec<u8>), AppError> {
+    sqlx::query_as::<_, (i64, Option<String>, Option<String>, Option<Vec<u8>>)>(
+        "SELECT t.player_id, t.document_name, t.document_type, t.document FROM therapeutic_use_exemptions t \
+         JOIN players p ON t.player_id = p.id JOIN users u ON p.user_id = u.id WHERE t.id = $1 AND u.club_id = $2",
+    )
+    .bind(id)
+    .bind(club_id)
+    .fetch_optional(executor)
+    .await?
+    .and_then(|(player_id, name, content_type, bytes)| Some((player_id, name?, content_type?, bytes?)))
+    .ok_or_else(|| AppError::NotFound("TUE certificate not found".into()))
+}
+
+/// Remind the player, their guardians and the club's medical officers once
+/// about each TUE expiring within `days`. Returns the number of TUEs reminded.
+pub async fn send_tue_reminders(pool: &PgPool, days: i64) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query_as::<_, (i64, i64, String, String, NaiveDate)>(
+        "SELECT t.id, p.id, p.first_name || ' ' || p.last_name, t.substance, t.expires_on FROM therapeutic_use_exemptions t \
+         JOIN players p ON t.player_id = p.id JOIN users u ON p.user_id = u.id \
+         WHERE t.reminded_at IS NULL AND u.deleted_at IS NULL AND t.expires_on >= CURRENT_DATE \
+         AND t.expires_on <= CURRENT_DATE + $1::int ORDER BY t.expires_on",
+    )
+    .bind(days as i32)
+    .fetch_all(pool)
+    .await?;
+
+    let mut conn = pool.acquire().await?;
+    for (id, player_id, player_name, substance, expires_on) in &due {
+        let mut tx = conn.begin().await?;
+        let recipients: Vec<i64> = sqlx::query_scalar(
+            "SELECT p.user_id FROM players p WHERE p.id = $1 \
+             UNION SELECT g.guardian_id FROM player_guardians g WHERE g.player_id = $1 \
+             UNION SELECT mo.id FROM users mo JOIN roles r ON mo.role_id = r.id JOIN players p ON p.id = $1 JOIN users pu ON p.user_id = pu.id \
+             WHERE r.name = $2 AND mo.club_id = pu.club_id AND mo.deleted_at IS NULL",
+        )
+        .bind(player_id)
+        .bind(MEDICAL_OFFICER)
+        .fetch_all(&mut *tx)
+        .await?;
+        let body = format!(
+            "The TUE of {} for {} expires on {}. Apply for a renewal in time.",
+            player_name, substance, expires_on
+        );
+        for user_id in recipients {
+            notify(&mut *tx, user_id, "TUE expiring soon", &body, Some("/antidoping.html")).await?;
+        }
+        sqlx::query("UPDATE therapeutic_use_exemptions SET reminded_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+    Ok(due.len())
+}
+
+/// Schedule the TUE expiry reminders.
+pub fn register_reminder_job(scheduler: &mut Scheduler, days: i64) {
+    scheduler
+        .register(REMINDER_JOB, move |pool, _payload| async move {
+            let reminded = send_tue_reminders(&pool, days).await.map_err(|e| e.to_string())?;
+            if reminded > 0 {
+                tracing::info!("TUE reminders: {} expiring exemptions", reminded);
+            }
+            Ok(())
+        })
+        .every(REMINDER_JOB, REMINDER_INTERVAL);
+}
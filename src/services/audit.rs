@@ -0,0 +1,106 @@
+//! Service for the audit log of mutating actions.
+//! Handlers call `record` inside the same transaction as their write, so an
+//! entry exists exactly when the change was committed.
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use serde_json::Value;
+use sqlx::PgConnection;
+
+use crate::auth::Claims;
+use crate::services::clubs::Club;
+
+/// Header carrying the request id. Set by clients or proxies when present.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Fields never written to the audit log.
+const REDACTED_FIELDS: &[&str] = &["password_hash", "totp_secret", "code_hash"];
+
+/// Who is making a change, and in which request.
+///
+/// Extracted from the request: the actor comes from the JWT claims set by the
+/// auth middleware, the club from the tenant, the request id from the
+/// `x-request-id` header.
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor_id: Option<i64>,
+    pub club_id: Option<i64>,
+    pub request_id: Option<String>,
+}
+
+impl AuditContext {
+    /// Attribute changes to a user explicitly, e.g. a newly registered account.
+    pub fn with_actor(mut self, actor_id: i64) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    /// Attribute changes to a club explicitly, e.g. from the command line.
+    pub fn with_club(mut self, club_id: i64) -> Self {
+        self.club_id = Some(club_id);
+        self
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(AuditContext {
+            actor_id: parts.extensions.get::<Claims>().map(|c| c.sub),
+            club_id: parts.extensions.get::<Club>().map(|c| c.id),
+            request_id: parts
+                .headers
+                .get(REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.chars().take(100).collect()),
+        })
+    }
+}
+
+/// Remove secrets from a row snapshot.
+pub fn redact(mut value: Value) -> Value {
+    if let Value::Object(map) = &mut value {
+        for field in REDACTED_FIELDS {
+            map.remove(*field);
+        }
+    }
+    value
+}
+
+/// Current state of a row as JSON, or None if it does not exist.
+/// `table` must be a fixed table name, never user input.
+pub async fn snapshot(conn: &mut PgConnection, table: &str, id: i64) -> Result<Option<Value>, sqlx::Error> {
+    let row: Option<(Value,)> = sqlx::query_as(&format!("SELECT row_to_json(t)::jsonb FROM {} t WHERE t.id = $1", table))
+        .bind(id)
+        .fetch_optional(conn)
+        .await?;
+    Ok(row.map(|(value,)| redact(value)))
+}
+
+/// Write one audit entry.
+pub async fn record(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    action: &str,
+    entity_type: &str,
+    entity_id: Option<i64>,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO audit_log (actor_id, action, entity_type, entity_id, before_data, after_data, request_id, club_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(ctx.actor_id)
+    .bind(action)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(before.map(redact))
+    .bind(after.map(redact))
+    .bind(&ctx.request_id)
+    .bind(ctx.club_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
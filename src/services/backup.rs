@@ -0,0 +1,439 @@
+//! Service for full club backups and restores.
+//!
+//! A backup is a zip archive holding `manifest.json` and one JSON array per
+//! table under `tables/`. The manifest records the schema version, the restore
+//! order (parents before children), each table's columns and row count, and a
+//! SHA-256 checksum of every table file. Restore validates all of that before
+//! writing anything, then loads the rows into an empty Postgres or SQLite
+//! database in a single transaction.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{PgPool, SqlitePool};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Cursor, Read, Write};
+use std::str::FromStr;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::errors::AppError;
+
+/// Identifies our archives.
+pub const BACKUP_FORMAT: &str = "tornadoes-backup";
+/// Version of the archive layout itself.
+pub const FORMAT_VERSION: u32 = 1;
+/// Newest migration in `migrations/`. Bump this with every new migration so
+/// archives from a different schema are refused on restore.
+pub const SCHEMA_VERSION: &str = "20260417090000";
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableEntry {
+    pub name: String,
+    pub file: String,
+    pub rows: usize,
+    pub sha256: String,
+    pub columns: Vec<ColumnInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub schema_version: String,
+    pub app_version: String,
+    pub created_at: String,
+    /// Tables in restore order.
+    pub tables: Vec<TableEntry>,
+}
+
+/// Raw data of one table as read from the source database.
+pub struct TableDump {
+    pub name: String,
+    pub columns: Vec<ColumnInfo>,
+    /// JSON array of row objects.
+    pub json: String,
+}
+
+/// A validated table ready to load.
+pub struct TableData {
+    pub entry: TableEntry,
+    pub json: String,
+    pub rows: Vec<serde_json::Map<String, Value>>,
+}
+
+/// Outcome of a restore.
+#[derive(Serialize, Debug)]
+pub struct RestoreSummary {
+    pub tables: usize,
+    pub rows: usize,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Table and column names are interpolated into SQL, so only plain
+/// lower-case identifiers are accepted.
+pub fn is_safe_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Order tables so that every table comes after the tables it references.
+/// Self references are ignored; tables caught in a cycle are appended by name.
+pub fn dependency_order(tables: &[String], references: &[(String, String)]) -> Vec<String> {
+    let mut remaining: BTreeSet<&String> = tables.iter().collect();
+    let mut ordered: Vec<String> = Vec::new();
+    loop {
+        let ready: Vec<&String> = remaining
+            .iter()
+            .copied()
+            .filter(|table| {
+                references
+                    .iter()
+                    .filter(|(child, parent)| child == *table && parent != *table)
+                    .all(|(_, parent)| !remaining.contains(parent))
+            })
+            .collect();
+        if ready.is_empty() {
+            break;
+        }
+        for table in ready {
+            remaining.remove(table);
+            ordered.push(table.clone());
+        }
+    }
+    ordered.extend(remaining.into_iter().cloned());
+    ordered
+}
+
+/// Package table dumps (already in restore order) into a zip archive.
+pub fn build_archive(dumps: &[TableDump], created_at: &str) -> Result<Vec<u8>, AppError> {
+    let zip_error = |e: zip::result::ZipError| AppError::Internal(format!("Backup archive failed: {}", e));
+    let io_error = |e: std::io::Error| AppError::Internal(format!("Backup archive failed: {}", e));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+    let mut tables = Vec::new();
+    for dump in dumps {
+        let rows = serde_json::from_str::<Vec<Value>>(&dump.json)
+            .map_err(|e| AppError::Internal(format!("Table {} is not a JSON array: {}", dump.name, e)))?
+            .len();
+        let file = format!("tables/{}.json", dump.name);
+        writer.start_file(file.as_str(), options).map_err(zip_error)?;
+        writer.write_all(dump.json.as_bytes()).map_err(io_error)?;
+        tables.push(TableEntry {
+            name: dump.name.clone(),
+            file,
+            rows,
+            sha256: sha256_hex(dump.json.as_bytes()),
+            columns: dump.columns.clone(),
+        });
+    }
+
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        schema_version: SCHEMA_VERSION.to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: created_at.to_string(),
+        tables,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| AppError::Internal(format!("Backup manifest failed: {}", e)))?;
+    writer.start_file(MANIFEST_FILE, options).map_err(zip_error)?;
+    writer.write_all(&manifest_json).map_err(io_error)?;
+    Ok(writer.finish().map_err(zip_error)?.into_inner())
+}
+
+/// Dump every table of the Postgres database into a backup archive.
+pub async fn create_backup(pool: &PgPool) -> Result<Vec<u8>, AppError> {
+    let tables: Vec<String> = sqlx::query_as::<_, (String,)>(
+        "SELECT table_name::text FROM information_schema.tables \
+         WHERE table_schema = 'public' AND table_type = 'BASE TABLE' AND table_name NOT LIKE '\\_sqlx%'",
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(name,)| name)
+    .filter(|name| is_safe_identifier(name))
+    .collect();
+
+    let references: Vec<(String, String)> = sqlx::query_as(
+        "SELECT DISTINCT tc.table_name::text, ccu.table_name::text FROM information_schema.table_constraints tc \
+         JOIN information_schema.constraint_column_usage ccu \
+           ON tc.constraint_name = ccu.constraint_name AND tc.table_schema = ccu.table_schema \
+         WHERE tc.constraint_type = 'FOREIGN KEY' AND tc.table_schema = 'public'",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut columns: HashMap<String, Vec<ColumnInfo>> = HashMap::new();
+    for (table, name, data_type, nullable) in sqlx::query_as::<_, (String, String, String, String)>(
+        "SELECT table_name::text, column_name::text, data_type::text, is_nullable::text FROM information_schema.columns \
+         WHERE table_schema = 'public' ORDER BY table_name, ordinal_position",
+    )
+    .fetch_all(pool)
+    .await?
+    {
+        columns.entry(table).or_default().push(ColumnInfo { name, data_type, nullable: nullable == "YES" });
+    }
+
+    // Read every table in one snapshot so the archive is consistent.
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+    let mut dumps = Vec::new();
+    for table in dependency_order(&tables, &references) {
+        let table_columns = columns.remove(&table).unwrap_or_default();
+        let order = table_columns
+            .first()
+            .map(|c| format!(" ORDER BY t.\"{}\"", c.name))
+            .unwrap_or_default();
+        let (json,): (String,) = sqlx::query_as(&format!(
+            "SELECT COALESCE(json_agg(row_to_json(t){order}), '[]'::json)::text FROM \"{table}\" t"
+        ))
+        .fetch_one(&mut *tx)
+        .await?;
+        dumps.push(TableDump { name: table, columns: table_columns, json });
+    }
+    tx.commit().await?;
+
+    build_archive(&dumps, &chrono::Utc::now().to_rfc3339())
+}
+
+/// Open an archive and check the manifest, checksums and row counts.
+pub fn read_archive(bytes: &[u8]) -> Result<(BackupManifest, Vec<TableData>), AppError> {
+    let invalid = |message: String| AppError::BadRequest(format!("Invalid backup archive: {}", message));
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(|e| invalid(e.to_string()))?;
+    let mut read_file = |name: &str| -> Result<String, AppError> {
+        let mut file = archive.by_name(name).map_err(|_| invalid(format!("{} is missing", name)))?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(|e| invalid(format!("{}: {}", name, e)))?;
+        Ok(contents)
+    };
+
+    let manifest: BackupManifest = serde_json::from_str(&read_file(MANIFEST_FILE)?)
+        .map_err(|e| invalid(format!("manifest.json: {}", e)))?;
+    if manifest.format != BACKUP_FORMAT || manifest.format_version != FORMAT_VERSION {
+        return Err(invalid(format!(
+            "unsupported format {} version {}",
+            manifest.format, manifest.format_version
+        )));
+    }
+    if manifest.schema_version != SCHEMA_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "Backup was taken with schema {} but this build expects {}",
+            manifest.schema_version, SCHEMA_VERSION
+        )));
+    }
+
+    let mut tables = Vec::new();
+    for entry in &manifest.tables {
+        if !is_safe_identifier(&entry.name) || !entry.columns.iter().all(|c| is_safe_identifier(&c.name)) {
+            return Err(invalid(format!("unexpected table or column name in {}", entry.name)));
+        }
+        let json = read_file(&entry.file)?;
+        if sha256_hex(json.as_bytes()) != entry.sha256 {
+            return Err(invalid(format!("checksum mismatch for table {}", entry.name)));
+        }
+        let rows: Vec<serde_json::Map<String, Value>> =
+            serde_json::from_str(&json).map_err(|e| invalid(format!("{}: {}", entry.file, e)))?;
+        if rows.len() != entry.rows {
+            return Err(invalid(format!(
+                "table {} has {} rows but the manifest lists {}",
+                entry.name,
+                rows.len(),
+                entry.rows
+            )));
+        }
+        tables.push(TableData { entry: entry.clone(), json, rows });
+    }
+    Ok((manifest, tables))
+}
+
+/// Tables that the application or the migrations seed; their rows are replaced on restore.
+const SEEDED_TABLES: &[&str] = &["roles", "clubs", "fitness_test_types"];
+
+fn not_empty(table: &str, count: i64) -> AppError {
+    AppError::Conflict(format!(
+        "Target database is not empty: table {} has {} row(s). Restore only into an empty database.",
+        table, count
+    ))
+}
+
+/// Load a validated archive into an empty Postgres database whose schema
+/// has already been created by the migrations.
+pub async fn restore_postgres(pool: &PgPool, tables: &[TableData]) -> Result<RestoreSummary, AppError> {
+    let mut tx = pool.begin().await?;
+    for table in tables {
+        let name = &table.entry.name;
+        let (exists,): (bool,) = sqlx::query_as("SELECT to_regclass($1) IS NOT NULL")
+            .bind(format!("public.{}", name))
+            .fetch_one(&mut *tx)
+            .await?;
+        if !exists {
+            return Err(AppError::BadRequest(format!(
+                "Table {} does not exist in the target database. Run the migrations first.",
+                name
+            )));
+        }
+        let (count,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM \"{}\"", name))
+            .fetch_one(&mut *tx)
+            .await?;
+        if count > 0 && !SEEDED_TABLES.contains(&name.as_str()) {
+            return Err(not_empty(name, count));
+        }
+    }
+
+    let mut rows = 0;
+    for table in tables {
+        let name = &table.entry.name;
+        if SEEDED_TABLES.contains(&name.as_str()) {
+            sqlx::query(&format!("DELETE FROM \"{}\"", name)).execute(&mut *tx).await?;
+        }
+        // Postgres converts the JSON values to the column types itself.
+        sqlx::query(&format!(
+            "INSERT INTO \"{name}\" SELECT * FROM json_populate_recordset(NULL::\"{name}\", $1::json)"
+        ))
+        .bind(&table.json)
+        .execute(&mut *tx)
+        .await?;
+        rows += table.rows.len();
+
+        // Move serial sequences past the restored ids.
+        if table.entry.columns.iter().any(|c| c.name == "id") && !table.rows.is_empty() {
+            let (sequence,): (Option<String>,) = sqlx::query_as("SELECT pg_get_serial_sequence($1, 'id')")
+                .bind(format!("public.{}", name))
+                .fetch_one(&mut *tx)
+                .await?;
+            if let Some(sequence) = sequence {
+                sqlx::query(&format!("SELECT setval($1, (SELECT MAX(id) FROM \"{}\"))", name))
+                    .bind(sequence)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+    }
+    tx.commit().await?;
+    Ok(RestoreSummary { tables: tables.len(), rows })
+}
+
+/// SQLite column type for a Postgres `information_schema` data type.
+pub fn sqlite_type(data_type: &str) -> &'static str {
+    match data_type {
+        "bigint" | "integer" | "smallint" | "boolean" => "INTEGER",
+        "real" | "double precision" => "REAL",
+        "numeric" => "NUMERIC",
+        _ => "TEXT",
+    }
+}
+
+/// `CREATE TABLE` statement for SQLite built from the manifest columns.
+pub fn sqlite_create_table(entry: &TableEntry) -> String {
+    let columns: Vec<String> = entry
+        .columns
+        .iter()
+        .map(|c| {
+            if c.name == "id" {
+                "\"id\" INTEGER PRIMARY KEY".to_string()
+            } else {
+                format!(
+                    "\"{}\" {}{}",
+                    c.name,
+                    sqlite_type(&c.data_type),
+                    if c.nullable { "" } else { " NOT NULL" }
+                )
+            }
+        })
+        .collect();
+    format!("CREATE TABLE \"{}\" ({})", entry.name, columns.join(", "))
+}
+
+/// Load a validated archive into an empty SQLite database, creating any
+/// missing tables from the manifest.
+pub async fn restore_sqlite(pool: &SqlitePool, tables: &[TableData]) -> Result<RestoreSummary, AppError> {
+    let mut tx = pool.begin().await?;
+    for table in tables {
+        let name = &table.entry.name;
+        let (exists,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(name)
+            .fetch_one(&mut *tx)
+            .await?;
+        if exists == 0 {
+            sqlx::query(&sqlite_create_table(&table.entry)).execute(&mut *tx).await?;
+            continue;
+        }
+        let (count,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM \"{}\"", name))
+            .fetch_one(&mut *tx)
+            .await?;
+        if count > 0 && !SEEDED_TABLES.contains(&name.as_str()) {
+            return Err(not_empty(name, count));
+        }
+        if count > 0 {
+            sqlx::query(&format!("DELETE FROM \"{}\"", name)).execute(&mut *tx).await?;
+        }
+    }
+
+    let mut rows = 0;
+    for table in tables {
+        let columns: Vec<&str> = table.entry.columns.iter().map(|c| c.name.as_str()).collect();
+        let sql = format!(
+            "INSERT INTO \"{}\" ({}) VALUES ({})",
+            table.entry.name,
+            columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        for row in &table.rows {
+            let mut query = sqlx::query(&sql);
+            for column in &columns {
+                query = match row.get(*column) {
+                    None | Some(Value::Null) => query.bind(None::<String>),
+                    Some(Value::Bool(b)) => query.bind(*b),
+                    Some(Value::Number(n)) => match n.as_i64() {
+                        Some(i) => query.bind(i),
+                        None => query.bind(n.as_f64()),
+                    },
+                    Some(Value::String(s)) => query.bind(s.clone()),
+                    Some(other) => query.bind(other.to_string()),
+                };
+            }
+            query.execute(&mut *tx).await?;
+        }
+        rows += table.rows.len();
+    }
+    tx.commit().await?;
+    Ok(RestoreSummary { tables: tables.len(), rows })
+}
+
+/// Validate an archive and restore it into the database at `database_url`
+/// (`postgres://…` or `sqlite:…`).
+pub async fn restore(database_url: &str, bytes: &[u8]) -> Result<RestoreSummary, AppError> {
+    let (_, tables) = read_archive(bytes)?;
+    if database_url.starts_with("sqlite:") {
+        let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().max_connections(1).connect_with(options).await?;
+        let summary = restore_sqlite(&pool, &tables).await;
+        pool.close().await;
+        summary
+    } else {
+        let pool = sqlx::postgres::PgPoolOptions::new().max_connections(1).connect(database_url).await?;
+        let summary = restore_postgres(&pool, &tables).await;
+        pool.close().await;
+        summary
+    }
+}
@@ -0,0 +1,416 @@
+//! Service for tournament stages: group tables and knockout brackets.
+//! Knockout matches carry a round and bracket position; the winner of
+//! round `r`, position `p` plays in round `r + 1`, position `p / 2`.
+
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::errors::AppError;
+use crate::services::standings::{self, StandingRow};
+
+/// Placeholder team name for a bracket slot whose occupant is not yet known.
+pub const TBD: &str = "TBD";
+
+/// Which side of a fixture a team plays on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Home,
+    Away,
+}
+
+pub fn validate_format(format: &str) -> bool {
+    matches!(format, "league" | "groups_knockout" | "single_elimination")
+}
+
+pub fn validate_stage_type(stage_type: &str) -> bool {
+    matches!(stage_type, "group" | "knockout")
+}
+
+/// Whether a tournament of `format` may contain a stage of `stage_type`.
+pub fn format_allows_stage(format: &str, stage_type: &str) -> bool {
+    match format {
+        "league" => stage_type == "group",
+        "single_elimination" => stage_type == "knockout",
+        _ => true,
+    }
+}
+
+/// Decide the winner of a knockout match.
+/// A level score after normal and extra time is decided by the 7-metre shootout.
+/// Returns None while the match (or its shootout) is undecided.
+pub fn decide_winner(home_score: Option<i32>, away_score: Option<i32>, home_shootout: Option<i32>, away_shootout: Option<i32>) -> Option<Side> {
+    let (home, away) = (home_score?, away_score?);
+    if home != away {
+        return Some(if home > away { Side::Home } else { Side::Away });
+    }
+    let (home, away) = (home_shootout?, away_shootout?);
+    if home != away {
+        Some(if home > away { Side::Home } else { Side::Away })
+    } else {
+        None
+    }
+}
+
+/// The round, position and side the winner of a bracket match moves to.
+pub fn next_slot(round: i32, bracket_position: i32) -> (i32, i32, Side) {
+    let side = if bracket_position % 2 == 0 { Side::Home } else { Side::Away };
+    (round + 1, bracket_position / 2, side)
+}
+
+/// Seeds (1-based) in bracket slot order for a bracket of `size` slots,
+/// so that seed 1 and seed 2 can only meet in the final.
+/// `size` must be a power of two.
+pub fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![1];
+    while order.len() < size {
+        let n = order.len() * 2;
+        order = order.iter().flat_map(|&s| [s, n + 1 - s]).collect();
+    }
+    order
+}
+
+/// Display name of a knockout round counted from the final backwards.
+pub fn round_name(round: i32, total_rounds: i32) -> String {
+    match total_rounds - round {
+        0 => "Final".to_string(),
+        1 => "Semi-finals".to_string(),
+        2 => "Quarter-finals".to_string(),
+        n => format!("Round of {}", 2_i32.pow(n as u32 + 1)),
+    }
+}
+
+/// A first-round pairing or bye: `(position, home, away)`; a `None` side is a bye.
+pub type Pairing = (i32, Option<String>, Option<String>);
+
+/// Pair seeded `teams` (best first) for the first round of a knockout bracket.
+/// Byes are given to the top seeds when the field is not a power of two.
+pub fn first_round_pairings(teams: &[String]) -> Vec<Pairing> {
+    let size = teams.len().next_power_of_two();
+    let slots: Vec<Option<String>> = seed_order(size).into_iter().map(|s| teams.get(s - 1).cloned()).collect();
+    slots
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| (i as i32, pair[0].clone(), pair[1].clone()))
+        .collect()
+}
+
+// ─── Bracket view ────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct BracketMatch {
+    pub id: i64,
+    pub match_date: String,
+    pub home_team: String,
+    pub away_team: String,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
+    pub home_shootout: Option<i32>,
+    pub away_shootout: Option<i32>,
+    pub bracket_position: Option<i32>,
+    pub winner: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct GroupView {
+    pub id: i64,
+    pub name: String,
+    pub standings: Vec<StandingRow>,
+    pub matches: Vec<BracketMatch>,
+}
+
+#[derive(Serialize)]
+pub struct RoundView {
+    pub round: i32,
+    pub name: String,
+    pub matches: Vec<BracketMatch>,
+}
+
+#[derive(Serialize)]
+pub struct StageView {
+    pub id: i64,
+    pub name: String,
+    pub stage_type: String,
+    pub position: i32,
+    pub groups: Vec<GroupView>,
+    pub rounds: Vec<RoundView>,
+}
+
+#[derive(Serialize)]
+pub struct BracketResponse {
+    pub tournament_id: i64,
+    pub tournament_name: String,
+    pub format: String,
+    pub stages: Vec<StageView>,
+}
+
+type MatchRow = (i64, chrono::NaiveDate, String, String, Option<i32>, Option<i32>, Option<i32>, Option<i32>, Option<i32>, Option<i32>, Option<i64>);
+
+fn to_bracket_match(row: MatchRow) -> (Option<i32>, Option<i64>, BracketMatch) {
+    let (id, date, home_team, away_team, home_score, away_score, home_shootout, away_shootout, round, bracket_position, group_id) = row;
+    let winner = decide_winner(home_score, away_score, home_shootout, away_shootout).map(|side| match side {
+        Side::Home => home_team.clone(),
+        Side::Away => away_team.clone(),
+    });
+    (
+        round,
+        group_id,
+        BracketMatch {
+            id,
+            match_date: date.to_string(),
+            home_team,
+            away_team,
+            home_score,
+            away_score,
+            home_shootout,
+            away_shootout,
+            bracket_position,
+            winner,
+        },
+    )
+}
+
+/// Load all stages of a tournament with group tables and knockout rounds.
+/// Returns None if the tournament does not exist.
+pub async fn load_bracket(pool: &PgPool, tournament_id: i64) -> Result<Option<BracketResponse>, sqlx::Error> {
+    let tournament = sqlx::query_as::<_, (String, String)>("SELECT name, format FROM tournaments WHERE id = $1 AND deleted_at IS NULL")
+        .bind(tournament_id)
+        .fetch_optional(pool)
+        .await?;
+    let (tournament_name, format) = match tournament {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let rules = standings::load_rules(pool, tournament_id)
+        .await?
+        .map(|(_, rules)| rules)
+        .unwrap_or_default();
+
+    let stage_rows = sqlx::query_as::<_, (i64, String, String, i32)>(
+        "SELECT id, name, stage_type, position FROM tournament_stages WHERE tournament_id = $1 ORDER BY position, id",
+    )
+    .bind(tournament_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut stages = Vec::new();
+    for (stage_id, name, stage_type, position) in stage_rows {
+        let matches = sqlx::query_as::<_, MatchRow>(
+            "SELECT id, date, home_team, away_team, home_score, away_score, home_shootout, away_shootout, round, bracket_position, group_id \
+             FROM matches WHERE stage_id = $1 AND deleted_at IS NULL ORDER BY round NULLS FIRST, bracket_position NULLS LAST, date, id",
+        )
+        .bind(stage_id)
+        .fetch_all(pool)
+        .await?;
+        let mut matches: Vec<(Option<i32>, Option<i64>, BracketMatch)> = matches.into_iter().map(to_bracket_match).collect();
+
+        let mut groups = Vec::new();
+        let group_rows = sqlx::query_as::<_, (i64, String)>("SELECT id, name FROM tournament_groups WHERE stage_id = $1 ORDER BY name")
+            .bind(stage_id)
+            .fetch_all(pool)
+            .await?;
+        for (group_id, group_name) in group_rows {
+            let (in_group, rest): (Vec<_>, Vec<_>) = matches.into_iter().partition(|(_, g, _)| *g == Some(group_id));
+            matches = rest;
+            groups.push(GroupView {
+                id: group_id,
+                name: group_name,
+                standings: standings::compute_group_standings(pool, group_id, &rules).await?,
+                matches: in_group.into_iter().map(|(_, _, m)| m).collect(),
+            });
+        }
+
+        let total_rounds = matches.iter().filter_map(|(r, _, _)| *r).max().unwrap_or(0);
+        let mut rounds: Vec<RoundView> = Vec::new();
+        for (round, _, m) in matches {
+            let round = round.unwrap_or(0);
+            match rounds.last_mut() {
+                Some(r) if r.round == round => r.matches.push(m),
+                _ => rounds.push(RoundView {
+                    round,
+                    name: if round > 0 { round_name(round, total_rounds) } else { "Unscheduled".to_string() },
+                    matches: vec![m],
+                }),
+            }
+        }
+
+        stages.push(StageView { id: stage_id, name, stage_type, position, groups, rounds });
+    }
+
+    Ok(Some(BracketResponse { tournament_id, tournament_name, format, stages }))
+}
+
+// ─── Bracket generation and advancement ─────────────────────────────
+
+/// Qualifiers from the group stages of a tournament: the top `per_group`
+/// of every group, ordered all group winners first, then all runners-up.
+pub async fn group_qualifiers(pool: &PgPool, tournament_id: i64, per_group: usize) -> Result<Vec<String>, sqlx::Error> {
+    let rules = standings::load_rules(pool, tournament_id)
+        .await?
+        .map(|(_, rules)| rules)
+        .unwrap_or_default();
+    let groups = sqlx::query_scalar::<_, i64>(
+        "SELECT g.id FROM tournament_groups g JOIN tournament_stages s ON g.stage_id = s.id \
+         WHERE s.tournament_id = $1 AND s.stage_type = 'group' ORDER BY s.position, g.name",
+    )
+    .bind(tournament_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut tables = Vec::new();
+    for group_id in groups {
+        tables.push(standings::compute_group_standings(pool, group_id, &rules).await?);
+    }
+    let mut qualifiers = Vec::new();
+    for place in 0..per_group {
+        for table in &tables {
+            if let Some(row) = table.get(place) {
+                qualifiers.push(row.team.clone());
+            }
+        }
+    }
+    Ok(qualifiers)
+}
+
+/// Create every match of a knockout stage for seeded `teams`.
+/// Later rounds are created with `TBD` teams and filled as winners advance;
+/// teams with a first-round bye are placed straight into round two.
+/// Returns the number of matches created.
+pub async fn generate_knockout(
+    tx: &mut Transaction<'_, Postgres>,
+    tournament_id: i64,
+    stage_id: i64,
+    teams: &[String],
+    date: chrono::NaiveDate,
+    location: Option<&str>,
+) -> Result<usize, sqlx::Error> {
+    let size = teams.len().next_power_of_two();
+    let total_rounds = size.trailing_zeros() as i32;
+    let mut created = 0;
+
+    for round in 2..=total_rounds {
+        let matches_in_round = (size >> round) as i32;
+        for position in 0..matches_in_round {
+            insert_bracket_match(tx, tournament_id, stage_id, round, position, TBD, TBD, date, location).await?;
+            created += 1;
+        }
+    }
+
+    for (position, home, away) in first_round_pairings(teams) {
+        match (home, away) {
+            (Some(home), Some(away)) => {
+                insert_bracket_match(tx, tournament_id, stage_id, 1, position, &home, &away, date, location).await?;
+                created += 1;
+            }
+            (Some(team), None) | (None, Some(team)) => {
+                let (round, next_position, side) = next_slot(1, position);
+                place_team(tx, stage_id, round, next_position, side, &team).await?;
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(created)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_bracket_match(
+    tx: &mut Transaction<'_, Postgres>,
+    tournament_id: i64,
+    stage_id: i64,
+    round: i32,
+    position: i32,
+    home_team: &str,
+    away_team: &str,
+    date: chrono::NaiveDate,
+    location: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO matches (date, home_team, away_team, location, tournament_id, season_id, stage_id, round, bracket_position, club_id) \
+         SELECT $1, $2, $3, $4, t.id, t.season_id, $5, $6, $7, t.club_id FROM tournaments t WHERE t.id = $8",
+    )
+    .bind(date)
+    .bind(home_team)
+    .bind(away_team)
+    .bind(location)
+    .bind(stage_id)
+    .bind(round)
+    .bind(position)
+    .bind(tournament_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn place_team(
+    tx: &mut Transaction<'_, Postgres>,
+    stage_id: i64,
+    round: i32,
+    position: i32,
+    side: Side,
+    team: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    let sql = match side {
+        Side::Home => "UPDATE matches SET home_team = $1, updated_at = NOW() WHERE stage_id = $2 AND round = $3 AND bracket_position = $4 AND deleted_at IS NULL RETURNING id",
+        Side::Away => "UPDATE matches SET away_team = $1, updated_at = NOW() WHERE stage_id = $2 AND round = $3 AND bracket_position = $4 AND deleted_at IS NULL RETURNING id",
+    };
+    sqlx::query_scalar::<_, i64>(sql)
+        .bind(team)
+        .bind(stage_id)
+        .bind(round)
+        .bind(position)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+/// Move the winner of a knockout match into its slot in the next round.
+/// Does nothing for non-knockout matches and the final. When a corrected
+/// result no longer decides a winner the slot goes back to TBD, and once the
+/// next match has a result its line-up can no longer change.
+/// Returns the id of the match the winner was placed into.
+pub async fn advance_winner(tx: &mut Transaction<'_, Postgres>, match_id: i64) -> Result<Option<i64>, AppError> {
+    let row = sqlx::query_as::<_, (i64, i32, i32, String, String, Option<i32>, Option<i32>, Option<i32>, Option<i32>)>(
+        "SELECT m.stage_id, m.round, m.bracket_position, m.home_team, m.away_team, \
+                m.home_score, m.away_score, m.home_shootout, m.away_shootout \
+         FROM matches m JOIN tournament_stages s ON m.stage_id = s.id \
+         WHERE m.id = $1 AND m.deleted_at IS NULL AND s.stage_type = 'knockout' AND m.round IS NOT NULL AND m.bracket_position IS NOT NULL",
+    )
+    .bind(match_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+    let (stage_id, round, position, home_team, away_team, home_score, away_score, home_shootout, away_shootout) = match row {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+    let winner = match decide_winner(home_score, away_score, home_shootout, away_shootout) {
+        Some(Side::Home) => Some(home_team),
+        Some(Side::Away) => Some(away_team),
+        None => None,
+    };
+    let (next_round, next_position, side) = next_slot(round, position);
+    let next = sqlx::query_as::<_, (i64, String, String, bool)>(
+        "SELECT id, home_team, away_team, home_score IS NOT NULL OR away_score IS NOT NULL \
+         FROM matches WHERE stage_id = $1 AND round = $2 AND bracket_position = $3 AND deleted_at IS NULL",
+    )
+    .bind(stage_id)
+    .bind(next_round)
+    .bind(next_position)
+    .fetch_optional(&mut **tx)
+    .await?;
+    let (next_id, next_home, next_away, next_played) = match next {
+        Some(n) => n,
+        None => return Ok(None),
+    };
+    let occupant = match side {
+        Side::Home => next_home,
+        Side::Away => next_away,
+    };
+    let team = winner.as_deref().unwrap_or(TBD);
+    if occupant != team {
+        if next_played {
+            return Err(AppError::Conflict(
+                "The next round match already has a result; correct that result first".into(),
+            ));
+        }
+        place_team(tx, stage_id, next_round, next_position, side, team).await?;
+    }
+    Ok(winner.map(|_| next_id))
+}
@@ -0,0 +1,229 @@
+//! Clubs sharing the installation, and which club a request is for.
+//!
+//! The club (tenant) comes from the `X-Club` header, else from the subdomain
+//! of the `Host` header under `tenancy.base_domain`, else it is
+//! `tenancy.default_club`. The auth middleware then refuses tokens issued by
+//! another club, so signed-in handlers can scope by `claims.club_id`.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+
+use crate::auth::Claims;
+use crate::config::{self, TenancyConfig};
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::{password, totp};
+
+/// Header naming the club by slug, for API clients and local development.
+pub const CLUB_HEADER: &str = "x-club";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Club {
+    pub id: i64,
+    pub slug: String,
+    pub name: String,
+    pub logo_url: Option<String>,
+    pub primary_color: Option<String>,
+}
+
+/// The club label in `host` (port ignored), e.g. `hawks` for `hawks.example.com`
+/// with base domain `example.com`. None for the bare domain, `www` and other hosts.
+pub fn slug_from_host(host: &str, base_domain: &str) -> Option<String> {
+    if base_domain.is_empty() {
+        return None;
+    }
+    let host = host.split(':').next().unwrap_or_default().to_ascii_lowercase();
+    let label = host.strip_suffix(&base_domain.to_ascii_lowercase())?.strip_suffix('.')?;
+    (!label.is_empty() && !label.contains('.') && label != "www").then(|| label.to_string())
+}
+
+/// The slug a request asks for: header, then subdomain, then the default club.
+pub fn requested_slug(headers: &HeaderMap, tenancy: &TenancyConfig) -> String {
+    let header = headers
+        .get(CLUB_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+    let host = || {
+        headers
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|host| slug_from_host(host, &tenancy.base_domain))
+    };
+    header.or_else(host).unwrap_or_else(|| tenancy.default_club.clone())
+}
+
+pub fn validate_slug(slug: &str) -> Result<(), AppError> {
+    let valid = slug.len() <= 50
+        && slug.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest("Club slug must be lowercase letters, digits and dashes".into()))
+    }
+}
+
+const CLUB_COLUMNS: &str = "id, slug, name, logo_url, primary_color";
+
+pub async fn by_slug<'e>(executor: impl PgExecutor<'e>, slug: &str) -> Result<Option<Club>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM clubs WHERE slug = $1", CLUB_COLUMNS))
+        .bind(slug)
+        .fetch_optional(executor)
+        .await
+}
+
+pub async fn get<'e>(executor: impl PgExecutor<'e>, id: i64) -> Result<Option<Club>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM clubs WHERE id = $1", CLUB_COLUMNS))
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+}
+
+pub async fn list<'e>(executor: impl PgExecutor<'e>) -> Result<Vec<Club>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM clubs ORDER BY slug", CLUB_COLUMNS))
+        .fetch_all(executor)
+        .await
+}
+
+/// Middleware: resolve the club for every request and add it as an extension.
+/// 404 for a club that does not exist.
+pub async fn resolve_tenant(State(pool): State<PgPool>, mut req: Request, next: Next) -> Result<Response, AppError> {
+    let slug = requested_slug(req.headers(), &config::get().tenancy);
+    let club = by_slug(&pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Unknown club '{}'", slug)))?;
+    req.extensions_mut().insert(club);
+    Ok(next.run(req).await)
+}
+
+/// 404 unless row `id` of `table` belongs to the club. `table` must be a fixed
+/// name of a table with a `club_id` column, never user input.
+pub async fn require_owned<'e>(executor: impl PgExecutor<'e>, table: &str, id: i64, club_id: i64, what: &str) -> Result<(), AppError> {
+    let owned: bool = sqlx::query_scalar(&format!("SELECT EXISTS (SELECT 1 FROM {} WHERE id = $1 AND club_id = $2)", table))
+        .bind(id)
+        .bind(club_id)
+        .fetch_one(executor)
+        .await?;
+    if owned {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(format!("{} not found", what)))
+    }
+}
+
+/// Whether this is the default club, whose admins manage the installation.
+pub fn is_host_club(club: &Club) -> bool {
+    club.slug == config::get().tenancy.default_club
+}
+
+/// Allow admins of the default club only: for backups and other installation-wide data.
+pub fn require_host_admin(claims: &Claims, club: &Club) -> Result<(), AppError> {
+    if claims.role == "admin" && claims.club_id == club.id && is_host_club(club) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("Only admins of the host club can do this".into()))
+    }
+}
+
+fn validate_branding(name: &str, logo_url: Option<&str>, primary_color: Option<&str>) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest("Club name is required".into()));
+    }
+    if let Some(url) = logo_url {
+        if !url.starts_with('/') && !url.starts_with("https://") {
+            return Err(AppError::BadRequest("Logo URL must be a path on this site or an https:// URL".into()));
+        }
+    }
+    if let Some(color) = primary_color {
+        let hex = color.strip_prefix('#').unwrap_or_default();
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(AppError::BadRequest("Primary colour must look like #f97316".into()));
+        }
+    }
+    Ok(())
+}
+
+/// Create a club. Its first admin is added separately.
+pub async fn create(conn: &mut PgConnection, ctx: &AuditContext, slug: &str, name: &str) -> Result<i64, AppError> {
+    validate_slug(slug)?;
+    validate_branding(name, None, None)?;
+    let id: i64 = sqlx::query_scalar("INSERT INTO clubs (slug, name) VALUES ($1, $2) RETURNING id")
+        .bind(slug)
+        .bind(name.trim())
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| match &e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict("A club with that slug already exists".into()),
+            _ => AppError::Database(e),
+        })?;
+    let after = audit::snapshot(&mut *conn, "clubs", id).await?;
+    audit::record(conn, &ctx.clone().with_club(id), "create", "club", Some(id), None, after).await?;
+    Ok(id)
+}
+
+/// Change the name, logo and colour shown on the club's pages.
+pub async fn update_branding(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    id: i64,
+    name: &str,
+    logo_url: Option<&str>,
+    primary_color: Option<&str>,
+) -> Result<(), AppError> {
+    validate_branding(name, logo_url, primary_color)?;
+    let before = audit::snapshot(&mut *conn, "clubs", id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Club not found".into()))?;
+    sqlx::query("UPDATE clubs SET name = $2, logo_url = $3, primary_color = $4 WHERE id = $1")
+        .bind(id)
+        .bind(name.trim())
+        .bind(logo_url)
+        .bind(primary_color)
+        .execute(&mut *conn)
+        .await?;
+    let after = audit::snapshot(&mut *conn, "clubs", id).await?;
+    audit::record(conn, ctx, "update_branding", "club", Some(id), Some(before), after).await?;
+    Ok(())
+}
+
+/// Add an admin account to a club, such as a new club's first admin.
+/// Returns the user id and a random temporary password, shown only once.
+pub async fn add_admin(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, email: &str, name: &str) -> Result<(i64, String), AppError> {
+    let email = email.trim();
+    if !email.contains('@') || name.trim().is_empty() {
+        return Err(AppError::BadRequest("An email address and a name are required".into()));
+    }
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE email = $1 AND club_id = $2)")
+        .bind(email)
+        .bind(club_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if exists {
+        return Err(AppError::Conflict("Email already registered".into()));
+    }
+    let mut bytes = [0u8; 15];
+    OsRng.fill_bytes(&mut bytes);
+    let temporary = totp::base32_encode(&bytes).to_lowercase();
+    let password_hash = password::hash(&config::get().password, &temporary)?;
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO users (email, password_hash, name, role_id, status, club_id, created_at) \
+         SELECT $1, $2, $3, r.id, 'active', $4, NOW() FROM roles r WHERE r.name = 'admin' RETURNING id",
+    )
+    .bind(email)
+    .bind(&password_hash)
+    .bind(name.trim())
+    .bind(club_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "users", id).await?;
+    audit::record(conn, &ctx.clone().with_club(club_id), "create", "user", Some(id), None, after).await?;
+    Ok((id, temporary))
+}
@@ -0,0 +1,280 @@
+//! Disciplinary record: cards and 2-minute suspensions per player and
+//! tournament, and the match bans they lead to.
+//!
+//! Cards are match events (`yellow_card`, `suspension` for 2 minutes,
+//! `red_card`, `blue_card` for a red card with a written report). Each
+//! tournament has rules of the form "every Nth event of a type bans the
+//! player for M matches". A ban covers the next M fixtures of the same team
+//! in the tournament after the match it was given in; a fixture counts as
+//! served once it has been played.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{PgConnection, PgExecutor};
+use std::collections::HashMap;
+
+use crate::services::audit::{self, AuditContext};
+use crate::services::notifications::notify;
+use crate::services::teams::{self, TeamScope};
+
+pub const CARD_EVENTS: &[&str] = &["yellow_card", "suspension", "red_card", "blue_card"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Rule {
+    pub event_type: String,
+    /// Every `threshold`-th event of the type in the tournament triggers a ban.
+    pub threshold: i32,
+    pub ban_matches: i32,
+}
+
+/// Rules of a tournament that has none configured: a red card with a report
+/// bans the player for the next match.
+pub fn default_rules() -> Vec<Rule> {
+    vec![Rule { event_type: "blue_card".to_string(), threshold: 1, ban_matches: 1 }]
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Tally {
+    pub player_id: i64,
+    pub player_name: String,
+    pub tournament_id: i64,
+    pub tournament_name: String,
+    pub yellow_cards: i64,
+    pub two_minutes: i64,
+    pub red_cards: i64,
+    pub blue_cards: i64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Ban {
+    pub id: i64,
+    pub player_id: i64,
+    pub player_name: String,
+    pub tournament_id: i64,
+    /// The match the ban was given in.
+    pub match_id: i64,
+    pub reason: String,
+    pub matches: i32,
+    /// Later fixtures of the team in the tournament already played, up to `matches`.
+    pub served: i64,
+    pub remaining: i64,
+}
+
+/// Check a rule set. Returns every problem found.
+pub fn validate_rules(rules: &[Rule]) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    for (i, rule) in rules.iter().enumerate() {
+        if !CARD_EVENTS.contains(&rule.event_type.as_str()) {
+            errors.push(format!("Unknown event type '{}'. Use one of: {}", rule.event_type, CARD_EVENTS.join(", ")));
+        }
+        if rules[..i].iter().any(|r| r.event_type == rule.event_type) {
+            errors.push(format!("'{}' has more than one rule", rule.event_type));
+        }
+        if rule.threshold < 1 || rule.ban_matches < 1 {
+            errors.push(format!("The rule for '{}' needs a threshold and a ban of at least 1", rule.event_type));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// The rules of a tournament, or the defaults when it has none.
+pub async fn rules<'e>(executor: impl PgExecutor<'e>, tournament_id: i64) -> Result<Vec<Rule>, sqlx::Error> {
+    let rules: Vec<Rule> = sqlx::query_as(
+        "SELECT event_type, threshold, ban_matches FROM disciplinary_rules WHERE tournament_id = $1 ORDER BY event_type",
+    )
+    .bind(tournament_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(if rules.is_empty() { default_rules() } else { rules })
+}
+
+/// Replace a tournament's rules; an empty list restores the defaults.
+/// Bans already given are kept.
+pub async fn set_rules(conn: &mut PgConnection, ctx: &AuditContext, tournament_id: i64, new_rules: &[Rule]) -> Result<(), sqlx::Error> {
+    let before = rules(&mut *conn, tournament_id).await?;
+    sqlx::query("DELETE FROM disciplinary_rules WHERE tournament_id = $1")
+        .bind(tournament_id)
+        .execute(&mut *conn)
+        .await?;
+    for rule in new_rules {
+        sqlx::query("INSERT INTO disciplinary_rules (tournament_id, event_type, threshold, ban_matches) VALUES ($1, $2, $3, $4)")
+            .bind(tournament_id)
+            .bind(&rule.event_type)
+            .bind(rule.threshold)
+            .bind(rule.ban_matches)
+            .execute(&mut *conn)
+            .await?;
+    }
+    let after = rules(&mut *conn, tournament_id).await?;
+    audit::record(
+        conn,
+        ctx,
+        "update_disciplinary_rules",
+        "tournament",
+        Some(tournament_id),
+        Some(json!({ "rules": before })),
+        Some(json!({ "rules": after })),
+    )
+    .await
+}
+
+fn describe(event_type: &str) -> &'static str {
+    match event_type {
+        "yellow_card" => "yellow card",
+        "suspension" => "2-minute suspension",
+        "red_card" => "red card",
+        _ => "red card with report",
+    }
+}
+
+/// Apply the tournament's rules to a newly recorded match event. Returns the
+/// id of the ban it triggered, if any; the player is notified.
+pub async fn apply_rules(conn: &mut PgConnection, ctx: &AuditContext, event_id: i64) -> Result<Option<i64>, sqlx::Error> {
+    let event = sqlx::query_as::<_, (i64, String, i64, Option<i64>)>(
+        "SELECT e.player_id, e.event_type, e.match_id, m.tournament_id FROM match_events e JOIN matches m ON e.match_id = m.id WHERE e.id = $1",
+    )
+    .bind(event_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let (player_id, event_type, match_id, Some(tournament_id)) = event else {
+        return Ok(None);
+    };
+    let Some(rule) = rules(&mut *conn, tournament_id).await?.into_iter().find(|r| r.event_type == event_type) else {
+        return Ok(None);
+    };
+
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM match_events e JOIN matches m ON e.match_id = m.id \
+         WHERE e.player_id = $1 AND e.event_type = $2 AND m.tournament_id = $3 AND m.deleted_at IS NULL",
+    )
+    .bind(player_id)
+    .bind(&event_type)
+    .bind(tournament_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if count % i64::from(rule.threshold) != 0 {
+        return Ok(None);
+    }
+
+    let reason = if rule.threshold == 1 {
+        describe(&event_type).to_string()
+    } else {
+        format!("{} {}s in the tournament", count, describe(&event_type))
+    };
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO match_bans (player_id, tournament_id, match_id, event_id, matches, reason) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+    )
+    .bind(player_id)
+    .bind(tournament_id)
+    .bind(match_id)
+    .bind(event_id)
+    .bind(rule.ban_matches)
+    .bind(&reason)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "match_bans", id).await?;
+    audit::record(conn, ctx, "create", "match_ban", Some(id), None, after).await?;
+
+    let user_id: i64 = sqlx::query_scalar("SELECT user_id FROM players WHERE id = $1")
+        .bind(player_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let matches = if rule.ban_matches == 1 { "the next match".to_string() } else { format!("the next {} matches", rule.ban_matches) };
+    notify(&mut *conn, user_id, "Match ban", &format!("You are banned for {} ({}).", matches, reason), None).await?;
+    Ok(Some(id))
+}
+
+const TALLY_SELECT: &str = "SELECT e.player_id, p.first_name || ' ' || p.last_name AS player_name, t.id AS tournament_id, \
+     t.name AS tournament_name, \
+     COUNT(*) FILTER (WHERE e.event_type = 'yellow_card') AS yellow_cards, \
+     COUNT(*) FILTER (WHERE e.event_type = 'suspension') AS two_minutes, \
+     COUNT(*) FILTER (WHERE e.event_type = 'red_card') AS red_cards, \
+     COUNT(*) FILTER (WHERE e.event_type = 'blue_card') AS blue_cards \
+     FROM match_events e JOIN matches m ON e.match_id = m.id JOIN tournaments t ON m.tournament_id = t.id \
+     JOIN players p ON e.player_id = p.id \
+     WHERE m.deleted_at IS NULL AND e.event_type IN ('yellow_card', 'suspension', 'red_card', 'blue_card')";
+
+/// Card counts per player in a tournament, for players visible under `scope`.
+pub async fn tournament_tallies<'e>(executor: impl PgExecutor<'e>, tournament_id: i64, scope: &TeamScope) -> Result<Vec<Tally>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "{} AND t.id = $2 AND {} GROUP BY e.player_id, p.first_name, p.last_name, t.id, t.name ORDER BY p.last_name, p.first_name",
+        TALLY_SELECT,
+        teams::user_in_scope("p.user_id", 1)
+    ))
+    .bind(scope.ids())
+    .bind(tournament_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// A player's card counts in every tournament they have any in.
+pub async fn player_tallies<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Tally>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "{} AND e.player_id = $1 GROUP BY e.player_id, p.first_name, p.last_name, t.id, t.name, t.start_date ORDER BY t.start_date DESC",
+        TALLY_SELECT
+    ))
+    .bind(player_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Fixtures of the banned match's team in the tournament after it, as SQL
+/// over the ban `b` and its match `bm`.
+const LATER_FIXTURES: &str = "FROM matches f WHERE f.tournament_id = b.tournament_id AND f.deleted_at IS NULL \
+     AND f.team_id IS NOT DISTINCT FROM bm.team_id AND (f.date, f.id) > (bm.date, bm.id)";
+
+fn ban_select() -> String {
+    format!(
+        "SELECT b.id, b.player_id, p.first_name || ' ' || p.last_name AS player_name, b.tournament_id, b.match_id, b.reason, b.matches, \
+         LEAST(b.matches, (SELECT COUNT(*) {later} AND (f.date < CURRENT_DATE OR f.home_score IS NOT NULL))) AS served, \
+         b.matches - LEAST(b.matches, (SELECT COUNT(*) {later} AND (f.date < CURRENT_DATE OR f.home_score IS NOT NULL))) AS remaining \
+         FROM match_bans b JOIN matches bm ON b.match_id = bm.id JOIN players p ON b.player_id = p.id",
+        later = LATER_FIXTURES
+    )
+}
+
+/// Bans given in a tournament to players visible under `scope`, most recent first.
+pub async fn tournament_bans<'e>(executor: impl PgExecutor<'e>, tournament_id: i64, scope: &TeamScope) -> Result<Vec<Ban>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "{} WHERE b.tournament_id = $2 AND {} ORDER BY bm.date DESC, b.id DESC",
+        ban_select(),
+        teams::user_in_scope("p.user_id", 1)
+    ))
+    .bind(scope.ids())
+    .bind(tournament_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// A player's bans in every tournament, most recent first.
+pub async fn player_bans<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Ban>, sqlx::Error> {
+    sqlx::query_as(&format!("{} WHERE b.player_id = $1 ORDER BY bm.date DESC, b.id DESC", ban_select()))
+        .bind(player_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// Players banned from a match: the match is one of the first `matches`
+/// fixtures after the one the ban was given in.
+pub async fn banned_players<'e>(executor: impl PgExecutor<'e>, match_id: i64) -> Result<HashMap<i64, String>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, String, i32, i64)>(&format!(
+        "SELECT b.player_id, b.reason, b.matches, (SELECT COUNT(*) {} AND (f.date, f.id) < (m.date, m.id)) AS before \
+         FROM match_bans b JOIN matches bm ON b.match_id = bm.id JOIN matches m ON m.id = $1 \
+         WHERE m.tournament_id = b.tournament_id AND m.team_id IS NOT DISTINCT FROM bm.team_id AND (m.date, m.id) > (bm.date, bm.id)",
+        LATER_FIXTURES
+    ))
+    .bind(match_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter(|(_, _, matches, before)| *before < i64::from(*matches))
+        .map(|(player_id, reason, matches, before)| {
+            (player_id, format!("banned ({}, match {} of {})", reason, before + 1, matches))
+        })
+        .collect())
+}
@@ -0,0 +1,336 @@
+//! Service for exporting matches, events, attendance, rosters and fitness results as CSV, JSON Lines or XLSX.
+//! Rows are fetched from Postgres as JSON objects (`row_to_json`) so one query per
+//! dataset serves every format. CSV and JSON Lines are streamed in chunks; XLSX is
+//! built in memory because the workbook is a zip archive.
+
+use axum::body::Bytes;
+use rust_xlsxwriter::{Format, Workbook};
+use serde_json::Value;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+/// Rows per streamed chunk.
+const CHUNK_ROWS: usize = 500;
+
+/// Output format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<ExportFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" | "ndjson" => Some(ExportFormat::JsonLines),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// Pick a format from an `Accept` header; CSV when nothing matches.
+    pub fn from_accept(accept: &str) -> ExportFormat {
+        if accept.contains("spreadsheetml") {
+            ExportFormat::Xlsx
+        } else if accept.contains("ndjson") || accept.contains("jsonl") {
+            ExportFormat::JsonLines
+        } else {
+            ExportFormat::Csv
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::JsonLines => "application/x-ndjson",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// Season, date range and team filters. Dates compare against the match date
+/// (the test date for fitness results).
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub club_id: i64,
+    pub season_id: Option<i64>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    /// Only these teams; `None` for the whole club.
+    pub teams: Option<Vec<i64>>,
+}
+
+/// A table that can be exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportDataset {
+    Matches,
+    Events,
+    Attendance,
+    Players,
+    Fitness,
+}
+
+/// Filter on the match table aliased `m`, using bind parameters $1..$5.
+/// Matches in the trash are never exported.
+const MATCH_FILTER: &str = "m.deleted_at IS NULL AND m.club_id = $5 AND ($1::bigint IS NULL OR m.season_id = $1) \
+     AND ($2::date IS NULL OR m.date >= $2) AND ($3::date IS NULL OR m.date <= $3) \
+     AND ($4::bigint[] IS NULL OR m.team_id = ANY($4))";
+
+impl ExportDataset {
+    pub fn parse(name: &str) -> Option<ExportDataset> {
+        match name {
+            "matches" => Some(ExportDataset::Matches),
+            "events" => Some(ExportDataset::Events),
+            "attendance" => Some(ExportDataset::Attendance),
+            "players" => Some(ExportDataset::Players),
+            "fitness" => Some(ExportDataset::Fitness),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportDataset::Matches => "matches",
+            ExportDataset::Events => "events",
+            ExportDataset::Attendance => "attendance",
+            ExportDataset::Players => "players",
+            ExportDataset::Fitness => "fitness",
+        }
+    }
+
+    /// Output columns, in order.
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            ExportDataset::Matches => &[
+                "id", "date", "season_id", "tournament_id", "tournament", "home_team", "away_team",
+                "location", "home_score", "away_score",
+            ],
+            ExportDataset::Events => &[
+                "id", "match_id", "match_date", "season_id", "player_id", "first_name", "last_name",
+                "event_type", "minute", "period", "is_fast_break", "is_penalty",
+            ],
+            ExportDataset::Attendance => &[
+                "id", "player_id", "first_name", "last_name", "match_id", "date", "attended",
+            ],
+            ExportDataset::Players => &[
+                "id", "first_name", "last_name", "email", "date_of_birth", "position", "jersey_number",
+            ],
+            ExportDataset::Fitness => &[
+                "id", "tested_on", "player_id", "first_name", "last_name", "test", "unit", "value", "notes",
+            ],
+        }
+    }
+
+    /// Inner query; rows are converted with `row_to_json` so the column names
+    /// above become the JSON keys.
+    fn query(&self) -> String {
+        match self {
+            ExportDataset::Matches => format!(
+                "SELECT m.id, m.date, m.season_id, m.tournament_id, t.name AS tournament, m.home_team, m.away_team, \
+                 m.location, m.home_score, m.away_score \
+                 FROM matches m LEFT JOIN tournaments t ON m.tournament_id = t.id \
+                 WHERE {MATCH_FILTER} ORDER BY m.date, m.id"
+            ),
+            ExportDataset::Events => format!(
+                "SELECT e.id, e.match_id, m.date AS match_date, m.season_id, e.player_id, p.first_name, p.last_name, \
+                 e.event_type, e.minute, e.period, e.is_fast_break, e.is_penalty \
+                 FROM match_events e JOIN matches m ON e.match_id = m.id JOIN players p ON e.player_id = p.id \
+                 WHERE {MATCH_FILTER} ORDER BY m.date, e.match_id, e.id"
+            ),
+            // Attendance without a match (training) only has its own date and no season.
+            ExportDataset::Attendance => "SELECT a.id, a.player_id, p.first_name, p.last_name, a.match_id, \
+                 COALESCE(a.date, m.date) AS date, a.attended \
+                 FROM attendance a JOIN players p ON a.player_id = p.id JOIN users u ON p.user_id = u.id \
+                 LEFT JOIN matches m ON a.match_id = m.id \
+                 WHERE u.deleted_at IS NULL AND u.club_id = $5 AND (m.id IS NULL OR m.deleted_at IS NULL) \
+                 AND ($1::bigint IS NULL OR m.season_id = $1) \
+                 AND ($2::date IS NULL OR COALESCE(a.date, m.date) >= $2) \
+                 AND ($3::date IS NULL OR COALESCE(a.date, m.date) <= $3) \
+                 AND ($4::bigint[] IS NULL OR a.team_id = ANY($4)) \
+                 ORDER BY COALESCE(a.date, m.date), a.id"
+                .to_string(),
+            // With filters, the roster is the players who were in a squad, recorded
+            // an event or had attendance in a matching match.
+            ExportDataset::Players => format!(
+                "SELECT p.id, p.first_name, p.last_name, u.email, p.date_of_birth, p.position, p.jersey_number \
+                 FROM players p JOIN users u ON p.user_id = u.id \
+                 WHERE u.deleted_at IS NULL AND u.club_id = $5 AND (($1::bigint IS NULL AND $2::date IS NULL AND $3::date IS NULL) OR p.id IN ( \
+                   SELECT x.player_id FROM ( \
+                     SELECT player_id, match_id FROM match_squads \
+                     UNION SELECT player_id, match_id FROM match_events \
+                     UNION SELECT player_id, match_id FROM attendance WHERE match_id IS NOT NULL \
+                   ) x JOIN matches m ON x.match_id = m.id WHERE {MATCH_FILTER})) \
+                 AND ($4::bigint[] IS NULL OR EXISTS ( \
+                   SELECT 1 FROM team_members tm WHERE tm.user_id = p.user_id AND tm.team_id = ANY($4))) \
+                 ORDER BY p.last_name, p.first_name, p.id"
+            ),
+            // Fitness tests are not tied to matches: the season filter uses the season's dates.
+            ExportDataset::Fitness => "SELECT r.id, r.tested_on, r.player_id, p.first_name, p.last_name, ft.name AS test, \
+                 ft.unit, r.value, r.notes \
+                 FROM fitness_results r JOIN fitness_test_types ft ON r.test_type_id = ft.id \
+                 JOIN players p ON r.player_id = p.id JOIN users u ON p.user_id = u.id \
+                 WHERE u.deleted_at IS NULL AND ft.club_id = $5 \
+                 AND ($1::bigint IS NULL OR EXISTS ( \
+                   SELECT 1 FROM seasons s WHERE s.id = $1 AND r.tested_on BETWEEN s.start_date AND s.end_date)) \
+                 AND ($2::date IS NULL OR r.tested_on >= $2) AND ($3::date IS NULL OR r.tested_on <= $3) \
+                 AND ($4::bigint[] IS NULL OR EXISTS ( \
+                   SELECT 1 FROM team_members tm WHERE tm.user_id = p.user_id AND tm.team_id = ANY($4))) \
+                 ORDER BY ft.name, r.tested_on, p.last_name, p.first_name, r.id"
+                .to_string(),
+        }
+    }
+}
+
+/// Text for a CSV cell: nulls are empty, strings unquoted.
+pub fn cell_text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Encode rows as CSV, optionally preceded by the header row.
+pub fn csv_chunk(columns: &[&str], rows: &[Value], header: bool) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if header {
+        writer.write_record(columns)?;
+    }
+    for row in rows {
+        writer.write_record(columns.iter().map(|c| cell_text(row.get(*c))))?;
+    }
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+/// Encode rows as an XLSX workbook with a bold header row.
+pub fn xlsx_workbook(sheet_name: &str, columns: &[&str], rows: &[Value]) -> Result<Vec<u8>, rust_xlsxwriter::XlsxError> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(sheet_name)?;
+    let bold = Format::new().set_bold();
+    for (col, name) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, &bold)?;
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let r = i as u32 + 1;
+        for (col, name) in columns.iter().enumerate() {
+            let c = col as u16;
+            match row.get(*name) {
+                None | Some(Value::Null) => {}
+                Some(Value::Number(n)) => {
+                    sheet.write_number(r, c, n.as_f64().unwrap_or_default())?;
+                }
+                Some(Value::Bool(b)) => {
+                    sheet.write_boolean(r, c, *b)?;
+                }
+                Some(Value::String(s)) => {
+                    sheet.write_string(r, c, s)?;
+                }
+                Some(other) => {
+                    sheet.write_string(r, c, other.to_string())?;
+                }
+            }
+        }
+    }
+    workbook.save_to_buffer()
+}
+
+fn json_query(dataset: ExportDataset) -> String {
+    format!("SELECT row_to_json(t)::text FROM ({}) t", dataset.query())
+}
+
+/// Load every row of a dataset. Used for XLSX, which cannot be streamed.
+pub async fn fetch_rows(pool: &PgPool, dataset: ExportDataset, filter: &ExportFilter) -> Result<Vec<Value>, sqlx::Error> {
+    let rows: Vec<(String,)> = sqlx::query_as(&json_query(dataset))
+        .bind(filter.season_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(&filter.teams)
+        .bind(filter.club_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(json,)| serde_json::from_str(&json).ok())
+        .collect())
+}
+
+/// Stream a dataset as CSV or JSON Lines chunks over a channel.
+///
+/// The query runs in a background task and sends a chunk every
+/// `CHUNK_ROWS` rows, so memory use does not grow with the table size.
+/// A database error ends the stream with an error, which aborts the response.
+pub fn stream_rows(
+    pool: PgPool,
+    dataset: ExportDataset,
+    filter: ExportFilter,
+    format: ExportFormat,
+) -> mpsc::Receiver<Result<Bytes, std::io::Error>> {
+    let (sender, receiver) = mpsc::channel(4);
+    tokio::spawn(async move {
+        use futures_util::TryStreamExt;
+
+        let columns = dataset.columns();
+        let sql = json_query(dataset);
+        let mut rows = sqlx::query_as::<_, (String,)>(&sql)
+            .bind(filter.season_id)
+            .bind(filter.from)
+            .bind(filter.to)
+            .bind(&filter.teams)
+            .bind(filter.club_id)
+            .fetch(&pool);
+
+        let encode = |batch: &[String], header: bool| -> Result<Bytes, std::io::Error> {
+            match format {
+                ExportFormat::JsonLines => Ok(Bytes::from(batch.iter().map(|line| format!("{}\n", line)).collect::<String>())),
+                _ => {
+                    let values: Vec<Value> = batch.iter().filter_map(|j| serde_json::from_str(j).ok()).collect();
+                    csv_chunk(columns, &values, header).map(Bytes::from).map_err(std::io::Error::other)
+                }
+            }
+        };
+
+        let mut batch: Vec<String> = Vec::with_capacity(CHUNK_ROWS);
+        let mut header = format == ExportFormat::Csv;
+        loop {
+            let finished = match rows.try_next().await {
+                Ok(Some((json,))) => {
+                    batch.push(json);
+                    if batch.len() < CHUNK_ROWS {
+                        continue;
+                    }
+                    false
+                }
+                Ok(None) => true,
+                Err(e) => {
+                    let _ = sender.send(Err(std::io::Error::other(e))).await;
+                    return;
+                }
+            };
+            if !batch.is_empty() || header {
+                let chunk = encode(&batch, header);
+                let failed = chunk.is_err();
+                // Stop when the client has gone away or encoding failed.
+                if sender.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+            header = false;
+            batch.clear();
+            if finished {
+                return;
+            }
+        }
+    });
+    receiver
+}
@@ -0,0 +1,775 @@
+//! Membership fees: fee plans, invoices, payments and receipts.
+//!
+//! The treasurer sets a fee plan per season and member category and issues
+//! an invoice from it to each player; a player with a guardian is billed to
+//! the guardian. Payments are recorded by hand, partial ones included, or
+//! confirmed by the online payment provider. Balances and statuses are
+//! computed from the payments, never stored.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{Connection, PgConnection, PgExecutor, PgPool};
+use std::time::Duration;
+
+use crate::auth::Claims;
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs;
+use crate::services::guardians;
+use crate::services::jobs::Scheduler;
+use crate::services::notifications::notify;
+use crate::services::payments::{Checkout, PaymentProvider, PaymentRequest, PaymentStatus, Payments};
+use crate::services::pdf::PdfDocument;
+
+pub const TREASURER: &str = "treasurer";
+
+/// Methods the treasurer can record. "online" is only set by the provider sync.
+pub const PAYMENT_METHODS: &[&str] = &["cash", "bank_transfer", "card", "mobile_money"];
+pub const STATUSES: &[&str] = &["open", "partial", "paid", "overdue", "cancelled"];
+
+/// Job name of the online payment sync in the `jobs` table.
+pub const SYNC_JOB: &str = "fees.sync_payments";
+
+/// How often pending online payments are checked with the provider.
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Whether `claims` may manage fees: treasurers and admins.
+pub fn is_treasurer(claims: &Claims) -> bool {
+    claims.role == "admin" || claims.role == TREASURER
+}
+
+pub fn require_treasurer(claims: &Claims) -> Result<(), AppError> {
+    if is_treasurer(claims) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("Only the treasurer can manage membership fees".into()))
+    }
+}
+
+/// "EUR 12.50" from 1250 cents.
+pub fn format_amount(cents: i64, currency: &str) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{} {}{}.{:02}", currency, sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+fn payment_method_label(method: &str) -> &str {
+    match method {
+        "cash" => "Cash",
+        "bank_transfer" => "Bank transfer",
+        "card" => "Card",
+        "mobile_money" => "Mobile money",
+        "online" => "Online payment",
+        other => other,
+    }
+}
+
+fn validate_currency(currency: &str) -> Result<(), AppError> {
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(AppError::BadRequest("Currency must be an ISO 4217 code such as EUR".into()));
+    }
+    Ok(())
+}
+
+// ─── Fee plans ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FeePlan {
+    pub id: i64,
+    pub season_id: i64,
+    pub season_name: String,
+    pub category: String,
+    pub name: String,
+    pub amount_cents: i64,
+    pub currency: String,
+    pub due_date: NaiveDate,
+    /// Invoices issued from the plan, cancelled ones excluded.
+    pub invoices: i64,
+}
+
+pub struct FeePlanDetails<'a> {
+    pub season_id: i64,
+    pub category: &'a str,
+    pub name: &'a str,
+    pub amount_cents: i64,
+    pub currency: &'a str,
+    pub due_date: NaiveDate,
+}
+
+impl FeePlanDetails<'_> {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.category.trim().is_empty() || self.category.trim().len() > 50 {
+            return Err(AppError::BadRequest("Category is required (at most 50 characters)".into()));
+        }
+        if self.name.trim().is_empty() || self.name.trim().len() > 100 {
+            return Err(AppError::BadRequest("Name is required (at most 100 characters)".into()));
+        }
+        if self.amount_cents <= 0 {
+            return Err(AppError::BadRequest("The fee must be more than zero".into()));
+        }
+        validate_currency(self.currency)
+    }
+}
+
+fn unique_plan(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::Conflict("There already is a fee plan for that season and category".into())
+        }
+        _ => AppError::Database(e),
+    }
+}
+
+/// The club's fee plans, of one season or all, newest season first.
+pub async fn plans<'e>(executor: impl PgExecutor<'e>, club_id: i64, season_id: Option<i64>) -> Result<Vec<FeePlan>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT f.id, f.season_id, s.name AS season_name, f.category, f.name, f.amount_cents, f.currency, f.due_date, \
+         (SELECT COUNT(*) FROM invoices i WHERE i.fee_plan_id = f.id AND i.cancelled_at IS NULL) AS invoices \
+         FROM fee_plans f JOIN seasons s ON f.season_id = s.id \
+         WHERE f.club_id = $1 AND ($2::bigint IS NULL OR f.season_id = $2) \
+         ORDER BY s.start_date DESC, f.category",
+    )
+    .bind(club_id)
+    .bind(season_id)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn create_plan(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, details: &FeePlanDetails<'_>) -> Result<i64, AppError> {
+    details.validate()?;
+    clubs::require_owned(&mut *conn, "seasons", details.season_id, club_id, "Season").await?;
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO fee_plans (club_id, season_id, category, name, amount_cents, currency, due_date) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+    )
+    .bind(club_id)
+    .bind(details.season_id)
+    .bind(details.category.trim())
+    .bind(details.name.trim())
+    .bind(details.amount_cents)
+    .bind(details.currency)
+    .bind(details.due_date)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(unique_plan)?;
+    let after = audit::snapshot(&mut *conn, "fee_plans", id).await?;
+    audit::record(conn, ctx, "create", "fee_plan", Some(id), None, after).await?;
+    Ok(id)
+}
+
+/// Change a plan. Invoices already issued keep their amount and due date.
+pub async fn update_plan(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    id: i64,
+    details: &FeePlanDetails<'_>,
+) -> Result<(), AppError> {
+    details.validate()?;
+    clubs::require_owned(&mut *conn, "fee_plans", id, club_id, "Fee plan").await?;
+    clubs::require_owned(&mut *conn, "seasons", details.season_id, club_id, "Season").await?;
+    let before = audit::snapshot(&mut *conn, "fee_plans", id).await?;
+    sqlx::query(
+        "UPDATE fee_plans SET season_id = $2, category = $3, name = $4, amount_cents = $5, currency = $6, due_date = $7 \
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(details.season_id)
+    .bind(details.category.trim())
+    .bind(details.name.trim())
+    .bind(details.amount_cents)
+    .bind(details.currency)
+    .bind(details.due_date)
+    .execute(&mut *conn)
+    .await
+    .map_err(unique_plan)?;
+    let after = audit::snapshot(&mut *conn, "fee_plans", id).await?;
+    audit::record(conn, ctx, "update", "fee_plan", Some(id), before, after).await?;
+    Ok(())
+}
+
+// ─── Invoices ───────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Invoice {
+    pub id: i64,
+    pub number: String,
+    pub fee_plan_id: i64,
+    pub season_id: i64,
+    pub player_id: i64,
+    #[serde(skip)]
+    pub player_user_id: i64,
+    pub player_name: String,
+    /// The user who pays: the player or a guardian.
+    pub billed_to: i64,
+    pub billed_to_name: String,
+    #[serde(skip)]
+    pub billed_to_email: String,
+    pub description: String,
+    pub amount_cents: i64,
+    pub paid_cents: i64,
+    /// Negative when online payments overlapped with a manual one.
+    pub balance_cents: i64,
+    pub currency: String,
+    pub issued_on: NaiveDate,
+    pub due_date: NaiveDate,
+    /// One of `STATUSES`.
+    pub status: String,
+    /// Days past the due date of an overdue invoice, 0 otherwise.
+    pub days_overdue: i32,
+}
+
+/// Every invoice with its payments totalled and its status, as a subquery
+/// aliased `inv`.
+const INVOICES: &str = "(SELECT i.id, i.club_id, 'INV-' || lpad(i.id::text, 6, '0') AS number, i.fee_plan_id, f.season_id, \
+     i.player_id, p.user_id AS player_user_id, p.first_name || ' ' || p.last_name AS player_name, \
+     i.billed_to, b.name AS billed_to_name, b.email AS billed_to_email, i.description, i.amount_cents, \
+     COALESCE(paid.total, 0) AS paid_cents, i.amount_cents - COALESCE(paid.total, 0) AS balance_cents, \
+     i.currency, i.issued_on, i.due_date, \
+     CASE WHEN i.cancelled_at IS NOT NULL THEN 'cancelled' \
+          WHEN COALESCE(paid.total, 0) >= i.amount_cents THEN 'paid' \
+          WHEN i.due_date < CURRENT_DATE THEN 'overdue' \
+          WHEN COALESCE(paid.total, 0) > 0 THEN 'partial' ELSE 'open' END AS status, \
+     CASE WHEN i.cancelled_at IS NULL AND COALESCE(paid.total, 0) < i.amount_cents AND i.due_date < CURRENT_DATE \
+          THEN CURRENT_DATE - i.due_date ELSE 0 END AS days_overdue \
+     FROM invoices i JOIN fee_plans f ON i.fee_plan_id = f.id JOIN players p ON i.player_id = p.id \
+     JOIN users b ON i.billed_to = b.id \
+     LEFT JOIN LATERAL (SELECT SUM(ip.amount_cents)::bigint AS total FROM invoice_payments ip WHERE ip.invoice_id = i.id) paid ON TRUE \
+     ) inv";
+
+/// SQL condition: the user bound as `$param` is the payer, the player or one
+/// of the player's guardians of the invoice `inv`.
+fn payer_or_player(param: usize) -> String {
+    format!(
+        "(inv.billed_to = ${param} OR inv.player_user_id = ${param} \
+          OR EXISTS (SELECT 1 FROM player_guardians g WHERE g.player_id = inv.player_id AND g.guardian_id = ${param}))"
+    )
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InvoiceFilter {
+    pub status: Option<String>,
+    pub season_id: Option<i64>,
+    pub player_id: Option<i64>,
+}
+
+/// Invoices of the club matching `filter`, by due date. With a `viewer`, only
+/// the invoices they pay or that are for them or their children.
+pub async fn list<'e>(
+    executor: impl PgExecutor<'e>,
+    club_id: i64,
+    viewer: Option<i64>,
+    filter: &InvoiceFilter,
+) -> Result<Vec<Invoice>, AppError> {
+    if let Some(status) = &filter.status {
+        if !STATUSES.contains(&status.as_str()) {
+            return Err(AppError::BadRequest(format!("Status must be one of: {}", STATUSES.join(", "))));
+        }
+    }
+    let invoices = sqlx::query_as(&format!(
+        "SELECT * FROM {} WHERE inv.club_id = $1 AND ($2::bigint IS NULL OR {}) \
+         AND ($3::text IS NULL OR inv.status = $3) AND ($4::bigint IS NULL OR inv.season_id = $4) \
+         AND ($5::bigint IS NULL OR inv.player_id = $5) \
+         ORDER BY inv.days_overdue DESC, inv.due_date, inv.id",
+        INVOICES,
+        payer_or_player(2)
+    ))
+    .bind(club_id)
+    .bind(viewer)
+    .bind(&filter.status)
+    .bind(filter.season_id)
+    .bind(filter.player_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(invoices)
+}
+
+/// An invoice `claims` may see: treasurers see the club's, others only the
+/// ones they pay or that are for them or their children. 404 for other clubs.
+pub async fn require_invoice_access(conn: &mut PgConnection, claims: &Claims, id: i64) -> Result<Invoice, AppError> {
+    let invoice: Invoice = sqlx::query_as(&format!("SELECT * FROM {} WHERE inv.id = $1 AND inv.club_id = $2", INVOICES))
+        .bind(id)
+        .bind(claims.club_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invoice not found".into()))?;
+    let allowed = is_treasurer(claims)
+        || invoice.billed_to == claims.sub
+        || invoice.player_user_id == claims.sub
+        || guardians::is_guardian_of(&mut *conn, claims.sub, invoice.player_id).await?;
+    if allowed {
+        Ok(invoice)
+    } else {
+        Err(AppError::Forbidden("This invoice is not addressed to you".into()))
+    }
+}
+
+/// How many invoices were issued from a plan, and how many players already had one.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssueSummary {
+    pub issued: usize,
+    pub skipped: usize,
+}
+
+/// Issue invoices from a fee plan to players of the club. A player who has a
+/// guardian is billed to the guardian linked first, unless `bill_guardians`
+/// is false. Players already invoiced for the plan are skipped.
+pub async fn issue_invoices(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    plan_id: i64,
+    player_ids: &[i64],
+    bill_guardians: bool,
+) -> Result<IssueSummary, AppError> {
+    let (name, amount_cents, currency, due_date): (String, i64, String, NaiveDate) =
+        sqlx::query_as("SELECT name, amount_cents, currency, due_date FROM fee_plans WHERE id = $1 AND club_id = $2")
+            .bind(plan_id)
+            .bind(club_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Fee plan not found".into()))?;
+    let known: Vec<i64> = sqlx::query_scalar(
+        "SELECT p.id FROM players p JOIN users u ON p.user_id = u.id \
+         WHERE p.id = ANY($1) AND u.club_id = $2 AND u.deleted_at IS NULL",
+    )
+    .bind(player_ids)
+    .bind(club_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let unknown: Vec<String> = player_ids.iter().filter(|id| !known.contains(id)).map(|id| id.to_string()).collect();
+    if !unknown.is_empty() {
+        return Err(AppError::BadRequest(format!("Unknown players: {}", unknown.join(", "))));
+    }
+
+    let mut summary = IssueSummary { issued: 0, skipped: 0 };
+    for &player_id in &known {
+        let id: Option<i64> = sqlx::query_scalar(
+            "INSERT INTO invoices (club_id, fee_plan_id, player_id, billed_to, description, amount_cents, currency, due_date, created_by) \
+             SELECT $1, $2, p.id, COALESCE(CASE WHEN $3 THEN ( \
+                 SELECT g.guardian_id FROM player_guardians g JOIN users gu ON g.guardian_id = gu.id \
+                 WHERE g.player_id = p.id AND gu.deleted_at IS NULL ORDER BY g.created_at, g.id LIMIT 1) END, p.user_id), \
+               $5 || ' - ' || p.first_name || ' ' || p.last_name, $6, $7, $8, $9 \
+             FROM players p WHERE p.id = $4 \
+             ON CONFLICT (fee_plan_id, player_id) DO NOTHING RETURNING id",
+        )
+        .bind(club_id)
+        .bind(plan_id)
+        .bind(bill_guardians)
+        .bind(player_id)
+        .bind(&name)
+        .bind(amount_cents)
+        .bind(&currency)
+        .bind(due_date)
+        .bind(ctx.actor_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some(id) = id else {
+            summary.skipped += 1;
+            continue;
+        };
+        let after = audit::snapshot(&mut *conn, "invoices", id).await?;
+        let billed_to = after.as_ref().and_then(|a| a["billed_to"].as_i64());
+        audit::record(&mut *conn, ctx, "create", "invoice", Some(id), None, after).await?;
+        if let Some(billed_to) = billed_to {
+            let body = format!("{}: {} due on {}.", name, format_amount(amount_cents, &currency), due_date);
+            notify(&mut *conn, billed_to, "New membership invoice", &body, Some("/fees.html")).await?;
+        }
+        summary.issued += 1;
+    }
+    Ok(summary)
+}
+
+/// Cancel an invoice issued in error. Invoices with payments cannot be cancelled.
+pub async fn cancel_invoice(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, id: i64) -> Result<(), AppError> {
+    clubs::require_owned(&mut *conn, "invoices", id, club_id, "Invoice").await?;
+    let before = audit::snapshot(&mut *conn, "invoices", id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invoice not found".into()))?;
+    if !before["cancelled_at"].is_null() {
+        return Err(AppError::Conflict("This invoice is already cancelled".into()));
+    }
+    let paid: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM invoice_payments WHERE invoice_id = $1)")
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if paid {
+        return Err(AppError::Conflict("Payments have been recorded on this invoice; it cannot be cancelled".into()));
+    }
+    sqlx::query("UPDATE invoices SET cancelled_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    let after = audit::snapshot(&mut *conn, "invoices", id).await?;
+    audit::record(conn, ctx, "cancel", "invoice", Some(id), Some(before), after).await?;
+    Ok(())
+}
+
+/// What one payer owes in one currency.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Balance {
+    pub user_id: i64,
+    pub name: String,
+    pub email: String,
+    pub currency: String,
+    pub invoiced_cents: i64,
+    pub paid_cents: i64,
+    pub balance_cents: i64,
+    pub overdue_cents: i64,
+}
+
+/// Balances per payer over the club's invoices (of one season or all), cancelled
+/// invoices excluded. Largest balance first.
+pub async fn balances<'e>(executor: impl PgExecutor<'e>, club_id: i64, season_id: Option<i64>) -> Result<Vec<Balance>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT inv.billed_to AS user_id, inv.billed_to_name AS name, inv.billed_to_email AS email, inv.currency, \
+         SUM(inv.amount_cents)::bigint AS invoiced_cents, SUM(inv.paid_cents)::bigint AS paid_cents, \
+         SUM(inv.balance_cents)::bigint AS balance_cents, \
+         COALESCE(SUM(inv.balance_cents) FILTER (WHERE inv.status = 'overdue'), 0)::bigint AS overdue_cents \
+         FROM {} WHERE inv.club_id = $1 AND inv.status <> 'cancelled' AND ($2::bigint IS NULL OR inv.season_id = $2) \
+         GROUP BY inv.billed_to, inv.billed_to_name, inv.billed_to_email, inv.currency \
+         ORDER BY balance_cents DESC, name",
+        INVOICES
+    ))
+    .bind(club_id)
+    .bind(season_id)
+    .fetch_all(executor)
+    .await
+}
+
+// ─── Payments ───────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Payment {
+    pub id: i64,
+    pub invoice_id: i64,
+    pub receipt_number: String,
+    pub amount_cents: i64,
+    pub paid_on: NaiveDate,
+    pub method: String,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
+
+const PAYMENT_SELECT: &str = "SELECT id, invoice_id, 'R-' || lpad(id::text, 6, '0') AS receipt_number, amount_cents, paid_on, \
+     method, reference, notes FROM invoice_payments";
+
+/// Payments on an invoice, oldest first.
+pub async fn payments<'e>(executor: impl PgExecutor<'e>, invoice_id: i64) -> Result<Vec<Payment>, sqlx::Error> {
+    sqlx::query_as(&format!("{} WHERE invoice_id = $1 ORDER BY paid_on, id", PAYMENT_SELECT))
+        .bind(invoice_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// The invoice a payment of the club belongs to. 404 for other clubs' payments.
+pub async fn invoice_of_payment<'e>(executor: impl PgExecutor<'e>, club_id: i64, payment_id: i64) -> Result<i64, AppError> {
+    sqlx::query_scalar("SELECT ip.invoice_id FROM invoice_payments ip JOIN invoices i ON ip.invoice_id = i.id WHERE ip.id = $1 AND i.club_id = $2")
+        .bind(payment_id)
+        .bind(club_id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payment not found".into()))
+}
+
+/// A payment received by the treasurer.
+pub struct PaymentDetails<'a> {
+    pub amount_cents: i64,
+    pub paid_on: NaiveDate,
+    pub method: &'a str,
+    pub reference: Option<&'a str>,
+    pub notes: Option<&'a str>,
+}
+
+impl PaymentDetails<'_> {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.amount_cents <= 0 {
+            return Err(AppError::BadRequest("The amount must be more than zero".into()));
+        }
+        if !PAYMENT_METHODS.contains(&self.method) {
+            return Err(AppError::BadRequest(format!("Method must be one of: {}", PAYMENT_METHODS.join(", "))));
+        }
+        if self.reference.is_some_and(|r| r.len() > 100) {
+            return Err(AppError::BadRequest("Reference must be at most 100 characters".into()));
+        }
+        if self.paid_on > chrono::Utc::now().date_naive() {
+            return Err(AppError::BadRequest("A payment cannot be dated in the future".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Lock an invoice and return what is left to pay, and the currency.
+/// Conflict when cancelled or paid.
+async fn outstanding(conn: &mut PgConnection, invoice_id: i64) -> Result<(i64, String), AppError> {
+    let (amount_cents, currency, cancelled): (i64, String, bool) =
+        sqlx::query_as("SELECT amount_cents, currency, cancelled_at IS NOT NULL FROM invoices WHERE id = $1 FOR UPDATE")
+            .bind(invoice_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invoice not found".into()))?;
+    if cancelled {
+        return Err(AppError::Conflict("This invoice is cancelled".into()));
+    }
+    let paid: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(amount_cents), 0)::bigint FROM invoice_payments WHERE invoice_id = $1")
+        .bind(invoice_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if paid >= amount_cents {
+        return Err(AppError::Conflict("This invoice is already paid".into()));
+    }
+    Ok((amount_cents - paid, currency))
+}
+
+async fn insert_payment(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    invoice_id: i64,
+    details: &PaymentDetails<'_>,
+) -> Result<i64, AppError> {
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO invoice_payments (invoice_id, amount_cents, paid_on, method, reference, notes, recorded_by) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+    )
+    .bind(invoice_id)
+    .bind(details.amount_cents)
+    .bind(details.paid_on)
+    .bind(details.method)
+    .bind(details.reference)
+    .bind(details.notes)
+    .bind(ctx.actor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "invoice_payments", id).await?;
+    audit::record(&mut *conn, ctx, "create", "invoice_payment", Some(id), None, after).await?;
+
+    let (billed_to, currency): (i64, String) = sqlx::query_as("SELECT billed_to, currency FROM invoices WHERE id = $1")
+        .bind(invoice_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let body = format!(
+        "We received {} on {}. Your receipt is ready.",
+        format_amount(details.amount_cents, &currency),
+        details.paid_on
+    );
+    notify(&mut *conn, billed_to, "Payment received", &body, Some("/fees.html")).await?;
+    Ok(id)
+}
+
+/// Record a payment the treasurer received. It may be partial, but not more
+/// than the invoice's balance.
+pub async fn record_payment(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    invoice_id: i64,
+    details: &PaymentDetails<'_>,
+) -> Result<i64, AppError> {
+    details.validate()?;
+    let (balance, currency) = outstanding(&mut *conn, invoice_id).await?;
+    if details.amount_cents > balance {
+        return Err(AppError::BadRequest(format!(
+            "The amount is more than the balance of {}",
+            format_amount(balance, &currency)
+        )));
+    }
+    insert_payment(conn, ctx, invoice_id, details).await
+}
+
+/// Receipt for a payment, as a PDF.
+pub async fn receipt_pdf(conn: &mut PgConnection, invoice: &Invoice, payment_id: i64) -> Result<Vec<u8>, AppError> {
+    let payments = payments(&mut *conn, invoice.id).await?;
+    let position = payments
+        .iter()
+        .position(|p| p.id == payment_id)
+        .ok_or_else(|| AppError::NotFound("Payment not found".into()))?;
+    let payment = &payments[position];
+    let paid_to_date: i64 = payments[..=position].iter().map(|p| p.amount_cents).sum();
+    let club: String = sqlx::query_scalar("SELECT c.name FROM clubs c JOIN invoices i ON i.club_id = c.id WHERE i.id = $1")
+        .bind(invoice.id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let amount = |cents: i64| format_amount(cents, &invoice.currency);
+    let mut doc = PdfDocument::new();
+    doc.title(&club);
+    doc.heading(&format!("Receipt {}", payment.receipt_number));
+    doc.text(&format!("Date: {}", payment.paid_on));
+    doc.text(&format!("Received from: {}", invoice.billed_to_name));
+    doc.text(&format!("For: {}", invoice.description));
+    doc.text(&format!("Invoice: {} (issued {})", invoice.number, invoice.issued_on));
+    doc.text(&format!("Amount received: {}", amount(payment.amount_cents)));
+    doc.text(&format!("Method: {}", payment_method_label(&payment.method)));
+    if let Some(reference) = &payment.reference {
+        doc.text(&format!("Reference: {}", reference));
+    }
+    doc.heading("Invoice balance");
+    doc.mono(&format!("{:<20}{:>20}", "Invoice total", amount(invoice.amount_cents)));
+    doc.mono(&format!("{:<20}{:>20}", "Paid to date", amount(paid_to_date)));
+    doc.mono(&format!("{:<20}{:>20}", "Balance", amount(invoice.amount_cents - paid_to_date)));
+    Ok(doc.render())
+}
+
+// ─── Online payments ────────────────────────────────────────────────
+
+/// Start an online payment of an invoice with the provider, for its balance
+/// or a part of it. Online payments still pending count as paid here, so the
+/// invoice cannot be paid twice over.
+pub async fn start_checkout(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    provider: &dyn PaymentProvider,
+    invoice: &Invoice,
+    amount_cents: Option<i64>,
+) -> Result<Checkout, AppError> {
+    let (balance, currency) = outstanding(&mut *conn, invoice.id).await?;
+    let pending: i64 =
+        sqlx::query_scalar("SELECT COALESCE(SUM(amount_cents), 0)::bigint FROM payment_intents WHERE invoice_id = $1 AND status = 'pending'")
+            .bind(invoice.id)
+            .fetch_one(&mut *conn)
+            .await?;
+    if pending >= balance {
+        return Err(AppError::Conflict("An online payment of the balance is already in progress".into()));
+    }
+    let balance = balance - pending;
+    let amount_cents = amount_cents.unwrap_or(balance);
+    if amount_cents <= 0 || amount_cents > balance {
+        return Err(AppError::BadRequest(format!(
+            "The amount must be more than zero and at most the balance of {}",
+            format_amount(balance, &currency)
+        )));
+    }
+    let request = PaymentRequest {
+        reference: invoice.number.clone(),
+        description: invoice.description.clone(),
+        amount_cents,
+        currency: invoice.currency.clone(),
+        payer_email: invoice.billed_to_email.clone(),
+    };
+    let checkout = provider
+        .create_payment(&request)
+        .await
+        .map_err(|e| AppError::Internal(format!("Payment provider {} failed: {}", provider.name(), e)))?;
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO payment_intents (invoice_id, provider, reference, amount_cents, checkout_url, started_by) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+    )
+    .bind(invoice.id)
+    .bind(checkout.provider)
+    .bind(&checkout.reference)
+    .bind(amount_cents)
+    .bind(&checkout.checkout_url)
+    .bind(ctx.actor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "payment_intents", id).await?;
+    audit::record(conn, ctx, "create", "payment_intent", Some(id), None, after).await?;
+    Ok(checkout)
+}
+
+/// Ask the provider about every pending online payment and record the ones
+/// that succeeded, up to the invoice's balance. What a cancelled invoice or
+/// one settled in the meantime cannot take is flagged for refund instead.
+/// Returns how many payments were settled either way.
+pub async fn sync_payments(pool: &PgPool, provider: &dyn PaymentProvider) -> Result<usize, AppError> {
+    let pending = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, reference FROM payment_intents WHERE provider = $1 AND status = 'pending' ORDER BY id",
+    )
+    .bind(provider.name())
+    .fetch_all(pool)
+    .await?;
+
+    let mut settled = 0;
+    let mut conn = pool.acquire().await?;
+    for (id, reference) in pending {
+        let status = match provider.payment_status(&reference).await {
+            Ok(PaymentStatus::Pending) => continue,
+            Ok(status) => status,
+            Err(e) => {
+                tracing::warn!("Payment provider {}: status of {} failed: {}", provider.name(), reference, e);
+                continue;
+            }
+        };
+        let mut tx = conn.begin().await?;
+        let row: Option<(i64, i64, i64)> = sqlx::query_as(
+            "UPDATE payment_intents pi SET status = $2, updated_at = NOW() FROM invoices i \
+             WHERE pi.id = $1 AND pi.status = 'pending' AND pi.invoice_id = i.id \
+             RETURNING pi.invoice_id, pi.amount_cents, i.club_id",
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((invoice_id, amount_cents, club_id)) = row else {
+            continue;
+        };
+        if status == PaymentStatus::Succeeded {
+            let ctx = AuditContext::default().with_club(club_id);
+            let (cancelled, balance): (bool, i64) = sqlx::query_as(
+                "SELECT i.cancelled_at IS NOT NULL, \
+                 i.amount_cents - COALESCE((SELECT SUM(amount_cents) FROM invoice_payments WHERE invoice_id = i.id), 0)::bigint \
+                 FROM invoices i WHERE i.id = $1 FOR UPDATE",
+            )
+            .bind(invoice_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            let recorded = if cancelled { 0 } else { amount_cents.min(balance.max(0)) };
+            if recorded > 0 {
+                let details = PaymentDetails {
+                    amount_cents: recorded,
+                    paid_on: chrono::Utc::now().date_naive(),
+                    method: "online",
+                    reference: Some(&reference),
+                    notes: None,
+                };
+                let payment_id = insert_payment(&mut tx, &ctx, invoice_id, &details).await?;
+                sqlx::query("UPDATE payment_intents SET payment_id = $2 WHERE id = $1")
+                    .bind(id)
+                    .bind(payment_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            if recorded < amount_cents {
+                let before = audit::snapshot(&mut tx, "payment_intents", id).await?;
+                sqlx::query("UPDATE payment_intents SET refund_cents = $2 WHERE id = $1")
+                    .bind(id)
+                    .bind(amount_cents - recorded)
+                    .execute(&mut *tx)
+                    .await?;
+                let after = audit::snapshot(&mut tx, "payment_intents", id).await?;
+                audit::record(&mut tx, &ctx, "flag_refund", "payment_intent", Some(id), before, after).await?;
+                tracing::warn!(
+                    "Online payment {} for invoice {}: {} cents to refund ({})",
+                    reference,
+                    invoice_id,
+                    amount_cents - recorded,
+                    if cancelled { "invoice cancelled" } else { "more than the balance" }
+                );
+            }
+        }
+        tx.commit().await?;
+        settled += 1;
+    }
+    Ok(settled)
+}
+
+/// Schedule the online payment sync when a provider is configured.
+pub fn register_sync_job(scheduler: &mut Scheduler, payments: &Payments) {
+    if payments.provider().is_none() {
+        return;
+    }
+    let payments = payments.clone();
+    scheduler
+        .register(SYNC_JOB, move |pool, _payload| {
+            let payments = payments.clone();
+            async move {
+                let Some(provider) = payments.provider() else {
+                    return Ok(());
+                };
+                let settled = sync_payments(&pool, provider).await.map_err(|e| e.to_string())?;
+                if settled > 0 {
+                    tracing::info!("Online payments: {} settled", settled);
+                }
+                Ok(())
+            }
+        })
+        .every(SYNC_JOB, SYNC_INTERVAL);
+}
@@ -0,0 +1,294 @@
+//! Fitness testing.
+//!
+//! Each club configures the tests it runs (beep test, sprints, jumps, ...)
+//! with a unit and whether a higher value is better. Results are recorded per
+//! player and test date; recording the same test for a player twice on one
+//! day replaces the earlier value. Percentiles compare each player's latest
+//! result with the rest of a team, so timed tests rank the fastest highest.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{PgConnection, PgExecutor};
+
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TestType {
+    pub id: i64,
+    pub name: String,
+    pub unit: String,
+    pub higher_is_better: bool,
+    pub description: Option<String>,
+}
+
+/// The test type fields a coach or admin provides.
+pub struct TestTypeDetails<'a> {
+    pub name: &'a str,
+    pub unit: &'a str,
+    pub higher_is_better: bool,
+    pub description: Option<&'a str>,
+}
+
+impl TestTypeDetails<'_> {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.name.trim().is_empty() || self.name.trim().len() > 100 {
+            return Err(AppError::BadRequest("Test name is required (at most 100 characters)".into()));
+        }
+        if self.unit.trim().is_empty() || self.unit.trim().len() > 20 {
+            return Err(AppError::BadRequest("Unit is required (at most 20 characters)".into()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FitnessResult {
+    pub id: i64,
+    pub tested_on: NaiveDate,
+    pub value: f64,
+    pub notes: Option<String>,
+    /// Difference from the player's previous result in the same test.
+    pub change: Option<f64>,
+}
+
+/// A player's results in one test, oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct Progression {
+    pub test_type_id: i64,
+    pub name: String,
+    pub unit: String,
+    pub higher_is_better: bool,
+    pub best: f64,
+    pub latest: f64,
+    pub results: Vec<FitnessResult>,
+}
+
+/// A player's latest result in a test and where it ranks in the group.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Standing {
+    pub player_id: i64,
+    pub player_name: String,
+    pub tested_on: NaiveDate,
+    pub value: f64,
+    /// Share of the group with a worse result, 0 to 100.
+    pub percentile: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Percentiles {
+    pub test: TestType,
+    pub date: NaiveDate,
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub p25: Option<f64>,
+    pub median: Option<f64>,
+    pub p75: Option<f64>,
+    /// Best first.
+    pub players: Vec<Standing>,
+}
+
+/// Linear interpolation between the closest ranks, as `percentile_cont` does.
+fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+fn unique_name(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict("A fitness test with that name already exists".into()),
+        _ => AppError::Database(e),
+    }
+}
+
+const TEST_TYPE_SELECT: &str = "SELECT id, name, unit, higher_is_better, description FROM fitness_test_types";
+
+pub async fn test_types<'e>(executor: impl PgExecutor<'e>, club_id: i64) -> Result<Vec<TestType>, sqlx::Error> {
+    sqlx::query_as(&format!("{} WHERE club_id = $1 ORDER BY name", TEST_TYPE_SELECT))
+        .bind(club_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// A test type of the club. 404 for other clubs' tests.
+pub async fn test_type<'e>(executor: impl PgExecutor<'e>, club_id: i64, id: i64) -> Result<TestType, AppError> {
+    sqlx::query_as(&format!("{} WHERE id = $1 AND club_id = $2", TEST_TYPE_SELECT))
+        .bind(id)
+        .bind(club_id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fitness test not found".into()))
+}
+
+pub async fn create_test_type(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, details: &TestTypeDetails<'_>) -> Result<i64, AppError> {
+    details.validate()?;
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO fitness_test_types (club_id, name, unit, higher_is_better, description) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(club_id)
+    .bind(details.name.trim())
+    .bind(details.unit.trim())
+    .bind(details.higher_is_better)
+    .bind(details.description)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(unique_name)?;
+    let after = audit::snapshot(&mut *conn, "fitness_test_types", id).await?;
+    audit::record(conn, ctx, "create", "fitness_test", Some(id), None, after).await?;
+    Ok(id)
+}
+
+pub async fn update_test_type(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    id: i64,
+    details: &TestTypeDetails<'_>,
+) -> Result<(), AppError> {
+    details.validate()?;
+    clubs::require_owned(&mut *conn, "fitness_test_types", id, club_id, "Fitness test").await?;
+    let before = audit::snapshot(&mut *conn, "fitness_test_types", id).await?;
+    sqlx::query("UPDATE fitness_test_types SET name = $2, unit = $3, higher_is_better = $4, description = $5 WHERE id = $1")
+        .bind(id)
+        .bind(details.name.trim())
+        .bind(details.unit.trim())
+        .bind(details.higher_is_better)
+        .bind(details.description)
+        .execute(&mut *conn)
+        .await
+        .map_err(unique_name)?;
+    let after = audit::snapshot(&mut *conn, "fitness_test_types", id).await?;
+    audit::record(conn, ctx, "update", "fitness_test", Some(id), before, after).await?;
+    Ok(())
+}
+
+/// Record a player's result, replacing any result of the same test on that date.
+pub async fn record(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    test_type_id: i64,
+    player_id: i64,
+    tested_on: NaiveDate,
+    value: f64,
+    notes: Option<&str>,
+) -> Result<i64, AppError> {
+    if !value.is_finite() {
+        return Err(AppError::BadRequest("Result values must be numbers".into()));
+    }
+    let existing: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM fitness_results WHERE test_type_id = $1 AND player_id = $2 AND tested_on = $3")
+            .bind(test_type_id)
+            .bind(player_id)
+            .bind(tested_on)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let before = match existing {
+        Some(id) => audit::snapshot(&mut *conn, "fitness_results", id).await?,
+        None => None,
+    };
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO fitness_results (test_type_id, player_id, tested_on, value, notes, recorded_by) VALUES ($1, $2, $3, $4, $5, $6) \
+         ON CONFLICT (test_type_id, player_id, tested_on) \
+         DO UPDATE SET value = EXCLUDED.value, notes = EXCLUDED.notes, recorded_by = EXCLUDED.recorded_by, updated_at = NOW() \
+         RETURNING id",
+    )
+    .bind(test_type_id)
+    .bind(player_id)
+    .bind(tested_on)
+    .bind(value)
+    .bind(notes)
+    .bind(ctx.actor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "fitness_results", id).await?;
+    let action = if existing.is_some() { "update" } else { "create" };
+    audit::record(conn, ctx, action, "fitness_result", Some(id), before, after).await?;
+    Ok(id)
+}
+
+/// A player's progression in every test they took, by test name.
+pub async fn history<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Progression>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, String, String, bool, i64, NaiveDate, f64, Option<String>, Option<f64>)>(
+        "SELECT ft.id, ft.name, ft.unit, ft.higher_is_better, r.id, r.tested_on, r.value, r.notes, \
+         r.value - LAG(r.value) OVER (PARTITION BY r.test_type_id ORDER BY r.tested_on) \
+         FROM fitness_results r JOIN fitness_test_types ft ON r.test_type_id = ft.id \
+         WHERE r.player_id = $1 ORDER BY ft.name, ft.id, r.tested_on",
+    )
+    .bind(player_id)
+    .fetch_all(executor)
+    .await?;
+
+    let mut progressions: Vec<Progression> = Vec::new();
+    for (test_type_id, name, unit, higher_is_better, id, tested_on, value, notes, change) in rows {
+        let result = FitnessResult { id, tested_on, value, notes, change };
+        match progressions.last_mut() {
+            Some(p) if p.test_type_id == test_type_id => {
+                let better = if p.higher_is_better { value > p.best } else { value < p.best };
+                if better {
+                    p.best = value;
+                }
+                p.latest = value;
+                p.results.push(result);
+            }
+            _ => progressions.push(Progression {
+                test_type_id,
+                name,
+                unit,
+                higher_is_better,
+                best: value,
+                latest: value,
+                results: vec![result],
+            }),
+        }
+    }
+    Ok(progressions)
+}
+
+/// Percentiles of a test on `date`: each player's latest result on or before
+/// that date, ranked within the players of `teams` (the whole club for `None`).
+pub async fn percentiles(
+    conn: &mut PgConnection,
+    club_id: i64,
+    test_type_id: i64,
+    teams: Option<Vec<i64>>,
+    date: NaiveDate,
+) -> Result<Percentiles, AppError> {
+    let test = test_type(&mut *conn, club_id, test_type_id).await?;
+    let players: Vec<Standing> = sqlx::query_as(
+        "SELECT player_id, player_name, tested_on, value, \
+         100.0 * percent_rank() OVER (ORDER BY CASE WHEN $4 THEN value ELSE -value END) AS percentile \
+         FROM ( \
+           SELECT DISTINCT ON (r.player_id) r.player_id, p.first_name || ' ' || p.last_name AS player_name, r.tested_on, r.value \
+           FROM fitness_results r JOIN players p ON r.player_id = p.id JOIN users u ON p.user_id = u.id \
+           WHERE r.test_type_id = $1 AND r.tested_on <= $2 AND u.deleted_at IS NULL \
+           AND ($3::bigint[] IS NULL OR EXISTS ( \
+             SELECT 1 FROM team_members tm WHERE tm.user_id = p.user_id AND tm.team_id = ANY($3))) \
+           ORDER BY r.player_id, r.tested_on DESC \
+         ) latest ORDER BY percentile DESC, player_name",
+    )
+    .bind(test_type_id)
+    .bind(date)
+    .bind(&teams)
+    .bind(test.higher_is_better)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut values: Vec<f64> = players.iter().map(|p| p.value).collect();
+    values.sort_by(f64::total_cmp);
+    let mean = (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+    Ok(Percentiles {
+        count: players.len(),
+        mean,
+        p25: quantile(&values, 0.25),
+        median: quantile(&values, 0.5),
+        p75: quantile(&values, 0.75),
+        test,
+        date,
+        players,
+    })
+}
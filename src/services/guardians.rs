@@ -0,0 +1,241 @@
+//! Guardians of minor players, and the consents they give.
+//!
+//! A guardian account sees and acts for the players it is linked to and
+//! nobody else. `require_player_access` is the single check handlers use for
+//! anything a player, their guardians, or staff may read about a player.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{PgConnection, PgExecutor};
+
+use crate::auth::Claims;
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::teams;
+
+/// Consents a guardian (or an adult player) can give or withdraw.
+pub const CONSENT_TYPES: &[&str] = &["photos", "medical_treatment", "travel", "data_processing"];
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LinkedPlayer {
+    pub player_id: i64,
+    pub first_name: String,
+    pub last_name: String,
+    pub date_of_birth: chrono::NaiveDate,
+    pub relationship: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Guardian {
+    pub guardian_id: i64,
+    pub name: String,
+    pub email: String,
+    pub relationship: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Consent {
+    pub consent_type: String,
+    pub granted: bool,
+    pub notes: Option<String>,
+    pub given_by: i64,
+    pub given_by_name: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+pub async fn is_guardian_of<'e>(executor: impl PgExecutor<'e>, guardian_id: i64, player_id: i64) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM player_guardians WHERE guardian_id = $1 AND player_id = $2)")
+        .bind(guardian_id)
+        .bind(player_id)
+        .fetch_one(executor)
+        .await
+}
+
+/// Players a guardian is linked to.
+pub async fn linked_players<'e>(executor: impl PgExecutor<'e>, guardian_id: i64) -> Result<Vec<LinkedPlayer>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT p.id AS player_id, p.first_name, p.last_name, p.date_of_birth, g.relationship \
+         FROM player_guardians g JOIN players p ON g.player_id = p.id JOIN users u ON p.user_id = u.id \
+         WHERE g.guardian_id = $1 AND u.deleted_at IS NULL ORDER BY p.last_name, p.first_name",
+    )
+    .bind(guardian_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Ids of the players a guardian is linked to.
+pub async fn linked_player_ids<'e>(executor: impl PgExecutor<'e>, guardian_id: i64) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT player_id FROM player_guardians WHERE guardian_id = $1")
+        .bind(guardian_id)
+        .fetch_all(executor)
+        .await
+}
+
+pub async fn guardians_of<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Guardian>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT u.id AS guardian_id, u.name, u.email, g.relationship FROM player_guardians g JOIN users u ON g.guardian_id = u.id \
+         WHERE g.player_id = $1 AND u.deleted_at IS NULL ORDER BY u.name",
+    )
+    .bind(player_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Allow admins, coaches of the player's teams, the player themself, and the player's guardians.
+/// 404 for players of another club.
+pub async fn require_player_access(conn: &mut PgConnection, claims: &Claims, player_id: i64) -> Result<(), AppError> {
+    let owner: Option<i64> = sqlx::query_scalar(
+        "SELECT p.user_id FROM players p JOIN users u ON p.user_id = u.id WHERE p.id = $1 AND u.club_id = $2 AND u.deleted_at IS NULL",
+    )
+    .bind(player_id)
+    .bind(claims.club_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(owner) = owner else {
+        return Err(AppError::NotFound("Player not found".into()));
+    };
+    let allowed = match claims.role.as_str() {
+        "admin" => true,
+        "coach" => teams::can_see_player(&mut *conn, claims, player_id).await?,
+        "player" => owner == claims.sub,
+        "guardian" => is_guardian_of(&mut *conn, claims.sub, player_id).await?,
+        _ => false,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("You can only see players linked to your account".into()))
+    }
+}
+
+/// Link a guardian account to a player of the same club. Returns false when already linked.
+pub async fn link(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    guardian_id: i64,
+    player_id: i64,
+    relationship: Option<&str>,
+) -> Result<bool, AppError> {
+    let role: Option<String> = sqlx::query_scalar(
+        "SELECT r.name FROM users u JOIN roles r ON u.role_id = r.id WHERE u.id = $1 AND u.club_id = $2 AND u.deleted_at IS NULL",
+    )
+    .bind(guardian_id)
+    .bind(club_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match role.as_deref() {
+        Some("guardian") => {}
+        Some(_) => return Err(AppError::BadRequest("That user does not have the guardian role".into())),
+        None => return Err(AppError::NotFound("User not found".into())),
+    }
+    let player_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM players p JOIN users u ON p.user_id = u.id WHERE p.id = $1 AND u.club_id = $2)",
+    )
+        .bind(player_id)
+        .bind(club_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !player_exists {
+        return Err(AppError::NotFound("Player not found".into()));
+    }
+    let id: Option<i64> = sqlx::query_scalar(
+        "INSERT INTO player_guardians (player_id, guardian_id, relationship, created_by) VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (player_id, guardian_id) DO NOTHING RETURNING id",
+    )
+    .bind(player_id)
+    .bind(guardian_id)
+    .bind(relationship)
+    .bind(ctx.actor_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(id) = id else {
+        return Ok(false);
+    };
+    let after = audit::snapshot(&mut *conn, "player_guardians", id).await?;
+    audit::record(conn, ctx, "link_guardian", "player_guardian", Some(id), None, after).await?;
+    Ok(true)
+}
+
+/// Remove a link. Returns false when the club has none.
+pub async fn unlink(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, guardian_id: i64, player_id: i64) -> Result<bool, sqlx::Error> {
+    let id: Option<i64> = sqlx::query_scalar(
+        "SELECT g.id FROM player_guardians g JOIN users u ON g.guardian_id = u.id \
+         WHERE g.guardian_id = $1 AND g.player_id = $2 AND u.club_id = $3",
+    )
+        .bind(guardian_id)
+        .bind(player_id)
+        .bind(club_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let Some(id) = id else {
+        return Ok(false);
+    };
+    let before = audit::snapshot(&mut *conn, "player_guardians", id).await?;
+    sqlx::query("DELETE FROM player_guardians WHERE id = $1").bind(id).execute(&mut *conn).await?;
+    audit::record(conn, ctx, "unlink_guardian", "player_guardian", Some(id), before, None).await?;
+    Ok(true)
+}
+
+pub async fn consents<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Consent>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT c.consent_type, c.granted, c.notes, c.given_by, u.name AS given_by_name, c.updated_at \
+         FROM player_consents c LEFT JOIN users u ON c.given_by = u.id WHERE c.player_id = $1 ORDER BY c.consent_type",
+    )
+    .bind(player_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Give or withdraw a consent. Minors' consents come from their guardians:
+/// a player with a linked guardian cannot set their own.
+pub async fn set_consent(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    claims: &Claims,
+    player_id: i64,
+    consent_type: &str,
+    granted: bool,
+    notes: Option<&str>,
+) -> Result<(), AppError> {
+    if !CONSENT_TYPES.contains(&consent_type) {
+        return Err(AppError::BadRequest(format!("Unknown consent type '{}'. Use one of: {}", consent_type, CONSENT_TYPES.join(", "))));
+    }
+    match claims.role.as_str() {
+        "admin" | "guardian" => require_player_access(&mut *conn, claims, player_id).await?,
+        "player" => {
+            require_player_access(&mut *conn, claims, player_id).await?;
+            if !guardians_of(&mut *conn, player_id).await?.is_empty() {
+                return Err(AppError::Forbidden("Consents for this player are given by their guardians".into()));
+            }
+        }
+        _ => return Err(AppError::Forbidden("Only the player, their guardians or an admin can give consent".into())),
+    }
+
+    let existing: Option<i64> = sqlx::query_scalar("SELECT id FROM player_consents WHERE player_id = $1 AND consent_type = $2")
+        .bind(player_id)
+        .bind(consent_type)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let before = match existing {
+        Some(id) => audit::snapshot(&mut *conn, "player_consents", id).await?,
+        None => None,
+    };
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO player_consents (player_id, consent_type, granted, notes, given_by, updated_at) \
+         VALUES ($1, $2, $3, $4, $5, NOW()) \
+         ON CONFLICT (player_id, consent_type) DO UPDATE SET granted = EXCLUDED.granted, notes = EXCLUDED.notes, \
+         given_by = EXCLUDED.given_by, updated_at = NOW() RETURNING id",
+    )
+    .bind(player_id)
+    .bind(consent_type)
+    .bind(granted)
+    .bind(notes)
+    .bind(claims.sub)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "player_consents", id).await?;
+    let action = if granted { "grant_consent" } else { "withdraw_consent" };
+    audit::record(conn, ctx, action, "player_consent", Some(id), before, after).await?;
+    Ok(())
+}
@@ -0,0 +1,423 @@
+//! Service for bulk importing players, fixtures and historical results from CSV or XLSX.
+//! Rows are validated with the same rules as the single-record endpoints and
+//! applied in one transaction; any row error rolls back the whole import.
+
+use calamine::{open_workbook_from_rs, Data, DataType, Reader, Xlsx};
+use serde::Serialize;
+use sqlx::{Connection, PgConnection, PgPool};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use crate::auth::{create_account, validate_registration};
+use crate::errors::AppError;
+use crate::models::{MatchCreateRequest, PlayerRegisterDetails, RegisterRequest};
+use crate::services::audit::{self, AuditContext};
+use crate::services::matches;
+
+/// What a file contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    Players,
+    Fixtures,
+    Results,
+}
+
+impl ImportKind {
+    pub fn parse(name: &str) -> Option<ImportKind> {
+        match name {
+            "players" => Some(ImportKind::Players),
+            "fixtures" => Some(ImportKind::Fixtures),
+            "results" => Some(ImportKind::Results),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportKind::Players => "players",
+            ImportKind::Fixtures => "fixtures",
+            ImportKind::Results => "results",
+        }
+    }
+
+    /// Columns that must be present in the header row.
+    pub fn required_columns(&self) -> &'static [&'static str] {
+        match self {
+            ImportKind::Players => &["email", "password", "first_name", "last_name", "date_of_birth", "position", "jersey_number"],
+            ImportKind::Fixtures => &["date", "home_team", "away_team", "location"],
+            ImportKind::Results => &["date", "home_team", "away_team", "location", "home_score", "away_score"],
+        }
+    }
+}
+
+/// Spreadsheet format of an uploaded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ImportFormat {
+    pub fn parse(name: &str) -> Option<ImportFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(ImportFormat::Csv),
+            "xlsx" => Some(ImportFormat::Xlsx),
+            _ => None,
+        }
+    }
+}
+
+/// One data row keyed by lower-case column name, with its line in the source file.
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    pub line: usize,
+    pub fields: HashMap<String, String>,
+}
+
+impl ImportRow {
+    fn get(&self, column: &str) -> &str {
+        self.fields.get(column).map(String::as_str).unwrap_or("")
+    }
+
+    fn optional(&self, column: &str) -> Option<String> {
+        Some(self.get(column)).filter(|v| !v.is_empty()).map(str::to_string)
+    }
+
+    fn optional_number<T: std::str::FromStr>(&self, column: &str) -> Result<Option<T>, String> {
+        match self.optional(column) {
+            None => Ok(None),
+            Some(v) => v.parse().map(Some).map_err(|_| format!("Column '{}' must be a whole number, got '{}'", column, v)),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self, column: &str) -> Result<T, String> {
+        self.optional_number(column)?.ok_or_else(|| format!("Column '{}' is required", column))
+    }
+}
+
+/// A problem with one line of the file. Line 1 is the header row.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ImportError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Outcome of an import or dry run.
+#[derive(Serialize, Debug)]
+pub struct ImportReport {
+    pub success: bool,
+    pub message: String,
+    pub kind: String,
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub imported: usize,
+    pub errors: Vec<ImportError>,
+}
+
+fn header_error(message: String) -> Vec<ImportError> {
+    vec![ImportError { line: 1, message }]
+}
+
+fn build_rows(headers: Vec<String>, records: Vec<(usize, Vec<String>)>) -> Vec<ImportRow> {
+    records
+        .into_iter()
+        .filter(|(_, values)| values.iter().any(|v| !v.is_empty()))
+        .map(|(line, mut values)| {
+            // Short rows leave trailing columns empty rather than missing.
+            values.resize(headers.len(), String::new());
+            ImportRow { line, fields: headers.iter().cloned().zip(values).collect() }
+        })
+        .collect()
+}
+
+/// 1-based line number of the record starting at `offset`. The csv reader
+/// reports positions before any blank lines it skipped, so step over those
+/// and count newlines directly.
+fn line_at(bytes: &[u8], offset: u64) -> usize {
+    let mut start = (offset as usize).min(bytes.len());
+    while start < bytes.len() && (bytes[start] == b'\n' || bytes[start] == b'\r') {
+        start += 1;
+    }
+    bytes[..start].iter().filter(|b| **b == b'\n').count() + 1
+}
+
+/// Read a CSV file with a header row.
+pub fn read_csv(bytes: &[u8]) -> Result<Vec<ImportRow>, Vec<ImportError>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(bytes);
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| header_error(format!("Could not read header row: {}", e)))?
+        .iter()
+        .map(|h| h.to_ascii_lowercase())
+        .collect();
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        match record {
+            Ok(record) => {
+                let line = record.position().map(|p| line_at(bytes, p.byte())).unwrap_or(index + 2);
+                records.push((line, record.iter().map(str::to_string).collect()));
+            }
+            Err(e) => errors.push(ImportError {
+                line: e.position().map(|p| line_at(bytes, p.byte())).unwrap_or(index + 2),
+                message: format!("Malformed CSV: {}", e),
+            }),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(build_rows(headers, records))
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(_) | Data::DateTimeIso(_) => cell.as_date().map(|d| d.to_string()).unwrap_or_default(),
+        Data::Float(f) if f.fract() == 0.0 => format!("{}", *f as i64),
+        Data::Empty => String::new(),
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+/// Read the first worksheet of an XLSX file; its first row is the header.
+pub fn read_xlsx(bytes: &[u8]) -> Result<Vec<ImportRow>, Vec<ImportError>> {
+    let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes))
+        .map_err(|e| header_error(format!("Could not open workbook: {}", e)))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| header_error("Workbook has no worksheets".into()))?
+        .map_err(|e| header_error(format!("Could not read worksheet: {}", e)))?;
+    let first_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+
+    let mut rows = range.rows();
+    let headers: Vec<String> = match rows.next() {
+        Some(cells) => cells.iter().map(|c| cell_text(c).to_ascii_lowercase()).collect(),
+        None => return Ok(Vec::new()),
+    };
+    let records = rows
+        .enumerate()
+        .map(|(index, cells)| (first_row + index + 2, cells.iter().map(cell_text).collect()))
+        .collect();
+    Ok(build_rows(headers, records))
+}
+
+/// Read a file in the given format.
+pub fn read_rows(bytes: &[u8], format: ImportFormat) -> Result<Vec<ImportRow>, Vec<ImportError>> {
+    match format {
+        ImportFormat::Csv => read_csv(bytes),
+        ImportFormat::Xlsx => read_xlsx(bytes),
+    }
+}
+
+/// Check that every required column is present in the header.
+pub fn check_columns(kind: ImportKind, rows: &[ImportRow]) -> Result<(), Vec<ImportError>> {
+    let Some(first) = rows.first() else {
+        return Ok(());
+    };
+    let missing: Vec<&str> = kind
+        .required_columns()
+        .iter()
+        .copied()
+        .filter(|c| !first.fields.contains_key(*c))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(header_error(format!("Missing column(s): {}", missing.join(", "))))
+    }
+}
+
+/// Turn a players row into a registration request. A `role` column may name
+/// `coach`; otherwise the row is a player.
+pub fn player_request(row: &ImportRow) -> Result<RegisterRequest, String> {
+    let role = row.optional("role").unwrap_or_else(|| "player".to_string());
+    let player_details = if role == "player" {
+        let date_of_birth = chrono::NaiveDate::parse_from_str(row.get("date_of_birth"), "%Y-%m-%d")
+            .map_err(|_| "Invalid date_of_birth. Use YYYY-MM-DD.".to_string())?;
+        Some(PlayerRegisterDetails {
+            date_of_birth,
+            position: row.get("position").to_string(),
+            jersey_number: row.number("jersey_number")?,
+        })
+    } else {
+        None
+    };
+    Ok(RegisterRequest {
+        email: row.get("email").to_string(),
+        password: row.get("password").to_string(),
+        first_name: row.get("first_name").to_string(),
+        last_name: row.get("last_name").to_string(),
+        role,
+        player_details,
+        invite_code: None,
+    })
+}
+
+/// Turn a fixtures or results row into a match request.
+pub fn match_request(row: &ImportRow, kind: ImportKind) -> Result<MatchCreateRequest, String> {
+    let (home_score, away_score) = if kind == ImportKind::Results {
+        (Some(row.number("home_score")?), Some(row.number("away_score")?))
+    } else {
+        (row.optional_number("home_score")?, row.optional_number("away_score")?)
+    };
+    Ok(MatchCreateRequest {
+        match_date: row.get("date").to_string(),
+        home_team: row.get("home_team").to_string(),
+        away_team: row.get("away_team").to_string(),
+        location: row.optional("location"),
+        tournament_id: row.optional_number("tournament_id")?,
+        home_score,
+        away_score,
+        stage_id: None,
+        group_id: None,
+        round: None,
+        bracket_position: None,
+        team_id: row.optional_number("team_id")?,
+    })
+}
+
+fn error_message(e: AppError) -> String {
+    match e {
+        AppError::BadRequest(msg) | AppError::Conflict(msg) | AppError::NotFound(msg) => msg,
+        AppError::Database(sqlx::Error::Database(db)) => format!("Rejected by the database: {}", db.message()),
+        other => other.to_string(),
+    }
+}
+
+/// Error unless `team_id` is a team of the club.
+async fn check_team(conn: &mut PgConnection, club_id: i64, team_id: Option<i64>) -> Result<(), AppError> {
+    let Some(team_id) = team_id else {
+        return Ok(());
+    };
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM teams WHERE id = $1 AND club_id = $2)")
+        .bind(team_id)
+        .bind(club_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if exists {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!("Team {} not found", team_id)))
+    }
+}
+
+/// Validate and write one row into the club.
+async fn apply_row(conn: &mut PgConnection, club_id: i64, kind: ImportKind, row: &ImportRow) -> Result<(), AppError> {
+    match kind {
+        ImportKind::Players => {
+            let request = player_request(row).map_err(AppError::BadRequest)?;
+            validate_registration(&request)?;
+            let team_id = row.optional_number::<i64>("team_id").map_err(AppError::BadRequest)?;
+            check_team(conn, club_id, team_id).await?;
+            let user_id = create_account(conn, club_id, &request, "active").await?;
+            // Optional: place the player in a team straight away
+            if let Some(team_id) = team_id {
+                sqlx::query("INSERT INTO team_members (team_id, user_id) VALUES ($1, $2)")
+                    .bind(team_id)
+                    .bind(user_id)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+        ImportKind::Fixtures => {
+            let request = match_request(row, kind).map_err(AppError::BadRequest)?;
+            let date = matches::validate(&request)?;
+            check_team(conn, club_id, request.team_id).await?;
+            matches::check_references(conn, club_id, &request).await?;
+            matches::insert(conn, club_id, &request, date).await?;
+        }
+        ImportKind::Results => {
+            let request = match_request(row, kind).map_err(AppError::BadRequest)?;
+            let date = matches::validate(&request)?;
+            // A result for an existing fixture fills in its score.
+            let updated = sqlx::query(
+                "UPDATE matches SET home_score = $1, away_score = $2 \
+                 WHERE date = $3 AND home_team = $4 AND away_team = $5 AND club_id = $6 AND deleted_at IS NULL",
+            )
+            .bind(request.home_score)
+            .bind(request.away_score)
+            .bind(date)
+            .bind(&request.home_team)
+            .bind(&request.away_team)
+            .bind(club_id)
+            .execute(&mut *conn)
+            .await?;
+            if updated.rows_affected() == 0 {
+                check_team(conn, club_id, request.team_id).await?;
+                matches::check_references(conn, club_id, &request).await?;
+                matches::insert(conn, club_id, &request, date).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validate and apply all rows in a single transaction.
+///
+/// Each row runs inside a savepoint so one bad row does not hide errors in
+/// later rows. The transaction is committed only when every row succeeded and
+/// this is not a dry run; otherwise it is rolled back. A committed import is
+/// recorded as one summary entry in the audit log.
+pub async fn run_import(
+    pool: &PgPool,
+    ctx: &AuditContext,
+    club_id: i64,
+    kind: ImportKind,
+    rows: Vec<ImportRow>,
+    dry_run: bool,
+) -> Result<ImportReport, AppError> {
+    let total_rows = rows.len();
+    if let Err(errors) = check_columns(kind, &rows) {
+        return Ok(report(kind, dry_run, total_rows, 0, errors));
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut imported = 0;
+    let mut errors = Vec::new();
+    for row in &rows {
+        let mut savepoint = tx.begin().await?;
+        match apply_row(&mut savepoint, club_id, kind, row).await {
+            Ok(()) => {
+                savepoint.commit().await?;
+                imported += 1;
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                errors.push(ImportError { line: row.line, message: error_message(e) });
+            }
+        }
+    }
+
+    if errors.is_empty() && !dry_run {
+        let summary = serde_json::json!({ "kind": kind.as_str(), "rows": total_rows, "imported": imported });
+        audit::record(&mut tx, ctx, "import", kind.as_str(), None, None, Some(summary)).await?;
+        tx.commit().await?;
+    } else {
+        tx.rollback().await?;
+    }
+    Ok(report(kind, dry_run, total_rows, imported, errors))
+}
+
+/// Summarise an import. On a dry run `imported` counts the rows that would be saved;
+/// when any row fails nothing is saved and it is zero.
+pub fn report(kind: ImportKind, dry_run: bool, total_rows: usize, imported: usize, errors: Vec<ImportError>) -> ImportReport {
+    let success = errors.is_empty();
+    let message = match (success, dry_run) {
+        (true, true) => format!("Dry run: all {} row(s) are valid. Nothing was saved.", total_rows),
+        (true, false) => format!("Imported {} row(s).", imported),
+        (false, _) => format!("{} error(s) found. Nothing was saved.", errors.len()),
+    };
+    ImportReport {
+        success,
+        message,
+        kind: kind.as_str().to_string(),
+        dry_run,
+        total_rows,
+        imported: if success { imported } else { 0 },
+        errors,
+    }
+}
@@ -0,0 +1,212 @@
+//! Player injuries and medical clearance.
+//!
+//! An injury stays open until it is cleared; a player with an open injury on
+//! a match date is not eligible for that match. The free-text notes are
+//! medical information: the player, their guardians, admins and medical
+//! officers read them, coaches only see what affects availability.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{PgConnection, PgExecutor};
+use std::collections::HashMap;
+
+use crate::auth::Claims;
+use crate::errors::AppError;
+use crate::services::antidoping;
+use crate::services::audit::{self, AuditContext};
+use crate::services::teams::{self, TeamScope};
+
+pub const INJURY_TYPES: &[&str] = &[
+    "sprain", "strain", "fracture", "dislocation", "contusion", "concussion", "ligament", "tendon", "illness", "other",
+];
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Injury {
+    pub id: i64,
+    pub player_id: i64,
+    pub player_name: String,
+    pub injury_type: String,
+    pub body_part: String,
+    pub injured_on: NaiveDate,
+    pub expected_return: Option<NaiveDate>,
+    pub cleared_on: Option<NaiveDate>,
+    pub notes: Option<String>,
+    /// Upcoming matches of the player's teams before the expected return
+    /// (all upcoming matches when no return date is known). 0 once cleared.
+    pub matches_affected: i64,
+}
+
+/// The injury fields a coach or admin provides.
+pub struct InjuryDetails<'a> {
+    pub injury_type: &'a str,
+    pub body_part: &'a str,
+    pub injured_on: NaiveDate,
+    pub expected_return: Option<NaiveDate>,
+    pub notes: Option<&'a str>,
+}
+
+impl InjuryDetails<'_> {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !INJURY_TYPES.contains(&self.injury_type) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown injury type '{}'. Use one of: {}",
+                self.injury_type,
+                INJURY_TYPES.join(", ")
+            )));
+        }
+        if self.body_part.trim().is_empty() || self.body_part.len() > 50 {
+            return Err(AppError::BadRequest("Body part is required (at most 50 characters)".into()));
+        }
+        if self.expected_return.is_some_and(|d| d < self.injured_on) {
+            return Err(AppError::BadRequest("The expected return cannot be before the injury".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `claims` may read the medical notes of a player they can already see.
+/// Only the player, their guardians, admins and medical officers can; any
+/// other role cannot.
+pub fn can_read_notes(claims: &Claims) -> bool {
+    matches!(claims.role.as_str(), "admin" | "player" | "guardian" | antidoping::MEDICAL_OFFICER)
+}
+
+/// Blank the notes unless `claims` may read them.
+pub fn redact(mut injuries: Vec<Injury>, claims: &Claims) -> Vec<Injury> {
+    if !can_read_notes(claims) {
+        for injury in &mut injuries {
+            injury.notes = None;
+        }
+    }
+    injuries
+}
+
+const INJURY_SELECT: &str = "SELECT i.id, i.player_id, p.first_name || ' ' || p.last_name AS player_name, i.injury_type, \
+     i.body_part, i.injured_on, i.expected_return, i.cleared_on, i.notes, \
+     CASE WHEN i.cleared_on IS NOT NULL THEN 0 ELSE ( \
+       SELECT COUNT(*) FROM matches m WHERE m.club_id = u.club_id AND m.deleted_at IS NULL AND m.date >= CURRENT_DATE \
+       AND (i.expected_return IS NULL OR m.date < i.expected_return) \
+       AND (m.team_id IS NULL OR m.team_id IN (SELECT tm.team_id FROM team_members tm WHERE tm.user_id = p.user_id)) \
+     ) END AS matches_affected \
+     FROM player_injuries i JOIN players p ON i.player_id = p.id JOIN users u ON p.user_id = u.id";
+
+/// A player's injuries, most recent first.
+pub async fn for_player<'e>(executor: impl PgExecutor<'e>, player_id: i64) -> Result<Vec<Injury>, sqlx::Error> {
+    sqlx::query_as(&format!("{} WHERE i.player_id = $1 ORDER BY i.injured_on DESC, i.id DESC", INJURY_SELECT))
+        .bind(player_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// Injuries of the club's players visible under `scope`: open ones only
+/// unless `include_cleared`. Longest-standing first.
+pub async fn list<'e>(executor: impl PgExecutor<'e>, club_id: i64, scope: &TeamScope, include_cleared: bool) -> Result<Vec<Injury>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "{} WHERE u.club_id = $2 AND u.deleted_at IS NULL AND ($3 OR i.cleared_on IS NULL) AND {} ORDER BY i.injured_on, i.id",
+        INJURY_SELECT,
+        teams::user_in_scope("p.user_id", 1)
+    ))
+    .bind(scope.ids())
+    .bind(club_id)
+    .bind(include_cleared)
+    .fetch_all(executor)
+    .await
+}
+
+/// The player an injury of the club belongs to. 404 for other clubs' injuries.
+pub async fn player_of<'e>(executor: impl PgExecutor<'e>, club_id: i64, id: i64) -> Result<i64, AppError> {
+    sqlx::query_scalar(
+        "SELECT i.player_id FROM player_injuries i JOIN players p ON i.player_id = p.id JOIN users u ON p.user_id = u.id \
+         WHERE i.id = $1 AND u.club_id = $2",
+    )
+    .bind(id)
+    .bind(club_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Injury not found".into()))
+}
+
+pub async fn create(conn: &mut PgConnection, ctx: &AuditContext, player_id: i64, details: &InjuryDetails<'_>) -> Result<i64, AppError> {
+    details.validate()?;
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO player_injuries (player_id, injury_type, body_part, injured_on, expected_return, notes, reported_by) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+    )
+    .bind(player_id)
+    .bind(details.injury_type)
+    .bind(details.body_part.trim())
+    .bind(details.injured_on)
+    .bind(details.expected_return)
+    .bind(details.notes)
+    .bind(ctx.actor_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "player_injuries", id).await?;
+    audit::record(conn, ctx, "create", "injury", Some(id), None, after).await?;
+    Ok(id)
+}
+
+/// Correct an injury. The notes are left as they are unless `write_notes`:
+/// someone who cannot read them must not blank or overwrite them.
+pub async fn update(conn: &mut PgConnection, ctx: &AuditContext, id: i64, details: &InjuryDetails<'_>, write_notes: bool) -> Result<(), AppError> {
+    details.validate()?;
+    let before = audit::snapshot(&mut *conn, "player_injuries", id).await?;
+    sqlx::query(
+        "UPDATE player_injuries SET injury_type = $2, body_part = $3, injured_on = $4, expected_return = $5, \
+         notes = CASE WHEN $7 THEN $6 ELSE notes END, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(id)
+    .bind(details.injury_type)
+    .bind(details.body_part.trim())
+    .bind(details.injured_on)
+    .bind(details.expected_return)
+    .bind(details.notes)
+    .bind(write_notes)
+    .execute(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "player_injuries", id).await?;
+    audit::record(conn, ctx, "update", "injury", Some(id), before, after).await?;
+    Ok(())
+}
+
+/// Record the medical clearance of an injury. Conflict when already cleared.
+pub async fn clear(conn: &mut PgConnection, ctx: &AuditContext, id: i64, cleared_on: NaiveDate) -> Result<(), AppError> {
+    let before = audit::snapshot(&mut *conn, "player_injuries", id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Injury not found".into()))?;
+    if !before["cleared_on"].is_null() {
+        return Err(AppError::Conflict("This injury has already been cleared".into()));
+    }
+    let injured_on = before["injured_on"].as_str().and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    if injured_on.is_some_and(|d| cleared_on < d) {
+        return Err(AppError::BadRequest("Clearance cannot be before the injury".into()));
+    }
+    sqlx::query("UPDATE player_injuries SET cleared_on = $2, cleared_by = $3, updated_at = NOW() WHERE id = $1")
+        .bind(id)
+        .bind(cleared_on)
+        .bind(ctx.actor_id)
+        .execute(&mut *conn)
+        .await?;
+    let after = audit::snapshot(&mut *conn, "player_injuries", id).await?;
+    audit::record(conn, ctx, "clear", "injury", Some(id), Some(before), after).await?;
+    Ok(())
+}
+
+/// Players injured and not yet cleared on the date of a match, with a
+/// reason that leaves out the medical notes.
+pub async fn uncleared_players<'e>(executor: impl PgExecutor<'e>, match_id: i64) -> Result<HashMap<i64, String>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, String, String, Option<NaiveDate>)>(
+        "SELECT i.player_id, i.injury_type, i.body_part, i.expected_return FROM player_injuries i JOIN matches m ON m.id = $1 \
+         WHERE i.injured_on <= m.date AND (i.cleared_on IS NULL OR i.cleared_on > m.date) ORDER BY i.injured_on",
+    )
+    .bind(match_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(player_id, injury_type, body_part, expected_return)| {
+            let expected = expected_return.map(|d| format!(", expected back {}", d)).unwrap_or_default();
+            (player_id, format!("injured ({} {}{}), not medically cleared", body_part, injury_type, expected))
+        })
+        .collect())
+}
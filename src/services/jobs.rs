@@ -0,0 +1,318 @@
+//! In-process background jobs with their state kept in the `jobs` table.
+//!
+//! Features register a handler under a job name and either `enqueue` one-off
+//! jobs or ask the scheduler to run the job `every` so often. The worker loop
+//! claims due jobs with `FOR UPDATE SKIP LOCKED`, so several app instances can
+//! share the table. A failed run is retried with exponential backoff until
+//! `max_attempts` is reached; the job is then left as `failed` for an admin.
+
+use futures_util::FutureExt;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{PgExecutor, PgPool};
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Attempts before a job is marked `failed`.
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry; doubled for every further attempt.
+const BACKOFF_BASE: Duration = Duration::from_secs(30);
+
+/// Longest delay between two attempts.
+const BACKOFF_MAX: Duration = Duration::from_secs(60 * 60);
+
+/// A `running` job whose lock is older than this belonged to a process that
+/// died mid-run and is handed out again.
+const STALE_AFTER: Duration = Duration::from_secs(15 * 60);
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type JobHandler = Arc<dyn Fn(PgPool, Value) -> JobFuture + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobOutcome {
+    Succeeded,
+    /// Failed, will run again at the given time.
+    Retrying { run_at: chrono::NaiveDateTime, error: String },
+    /// Failed for the last time.
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRun {
+    pub id: i64,
+    pub name: String,
+    pub attempt: i32,
+    pub outcome: JobOutcome,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobSummary {
+    pub id: i64,
+    pub name: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: String,
+    pub last_error: Option<String>,
+    pub completed_at: Option<String>,
+    pub created_at: String,
+}
+
+/// Delay before the next attempt after `attempts` failed runs: 30s, 1m, 2m, … capped at 1h.
+pub fn backoff(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BACKOFF_BASE.saturating_mul(2u32.pow(exponent)).min(BACKOFF_MAX)
+}
+
+/// Queue a job to run at `run_at` (or as soon as possible). Returns the job id.
+/// Pass a transaction to make the job part of the caller's write.
+pub async fn enqueue<'e>(
+    executor: impl PgExecutor<'e>,
+    name: &str,
+    payload: Value,
+    run_at: Option<chrono::NaiveDateTime>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO jobs (name, payload, max_attempts, run_at) VALUES ($1, $2, $3, COALESCE($4, NOW())) RETURNING id",
+    )
+    .bind(name)
+    .bind(payload)
+    .bind(DEFAULT_MAX_ATTEMPTS)
+    .bind(run_at)
+    .fetch_one(executor)
+    .await
+}
+
+/// Jobs for the admin view, newest first.
+pub async fn list(pool: &PgPool, status: Option<&str>, name: Option<&str>, limit: i64) -> Result<Vec<JobSummary>, sqlx::Error> {
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(
+        i64,
+        String,
+        Value,
+        String,
+        i32,
+        i32,
+        chrono::NaiveDateTime,
+        Option<String>,
+        Option<chrono::NaiveDateTime>,
+        chrono::NaiveDateTime,
+    )> = sqlx::query_as(
+        "SELECT id, name, payload, status, attempts, max_attempts, run_at, last_error, completed_at, created_at \
+         FROM jobs \
+         WHERE ($1::TEXT IS NULL OR status = $1) AND ($2::TEXT IS NULL OR name = $2) \
+         ORDER BY created_at DESC, id DESC LIMIT $3",
+    )
+    .bind(status)
+    .bind(name)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    let format = |t: chrono::NaiveDateTime| t.format("%Y-%m-%dT%H:%M:%S").to_string();
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, payload, status, attempts, max_attempts, run_at, last_error, completed_at, created_at)| JobSummary {
+            id,
+            name,
+            payload,
+            status,
+            attempts,
+            max_attempts,
+            run_at: format(run_at),
+            last_error,
+            completed_at: completed_at.map(format),
+            created_at: format(created_at),
+        })
+        .collect())
+}
+
+/// Give a `failed` job a fresh set of attempts. Returns false when the job
+/// does not exist or has not failed.
+pub async fn retry<'e>(executor: impl PgExecutor<'e>, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE jobs SET status = 'pending', attempts = 0, run_at = NOW(), completed_at = NULL \
+         WHERE id = $1 AND status = 'failed'",
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The job runner. Build it at startup, register every job, then `spawn` it.
+pub struct Scheduler {
+    pool: PgPool,
+    poll_interval: Duration,
+    handlers: HashMap<String, JobHandler>,
+    recurring: Vec<(String, Duration)>,
+}
+
+impl Scheduler {
+    pub fn new(pool: PgPool, poll_interval: Duration) -> Self {
+        Scheduler { pool, poll_interval, handlers: HashMap::new(), recurring: Vec::new() }
+    }
+
+    /// Run `handler` for jobs called `name`. An `Err` is stored as the job's
+    /// `last_error` and triggers a retry.
+    pub fn register<F, Fut>(&mut self, name: &str, handler: F) -> &mut Self
+    where
+        F: Fn(PgPool, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.handlers.insert(name.to_string(), Arc::new(move |pool, payload| Box::pin(handler(pool, payload))));
+        self
+    }
+
+    /// Run the registered job `name` every `interval`. The schedule survives
+    /// restarts: the next run is due `interval` after the last one finished.
+    pub fn every(&mut self, name: &str, interval: Duration) -> &mut Self {
+        self.recurring.push((name.to_string(), interval));
+        self
+    }
+
+    /// Queue the next run of each recurring job that has none pending.
+    pub async fn schedule_recurring(&self) -> Result<(), sqlx::Error> {
+        for (name, interval) in &self.recurring {
+            let mut tx = self.pool.begin().await?;
+            // Serialise instances deciding about the same job name
+            sqlx::query("SELECT pg_advisory_xact_lock(hashtext('jobs:' || $1))")
+                .bind(name)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "INSERT INTO jobs (name, max_attempts, run_at) \
+                 SELECT $1, $2, COALESCE( \
+                     (SELECT MAX(completed_at) FROM jobs WHERE name = $1) + make_interval(secs => $3), NOW()) \
+                 WHERE NOT EXISTS (SELECT 1 FROM jobs WHERE name = $1 AND status IN ('pending', 'running'))",
+            )
+            .bind(name)
+            .bind(DEFAULT_MAX_ATTEMPTS)
+            .bind(interval.as_secs_f64())
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+
+    /// Claim and run the next due job, if any. Only jobs with a registered
+    /// handler are claimed.
+    pub async fn run_next(&self) -> Result<Option<JobRun>, sqlx::Error> {
+        let names: Vec<String> = self.handlers.keys().cloned().collect();
+        let claimed: Option<(i64, String, Value, i32, i32)> = sqlx::query_as(
+            "UPDATE jobs SET status = 'running', locked_at = NOW(), attempts = attempts + 1 \
+             WHERE id = ( \
+                 SELECT id FROM jobs \
+                 WHERE name = ANY($1) \
+                   AND (status = 'pending' OR (status = 'running' AND locked_at < NOW() - make_interval(secs => $2))) \
+                   AND run_at <= NOW() \
+                 ORDER BY run_at, id LIMIT 1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, name, payload, attempts, max_attempts",
+        )
+        .bind(&names)
+        .bind(STALE_AFTER.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((id, name, payload, attempt, max_attempts)) = claimed else {
+            return Ok(None);
+        };
+
+        let handler = self.handlers[&name].clone();
+        let result = AssertUnwindSafe(handler(self.pool.clone(), payload))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|_| Err("job panicked".to_string()));
+
+        let outcome = match result {
+            Ok(()) => {
+                sqlx::query("UPDATE jobs SET status = 'succeeded', locked_at = NULL, completed_at = NOW() WHERE id = $1")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+                JobOutcome::Succeeded
+            }
+            Err(error) if attempt >= max_attempts => {
+                sqlx::query(
+                    "UPDATE jobs SET status = 'failed', locked_at = NULL, last_error = $2, completed_at = NOW() WHERE id = $1",
+                )
+                .bind(id)
+                .bind(&error)
+                .execute(&self.pool)
+                .await?;
+                JobOutcome::Failed { error }
+            }
+            Err(error) => {
+                let run_at: chrono::NaiveDateTime = sqlx::query_scalar(
+                    "UPDATE jobs SET status = 'pending', locked_at = NULL, last_error = $2, \
+                            run_at = NOW() + make_interval(secs => $3) \
+                     WHERE id = $1 RETURNING run_at",
+                )
+                .bind(id)
+                .bind(&error)
+                .bind(backoff(attempt).as_secs_f64())
+                .fetch_one(&self.pool)
+                .await?;
+                JobOutcome::Retrying { run_at, error }
+            }
+        };
+        let label = match &outcome {
+            JobOutcome::Succeeded => "succeeded",
+            JobOutcome::Retrying { .. } => "retrying",
+            JobOutcome::Failed { .. } => "failed",
+        };
+        metrics::counter!("jobs_runs_total", "name" => name.clone(), "outcome" => label).increment(1);
+        Ok(Some(JobRun { id, name, attempt, outcome }))
+    }
+
+    /// Run jobs in the background until `shutdown` flips to true (or its
+    /// sender is dropped). A job already running is allowed to finish.
+    pub fn spawn(self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while !*shutdown.borrow() {
+                if let Err(e) = self.schedule_recurring().await {
+                    tracing::error!("Scheduling recurring jobs failed: {}", e);
+                }
+                while !*shutdown.borrow() {
+                    match self.run_next().await {
+                        Ok(Some(run)) => match run.outcome {
+                            JobOutcome::Succeeded => tracing::debug!("Job {} #{} succeeded", run.name, run.id),
+                            JobOutcome::Retrying { run_at, error } => tracing::warn!(
+                                "Job {} #{} failed (attempt {}), retrying at {}: {}",
+                                run.name,
+                                run.id,
+                                run.attempt,
+                                run_at,
+                                error
+                            ),
+                            JobOutcome::Failed { error } => tracing::error!(
+                                "Job {} #{} failed after {} attempts: {}",
+                                run.name,
+                                run.id,
+                                run.attempt,
+                                error
+                            ),
+                        },
+                        Ok(None) => break,
+                        Err(e) => {
+                            tracing::error!("Running jobs failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+                tokio::select! {
+                    changed = shutdown.changed() => if changed.is_err() { break },
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                }
+            }
+            tracing::info!("Job scheduler stopped");
+        })
+    }
+}
@@ -0,0 +1,362 @@
+//! Service for printable match reports (HTML and PDF) used for federation reporting.
+//! A report combines the match, its squad, the event timeline and the match statistics.
+
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::services::match_statistics::{self, MatchStatisticsResponse, TimelineEvent};
+use crate::services::pdf::PdfDocument;
+
+/// Goals per team in one period of play.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PeriodScore {
+    pub period: String,
+    pub home: i32,
+    pub away: i32,
+}
+
+/// A squad member as listed on the team sheet.
+#[derive(Debug, Clone)]
+pub struct SquadMember {
+    pub player_id: i64,
+    pub name: String,
+    pub shirt_number: i32,
+    pub is_goalkeeper: bool,
+}
+
+/// One player's line in the report.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PlayerLine {
+    pub player_id: i64,
+    pub name: String,
+    pub team: String,
+    pub shirt_number: Option<i32>,
+    pub is_goalkeeper: bool,
+    pub goals: i32,
+    pub shots: i32,
+    pub penalty_goals: i32,
+    pub fast_break_goals: i32,
+    pub saves: i32,
+    pub suspensions: i32,
+}
+
+/// One entry of the event timeline.
+#[derive(Serialize, Debug)]
+pub struct TimelineEntry {
+    pub minute: Option<i32>,
+    pub period: String,
+    pub team: String,
+    pub player: String,
+    pub event_type: String,
+}
+
+/// Everything shown on a match report.
+#[derive(Serialize)]
+pub struct MatchReport {
+    pub match_id: i64,
+    pub date: String,
+    pub location: Option<String>,
+    pub tournament: Option<String>,
+    pub home_team: String,
+    pub away_team: String,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
+    pub score_by_period: Vec<PeriodScore>,
+    pub players: Vec<PlayerLine>,
+    pub timeline: Vec<TimelineEntry>,
+    pub statistics: MatchStatisticsResponse,
+}
+
+const PERIODS: [(&str, &str); 3] = [
+    ("first_half", "1st half"),
+    ("second_half", "2nd half"),
+    ("extra_time", "Extra time"),
+];
+
+fn period_label(period: &str) -> &str {
+    PERIODS
+        .iter()
+        .find(|(key, _)| *key == period)
+        .map(|(_, label)| *label)
+        .unwrap_or(period)
+}
+
+/// Goals per team for each half; extra time is only listed when it was played.
+pub fn score_by_period(events: &[TimelineEvent]) -> Vec<PeriodScore> {
+    PERIODS
+        .iter()
+        .filter(|(key, _)| *key != "extra_time" || events.iter().any(|e| e.period == *key))
+        .map(|(key, _)| {
+            let goals = events.iter().filter(|e| e.period == *key && e.event_type == "goal");
+            let home = goals.clone().filter(|e| e.team == "home").count() as i32;
+            let away = goals.filter(|e| e.team != "home").count() as i32;
+            PeriodScore { period: key.to_string(), home, away }
+        })
+        .collect()
+}
+
+/// Per-player lines: every squad member (even without events) plus any
+/// other player who appears in the timeline. Ordered by shirt number.
+pub fn build_player_lines(squad: &[SquadMember], events: &[TimelineEvent]) -> Vec<PlayerLine> {
+    let mut lines: Vec<PlayerLine> = squad
+        .iter()
+        .map(|m| PlayerLine {
+            player_id: m.player_id,
+            name: m.name.clone(),
+            team: String::new(),
+            shirt_number: Some(m.shirt_number),
+            is_goalkeeper: m.is_goalkeeper,
+            ..Default::default()
+        })
+        .collect();
+
+    for e in events {
+        let index = match lines.iter().position(|l| l.player_id == e.player_id) {
+            Some(i) => i,
+            None => {
+                lines.push(PlayerLine {
+                    player_id: e.player_id,
+                    name: format!("{} {}", e.first_name, e.last_name),
+                    ..Default::default()
+                });
+                lines.len() - 1
+            }
+        };
+        let line = &mut lines[index];
+        if line.team.is_empty() {
+            line.team = e.team.clone();
+        }
+        match e.event_type.as_str() {
+            "goal" => {
+                line.goals += 1;
+                line.shots += 1;
+                if e.is_penalty {
+                    line.penalty_goals += 1;
+                }
+                if e.is_fast_break {
+                    line.fast_break_goals += 1;
+                }
+            }
+            "shot_missed" => line.shots += 1,
+            "save" => line.saves += 1,
+            "suspension" => line.suspensions += 1,
+            _ => {}
+        }
+    }
+
+    lines.sort_by_key(|l| (l.shirt_number.is_none(), l.shirt_number, l.player_id));
+    lines
+}
+
+/// Assemble the report for a match. Returns None if the match does not exist.
+pub async fn build_report(pool: &PgPool, match_id: i64) -> Result<Option<MatchReport>, sqlx::Error> {
+    let statistics = match match_statistics::compute_match_statistics(pool, match_id).await? {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    let (date, location, tournament, home_team, away_team, home_score, away_score) =
+        sqlx::query_as::<_, (chrono::NaiveDate, Option<String>, Option<String>, String, String, Option<i32>, Option<i32>)>(
+            "SELECT m.date, m.location, t.name, m.home_team, m.away_team, m.home_score, m.away_score \
+             FROM matches m LEFT JOIN tournaments t ON m.tournament_id = t.id WHERE m.id = $1",
+        )
+        .bind(match_id)
+        .fetch_one(pool)
+        .await?;
+
+    let squad: Vec<SquadMember> = sqlx::query_as::<_, (i64, String, i32, bool)>(
+        "SELECT s.player_id, p.first_name || ' ' || p.last_name, s.shirt_number, s.is_goalkeeper \
+         FROM match_squads s JOIN players p ON s.player_id = p.id WHERE s.match_id = $1",
+    )
+    .bind(match_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(player_id, name, shirt_number, is_goalkeeper)| SquadMember { player_id, name, shirt_number, is_goalkeeper })
+    .collect();
+
+    let events = match_statistics::load_timeline(pool, match_id).await?;
+
+    Ok(Some(MatchReport {
+        match_id,
+        date: date.to_string(),
+        location,
+        tournament,
+        home_team,
+        away_team,
+        // Fall back to the event log when no final score was entered.
+        home_score: home_score.or(Some(statistics.team_statistics.home_team_goals)),
+        away_score: away_score.or(Some(statistics.team_statistics.away_team_goals)),
+        score_by_period: score_by_period(&events),
+        players: build_player_lines(&squad, &events),
+        timeline: events
+            .iter()
+            .map(|e| TimelineEntry {
+                minute: e.minute,
+                period: e.period.clone(),
+                team: e.team.clone(),
+                player: format!("{} {}", e.first_name, e.last_name),
+                event_type: e.event_type.clone(),
+            })
+            .collect(),
+        statistics,
+    }))
+}
+
+fn score_text(score: Option<i32>) -> String {
+    score.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render the report as a standalone HTML page.
+pub fn render_html(report: &MatchReport) -> String {
+    let mut html = String::new();
+    let title = format!("{} vs {}", escape_html(&report.home_team), escape_html(&report.away_team));
+    html.push_str(&format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Match report: {title}</title>\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;margin-bottom:1.5em}}\
+         th,td{{border:1px solid #999;padding:4px 8px;text-align:left}}</style></head><body>\n"
+    ));
+    html.push_str(&format!(
+        "<h1>{title}</h1>\n<p><strong>{} &ndash; {}</strong></p>\n<p>Date: {}",
+        score_text(report.home_score),
+        score_text(report.away_score),
+        report.date
+    ));
+    if let Some(location) = &report.location {
+        html.push_str(&format!(" &middot; Venue: {}", escape_html(location)));
+    }
+    if let Some(tournament) = &report.tournament {
+        html.push_str(&format!(" &middot; Tournament: {}", escape_html(tournament)));
+    }
+    html.push_str("</p>\n");
+
+    html.push_str("<h2>Score by half</h2>\n<table><tr><th>Period</th><th>Home</th><th>Away</th></tr>\n");
+    for p in &report.score_by_period {
+        html.push_str(&format!("<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n", period_label(&p.period), p.home, p.away));
+    }
+    html.push_str("</table>\n");
+
+    let stats = &report.statistics.team_statistics;
+    html.push_str("<h2>Team statistics</h2>\n<table><tr><th></th><th>Home</th><th>Away</th></tr>\n");
+    for (label, home, away) in [
+        ("Goals", stats.home_team_goals, stats.away_team_goals),
+        ("Fast-break goals", stats.home_team_fast_break_goals, stats.away_team_fast_break_goals),
+        ("7m goals", stats.home_team_penalty_goals, stats.away_team_penalty_goals),
+        ("Goalkeeper saves", stats.home_team_goalkeeper_saves, stats.away_team_goalkeeper_saves),
+        ("Blocks and steals", stats.home_team_defense_events, stats.away_team_defense_events),
+    ] {
+        html.push_str(&format!("<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n", label, home, away));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str(
+        "<h2>Players</h2>\n<table><tr><th>No.</th><th>Name</th><th>Goals</th><th>Shots</th>\
+         <th>7m</th><th>Saves</th><th>2 min</th></tr>\n",
+    );
+    for p in &report.players {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            p.shirt_number.map(|n| n.to_string()).unwrap_or_default(),
+            escape_html(&p.name),
+            if p.is_goalkeeper { " (GK)" } else { "" },
+            p.goals,
+            p.shots,
+            p.penalty_goals,
+            p.saves,
+            p.suspensions
+        ));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Timeline</h2>\n<table><tr><th>Min</th><th>Period</th><th>Team</th><th>Player</th><th>Event</th></tr>\n");
+    for e in &report.timeline {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            e.minute.map(|m| m.to_string()).unwrap_or_default(),
+            period_label(&e.period),
+            e.team,
+            escape_html(&e.player),
+            e.event_type.replace('_', " ")
+        ));
+    }
+    html.push_str("</table>\n</body></html>\n");
+    html
+}
+
+/// Render the report as a PDF document.
+pub fn render_pdf(report: &MatchReport) -> Vec<u8> {
+    let mut doc = PdfDocument::new();
+    doc.title(&format!("{} vs {}", report.home_team, report.away_team));
+    doc.text(&format!(
+        "Final score: {} - {}",
+        score_text(report.home_score),
+        score_text(report.away_score)
+    ));
+    let mut details = format!("Date: {}", report.date);
+    if let Some(location) = &report.location {
+        details.push_str(&format!("   Venue: {}", location));
+    }
+    if let Some(tournament) = &report.tournament {
+        details.push_str(&format!("   Tournament: {}", tournament));
+    }
+    doc.text(&details);
+
+    doc.heading("Score by half");
+    doc.mono(&format!("{:<12} {:>5} {:>5}", "Period", "Home", "Away"));
+    for p in &report.score_by_period {
+        doc.mono(&format!("{:<12} {:>5} {:>5}", period_label(&p.period), p.home, p.away));
+    }
+
+    let stats = &report.statistics.team_statistics;
+    doc.heading("Team statistics");
+    doc.mono(&format!("{:<20} {:>5} {:>5}", "", "Home", "Away"));
+    for (label, home, away) in [
+        ("Goals", stats.home_team_goals, stats.away_team_goals),
+        ("Fast-break goals", stats.home_team_fast_break_goals, stats.away_team_fast_break_goals),
+        ("7m goals", stats.home_team_penalty_goals, stats.away_team_penalty_goals),
+        ("Goalkeeper saves", stats.home_team_goalkeeper_saves, stats.away_team_goalkeeper_saves),
+        ("Blocks and steals", stats.home_team_defense_events, stats.away_team_defense_events),
+    ] {
+        doc.mono(&format!("{:<20} {:>5} {:>5}", label, home, away));
+    }
+
+    doc.heading("Players");
+    doc.mono(&format!("{:>3}  {:<28} {:>5} {:>5} {:>4} {:>5} {:>5}", "No", "Name", "Goals", "Shots", "7m", "Saves", "2min"));
+    for p in &report.players {
+        let mut name = p.name.clone();
+        if p.is_goalkeeper {
+            name.push_str(" (GK)");
+        }
+        doc.mono(&format!(
+            "{:>3}  {:<28} {:>5} {:>5} {:>4} {:>5} {:>5}",
+            p.shirt_number.map(|n| n.to_string()).unwrap_or_default(),
+            name.chars().take(28).collect::<String>(),
+            p.goals,
+            p.shots,
+            p.penalty_goals,
+            p.saves,
+            p.suspensions
+        ));
+    }
+
+    doc.heading("Timeline");
+    for e in &report.timeline {
+        doc.mono(&format!(
+            "{:>3}'  {:<10} {:<5} {:<28} {}",
+            e.minute.map(|m| m.to_string()).unwrap_or_default(),
+            period_label(&e.period),
+            e.team,
+            e.player.chars().take(28).collect::<String>(),
+            e.event_type.replace('_', " ")
+        ));
+    }
+
+    doc.render()
+}
@@ -52,6 +52,41 @@ pub struct TeamResult {
     pub away_team: String,
 }
 
+/// A single match event with the player's name and team side.
+/// Shared by the statistics and the match report so both attribute events the same way.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TimelineEvent {
+    pub id: i64,
+    pub player_id: i64,
+    pub first_name: String,
+    pub last_name: String,
+    pub team: String, // "home" or "away"
+    pub event_type: String,
+    pub minute: Option<i32>,
+    pub period: String,
+    pub is_fast_break: bool,
+    pub is_penalty: bool,
+}
+
+/// Load all events of a match in chronological order.
+pub async fn load_timeline(pool: &PgPool, match_id: i64) -> Result<Vec<TimelineEvent>, sqlx::Error> {
+    sqlx::query_as::<_, TimelineEvent>(
+        r#"
+        SELECT e.id, e.player_id, p.first_name, p.last_name,
+            CASE WHEN e.match_id = m.id AND m.home_team = (SELECT home_team FROM matches WHERE id = $1) THEN 'home' ELSE 'away' END as team,
+            e.event_type, e.minute, e.period, e.is_fast_break, e.is_penalty
+        FROM match_events e
+        JOIN players p ON e.player_id = p.id
+        JOIN matches m ON e.match_id = m.id
+        WHERE e.match_id = $1
+        ORDER BY CASE e.period WHEN 'first_half' THEN 1 WHEN 'second_half' THEN 2 ELSE 3 END, e.minute, e.id
+        "#,
+    )
+    .bind(match_id)
+    .fetch_all(pool)
+    .await
+}
+
 /// Compute statistics for a match by match_id.
 /// Returns None if match does not exist.
 /// Returns error for DB issues.
@@ -62,7 +97,7 @@ pub struct TeamResult {
 pub async fn compute_match_statistics(pool: &PgPool, match_id: i64) -> Result<Option<MatchStatisticsResponse>, sqlx::Error> {
     // Get match info
     let match_row = sqlx::query!(
-        r#"SELECT id, tournament_id, season_id, home_team, away_team, home_score, away_score FROM matches WHERE id = $1"#,
+        r#"SELECT id, tournament_id, season_id, home_team, away_team, home_score, away_score FROM matches WHERE id = $1 AND deleted_at IS NULL"#,
         match_id
     ).fetch_optional(pool).await?;
     let match_row = match match_row {
@@ -71,17 +106,7 @@ pub async fn compute_match_statistics(pool: &PgPool, match_id: i64) -> Result<Op
     };
 
     // Get all events for this match, joined with players and match info
-    let events = sqlx::query!(
-        r#"
-        SELECT e.*, p.first_name, p.last_name,
-            CASE WHEN e.match_id = m.id AND m.home_team = (SELECT home_team FROM matches WHERE id = $1) THEN 'home' ELSE 'away' END as team
-        FROM match_events e
-        JOIN players p ON e.player_id = p.id
-        JOIN matches m ON e.match_id = m.id
-        WHERE e.match_id = $1
-        "#,
-        match_id
-    ).fetch_all(pool).await?;
+    let events = load_timeline(pool, match_id).await?;
 
     // Team-level aggregation
     let mut home_team_goals = 0;
@@ -98,10 +123,7 @@ pub async fn compute_match_statistics(pool: &PgPool, match_id: i64) -> Result<Op
     let mut player_stats = std::collections::HashMap::new();
 
     for e in &events {
-        let team = match &e.team {
-            Some(t) if t == "home" => "home",
-            _ => "away",
-        };
+        let team = if e.team == "home" { "home" } else { "away" };
         // Team-level stats
         match e.event_type.as_str() {
             "goal" => {
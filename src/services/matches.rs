@@ -0,0 +1,69 @@
+//! Creating matches, shared by the create endpoint and the fixture import.
+
+use sqlx::PgConnection;
+
+use crate::errors::AppError;
+use crate::models::MatchCreateRequest;
+use crate::services::clubs;
+
+/// Validate a new match and return its parsed date.
+pub fn validate(payload: &MatchCreateRequest) -> Result<chrono::NaiveDate, AppError> {
+    if payload.match_date.is_empty() || payload.home_team.is_empty() || payload.away_team.is_empty() || payload.location.as_deref().unwrap_or("").is_empty() {
+        return Err(AppError::BadRequest("Date, home team, away team, and location are required".into()));
+    }
+    let date = chrono::NaiveDate::parse_from_str(&payload.match_date, "%Y-%m-%d")
+        .map_err(|_| AppError::BadRequest("Invalid date format. Use YYYY-MM-DD.".into()))?;
+    if payload.round.is_some() != payload.bracket_position.is_some() {
+        return Err(AppError::BadRequest("Round and bracket position must be given together".into()));
+    }
+    Ok(date)
+}
+
+/// Check that the tournament, stage and group a new match refers to belong to
+/// the club and to each other: the stage to the tournament, the group to the stage.
+pub async fn check_references(conn: &mut PgConnection, club_id: i64, payload: &MatchCreateRequest) -> Result<(), AppError> {
+    if let Some(tournament_id) = payload.tournament_id {
+        clubs::require_owned(&mut *conn, "tournaments", tournament_id, club_id, "Tournament").await?;
+    }
+    let foreign: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM tournament_stages s JOIN tournaments t ON s.tournament_id = t.id \
+                        WHERE s.id = $1 AND (t.club_id <> $4 OR s.tournament_id IS DISTINCT FROM $3)) \
+         OR EXISTS (SELECT 1 FROM tournament_groups g JOIN tournament_stages s ON g.stage_id = s.id \
+                    JOIN tournaments t ON s.tournament_id = t.id \
+                    WHERE g.id = $2 AND (t.club_id <> $4 OR g.stage_id IS DISTINCT FROM $1))",
+    )
+    .bind(payload.stage_id)
+    .bind(payload.group_id)
+    .bind(payload.tournament_id)
+    .bind(club_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if foreign {
+        return Err(AppError::NotFound("Tournament stage not found".into()));
+    }
+    Ok(())
+}
+
+/// Insert a validated match for a club. Returns the new match id.
+pub async fn insert(conn: &mut PgConnection, club_id: i64, payload: &MatchCreateRequest, date: chrono::NaiveDate) -> Result<i64, sqlx::Error> {
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO matches (date, home_team, away_team, location, tournament_id, home_score, away_score, stage_id, group_id, round, bracket_position, team_id, club_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id",
+    )
+    .bind(date)
+    .bind(&payload.home_team)
+    .bind(&payload.away_team)
+    .bind(&payload.location)
+    .bind(payload.tournament_id)
+    .bind(payload.home_score)
+    .bind(payload.away_score)
+    .bind(payload.stage_id)
+    .bind(payload.group_id)
+    .bind(payload.round)
+    .bind(payload.bracket_position)
+    .bind(payload.team_id)
+    .bind(club_id)
+    .fetch_one(conn)
+    .await?;
+    Ok(id)
+}
@@ -1,2 +1,31 @@
 // This mod.rs file exposes all service modules for the crate.
 pub mod match_statistics;
+pub mod matches;
+pub mod standings;
+pub mod bracket;
+pub mod notifications;
+pub mod squad;
+pub mod pdf;
+pub mod match_report;
+pub mod import;
+pub mod export;
+pub mod backup;
+pub mod audit;
+pub mod trash;
+pub mod jobs;
+pub mod rate_limit;
+pub mod password;
+pub mod totp;
+pub mod two_factor;
+pub mod settings;
+pub mod registration;
+pub mod oidc;
+pub mod guardians;
+pub mod teams;
+pub mod clubs;
+pub mod injuries;
+pub mod antidoping;
+pub mod disciplinary;
+pub mod fitness;
+pub mod payments;
+pub mod fees;
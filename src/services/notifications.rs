@@ -0,0 +1,21 @@
+//! In-app notifications delivered to individual users.
+
+use sqlx::PgExecutor;
+
+/// Queue a notification for a user. Works inside or outside a transaction.
+pub async fn notify<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: i64,
+    title: &str,
+    body: &str,
+    link: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO notifications (user_id, title, body, link) VALUES ($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(title)
+        .bind(body)
+        .bind(link)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,369 @@
+//! Sign-in with OpenID Connect providers (authorization code flow with PKCE).
+//!
+//! `begin` stores a random `state` with the PKCE verifier and nonce and
+//! returns the provider's authorization URL. `finish` consumes the state,
+//! exchanges the code, verifies the ID token against the provider's keys and
+//! resolves the user: an identity linked earlier, or an existing account
+//! with the same verified email, which is then linked. Only ordinary accounts
+//! whose email has not just changed link by email; everyone else starts the
+//! login from a signed-in session to link. Nobody gets an account this way;
+//! sign-up stays with the registration rules.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand_core::{OsRng, RngCore};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgExecutor};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config::{OidcConfig, OidcProvider};
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs::Club;
+
+/// How long a user has to complete the login at the provider.
+pub const STATE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Discovery documents and key sets are fetched again after this long.
+const METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Roles whose accounts may be linked by a matching email alone.
+const EMAIL_LINK_ROLES: &[&str] = &["player", "guardian"];
+
+/// An account whose email changed more recently than this is not linked by email.
+const EMAIL_CHANGE_COOLDOWN: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Signature algorithms accepted on ID tokens. Symmetric ones are refused.
+const ALGORITHMS: &[Algorithm] = &[Algorithm::RS256, Algorithm::RS384, Algorithm::RS512, Algorithm::ES256, Algorithm::ES384];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Discovery {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    #[serde(default, deserialize_with = "flexible_bool")]
+    email_verified: bool,
+}
+
+/// Some providers send `email_verified` as the string "true".
+fn flexible_bool<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Text(String),
+    }
+    Ok(match Option::<Flag>::deserialize(deserializer)? {
+        Some(Flag::Bool(b)) => b,
+        Some(Flag::Text(s)) => s.eq_ignore_ascii_case("true"),
+        None => false,
+    })
+}
+
+/// The external account a login resolved to.
+#[derive(Debug, Clone)]
+pub struct ExternalIdentity {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    /// The signed-in user who started this login to link the provider.
+    pub link_user_id: Option<i64>,
+}
+
+/// Who a login resolved to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    User(i64),
+    /// No account in the club matches the identity.
+    NoMatch,
+    /// An account matches by email, but it has to be linked while signed in.
+    NeedsConfirmation,
+}
+
+/// A login in progress, as handed to the browser.
+pub struct LoginStart {
+    pub state: String,
+    pub authorization_url: String,
+}
+
+fn random_token(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buffer);
+    URL_SAFE_NO_PAD.encode(buffer)
+}
+
+/// The S256 PKCE challenge for a verifier (RFC 7636).
+pub fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+pub fn provider<'a>(config: &'a OidcConfig, name: &str) -> Result<&'a OidcProvider, AppError> {
+    config
+        .providers
+        .iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown sign-in provider '{}'", name)))
+}
+
+/// Where the provider sends the browser back. A `{club}` placeholder in the
+/// public URL is replaced with the club's slug.
+pub fn redirect_uri(config: &OidcConfig, provider: &OidcProvider, club: &Club) -> String {
+    let public_url = config.public_url.replace("{club}", &club.slug);
+    format!("{}/api/auth/oidc/{}/callback", public_url.trim_end_matches('/'), provider.name)
+}
+
+fn http() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| reqwest::Client::builder().timeout(HTTP_TIMEOUT).build().unwrap_or_default())
+}
+
+/// GET a JSON document, caching it for `METADATA_TTL` unless `refresh`.
+async fn fetch_json<T: serde::de::DeserializeOwned>(url: &str, refresh: bool) -> Result<T, AppError> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (Instant, serde_json::Value)>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+    let cached = cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(url)
+        .filter(|(fetched, _)| !refresh && fetched.elapsed() < METADATA_TTL)
+        .map(|(_, value)| value.clone());
+    let value = match cached {
+        Some(value) => value,
+        None => {
+            let value: serde_json::Value = http()
+                .get(url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| AppError::Internal(format!("Fetching {} failed: {}", url, e)))?
+                .json()
+                .await
+                .map_err(|e| AppError::Internal(format!("{} is not JSON: {}", url, e)))?;
+            cache.lock().unwrap_or_else(|e| e.into_inner()).insert(url.to_string(), (Instant::now(), value.clone()));
+            value
+        }
+    };
+    serde_json::from_value(value).map_err(|e| AppError::Internal(format!("Unexpected document at {}: {}", url, e)))
+}
+
+pub async fn discover(provider: &OidcProvider) -> Result<Discovery, AppError> {
+    let url = format!("{}/.well-known/openid-configuration", provider.issuer.trim_end_matches('/'));
+    fetch_json(&url, false).await
+}
+
+/// Start a login to a club: remember the state and build the authorization URL.
+/// `link_user_id` is the signed-in user when the login links the provider.
+pub async fn begin<'e>(
+    executor: impl PgExecutor<'e> + Copy,
+    config: &OidcConfig,
+    provider: &OidcProvider,
+    club: &Club,
+    link_user_id: Option<i64>,
+) -> Result<LoginStart, AppError> {
+    let discovery = discover(provider).await?;
+    let state = random_token(32);
+    let nonce = random_token(32);
+    let verifier = random_token(48);
+
+    sqlx::query("DELETE FROM oidc_login_states WHERE created_at < NOW() - make_interval(secs => $1)")
+        .bind(STATE_TTL.as_secs_f64())
+        .execute(executor)
+        .await?;
+    sqlx::query(
+        "INSERT INTO oidc_login_states (state, provider, code_verifier, nonce, club_id, link_user_id) VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(&state)
+    .bind(&provider.name)
+    .bind(&verifier)
+    .bind(&nonce)
+    .bind(club.id)
+    .bind(link_user_id)
+    .execute(executor)
+    .await?;
+
+    let mut url = url::Url::parse(&discovery.authorization_endpoint)
+        .map_err(|e| AppError::Internal(format!("Invalid authorization_endpoint: {}", e)))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &provider.client_id)
+        .append_pair("redirect_uri", &redirect_uri(config, provider, club))
+        .append_pair("scope", &provider.scopes.join(" "))
+        .append_pair("state", &state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &pkce_challenge(&verifier))
+        .append_pair("code_challenge_method", "S256");
+    Ok(LoginStart { state, authorization_url: url.into() })
+}
+
+/// Complete a login: consume the state, exchange the code and verify the ID token.
+/// The state must have been issued for the same club.
+pub async fn finish<'e>(
+    executor: impl PgExecutor<'e>,
+    config: &OidcConfig,
+    provider: &OidcProvider,
+    club: &Club,
+    state: &str,
+    code: &str,
+) -> Result<ExternalIdentity, AppError> {
+    let (verifier, nonce, link_user_id): (String, String, Option<i64>) = sqlx::query_as(
+        "DELETE FROM oidc_login_states WHERE state = $1 AND provider = $2 AND club_id = $4 \
+         AND created_at > NOW() - make_interval(secs => $3) RETURNING code_verifier, nonce, link_user_id",
+    )
+    .bind(state)
+    .bind(&provider.name)
+    .bind(STATE_TTL.as_secs_f64())
+    .bind(club.id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("The sign-in request expired or was already used. Please try again.".into()))?;
+
+    let discovery = discover(provider).await?;
+    let redirect_uri = redirect_uri(config, provider, club);
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("client_id", provider.client_id.as_str()),
+        ("code_verifier", verifier.as_str()),
+    ];
+    if !provider.client_secret.is_empty() {
+        form.push(("client_secret", provider.client_secret.as_str()));
+    }
+    let response = http()
+        .post(&discovery.token_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| AppError::Internal(format!("Token request to {} failed: {}", provider.name, e)))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        tracing::warn!("Token exchange with {} refused ({}): {}", provider.name, status, body);
+        return Err(AppError::Unauthorized("The sign-in provider refused the login".into()));
+    }
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Internal(format!("Unexpected token response from {}: {}", provider.name, e)))?;
+
+    let claims = verify_id_token(provider, &discovery, &tokens.id_token).await?;
+    if claims.nonce.as_deref() != Some(nonce.as_str()) {
+        return Err(AppError::Unauthorized("The sign-in response does not match this login".into()));
+    }
+    Ok(ExternalIdentity { subject: claims.sub, email: claims.email, email_verified: claims.email_verified, link_user_id })
+}
+
+async fn verify_id_token(provider: &OidcProvider, discovery: &Discovery, token: &str) -> Result<IdTokenClaims, AppError> {
+    let header = jsonwebtoken::decode_header(token)?;
+    if !ALGORITHMS.contains(&header.alg) {
+        return Err(AppError::Unauthorized(format!("ID token algorithm {:?} is not accepted", header.alg)));
+    }
+    // Keys rotate: refetch once when the key id is unknown
+    let mut keys: JwkSet = fetch_json(&discovery.jwks_uri, false).await?;
+    let find = |keys: &JwkSet| match &header.kid {
+        Some(kid) => keys.find(kid).cloned(),
+        None => keys.keys.first().cloned(),
+    };
+    let jwk = match find(&keys) {
+        Some(jwk) => jwk,
+        None => {
+            keys = fetch_json(&discovery.jwks_uri, true).await?;
+            find(&keys).ok_or_else(|| AppError::Unauthorized("ID token is signed with an unknown key".into()))?
+        }
+    };
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&provider.client_id]);
+    validation.set_issuer(&[&discovery.issuer]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    let data = jsonwebtoken::decode::<IdTokenClaims>(token, &DecodingKey::from_jwk(&jwk)?, &validation)?;
+    Ok(data.claims)
+}
+
+/// The club member an external identity signs in as. An identity not linked
+/// yet is linked to the signed-in user who started the login, or else by
+/// verified email to an ordinary account whose email has not just changed.
+pub async fn resolve_user(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    provider: &str,
+    identity: &ExternalIdentity,
+) -> Result<Resolution, AppError> {
+    let linked: Option<i64> = sqlx::query_scalar(
+        "UPDATE user_identities i SET last_login_at = NOW(), email = COALESCE($3, i.email) FROM users u \
+         WHERE i.user_id = u.id AND u.deleted_at IS NULL AND i.provider = $1 AND i.subject = $2 AND i.club_id = $4 \
+         RETURNING u.id",
+    )
+    .bind(provider)
+    .bind(&identity.subject)
+    .bind(&identity.email)
+    .bind(club_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(user_id) = linked {
+        if identity.link_user_id.is_some_and(|id| id != user_id) {
+            return Err(AppError::Conflict("This sign-in account is already linked to another user".into()));
+        }
+        return Ok(Resolution::User(user_id));
+    }
+
+    let user_id = match identity.link_user_id {
+        Some(user_id) => user_id,
+        None => {
+            let Some(email) = identity.email.as_deref().filter(|_| identity.email_verified) else {
+                return Ok(Resolution::NoMatch);
+            };
+            let account: Option<(i64, String, bool)> = sqlx::query_as(
+                "SELECT u.id, r.name, COALESCE(u.email_changed_at > NOW() - make_interval(secs => $3), FALSE) \
+                 FROM users u JOIN roles r ON u.role_id = r.id \
+                 WHERE LOWER(u.email) = LOWER($1) AND u.club_id = $2 AND u.deleted_at IS NULL",
+            )
+            .bind(email)
+            .bind(club_id)
+            .bind(EMAIL_CHANGE_COOLDOWN.as_secs_f64())
+            .fetch_optional(&mut *conn)
+            .await?;
+            match account {
+                None => return Ok(Resolution::NoMatch),
+                Some((_, role, email_changed)) if email_changed || !EMAIL_LINK_ROLES.contains(&role.as_str()) => {
+                    return Ok(Resolution::NeedsConfirmation);
+                }
+                Some((user_id, _, _)) => user_id,
+            }
+        }
+    };
+    let identity_id: i64 = sqlx::query_scalar(
+        "INSERT INTO user_identities (user_id, provider, subject, email, club_id, last_login_at) \
+         VALUES ($1, $2, $3, $4, $5, NOW()) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(provider)
+    .bind(&identity.subject)
+    .bind(&identity.email)
+    .bind(club_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "user_identities", identity_id).await?;
+    audit::record(conn, &ctx.clone().with_actor(user_id), "link_identity", "user_identity", Some(identity_id), None, after).await?;
+    Ok(Resolution::User(user_id))
+}
@@ -0,0 +1,148 @@
+//! Password policy and hashing.
+//!
+//! New passwords must meet the configured length, must not contain the
+//! account's email and must not appear in the breached-password list (a
+//! built-in list of common passwords plus an optional offline file, held in a
+//! bloom filter). Hashes use argon2id with the configured cost parameters;
+//! hashes made with other parameters are upgraded on the next login.
+
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+use crate::config::{self, PasswordConfig};
+use crate::errors::AppError;
+
+/// Built-in breached passwords, always checked.
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// False-positive rate of the breached-password filter.
+const FALSE_POSITIVE_RATE: f64 = 0.001;
+
+static BREACHED: OnceLock<BloomFilter> = OnceLock::new();
+
+/// A fixed-size set that may report false positives but never false negatives.
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// Size the filter for `expected_items` at the given false-positive rate.
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> BloomFilter {
+        let n = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-n * false_positive_rate.ln() / (ln2 * ln2)).ceil().max(64.0) as usize;
+        let hashes = ((bits as f64 / n) * ln2).round().clamp(1.0, 16.0) as u32;
+        BloomFilter { bits: vec![0; bits.div_ceil(64)], hashes }
+    }
+
+    /// A filter holding every non-empty, non-comment line of `lists`, lowercased.
+    pub fn from_lists(lists: &[&str]) -> BloomFilter {
+        let entries = || {
+            lists
+                .iter()
+                .flat_map(|list| list.lines())
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        };
+        let mut filter = BloomFilter::new(entries().count(), FALSE_POSITIVE_RATE);
+        for entry in entries() {
+            filter.insert(&entry.to_lowercase());
+        }
+        filter
+    }
+
+    /// Bit positions for `item`, by double hashing one SHA-256 digest.
+    fn positions(&self, item: &str) -> impl Iterator<Item = usize> {
+        let digest = Sha256::digest(item.as_bytes());
+        let h1 = u64::from_le_bytes(digest[..8].try_into().expect("8 bytes"));
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().expect("8 bytes")) | 1;
+        let size = (self.bits.len() * 64) as u64;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % size) as usize)
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        for bit in self.positions(item).collect::<Vec<_>>() {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        self.positions(item).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// The breached-password filter for the active config, built on first use.
+fn breached() -> &'static BloomFilter {
+    BREACHED.get_or_init(|| {
+        let extra = config::get().password.breached_list.as_ref().and_then(|path| {
+            std::fs::read_to_string(path)
+                .map_err(|e| tracing::error!("Could not read breached password list {}: {}", path.display(), e))
+                .ok()
+        });
+        BloomFilter::from_lists(&[COMMON_PASSWORDS, extra.as_deref().unwrap_or("")])
+    })
+}
+
+/// Check a new password against the policy. Returns the reason it is refused.
+pub fn check(policy: &PasswordConfig, breached: &BloomFilter, password: &str, email: &str) -> Result<(), String> {
+    let length = password.chars().count();
+    if length < policy.min_length {
+        return Err(format!("Password must be at least {} characters", policy.min_length));
+    }
+    if length > policy.max_length {
+        return Err(format!("Password must be at most {} characters", policy.max_length));
+    }
+    let lowered = password.to_lowercase();
+    let email = email.trim().to_lowercase();
+    let local_part = email.split('@').next().unwrap_or("");
+    if local_part.chars().count() >= 3 && lowered.contains(local_part) {
+        return Err("Password must not contain your email address".to_string());
+    }
+    if breached.contains(&lowered) {
+        return Err("This password appears in a list of breached passwords; choose another".to_string());
+    }
+    Ok(())
+}
+
+/// `check` with the active config, as an API error.
+pub fn validate(password: &str, email: &str) -> Result<(), AppError> {
+    check(&config::get().password, breached(), password, email).map_err(AppError::BadRequest)
+}
+
+fn params(policy: &PasswordConfig) -> Result<Params, argon2::Error> {
+    Params::new(policy.argon2_memory_kib, policy.argon2_iterations, policy.argon2_parallelism, None)
+}
+
+/// Hash a password with argon2id and the configured cost.
+pub fn hash(policy: &PasswordConfig, password: &str) -> Result<String, AppError> {
+    let params = params(policy).map_err(|e| AppError::Internal(format!("Invalid argon2 parameters: {}", e)))?;
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))
+}
+
+/// Check a password against a stored hash, whatever parameters it was made with.
+pub fn verify(password: &str, stored: &str) -> Result<bool, AppError> {
+    let parsed = PasswordHash::new(stored).map_err(|e| AppError::Internal(format!("Hash parse error: {}", e)))?;
+    Ok(Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
+
+/// Whether a stored hash was made with anything but the configured algorithm and cost.
+pub fn needs_rehash(policy: &PasswordConfig, stored: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(stored) else {
+        return true;
+    };
+    let (Ok(current), Ok(wanted)) = (Params::try_from(&parsed), params(policy)) else {
+        return true;
+    };
+    parsed.algorithm != Algorithm::Argon2id.ident()
+        || parsed.version != Some(Version::V0x13.into())
+        || current.m_cost() != wanted.m_cost()
+        || current.t_cost() != wanted.t_cost()
+        || current.p_cost() != wanted.p_cost()
+}
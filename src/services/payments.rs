@@ -0,0 +1,148 @@
+//! Online payment providers.
+//!
+//! A provider takes a payment for an invoice at its own checkout (a hosted
+//! card page, an M-Pesa STK push, ...) and later reports whether the money
+//! arrived. `fees::sync_payments` polls the pending ones, so adapters need no
+//! inbound webhook. Only the fake provider exists so far; real adapters
+//! implement `PaymentProvider` and get a `PaymentProviderKind`.
+
+use axum::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{FeesConfig, PaymentProviderKind};
+use crate::errors::AppError;
+
+/// What the payer is asked to pay.
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    /// Our reference, shown to the payer, e.g. the invoice number.
+    pub reference: String,
+    pub description: String,
+    pub amount_cents: i64,
+    pub currency: String,
+    pub payer_email: String,
+}
+
+/// A payment started with a provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct Checkout {
+    pub provider: &'static str,
+    /// The provider's id of the payment, used to ask for its status.
+    pub reference: String,
+    /// Where to send the payer, when the provider has a hosted page.
+    pub checkout_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+impl PaymentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Succeeded => "succeeded",
+            PaymentStatus::Failed => "failed",
+        }
+    }
+}
+
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Stored with each payment, e.g. "stripe" or "mpesa".
+    fn name(&self) -> &'static str;
+
+    async fn create_payment(&self, request: &PaymentRequest) -> Result<Checkout, String>;
+
+    async fn payment_status(&self, reference: &str) -> Result<PaymentStatus, String>;
+}
+
+/// The configured provider, shared by the handlers and the sync job.
+#[derive(Clone, Default)]
+pub struct Payments(Option<Arc<dyn PaymentProvider>>);
+
+impl Payments {
+    pub fn new(provider: Arc<dyn PaymentProvider>) -> Self {
+        Payments(Some(provider))
+    }
+
+    pub fn from_config(config: &FeesConfig) -> Self {
+        match config.payment_provider {
+            PaymentProviderKind::None => Payments(None),
+            PaymentProviderKind::Fake => Payments::new(Arc::new(FakeProvider::new())),
+        }
+    }
+
+    pub fn provider(&self) -> Option<&dyn PaymentProvider> {
+        self.0.as_deref()
+    }
+
+    /// The provider, or 400 when online payments are not set up.
+    pub fn require(&self) -> Result<&dyn PaymentProvider, AppError> {
+        self.provider()
+            .ok_or_else(|| AppError::BadRequest("Online payments are not available. Please pay the treasurer directly.".into()))
+    }
+}
+
+/// Provider that keeps payments in memory and never charges anyone. Payments
+/// stay pending until `complete` or `fail` is called, as a payer would at a
+/// real checkout.
+#[derive(Default)]
+pub struct FakeProvider {
+    payments: Mutex<HashMap<String, PaymentStatus>>,
+}
+
+impl FakeProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn settle(&self, reference: &str, status: PaymentStatus) -> bool {
+        let mut payments = self.payments.lock().unwrap_or_else(|e| e.into_inner());
+        match payments.get_mut(reference) {
+            Some(current) if *current == PaymentStatus::Pending => {
+                *current = status;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Mark a pending payment as paid. False for unknown or settled payments.
+    pub fn complete(&self, reference: &str) -> bool {
+        self.settle(reference, PaymentStatus::Succeeded)
+    }
+
+    /// Mark a pending payment as declined.
+    pub fn fail(&self, reference: &str) -> bool {
+        self.settle(reference, PaymentStatus::Failed)
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for FakeProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn create_payment(&self, request: &PaymentRequest) -> Result<Checkout, String> {
+        if request.amount_cents <= 0 {
+            return Err("amount must be positive".into());
+        }
+        let reference = format!("fake_{}", uuid::Uuid::new_v4().simple());
+        let mut payments = self.payments.lock().unwrap_or_else(|e| e.into_inner());
+        payments.insert(reference.clone(), PaymentStatus::Pending);
+        Ok(Checkout { provider: self.name(), reference, checkout_url: None })
+    }
+
+    async fn payment_status(&self, reference: &str) -> Result<PaymentStatus, String> {
+        let payments = self.payments.lock().unwrap_or_else(|e| e.into_inner());
+        payments.get(reference).copied().ok_or_else(|| format!("unknown payment {}", reference))
+    }
+}
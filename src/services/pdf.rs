@@ -0,0 +1,149 @@
+//! Minimal in-process PDF writer for printable reports.
+//! Lays out lines of text top to bottom on A4 pages using the standard
+//! PDF base fonts, so no font files need to be embedded.
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+
+#[derive(Clone, Copy)]
+enum Style {
+    Title,
+    Heading,
+    Body,
+    Mono,
+}
+
+impl Style {
+    fn font(&self) -> Name<'static> {
+        match self {
+            Style::Title | Style::Heading => Name(b"F2"),
+            Style::Body => Name(b"F1"),
+            Style::Mono => Name(b"F3"),
+        }
+    }
+
+    fn size(&self) -> f32 {
+        match self {
+            Style::Title => 18.0,
+            Style::Heading => 13.0,
+            Style::Body => 10.0,
+            Style::Mono => 9.0,
+        }
+    }
+
+    fn leading(&self) -> f32 {
+        self.size() * 1.4
+    }
+}
+
+/// A simple flowing text document.
+#[derive(Default)]
+pub struct PdfDocument {
+    lines: Vec<(Style, String)>,
+}
+
+impl PdfDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(&mut self, text: &str) -> &mut Self {
+        self.lines.push((Style::Title, text.to_string()));
+        self
+    }
+
+    pub fn heading(&mut self, text: &str) -> &mut Self {
+        self.lines.push((Style::Body, String::new()));
+        self.lines.push((Style::Heading, text.to_string()));
+        self
+    }
+
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.lines.push((Style::Body, text.to_string()));
+        self
+    }
+
+    /// A line in a fixed-width font, for aligned tables.
+    pub fn mono(&mut self, text: &str) -> &mut Self {
+        self.lines.push((Style::Mono, text.to_string()));
+        self
+    }
+
+    /// Render the document to PDF bytes.
+    pub fn render(&self) -> Vec<u8> {
+        // Split lines into pages by available height.
+        let mut pages: Vec<Vec<(Style, &str)>> = vec![Vec::new()];
+        let mut y = PAGE_HEIGHT - MARGIN;
+        for (style, text) in &self.lines {
+            if y - style.leading() < MARGIN {
+                pages.push(Vec::new());
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            y -= style.leading();
+            if let Some(page) = pages.last_mut() {
+                page.push((*style, text.as_str()));
+            }
+        }
+
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let fonts = [
+            (Name(b"F1"), Ref::new(3), Name(b"Helvetica")),
+            (Name(b"F2"), Ref::new(4), Name(b"Helvetica-Bold")),
+            (Name(b"F3"), Ref::new(5), Name(b"Courier")),
+        ];
+        let page_ids: Vec<Ref> = (0..pages.len()).map(|i| Ref::new(6 + 2 * i as i32)).collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(pages.len() as i32);
+        for (_, id, base_font) in fonts {
+            pdf.type1_font(id)
+                .base_font(base_font)
+                .encoding_predefined(Name(b"WinAnsiEncoding"));
+        }
+
+        for (page_lines, page_id) in pages.iter().zip(&page_ids) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(*page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            let mut font_dict = resources.fonts();
+            for (name, id, _) in fonts {
+                font_dict.pair(name, id);
+            }
+            font_dict.finish();
+            resources.finish();
+            page.finish();
+
+            let mut content = Content::new();
+            let mut y = PAGE_HEIGHT - MARGIN;
+            for (style, text) in page_lines {
+                y -= style.leading();
+                content.begin_text();
+                content.set_font(style.font(), style.size());
+                content.next_line(MARGIN, y);
+                content.show(Str(&encode_win_ansi(text)));
+                content.end_text();
+            }
+            pdf.stream(content_id, &content.finish());
+        }
+
+        pdf.finish()
+    }
+}
+
+/// Encode text for the WinAnsi base fonts; characters outside Latin-1 become `?`.
+fn encode_win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u32 as u8,
+            _ => b'?',
+        })
+        .collect()
+}
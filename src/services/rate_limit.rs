@@ -0,0 +1,255 @@
+//! Brute-force protection for the auth routes.
+//!
+//! Two layers: token buckets per client IP (every login/register route) and
+//! per account (login attempts for one email, existing or not), and a
+//! progressive lockout stored on the user after repeated wrong passwords.
+//! Buckets live in memory by default or in `rate_limit_buckets` so several
+//! instances share them.
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{RateLimitConfig, RateLimitStore};
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::jobs::Scheduler;
+use crate::telemetry;
+
+/// Job name of the cleanup of idle database buckets.
+pub const PRUNE_JOB: &str = "rate_limit.prune";
+
+/// Idle buckets are forgotten after this long; by then they have refilled.
+const IDLE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The in-memory store is swept for idle buckets once it holds this many.
+const MAX_MEMORY_KEYS: usize = 10_000;
+
+/// Longest lockout, however many failures.
+const MAX_LOCKOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A token bucket: `burst` requests at once, refilled at `per_minute`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl Limit {
+    /// Refill a bucket holding `tokens` for `elapsed` and take one token.
+    /// Returns the new token count and, when refused, the wait until a token is available.
+    pub fn take(&self, tokens: f64, elapsed: Duration) -> (f64, Option<Duration>) {
+        let rate = self.per_minute as f64 / 60.0;
+        let tokens = (tokens + elapsed.as_secs_f64() * rate).min(self.burst as f64);
+        if tokens >= 1.0 {
+            (tokens - 1.0, None)
+        } else {
+            (tokens, Some(Duration::from_secs_f64((1.0 - tokens) / rate)))
+        }
+    }
+}
+
+/// Lock length after `failures` consecutive wrong passwords: none below
+/// `threshold`, then `base`, doubled for every further failure, capped at a day.
+pub fn lockout_duration(failures: i32, threshold: i32, base: Duration) -> Option<Duration> {
+    if failures < threshold {
+        return None;
+    }
+    let exponent = (failures - threshold).min(20) as u32;
+    Some(base.saturating_mul(2u32.pow(exponent)).min(MAX_LOCKOUT))
+}
+
+enum Store {
+    Memory(Mutex<HashMap<String, (f64, Instant)>>),
+    Database(PgPool),
+}
+
+/// Shared by the auth routes as an `Extension` and as middleware state.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: Store,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig, pool: &PgPool) -> Arc<RateLimiter> {
+        let store = match config.store {
+            RateLimitStore::Memory => Store::Memory(Mutex::new(HashMap::new())),
+            RateLimitStore::Database => Store::Database(pool.clone()),
+        };
+        Arc::new(RateLimiter { config: config.clone(), store })
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Take a token from the bucket `key`, or fail with 429 and `Retry-After`.
+    pub async fn check(&self, key: &str, limit: Limit) -> Result<(), AppError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let wait = match &self.store {
+            Store::Memory(buckets) => {
+                let now = Instant::now();
+                let mut buckets = buckets.lock().unwrap_or_else(|e| e.into_inner());
+                if buckets.len() >= MAX_MEMORY_KEYS {
+                    buckets.retain(|_, (_, updated)| now.duration_since(*updated) < IDLE_TTL);
+                }
+                let (tokens, updated) = buckets.get(key).copied().unwrap_or((limit.burst as f64, now));
+                let (tokens, wait) = limit.take(tokens, now.duration_since(updated));
+                buckets.insert(key.to_string(), (tokens, now));
+                wait
+            }
+            Store::Database(pool) => take_from_database(pool, key, limit).await?,
+        };
+        match wait {
+            None => Ok(()),
+            Some(wait) => {
+                let scope = key.split(':').next().unwrap_or("other").to_string();
+                telemetry::record_rate_limited(scope);
+                Err(AppError::TooManyRequests(
+                    "Too many requests. Please try again later.".into(),
+                    wait.as_secs_f64().ceil() as u64,
+                ))
+            }
+        }
+    }
+
+    /// Count a login attempt against the account's bucket. Emails are only
+    /// unique within a club, so the club is part of the key.
+    pub async fn check_account(&self, club_id: i64, email: &str) -> Result<(), AppError> {
+        let limit = Limit { burst: self.config.account_burst, per_minute: self.config.account_per_minute };
+        self.check(&format!("account:{}:{}", club_id, email.trim().to_lowercase()), limit).await
+    }
+}
+
+async fn take_from_database(pool: &PgPool, key: &str, limit: Limit) -> Result<Option<Duration>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO rate_limit_buckets (key, tokens, updated_at) VALUES ($1, $2, NOW()) ON CONFLICT (key) DO NOTHING")
+        .bind(key)
+        .bind(limit.burst as f64)
+        .execute(&mut *tx)
+        .await?;
+    let (tokens, elapsed): (f64, f64) = sqlx::query_as(
+        "SELECT tokens, GREATEST(EXTRACT(EPOCH FROM NOW() - updated_at), 0)::FLOAT8 \
+         FROM rate_limit_buckets WHERE key = $1 FOR UPDATE",
+    )
+    .bind(key)
+    .fetch_one(&mut *tx)
+    .await?;
+    let (tokens, wait) = limit.take(tokens, Duration::from_secs_f64(elapsed));
+    sqlx::query("UPDATE rate_limit_buckets SET tokens = $2, updated_at = NOW() WHERE key = $1")
+        .bind(key)
+        .bind(tokens)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(wait)
+}
+
+/// The caller's address: the first `X-Forwarded-For` entry when trusted,
+/// otherwise the socket peer.
+fn client_ip(req: &Request, trust_forwarded_for: bool) -> String {
+    let forwarded = req
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(str::trim)
+        .filter(|ip| trust_forwarded_for && !ip.is_empty());
+    match forwarded {
+        Some(ip) => ip.to_string(),
+        None => req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+    }
+}
+
+/// Middleware: a token bucket per client IP and route.
+pub async fn limit_by_ip(State(limiter): State<Arc<RateLimiter>>, req: Request, next: Next) -> Result<Response, AppError> {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let key = format!("ip:{}:{}", client_ip(&req, limiter.config.trust_forwarded_for), route);
+    let limit = Limit { burst: limiter.config.ip_burst, per_minute: limiter.config.ip_per_minute };
+    limiter.check(&key, limit).await?;
+    Ok(next.run(req).await)
+}
+
+/// Count a wrong password; locks the account once the threshold is reached.
+pub async fn record_failed_login(pool: &PgPool, config: &RateLimitConfig, user_id: i64) -> Result<(), sqlx::Error> {
+    let failures: i32 = sqlx::query_scalar("UPDATE users SET failed_logins = failed_logins + 1 WHERE id = $1 RETURNING failed_logins")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if let Some(lock) = lockout_duration(failures, config.lockout_threshold, Duration::from_secs(config.lockout_base_secs)) {
+        sqlx::query("UPDATE users SET locked_until = NOW() + make_interval(secs => $2) WHERE id = $1")
+            .bind(user_id)
+            .bind(lock.as_secs_f64())
+            .execute(pool)
+            .await?;
+        tracing::warn!("Account {} locked for {}s after {} failed logins", user_id, lock.as_secs(), failures);
+    }
+    Ok(())
+}
+
+/// Seconds left on the account's lockout, if it is locked.
+pub async fn lockout_remaining(pool: &PgPool, user_id: i64) -> Result<Option<u64>, sqlx::Error> {
+    let remaining: Option<f64> = sqlx::query_scalar("SELECT EXTRACT(EPOCH FROM locked_until - NOW())::FLOAT8 FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+    Ok(remaining.filter(|secs| *secs > 0.0).map(|secs| secs.ceil() as u64))
+}
+
+/// Reset the failure count after a successful login.
+pub async fn clear_failed_logins(pool: &PgPool, user_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET failed_logins = 0, locked_until = NULL WHERE id = $1 AND (failed_logins > 0 OR locked_until IS NOT NULL)")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Admin unlock: clear the lockout and failure count. Returns false for an unknown user.
+pub async fn unlock(conn: &mut PgConnection, ctx: &AuditContext, user_id: i64) -> Result<bool, sqlx::Error> {
+    let Some(before) = audit::snapshot(&mut *conn, "users", user_id).await?.filter(|row| row["deleted_at"].is_null()) else {
+        return Ok(false);
+    };
+    sqlx::query("UPDATE users SET failed_logins = 0, locked_until = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    let after = audit::snapshot(&mut *conn, "users", user_id).await?;
+    audit::record(conn, ctx, "unlock_user", "user", Some(user_id), Some(before), after).await?;
+    Ok(true)
+}
+
+/// With the database store, drop idle buckets every hour.
+pub fn register_prune_job(scheduler: &mut Scheduler, config: &RateLimitConfig) {
+    if config.store != RateLimitStore::Database {
+        return;
+    }
+    scheduler
+        .register(PRUNE_JOB, |pool, _payload| async move {
+            sqlx::query("DELETE FROM rate_limit_buckets WHERE updated_at < NOW() - make_interval(secs => $1)")
+                .bind(IDLE_TTL.as_secs_f64())
+                .execute(&pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .every(PRUNE_JOB, IDLE_TTL);
+}
@@ -0,0 +1,225 @@
+//! Who may sign up, and how new accounts become active.
+//!
+//! The registration mode is an admin setting: `open` (players are active at
+//! once, coaches wait for approval), `approval` (every sign-up waits) or
+//! `invite_only` (an invite code is required). A valid invite always yields
+//! an active account with the role the admin chose.
+
+use chrono::NaiveDateTime;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgExecutor};
+
+use crate::services::audit::{self, AuditContext};
+use crate::services::{notifications, settings, totp};
+
+/// Settings key holding the registration mode.
+pub const MODE_KEY: &str = "registration_mode";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationMode {
+    #[default]
+    Open,
+    Approval,
+    InviteOnly,
+}
+
+/// `users.status` for a new account.
+pub fn initial_status(mode: RegistrationMode, role: &str, invited: bool) -> &'static str {
+    match mode {
+        _ if invited => "active",
+        RegistrationMode::Open if role == "player" => "active",
+        _ => "pending",
+    }
+}
+
+pub async fn mode<'e>(executor: impl PgExecutor<'e>, club_id: i64) -> Result<RegistrationMode, sqlx::Error> {
+    Ok(settings::get(executor, club_id, MODE_KEY).await?.unwrap_or_default())
+}
+
+pub async fn set_mode(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, mode: RegistrationMode) -> Result<(), sqlx::Error> {
+    let before = self::mode(&mut *conn, club_id).await?;
+    settings::set(&mut *conn, club_id, MODE_KEY, &mode).await?;
+    audit::record(
+        conn,
+        ctx,
+        "update_registration_mode",
+        "app_setting",
+        None,
+        Some(json!({ "mode": before })),
+        Some(json!({ "mode": mode })),
+    )
+    .await
+}
+
+fn hash_code(code: &str) -> String {
+    hex::encode(Sha256::digest(code.trim().to_lowercase().as_bytes()))
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Invite {
+    pub id: i64,
+    pub role: String,
+    pub email: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub created_by: Option<i64>,
+    pub used_by: Option<i64>,
+    pub used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+const INVITE_COLUMNS: &str = "id, role, email, expires_at, created_by, used_by, used_at, revoked_at, created_at";
+
+/// Create an invite. Returns it with the code, which is only shown this once.
+pub async fn create_invite(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    role: &str,
+    email: Option<&str>,
+    expires_at: NaiveDateTime,
+) -> Result<(Invite, String), sqlx::Error> {
+    let mut bytes = [0u8; 15];
+    OsRng.fill_bytes(&mut bytes);
+    let code = totp::base32_encode(&bytes).to_lowercase();
+    let invite: Invite = sqlx::query_as(&format!(
+        "INSERT INTO invites (code_hash, role, email, expires_at, created_by, club_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        INVITE_COLUMNS
+    ))
+    .bind(hash_code(&code))
+    .bind(role)
+    .bind(email)
+    .bind(expires_at)
+    .bind(ctx.actor_id)
+    .bind(club_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let after = audit::snapshot(&mut *conn, "invites", invite.id).await?;
+    audit::record(conn, ctx, "create_invite", "invite", Some(invite.id), None, after).await?;
+    Ok((invite, code))
+}
+
+/// Use an invite to the club for `email`. Returns its role, or None when the
+/// code is unknown, used, revoked, expired or meant for another address or club.
+pub async fn claim_invite(conn: &mut PgConnection, club_id: i64, code: &str, email: &str) -> Result<Option<(i64, String)>, sqlx::Error> {
+    sqlx::query_as(
+        "UPDATE invites SET used_at = NOW() \
+         WHERE code_hash = $1 AND club_id = $3 AND used_at IS NULL AND revoked_at IS NULL AND expires_at > NOW() \
+           AND (email IS NULL OR LOWER(email) = LOWER($2)) \
+         RETURNING id, role",
+    )
+    .bind(hash_code(code))
+    .bind(email)
+    .bind(club_id)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Record the account created from a claimed invite.
+pub async fn mark_invite_used(conn: &mut PgConnection, invite_id: i64, user_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE invites SET used_by = $2 WHERE id = $1")
+        .bind(invite_id)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Every invite to the club, newest first.
+pub async fn list_invites<'e>(executor: impl PgExecutor<'e>, club_id: i64) -> Result<Vec<Invite>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM invites WHERE club_id = $1 ORDER BY created_at DESC, id DESC", INVITE_COLUMNS))
+        .bind(club_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// Revoke an unused invite. Returns false when there is none.
+pub async fn revoke_invite(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, id: i64) -> Result<bool, sqlx::Error> {
+    let before = audit::snapshot(&mut *conn, "invites", id).await?;
+    let revoked = sqlx::query(
+        "UPDATE invites SET revoked_at = NOW() WHERE id = $1 AND club_id = $2 AND used_at IS NULL AND revoked_at IS NULL",
+    )
+    .bind(id)
+    .bind(club_id)
+    .execute(&mut *conn)
+    .await?;
+    if revoked.rows_affected() == 0 {
+        return Ok(false);
+    }
+    let after = audit::snapshot(&mut *conn, "invites", id).await?;
+    audit::record(conn, ctx, "revoke_invite", "invite", Some(id), before, after).await?;
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PendingUser {
+    pub id: i64,
+    pub email: String,
+    pub name: String,
+    pub role: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// Accounts waiting for approval, oldest first.
+pub async fn pending_users<'e>(executor: impl PgExecutor<'e>, club_id: i64) -> Result<Vec<PendingUser>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT u.id, u.email, u.name, r.name AS role, u.created_at FROM users u JOIN roles r ON u.role_id = r.id \
+         WHERE u.club_id = $1 AND u.status = 'pending' AND u.deleted_at IS NULL ORDER BY u.created_at, u.id",
+    )
+    .bind(club_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Tell the club's admins a sign-up is waiting.
+pub async fn notify_admins(conn: &mut PgConnection, club_id: i64, name: &str, role: &str) -> Result<(), sqlx::Error> {
+    let admins: Vec<i64> = sqlx::query_scalar(
+        "SELECT u.id FROM users u JOIN roles r ON u.role_id = r.id WHERE r.name = 'admin' AND u.club_id = $1 AND u.deleted_at IS NULL",
+    )
+    .bind(club_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    for admin in admins {
+        notifications::notify(
+            &mut *conn,
+            admin,
+            "Sign-up awaiting approval",
+            &format!("{} registered as {} and is waiting for approval.", name, role),
+            Some("/admin.html"),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Approve or reject a pending account and notify its owner.
+/// Returns false when there is no such pending user.
+pub async fn review(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, user_id: i64, approve: bool) -> Result<bool, sqlx::Error> {
+    let before = audit::snapshot(&mut *conn, "users", user_id).await?;
+    let status = if approve { "active" } else { "rejected" };
+    let updated = sqlx::query(
+        "UPDATE users SET status = $2 WHERE id = $1 AND club_id = $3 AND status = 'pending' AND deleted_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(status)
+    .bind(club_id)
+    .execute(&mut *conn)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Ok(false);
+    }
+    let (title, body) = if approve {
+        ("Account approved", "Your account has been approved. You can now log in.")
+    } else {
+        ("Account not approved", "Your registration was not approved. Contact the club if you think this is a mistake.")
+    };
+    notifications::notify(&mut *conn, user_id, title, body, None).await?;
+    let after = audit::snapshot(&mut *conn, "users", user_id).await?;
+    let action = if approve { "approve_user" } else { "reject_user" };
+    audit::record(conn, ctx, action, "user", Some(user_id), before, after).await?;
+    Ok(true)
+}
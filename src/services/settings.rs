@@ -0,0 +1,29 @@
+//! Runtime settings changed by admins, stored as JSON in `app_settings` per club.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sqlx::PgExecutor;
+
+/// The stored value for `key`, or None when unset or no longer in the expected shape.
+pub async fn get<'e, T: DeserializeOwned>(executor: impl PgExecutor<'e>, club_id: i64, key: &str) -> Result<Option<T>, sqlx::Error> {
+    let value: Option<Value> = sqlx::query_scalar("SELECT value FROM app_settings WHERE club_id = $1 AND key = $2")
+        .bind(club_id)
+        .bind(key)
+        .fetch_optional(executor)
+        .await?;
+    Ok(value.and_then(|v| serde_json::from_value(v).ok()))
+}
+
+/// Insert or replace the value for `key`. Callers record the change in the audit log.
+pub async fn set<'e, T: Serialize>(executor: impl PgExecutor<'e>, club_id: i64, key: &str, value: &T) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO app_settings (club_id, key, value, updated_at) VALUES ($1, $2, $3, NOW()) \
+         ON CONFLICT (club_id, key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()",
+    )
+    .bind(club_id)
+    .bind(key)
+    .bind(serde_json::to_value(value).unwrap_or(Value::Null))
+    .execute(executor)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Service for match squads (team sheets).
+//! A handball team sheet lists at most 16 players, with shirt numbers,
+//! goalkeepers and a starting seven of exactly one goalkeeper and six court players.
+
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+
+pub const MAX_SQUAD_SIZE: usize = 16;
+pub const STARTING_SIZE: usize = 7;
+
+/// One player on a team sheet.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SquadEntry {
+    pub player_id: i64,
+    pub shirt_number: i32,
+    #[serde(default)]
+    pub is_goalkeeper: bool,
+    #[serde(default)]
+    pub is_starting: bool,
+}
+
+/// Check a team sheet against the handball rules and player eligibility.
+///
+/// `ineligible` maps player ids to the reason they cannot be selected.
+/// When `complete` is set (publishing), the starting seven must be named;
+/// drafts may name fewer starters. Returns every problem found.
+pub fn validate_squad(entries: &[SquadEntry], ineligible: &HashMap<i64, String>, complete: bool) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    if entries.len() > MAX_SQUAD_SIZE {
+        errors.push(format!("A squad can have at most {} players, got {}", MAX_SQUAD_SIZE, entries.len()));
+    }
+
+    let mut players = HashSet::new();
+    let mut shirts = HashSet::new();
+    for e in entries {
+        if !players.insert(e.player_id) {
+            errors.push(format!("Player {} is listed more than once", e.player_id));
+        }
+        if !(1..=99).contains(&e.shirt_number) {
+            errors.push(format!("Shirt number {} must be between 1 and 99", e.shirt_number));
+        } else if !shirts.insert(e.shirt_number) {
+            errors.push(format!("Shirt number {} is used more than once", e.shirt_number));
+        }
+        if let Some(reason) = ineligible.get(&e.player_id) {
+            errors.push(format!("Player {} is not eligible: {}", e.player_id, reason));
+        }
+    }
+
+    if !entries.is_empty() && !entries.iter().any(|e| e.is_goalkeeper) {
+        errors.push("At least one goalkeeper must be designated".to_string());
+    }
+
+    let starters: Vec<&SquadEntry> = entries.iter().filter(|e| e.is_starting).collect();
+    let starting_goalkeepers = starters.iter().filter(|e| e.is_goalkeeper).count();
+    if starters.len() > STARTING_SIZE {
+        errors.push(format!("At most {} players can start, got {}", STARTING_SIZE, starters.len()));
+    }
+    if starting_goalkeepers > 1 {
+        errors.push("Only one goalkeeper can start".to_string());
+    }
+    if complete && (starters.len() != STARTING_SIZE || starting_goalkeepers != 1) {
+        errors.push(format!("The starting seven must be {} players including one goalkeeper", STARTING_SIZE));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Players who cannot be selected for a match, with the reason.
+/// Covers players who declared themselves unavailable, players serving a
+/// suspension on the match date or a match ban, and injured players not
+/// medically cleared.
+pub async fn ineligible_players(pool: &PgPool, match_id: i64) -> Result<HashMap<i64, String>, sqlx::Error> {
+    let mut ineligible = HashMap::new();
+
+    let unavailable = sqlx::query_as::<_, (i64, Option<String>)>(
+        "SELECT player_id, reason FROM match_availability WHERE match_id = $1 AND available = FALSE",
+    )
+    .bind(match_id)
+    .fetch_all(pool)
+    .await?;
+    for (player_id, reason) in unavailable {
+        let reason = reason.filter(|r| !r.is_empty()).unwrap_or_else(|| "no reason given".to_string());
+        ineligible.insert(player_id, format!("unavailable ({})", reason));
+    }
+
+    let suspended = sqlx::query_as::<_, (i64, String)>(
+        "SELECT s.player_id, s.reason FROM player_suspensions s JOIN matches m ON m.id = $1 \
+         WHERE s.starts_on <= m.date AND (s.ends_on IS NULL OR s.ends_on >= m.date)",
+    )
+    .bind(match_id)
+    .fetch_all(pool)
+    .await?;
+    for (player_id, reason) in suspended {
+        ineligible.insert(player_id, format!("suspended ({})", reason));
+    }
+
+    for (player_id, reason) in crate::services::disciplinary::banned_players(pool, match_id).await? {
+        ineligible.entry(player_id).or_insert(reason);
+    }
+
+    for (player_id, reason) in crate::services::injuries::uncleared_players(pool, match_id).await? {
+        ineligible.entry(player_id).or_insert(reason);
+    }
+
+    Ok(ineligible)
+}
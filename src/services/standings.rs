@@ -0,0 +1,314 @@
+//! Service for computing league tables (standings) for tournaments.
+//! Every team that appears in a tournament fixture is ranked, not only our own.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// A tiebreak rule applied, in order, to teams level on points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tiebreaker {
+    /// Points won in matches between the tied teams only.
+    HeadToHead,
+    /// Goal difference in matches between the tied teams only.
+    HeadToHeadGoalDifference,
+    /// Goals scored in matches between the tied teams only.
+    HeadToHeadGoalsFor,
+    /// Overall goal difference.
+    GoalDifference,
+    /// Overall goals scored.
+    GoalsFor,
+}
+
+impl Tiebreaker {
+    pub fn parse(name: &str) -> Option<Tiebreaker> {
+        match name.trim() {
+            "head_to_head" => Some(Tiebreaker::HeadToHead),
+            "head_to_head_goal_difference" => Some(Tiebreaker::HeadToHeadGoalDifference),
+            "head_to_head_goals_for" => Some(Tiebreaker::HeadToHeadGoalsFor),
+            "goal_difference" => Some(Tiebreaker::GoalDifference),
+            "goals_for" => Some(Tiebreaker::GoalsFor),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tiebreaker::HeadToHead => "head_to_head",
+            Tiebreaker::HeadToHeadGoalDifference => "head_to_head_goal_difference",
+            Tiebreaker::HeadToHeadGoalsFor => "head_to_head_goals_for",
+            Tiebreaker::GoalDifference => "goal_difference",
+            Tiebreaker::GoalsFor => "goals_for",
+        }
+    }
+}
+
+/// Whether points for a win, draw and loss are usable: none negative and a
+/// better result never worth fewer points.
+pub fn validate_points(win: i32, draw: i32, loss: i32) -> bool {
+    win >= draw && draw >= loss && loss >= 0
+}
+
+/// Parse a comma-separated tiebreaker list such as `"head_to_head,goal_difference"`.
+/// Returns the first unknown name as the error.
+pub fn parse_tiebreakers(list: &str) -> Result<Vec<Tiebreaker>, String> {
+    list.split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| Tiebreaker::parse(s).ok_or_else(|| s.trim().to_string()))
+        .collect()
+}
+
+/// Points awarded per result and the tiebreak order.
+#[derive(Debug, Clone)]
+pub struct StandingsRules {
+    pub points_for_win: i32,
+    pub points_for_draw: i32,
+    pub points_for_loss: i32,
+    pub tiebreakers: Vec<Tiebreaker>,
+}
+
+impl Default for StandingsRules {
+    /// Handball defaults: 2 points for a win, 1 for a draw.
+    fn default() -> Self {
+        StandingsRules {
+            points_for_win: 2,
+            points_for_draw: 1,
+            points_for_loss: 0,
+            tiebreakers: vec![
+                Tiebreaker::HeadToHead,
+                Tiebreaker::HeadToHeadGoalDifference,
+                Tiebreaker::GoalDifference,
+                Tiebreaker::GoalsFor,
+            ],
+        }
+    }
+}
+
+/// A completed match used as input to the table.
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+    pub home_team: String,
+    pub away_team: String,
+    pub home_score: i32,
+    pub away_score: i32,
+}
+
+/// One line of the league table.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct StandingRow {
+    pub position: i32,
+    pub team: String,
+    pub played: i32,
+    pub won: i32,
+    pub drawn: i32,
+    pub lost: i32,
+    pub goals_for: i32,
+    pub goals_against: i32,
+    pub goal_difference: i32,
+    pub points: i32,
+}
+
+/// Standings for a tournament along with the rules used to compute them.
+#[derive(Serialize)]
+pub struct StandingsResponse {
+    pub tournament_id: i64,
+    pub tournament_name: String,
+    pub points_for_win: i32,
+    pub points_for_draw: i32,
+    pub points_for_loss: i32,
+    pub tiebreakers: Vec<String>,
+    pub standings: Vec<StandingRow>,
+}
+
+/// Accumulate a result into a row from the point of view of one team.
+fn apply_result(row: &mut StandingRow, scored: i32, conceded: i32, rules: &StandingsRules) {
+    row.played += 1;
+    row.goals_for += scored;
+    row.goals_against += conceded;
+    row.goal_difference = row.goals_for - row.goals_against;
+    if scored > conceded {
+        row.won += 1;
+        row.points += rules.points_for_win;
+    } else if scored < conceded {
+        row.lost += 1;
+        row.points += rules.points_for_loss;
+    } else {
+        row.drawn += 1;
+        row.points += rules.points_for_draw;
+    }
+}
+
+/// Build rows for `teams` from `results`, optionally only counting matches
+/// where both sides are in `teams` (used for head-to-head mini tables).
+fn tabulate(teams: &[String], results: &[MatchResult], rules: &StandingsRules, only_between: bool) -> HashMap<String, StandingRow> {
+    let mut rows: HashMap<String, StandingRow> = teams
+        .iter()
+        .map(|t| (t.clone(), StandingRow { team: t.clone(), ..Default::default() }))
+        .collect();
+    for r in results {
+        let both = rows.contains_key(&r.home_team) && rows.contains_key(&r.away_team);
+        if only_between && !both {
+            continue;
+        }
+        if let Some(row) = rows.get_mut(&r.home_team) {
+            apply_result(row, r.home_score, r.away_score, rules);
+        }
+        if let Some(row) = rows.get_mut(&r.away_team) {
+            apply_result(row, r.away_score, r.home_score, rules);
+        }
+    }
+    rows
+}
+
+/// Split a tied group into ordered sub-groups using one tiebreaker.
+fn split_group(group: Vec<StandingRow>, tiebreaker: Tiebreaker, results: &[MatchResult], rules: &StandingsRules) -> Vec<Vec<StandingRow>> {
+    let names: Vec<String> = group.iter().map(|r| r.team.clone()).collect();
+    let mini = tabulate(&names, results, rules, true);
+    let key = |row: &StandingRow| -> i32 {
+        match tiebreaker {
+            Tiebreaker::HeadToHead => mini[&row.team].points,
+            Tiebreaker::HeadToHeadGoalDifference => mini[&row.team].goal_difference,
+            Tiebreaker::HeadToHeadGoalsFor => mini[&row.team].goals_for,
+            Tiebreaker::GoalDifference => row.goal_difference,
+            Tiebreaker::GoalsFor => row.goals_for,
+        }
+    };
+
+    let mut keyed: Vec<(i32, StandingRow)> = group.into_iter().map(|r| (key(&r), r)).collect();
+    keyed.sort_by_key(|k| std::cmp::Reverse(k.0));
+
+    let mut groups: Vec<Vec<StandingRow>> = Vec::new();
+    let mut last_key = None;
+    for (k, row) in keyed {
+        if last_key == Some(k) {
+            if let Some(g) = groups.last_mut() {
+                g.push(row);
+            }
+        } else {
+            groups.push(vec![row]);
+            last_key = Some(k);
+        }
+    }
+    groups
+}
+
+/// Compute the league table for `teams` from completed `results`.
+///
+/// Teams are ordered by points, then by each tiebreaker in `rules` in turn.
+/// Head-to-head rules are evaluated only among the teams still tied at that
+/// step. Teams that remain level after every rule are ordered by name.
+pub fn compute_standings(teams: &[String], results: &[MatchResult], rules: &StandingsRules) -> Vec<StandingRow> {
+    let rows = tabulate(teams, results, rules, false);
+
+    let mut by_points: Vec<StandingRow> = rows.into_values().collect();
+    by_points.sort_by(|a, b| b.points.cmp(&a.points).then_with(|| a.team.cmp(&b.team)));
+
+    let mut groups: Vec<Vec<StandingRow>> = Vec::new();
+    for row in by_points {
+        match groups.last_mut() {
+            Some(g) if g[0].points == row.points => g.push(row),
+            _ => groups.push(vec![row]),
+        }
+    }
+
+    for tiebreaker in &rules.tiebreakers {
+        groups = groups
+            .into_iter()
+            .flat_map(|g| {
+                if g.len() > 1 {
+                    split_group(g, *tiebreaker, results, rules)
+                } else {
+                    vec![g]
+                }
+            })
+            .collect();
+    }
+
+    let mut table: Vec<StandingRow> = Vec::new();
+    for mut group in groups {
+        group.sort_by(|a, b| a.team.cmp(&b.team));
+        table.extend(group);
+    }
+    for (i, row) in table.iter_mut().enumerate() {
+        row.position = i as i32 + 1;
+    }
+    table
+}
+
+/// Load the points and tiebreak configuration of a tournament.
+/// Returns the tournament name alongside the rules, or None if it does not exist.
+pub async fn load_rules(pool: &PgPool, tournament_id: i64) -> Result<Option<(String, StandingsRules)>, sqlx::Error> {
+    let tournament = sqlx::query_as::<_, (String, i32, i32, i32, String)>(
+        "SELECT name, points_for_win, points_for_draw, points_for_loss, tiebreakers FROM tournaments WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(tournament_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(tournament.map(|(name, points_for_win, points_for_draw, points_for_loss, tiebreakers)| {
+        // Unknown names can only come from manual edits; skip them rather than fail the table.
+        let tiebreakers = tiebreakers.split(',').filter_map(Tiebreaker::parse).collect();
+        (name, StandingsRules { points_for_win, points_for_draw, points_for_loss, tiebreakers })
+    }))
+}
+
+/// Build a table from raw `(home_team, away_team, home_score, away_score)` fixtures.
+/// Every team is listed; only fixtures with both scores recorded count.
+pub fn standings_from_fixtures(fixtures: Vec<(String, String, Option<i32>, Option<i32>)>, rules: &StandingsRules) -> Vec<StandingRow> {
+    let mut teams: Vec<String> = Vec::new();
+    let mut results: Vec<MatchResult> = Vec::new();
+    for (home_team, away_team, home_score, away_score) in fixtures {
+        for team in [&home_team, &away_team] {
+            if !teams.contains(team) {
+                teams.push(team.clone());
+            }
+        }
+        if let (Some(home_score), Some(away_score)) = (home_score, away_score) {
+            results.push(MatchResult { home_team, away_team, home_score, away_score });
+        }
+    }
+    compute_standings(&teams, &results, rules)
+}
+
+/// Compute standings for a tournament by id.
+/// Returns None if the tournament does not exist.
+///
+/// Every team appearing in a league or group fixture of the tournament is
+/// listed; knockout matches do not count towards the table.
+pub async fn compute_tournament_standings(pool: &PgPool, tournament_id: i64) -> Result<Option<StandingsResponse>, sqlx::Error> {
+    let (name, rules) = match load_rules(pool, tournament_id).await? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    let fixtures = sqlx::query_as::<_, (String, String, Option<i32>, Option<i32>)>(
+        "SELECT m.home_team, m.away_team, m.home_score, m.away_score FROM matches m \
+         LEFT JOIN tournament_stages s ON m.stage_id = s.id \
+         WHERE m.tournament_id = $1 AND m.deleted_at IS NULL AND (s.id IS NULL OR s.stage_type = 'group')",
+    )
+    .bind(tournament_id)
+    .fetch_all(pool)
+    .await?;
+
+    let standings = standings_from_fixtures(fixtures, &rules);
+
+    Ok(Some(StandingsResponse {
+        tournament_id,
+        tournament_name: name,
+        points_for_win: rules.points_for_win,
+        points_for_draw: rules.points_for_draw,
+        points_for_loss: rules.points_for_loss,
+        tiebreakers: rules.tiebreakers.iter().map(|t| t.as_str().to_string()).collect(),
+        standings,
+    }))
+}
+
+/// Compute the table for a single group of a group stage.
+pub async fn compute_group_standings(pool: &PgPool, group_id: i64, rules: &StandingsRules) -> Result<Vec<StandingRow>, sqlx::Error> {
+    let fixtures = sqlx::query_as::<_, (String, String, Option<i32>, Option<i32>)>(
+        "SELECT home_team, away_team, home_score, away_score FROM matches WHERE group_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(group_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(standings_from_fixtures(fixtures, rules))
+}
@@ -0,0 +1,360 @@
+//! Teams and age groups within the club, and the scoping rules built on them.
+//!
+//! Admins see every team. Everyone else sees the teams they are a member of,
+//! plus anything that has no team: club-wide matches (such as tournament
+//! brackets between other clubs) and players not yet placed in a team.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{PgConnection, PgExecutor};
+
+use crate::auth::Claims;
+use crate::errors::AppError;
+use crate::services::audit::{self, AuditContext};
+use crate::services::clubs;
+
+pub const GENDERS: &[&str] = &["men", "women", "mixed"];
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Team {
+    pub id: i64,
+    pub name: String,
+    pub age_group: Option<String>,
+    pub gender: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub players: i64,
+    pub coaches: i64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TeamMember {
+    pub user_id: i64,
+    pub name: String,
+    pub role: String,
+    pub player_id: Option<i64>,
+}
+
+/// Which teams a user may see.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TeamScope {
+    All,
+    Teams(Vec<i64>),
+}
+
+impl TeamScope {
+    /// Team ids to bind as a `BIGINT[]` filter; `None` means no filter.
+    pub fn ids(&self) -> Option<Vec<i64>> {
+        match self {
+            TeamScope::All => None,
+            TeamScope::Teams(ids) => Some(ids.clone()),
+        }
+    }
+
+    /// Whether something owned by `team_id` is visible. No team means club-wide.
+    pub fn allows(&self, team_id: Option<i64>) -> bool {
+        match (self, team_id) {
+            (TeamScope::All, _) | (_, None) => true,
+            (TeamScope::Teams(ids), Some(id)) => ids.contains(&id),
+        }
+    }
+}
+
+pub async fn member_team_ids<'e>(executor: impl PgExecutor<'e>, user_id: i64) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT team_id FROM team_members WHERE user_id = $1 ORDER BY team_id")
+        .bind(user_id)
+        .fetch_all(executor)
+        .await
+}
+
+pub async fn scope<'e>(executor: impl PgExecutor<'e>, claims: &Claims) -> Result<TeamScope, sqlx::Error> {
+    if claims.role == "admin" {
+        return Ok(TeamScope::All);
+    }
+    Ok(TeamScope::Teams(member_team_ids(executor, claims.sub).await?))
+}
+
+fn forbidden() -> AppError {
+    AppError::Forbidden("This belongs to a team you are not part of".into())
+}
+
+/// Allow admins, and members of `team_id` when there is one.
+/// 404 for a team of another club.
+pub async fn require_team_access<'e>(executor: impl PgExecutor<'e>, claims: &Claims, team_id: Option<i64>) -> Result<(), AppError> {
+    let Some(team_id) = team_id else {
+        return Ok(());
+    };
+    let member: Option<bool> = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM team_members tm WHERE tm.team_id = t.id AND tm.user_id = $2) \
+         FROM teams t WHERE t.id = $1 AND t.club_id = $3",
+    )
+    .bind(team_id)
+    .bind(claims.sub)
+    .bind(claims.club_id)
+    .fetch_optional(executor)
+    .await?;
+    match member {
+        None => Err(AppError::NotFound("Team not found".into())),
+        Some(member) if member || claims.role == "admin" => Ok(()),
+        Some(_) => Err(forbidden()),
+    }
+}
+
+/// Allow access to a match if the user may see its team, and return that team.
+/// 404 for missing or trashed matches and those of another club.
+pub async fn require_match_access<'e>(executor: impl PgExecutor<'e>, claims: &Claims, match_id: i64) -> Result<Option<i64>, AppError> {
+    let row: Option<(Option<i64>, bool)> = sqlx::query_as(
+        "SELECT m.team_id, EXISTS (SELECT 1 FROM team_members tm WHERE tm.team_id = m.team_id AND tm.user_id = $2) \
+         FROM matches m WHERE m.id = $1 AND m.club_id = $3 AND m.deleted_at IS NULL",
+    )
+    .bind(match_id)
+    .bind(claims.sub)
+    .bind(claims.club_id)
+    .fetch_optional(executor)
+    .await?;
+    match row {
+        None => Err(AppError::NotFound("Match not found".into())),
+        Some((team_id, member)) if team_id.is_none() || member || claims.role == "admin" => Ok(team_id),
+        Some(_) => Err(forbidden()),
+    }
+}
+
+/// Whether a user may see a player of their club: admins always; others when
+/// the player shares one of their teams or is in no team yet.
+pub async fn can_see_player<'e>(executor: impl PgExecutor<'e>, claims: &Claims, player_id: i64) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT $3 OR NOT EXISTS (SELECT 1 FROM team_members tm WHERE tm.user_id = p.user_id) \
+         OR EXISTS (SELECT 1 FROM team_members tm JOIN team_members mine ON mine.team_id = tm.team_id \
+                    WHERE tm.user_id = p.user_id AND mine.user_id = $2) \
+         FROM players p JOIN users u ON p.user_id = u.id WHERE p.id = $1 AND u.club_id = $4",
+    )
+    .bind(player_id)
+    .bind(claims.sub)
+    .bind(claims.role == "admin")
+    .bind(claims.club_id)
+    .fetch_optional(executor)
+    .await
+    .map(|visible| visible.unwrap_or(false))
+}
+
+/// SQL condition for list queries: the user in `user_column` is visible under a
+/// scope bound as the `BIGINT[]` parameter `$param` (see `TeamScope::ids`).
+pub fn user_in_scope(user_column: &str, param: usize) -> String {
+    format!(
+        "(${param}::bigint[] IS NULL \
+          OR NOT EXISTS (SELECT 1 FROM team_members tm WHERE tm.user_id = {user_column}) \
+          OR EXISTS (SELECT 1 FROM team_members tm WHERE tm.user_id = {user_column} AND tm.team_id = ANY(${param})))"
+    )
+}
+
+/// Players among `player_ids` who are not in the match's team, or not even in
+/// its club. For club-wide matches only the club counts.
+pub async fn players_outside_match_team<'e>(executor: impl PgExecutor<'e>, match_id: i64, player_ids: &[i64]) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT p.id FROM players p JOIN users u ON p.user_id = u.id, matches m WHERE m.id = $1 AND p.id = ANY($2) \
+         AND (u.club_id <> m.club_id OR (m.team_id IS NOT NULL \
+              AND NOT EXISTS (SELECT 1 FROM team_members tm WHERE tm.team_id = m.team_id AND tm.user_id = p.user_id))) \
+         ORDER BY p.id",
+    )
+    .bind(match_id)
+    .bind(player_ids)
+    .fetch_all(executor)
+    .await
+}
+
+/// The team a new match or training session belongs to: the one requested,
+/// or the creator's only team. Admins may leave it empty for a club-wide match.
+pub async fn team_for_new(conn: &mut PgConnection, claims: &Claims, requested: Option<i64>) -> Result<Option<i64>, AppError> {
+    if let Some(team_id) = requested {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM teams WHERE id = $1 AND club_id = $2)")
+            .bind(team_id)
+            .bind(claims.club_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        if !exists {
+            return Err(AppError::BadRequest("Team not found".into()));
+        }
+        require_team_access(&mut *conn, claims, Some(team_id)).await?;
+        return Ok(Some(team_id));
+    }
+    if claims.role == "admin" {
+        return Ok(None);
+    }
+    match member_team_ids(&mut *conn, claims.sub).await?.as_slice() {
+        [only] => Ok(Some(*only)),
+        _ => Err(AppError::BadRequest("team_id is required: you coach more than one team or none".into())),
+    }
+}
+
+const TEAM_COLUMNS: &str = "t.id, t.name, t.age_group, t.gender, t.created_at, \
+     (SELECT COUNT(*) FROM team_members tm JOIN users u ON tm.user_id = u.id JOIN roles r ON u.role_id = r.id \
+      WHERE tm.team_id = t.id AND r.name = 'player' AND u.deleted_at IS NULL) AS players, \
+     (SELECT COUNT(*) FROM team_members tm JOIN users u ON tm.user_id = u.id JOIN roles r ON u.role_id = r.id \
+      WHERE tm.team_id = t.id AND r.name = 'coach' AND u.deleted_at IS NULL) AS coaches";
+
+pub async fn list<'e>(executor: impl PgExecutor<'e>, club_id: i64, scope: &TeamScope) -> Result<Vec<Team>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM teams t WHERE t.club_id = $2 AND ($1::bigint[] IS NULL OR t.id = ANY($1)) ORDER BY t.name",
+        TEAM_COLUMNS
+    ))
+    .bind(scope.ids())
+    .bind(club_id)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn get<'e>(executor: impl PgExecutor<'e>, club_id: i64, id: i64) -> Result<Option<Team>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM teams t WHERE t.id = $1 AND t.club_id = $2", TEAM_COLUMNS))
+        .bind(id)
+        .bind(club_id)
+        .fetch_optional(executor)
+        .await
+}
+
+pub async fn members<'e>(executor: impl PgExecutor<'e>, team_id: i64) -> Result<Vec<TeamMember>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT u.id AS user_id, u.name, r.name AS role, p.id AS player_id \
+         FROM team_members tm JOIN users u ON tm.user_id = u.id JOIN roles r ON u.role_id = r.id \
+         LEFT JOIN players p ON p.user_id = u.id \
+         WHERE tm.team_id = $1 AND u.deleted_at IS NULL ORDER BY r.name, u.name",
+    )
+    .bind(team_id)
+    .fetch_all(executor)
+    .await
+}
+
+fn validate(name: &str, gender: Option<&str>) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest("Team name is required".into()));
+    }
+    if let Some(gender) = gender {
+        if !GENDERS.contains(&gender) {
+            return Err(AppError::BadRequest("Gender must be 'men', 'women' or 'mixed'".into()));
+        }
+    }
+    Ok(())
+}
+
+fn unique_name(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict("A team with that name already exists".into()),
+        _ => AppError::Database(e),
+    }
+}
+
+pub async fn create(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    name: &str,
+    age_group: Option<&str>,
+    gender: Option<&str>,
+) -> Result<i64, AppError> {
+    validate(name, gender)?;
+    let id: i64 = sqlx::query_scalar("INSERT INTO teams (name, age_group, gender, club_id) VALUES ($1, $2, $3, $4) RETURNING id")
+        .bind(name.trim())
+        .bind(age_group)
+        .bind(gender)
+        .bind(club_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(unique_name)?;
+    let after = audit::snapshot(&mut *conn, "teams", id).await?;
+    audit::record(conn, ctx, "create", "team", Some(id), None, after).await?;
+    Ok(id)
+}
+
+pub async fn update(
+    conn: &mut PgConnection,
+    ctx: &AuditContext,
+    club_id: i64,
+    id: i64,
+    name: &str,
+    age_group: Option<&str>,
+    gender: Option<&str>,
+) -> Result<(), AppError> {
+    validate(name, gender)?;
+    clubs::require_owned(&mut *conn, "teams", id, club_id, "Team").await?;
+    let before = audit::snapshot(&mut *conn, "teams", id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Team not found".into()))?;
+    sqlx::query("UPDATE teams SET name = $1, age_group = $2, gender = $3 WHERE id = $4")
+        .bind(name.trim())
+        .bind(age_group)
+        .bind(gender)
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(unique_name)?;
+    let after = audit::snapshot(&mut *conn, "teams", id).await?;
+    audit::record(conn, ctx, "update", "team", Some(id), Some(before), after).await?;
+    Ok(())
+}
+
+/// Delete a team with no matches or training on record.
+pub async fn delete(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, id: i64) -> Result<(), AppError> {
+    clubs::require_owned(&mut *conn, "teams", id, club_id, "Team").await?;
+    let before = audit::snapshot(&mut *conn, "teams", id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Team not found".into()))?;
+    let in_use: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM matches WHERE team_id = $1) OR EXISTS (SELECT 1 FROM attendance WHERE team_id = $1)",
+    )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if in_use {
+        return Err(AppError::Conflict("The team has matches or training on record and cannot be deleted".into()));
+    }
+    sqlx::query("DELETE FROM teams WHERE id = $1").bind(id).execute(&mut *conn).await?;
+    audit::record(conn, ctx, "delete", "team", Some(id), Some(before), None).await?;
+    Ok(())
+}
+
+/// Add a player or coach of the club to a team. Returns false when already a member.
+pub async fn add_member(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, team_id: i64, user_id: i64) -> Result<bool, AppError> {
+    clubs::require_owned(&mut *conn, "teams", team_id, club_id, "Team").await?;
+    let role: Option<String> = sqlx::query_scalar(
+        "SELECT r.name FROM users u JOIN roles r ON u.role_id = r.id WHERE u.id = $1 AND u.club_id = $2 AND u.deleted_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(club_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match role.as_deref() {
+        Some("player") | Some("coach") => {}
+        Some(_) => return Err(AppError::BadRequest("Only players and coaches belong to teams".into())),
+        None => return Err(AppError::NotFound("User not found".into())),
+    }
+    let id: Option<i64> = sqlx::query_scalar(
+        "INSERT INTO team_members (team_id, user_id) VALUES ($1, $2) ON CONFLICT (team_id, user_id) DO NOTHING RETURNING id",
+    )
+    .bind(team_id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(id) = id else {
+        return Ok(false);
+    };
+    let after = audit::snapshot(&mut *conn, "team_members", id).await?;
+    audit::record(conn, ctx, "add_team_member", "team_member", Some(id), None, after).await?;
+    Ok(true)
+}
+
+/// Remove a member. Returns false when the user was not in the club's team.
+pub async fn remove_member(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, team_id: i64, user_id: i64) -> Result<bool, sqlx::Error> {
+    let id: Option<i64> = sqlx::query_scalar(
+        "SELECT tm.id FROM team_members tm JOIN teams t ON tm.team_id = t.id WHERE tm.team_id = $1 AND tm.user_id = $2 AND t.club_id = $3",
+    )
+    .bind(team_id)
+    .bind(user_id)
+    .bind(club_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(id) = id else {
+        return Ok(false);
+    };
+    let before = audit::snapshot(&mut *conn, "team_members", id).await?;
+    sqlx::query("DELETE FROM team_members WHERE id = $1").bind(id).execute(&mut *conn).await?;
+    audit::record(conn, ctx, "remove_team_member", "team_member", Some(id), before, None).await?;
+    Ok(true)
+}
@@ -0,0 +1,182 @@
+//! Soft-deleted users, matches, seasons and tournaments.
+//!
+//! Deleting one of these only sets `deleted_at`; list queries skip such rows.
+//! Admins can list and restore them until the purge job hard-deletes rows
+//! older than the retention window.
+
+use serde::Serialize;
+use sqlx::{Connection, PgConnection, PgPool};
+use std::time::Duration;
+
+use crate::services::audit::{self, AuditContext};
+use crate::services::jobs::Scheduler;
+
+/// Job name of the purge in the `jobs` table.
+pub const PURGE_JOB: &str = "trash.purge";
+
+/// How often the purge job runs.
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashEntity {
+    Match,
+    Tournament,
+    Season,
+    User,
+}
+
+impl TrashEntity {
+    /// Purge order: children before the rows they reference.
+    pub const ALL: [TrashEntity; 4] = [TrashEntity::Match, TrashEntity::Tournament, TrashEntity::Season, TrashEntity::User];
+
+    pub fn parse(name: &str) -> Option<TrashEntity> {
+        match name {
+            "matches" => Some(TrashEntity::Match),
+            "tournaments" => Some(TrashEntity::Tournament),
+            "seasons" => Some(TrashEntity::Season),
+            "users" => Some(TrashEntity::User),
+            _ => None,
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        match self {
+            TrashEntity::Match => "matches",
+            TrashEntity::Tournament => "tournaments",
+            TrashEntity::Season => "seasons",
+            TrashEntity::User => "users",
+        }
+    }
+
+    /// Entity name used in the audit log.
+    pub fn entity_type(&self) -> &'static str {
+        match self {
+            TrashEntity::Match => "match",
+            TrashEntity::Tournament => "tournament",
+            TrashEntity::Season => "season",
+            TrashEntity::User => "user",
+        }
+    }
+
+    /// SQL expression describing a row to a human.
+    fn label(&self) -> &'static str {
+        match self {
+            TrashEntity::Match => "home_team || ' vs ' || away_team || ' (' || date || ')'",
+            TrashEntity::Tournament | TrashEntity::Season => "name",
+            TrashEntity::User => "name || ' <' || email || '>'",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashItem {
+    pub entity: &'static str,
+    pub id: i64,
+    pub label: String,
+    pub deleted_at: String,
+    pub purge_after: String,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PurgeSummary {
+    pub purged: usize,
+    /// Rows still referenced by live data (for example a season with matches).
+    pub skipped: usize,
+}
+
+/// Soft-deleted rows of a club, most recently deleted first.
+pub async fn list(pool: &PgPool, club_id: i64, entity: Option<TrashEntity>, retention_days: i64) -> Result<Vec<TrashItem>, sqlx::Error> {
+    let mut items = Vec::new();
+    for kind in TrashEntity::ALL.into_iter().filter(|k| entity.is_none_or(|e| e == *k)) {
+        let rows: Vec<(i64, String, chrono::NaiveDateTime)> = sqlx::query_as(&format!(
+            "SELECT id, {}, deleted_at FROM {} WHERE deleted_at IS NOT NULL AND club_id = $1",
+            kind.label(),
+            kind.table()
+        ))
+        .bind(club_id)
+        .fetch_all(pool)
+        .await?;
+        items.extend(rows.into_iter().map(|(id, label, deleted_at)| TrashItem {
+            entity: kind.table(),
+            id,
+            label,
+            deleted_at: deleted_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            purge_after: (deleted_at + chrono::Duration::days(retention_days)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }));
+    }
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(items)
+}
+
+/// Undo a soft delete. Returns false when the row is not in the club's trash.
+pub async fn restore(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, entity: TrashEntity, id: i64) -> Result<bool, sqlx::Error> {
+    let before = audit::snapshot(&mut *conn, entity.table(), id).await?;
+    let result = sqlx::query(&format!(
+        "UPDATE {} SET deleted_at = NULL WHERE id = $1 AND club_id = $2 AND deleted_at IS NOT NULL",
+        entity.table()
+    ))
+    .bind(id)
+    .bind(club_id)
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    let after = audit::snapshot(&mut *conn, entity.table(), id).await?;
+    audit::record(conn, ctx, "restore", entity.entity_type(), Some(id), before, after).await?;
+    Ok(true)
+}
+
+/// Hard-delete rows that have been in the trash longer than `retention_days`.
+///
+/// Each row is deleted in its own savepoint: a row that is still referenced
+/// (the foreign key refuses the delete) is skipped and retried on the next run.
+pub async fn purge(pool: &PgPool, retention_days: i64) -> Result<PurgeSummary, sqlx::Error> {
+    let ctx = AuditContext::default();
+    let mut summary = PurgeSummary::default();
+    let mut tx = pool.begin().await?;
+    for entity in TrashEntity::ALL {
+        let ids: Vec<i64> = sqlx::query_scalar(&format!(
+            "SELECT id FROM {} WHERE deleted_at < NOW() - make_interval(days => $1) ORDER BY id",
+            entity.table()
+        ))
+        .bind(retention_days as i32)
+        .fetch_all(&mut *tx)
+        .await?;
+        for id in ids {
+            let mut savepoint = tx.begin().await?;
+            let before = audit::snapshot(&mut savepoint, entity.table(), id).await?;
+            let deleted = sqlx::query(&format!("DELETE FROM {} WHERE id = $1", entity.table()))
+                .bind(id)
+                .execute(&mut *savepoint)
+                .await;
+            match deleted {
+                Ok(_) => {
+                    audit::record(&mut savepoint, &ctx, "purge", entity.entity_type(), Some(id), before, None).await?;
+                    savepoint.commit().await?;
+                    summary.purged += 1;
+                }
+                Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                    savepoint.rollback().await?;
+                    summary.skipped += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    tx.commit().await?;
+    Ok(summary)
+}
+
+/// Register the daily purge with the job scheduler.
+pub fn register_purge_job(scheduler: &mut Scheduler, retention_days: i64) {
+    scheduler
+        .register(PURGE_JOB, move |pool, _payload| async move {
+            let summary = purge(&pool, retention_days).await.map_err(|e| e.to_string())?;
+            if summary.purged > 0 || summary.skipped > 0 {
+                tracing::info!("Trash purge: {} rows deleted, {} still referenced", summary.purged, summary.skipped);
+            }
+            Ok(())
+        })
+        .every(PURGE_JOB, PURGE_INTERVAL);
+}
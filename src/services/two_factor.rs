@@ -0,0 +1,209 @@
+//! Two-factor enrolment, code checks and the admin policy.
+//!
+//! Enrolment stores a pending secret; 2FA becomes active once a code from it
+//! has been verified, at which point a fresh set of recovery codes is issued.
+//! The policy (roles that must use 2FA) is an admin setting; such users
+//! are taken through enrolment at their next login.
+
+use serde_json::json;
+use sqlx::{PgConnection, PgExecutor};
+
+use crate::services::audit::{self, AuditContext};
+use crate::services::{settings, totp};
+
+/// Settings key holding the roles that must use 2FA.
+pub const POLICY_KEY: &str = "two_factor_required_roles";
+
+/// Roles the policy can cover.
+pub const PRIVILEGED_ROLES: &[&str] = &["admin", "coach"];
+
+/// Recovery codes issued at a time.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Debug, Clone, Default)]
+pub struct TwoFactorState {
+    /// Set during enrolment and while enabled.
+    pub secret: Option<String>,
+    pub enabled: bool,
+    pub last_step: Option<i64>,
+}
+
+/// What `login_handler` does after the password checked out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginStep {
+    /// Issue the token.
+    Done,
+    /// Ask for a code.
+    Verify,
+    /// The policy requires 2FA and the user has none: enrol first.
+    Setup,
+}
+
+impl LoginStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginStep::Done => "done",
+            LoginStep::Verify => "verify",
+            LoginStep::Setup => "setup",
+        }
+    }
+}
+
+/// The user's 2FA state, or None for an unknown or deleted user.
+pub async fn state(conn: &mut PgConnection, user_id: i64) -> Result<Option<TwoFactorState>, sqlx::Error> {
+    let row: Option<(Option<String>, bool, Option<i64>)> = sqlx::query_as(
+        "SELECT totp_secret, totp_enabled_at IS NOT NULL, totp_last_step FROM users WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(row.map(|(secret, enabled, last_step)| TwoFactorState { secret, enabled, last_step }))
+}
+
+/// Roles that must use 2FA. None by default.
+pub async fn required_roles<'e>(executor: impl PgExecutor<'e>, club_id: i64) -> Result<Vec<String>, sqlx::Error> {
+    Ok(settings::get(executor, club_id, POLICY_KEY).await?.unwrap_or_default())
+}
+
+/// Replace the policy. Roles must be from `PRIVILEGED_ROLES`.
+pub async fn set_required_roles(conn: &mut PgConnection, ctx: &AuditContext, club_id: i64, roles: &[String]) -> Result<(), sqlx::Error> {
+    let before = required_roles(&mut *conn, club_id).await?;
+    settings::set(&mut *conn, club_id, POLICY_KEY, &roles).await?;
+    audit::record(
+        conn,
+        ctx,
+        "update_security_policy",
+        "app_setting",
+        None,
+        Some(json!({ "require_2fa_roles": before })),
+        Some(json!({ "require_2fa_roles": roles })),
+    )
+    .await
+}
+
+pub async fn login_step(conn: &mut PgConnection, club_id: i64, user_id: i64, role: &str) -> Result<LoginStep, sqlx::Error> {
+    if state(&mut *conn, user_id).await?.is_some_and(|s| s.enabled) {
+        return Ok(LoginStep::Verify);
+    }
+    if required_roles(&mut *conn, club_id).await?.iter().any(|r| r == role) {
+        return Ok(LoginStep::Setup);
+    }
+    Ok(LoginStep::Done)
+}
+
+/// Store a new pending secret and return it. Callers check 2FA is not already enabled.
+pub async fn begin_enrolment(conn: &mut PgConnection, ctx: &AuditContext, user_id: i64) -> Result<String, sqlx::Error> {
+    let before = audit::snapshot(&mut *conn, "users", user_id).await?;
+    let secret = totp::generate_secret();
+    sqlx::query("UPDATE users SET totp_secret = $2, totp_last_step = NULL WHERE id = $1 AND totp_enabled_at IS NULL")
+        .bind(user_id)
+        .bind(&secret)
+        .execute(&mut *conn)
+        .await?;
+    let after = audit::snapshot(&mut *conn, "users", user_id).await?;
+    audit::record(conn, ctx, "begin_2fa_enrolment", "user", Some(user_id), before, after).await?;
+    Ok(secret)
+}
+
+/// Check a 6-digit TOTP code or, once 2FA is enabled, a recovery code.
+/// A code is accepted only once.
+pub async fn check_code(conn: &mut PgConnection, user_id: i64, state: &TwoFactorState, code: &str) -> Result<bool, sqlx::Error> {
+    let code = code.trim();
+    if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+        let Some(secret) = &state.secret else {
+            return Ok(false);
+        };
+        let Some(step) = totp::verify(secret, code, chrono::Utc::now().timestamp(), state.last_step) else {
+            return Ok(false);
+        };
+        // Guarded on the previous step so two concurrent logins cannot both use it
+        let updated = sqlx::query(
+            "UPDATE users SET totp_last_step = $2 WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)",
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut *conn)
+        .await?;
+        return Ok(updated.rows_affected() == 1);
+    }
+    if !state.enabled {
+        return Ok(false);
+    }
+    let used = sqlx::query(
+        "UPDATE user_recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(totp::hash_recovery_code(code))
+    .execute(&mut *conn)
+    .await?;
+    Ok(used.rows_affected() == 1)
+}
+
+/// Issue a new set of recovery codes, invalidating the old ones.
+pub async fn replace_recovery_codes(conn: &mut PgConnection, user_id: i64) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    let codes = totp::generate_recovery_codes(RECOVERY_CODE_COUNT);
+    let hashes: Vec<String> = codes.iter().map(|c| totp::hash_recovery_code(c)).collect();
+    sqlx::query("INSERT INTO user_recovery_codes (user_id, code_hash) SELECT $1, UNNEST($2::TEXT[])")
+        .bind(user_id)
+        .bind(&hashes)
+        .execute(&mut *conn)
+        .await?;
+    Ok(codes)
+}
+
+/// Replace the recovery codes at the user's request. The audit entry holds
+/// how many unused codes there were and are, never the codes.
+pub async fn regenerate_recovery_codes(conn: &mut PgConnection, ctx: &AuditContext, user_id: i64) -> Result<Vec<String>, sqlx::Error> {
+    let unused: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let codes = replace_recovery_codes(&mut *conn, user_id).await?;
+    audit::record(
+        conn,
+        ctx,
+        "regenerate_recovery_codes",
+        "user",
+        Some(user_id),
+        Some(json!({ "unused_recovery_codes": unused })),
+        Some(json!({ "unused_recovery_codes": codes.len() })),
+    )
+    .await?;
+    Ok(codes)
+}
+
+/// Activate the pending secret. Returns the recovery codes to show once.
+pub async fn enable(conn: &mut PgConnection, ctx: &AuditContext, user_id: i64) -> Result<Vec<String>, sqlx::Error> {
+    let before = audit::snapshot(&mut *conn, "users", user_id).await?;
+    sqlx::query("UPDATE users SET totp_enabled_at = NOW() WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    let codes = replace_recovery_codes(&mut *conn, user_id).await?;
+    let after = audit::snapshot(&mut *conn, "users", user_id).await?;
+    audit::record(conn, ctx, "enable_2fa", "user", Some(user_id), before, after).await?;
+    Ok(codes)
+}
+
+/// Remove 2FA from an account: the user turning it off (`disable_2fa`) or
+/// an admin resetting a lost device (`reset_2fa`). Returns false for an unknown user.
+pub async fn disable(conn: &mut PgConnection, ctx: &AuditContext, user_id: i64, action: &str) -> Result<bool, sqlx::Error> {
+    let Some(before) = audit::snapshot(&mut *conn, "users", user_id).await?.filter(|row| row["deleted_at"].is_null()) else {
+        return Ok(false);
+    };
+    sqlx::query("UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    let after = audit::snapshot(&mut *conn, "users", user_id).await?;
+    audit::record(conn, ctx, action, "user", Some(user_id), Some(before), after).await?;
+    Ok(true)
+}
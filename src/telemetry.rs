@@ -0,0 +1,135 @@
+//! Logging, request ids and Prometheus metrics.
+//!
+//! Every request passes through `request_id` (outermost) and `track_requests`:
+//! the first makes sure an `x-request-id` header exists, the second logs one
+//! structured line per request and records the HTTP metrics.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+use std::sync::OnceLock;
+use std::time::Instant;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogFormat, LoggingConfig};
+use crate::services::audit::REQUEST_ID_HEADER;
+
+/// Latency histogram buckets in seconds.
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// The id of the authenticated user, copied onto the response by the auth
+/// middleware so the request log can include it.
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentUser(pub i64);
+
+/// Install the global log subscriber. `level` uses the `RUST_LOG` filter syntax.
+pub fn init_tracing(logging: &LoggingConfig) {
+    let filter = EnvFilter::try_new(&logging.level).unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    let result = match logging.format {
+        LogFormat::Json => builder.json().flatten_event(true).try_init(),
+        LogFormat::Text => builder.try_init(),
+    };
+    if let Err(e) = result {
+        eprintln!("Logging already initialised: {}", e);
+    }
+}
+
+/// The Prometheus recorder, installed on first use.
+pub fn prometheus() -> &'static PrometheusHandle {
+    PROMETHEUS.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full("http_request_duration_seconds".to_string()), LATENCY_BUCKETS)
+            .expect("valid latency buckets")
+            .build_recorder();
+        let handle = recorder.handle();
+        // Fails only if another recorder was installed first; metrics then go there.
+        let _ = metrics::set_global_recorder(recorder);
+        handle
+    })
+}
+
+/// Render all metrics in the Prometheus text format, refreshing the pool gauges first.
+pub fn render_metrics(pool: &PgPool) -> String {
+    let handle = prometheus();
+    let idle = pool.num_idle() as f64;
+    metrics::gauge!("db_pool_connections", "state" => "idle").set(idle);
+    metrics::gauge!("db_pool_connections", "state" => "in_use").set(pool.size() as f64 - idle);
+    metrics::gauge!("db_pool_max_connections").set(pool.options().get_max_connections() as f64);
+    handle.render()
+}
+
+/// Count a failed login attempt.
+pub fn record_login_failure(reason: &'static str) {
+    metrics::counter!("auth_login_failures_total", "reason" => reason).increment(1);
+}
+
+/// Count a request refused by a rate limit (`ip` or `account`).
+pub fn record_rate_limited(scope: String) {
+    metrics::counter!("rate_limited_total", "scope" => scope).increment(1);
+}
+
+/// Middleware: reuse the caller's `x-request-id` or assign a new one, and echo it on the response.
+pub async fn request_id(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|v| !v.is_empty() && v.len() <= 100)
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()).expect("uuid is a valid header"));
+    req.headers_mut().insert(REQUEST_ID_HEADER, id.clone());
+    let mut response = next.run(req).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, id);
+    response
+}
+
+/// Middleware: one structured log line and the HTTP metrics for every request.
+/// Routes are labelled by their pattern (`/api/matches/:id`) to keep label sets small.
+pub async fn track_requests(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    let response = next.run(req).await;
+
+    let latency = start.elapsed();
+    let status = response.status().as_u16();
+    let user_id = response.extensions().get::<CurrentUser>().map(|u| u.0);
+    metrics::counter!(
+        "http_requests_total",
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status.to_string()
+    )
+    .increment(1);
+    metrics::histogram!("http_request_duration_seconds", "method" => method.clone(), "route" => route.clone())
+        .record(latency.as_secs_f64());
+    tracing::info!(
+        target: "http",
+        method = %method,
+        route = %route,
+        status,
+        latency_ms = latency.as_secs_f64() * 1000.0,
+        user_id,
+        request_id = %request_id,
+        "request"
+    );
+    response
+}
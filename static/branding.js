@@ -0,0 +1,22 @@
+// Club branding: name, logo and colour of the club this address belongs to.
+// Pages mark the places to fill with data-club-name and data-club-logo;
+// the markup keeps the default club's branding until /api/club answers.
+(function () {
+    fetch('/api/club')
+        .then(function (res) { return res.ok ? res.json() : null; })
+        .then(function (club) {
+            if (!club) return;
+            document.querySelectorAll('[data-club-name]').forEach(function (el) {
+                el.textContent = club.name;
+            });
+            document.querySelectorAll('img[data-club-logo]').forEach(function (img) {
+                if (club.logo_url) img.src = club.logo_url;
+                img.alt = club.name + ' Logo';
+            });
+            document.title = document.title.replace('Tornadoes', club.name);
+            if (club.primary_color) {
+                document.documentElement.style.setProperty('--accent', club.primary_color);
+            }
+        })
+        .catch(function () { /* keep the default branding */ });
+})();
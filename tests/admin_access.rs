@@ -8,7 +8,13 @@ use axum::http::Request;
 fn get_token_for_role(role: &str) -> String {
     // Use the same secret and Claims struct as the app
     use handball_team_app::auth::{create_token};
-    create_token(1, "admin@example.com", role, "Admin").unwrap()
+    // The fixture account with that role, since a token's role must match its user's
+    let user_id = match role {
+        "admin" => 1,
+        "coach" => 2,
+        _ => 3,
+    };
+    create_token(user_id, "admin@example.com", role, "Admin", 1).unwrap()
 }
 
 #[tokio::test]
@@ -0,0 +1,148 @@
+//! Tests for anti-doping declarations, TUEs and the medical officer role.
+
+use axum::http::StatusCode;
+use axum::Router;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::antidoping;
+use serde_json::{json, Value};
+
+mod common;
+use common::{admin, player_id, pool, send, send_raw, signup};
+
+/// A coach account promoted to medical officer by an admin.
+async fn medical_officer(app: &Router) -> String {
+    let (user_id, coach) = signup(app, "coach", "doctor").await;
+    let promote = json!({ "user_id": user_id, "role_name": "medical_officer" });
+    let (status, _) = send(app, "POST", "/api/users/role", Some(&coach), promote.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "only admins assign roles");
+    let (status, _) = send(app, "POST", "/api/users/role", Some(&admin()), promote).await;
+    assert_eq!(status, StatusCode::OK);
+    create_token(user_id, "doctor@example.com", "medical_officer", "Doctor", 1).unwrap()
+}
+
+#[tokio::test]
+async fn test_player_cannot_make_themselves_medical_officer() {
+    let app = build_app_for_test().await;
+    let (user_id, player) = signup(&app, "player", "selfpromo").await;
+    let update = json!({ "name": "Self Promo", "email": format!("selfpromo-{}@example.com", user_id), "role": "medical_officer" });
+    let (status, _) = send(&app, "PATCH", &format!("/api/users/{}", user_id), Some(&player), update).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let role: String = sqlx::query_scalar("SELECT r.name FROM users u JOIN roles r ON u.role_id = r.id WHERE u.id = $1")
+        .bind(user_id)
+        .fetch_one(&pool().await)
+        .await
+        .unwrap();
+    assert_eq!(role, "player");
+}
+
+#[tokio::test]
+async fn test_declarations_are_private_and_reviewed() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let officer = medical_officer(&app).await;
+    let (player_user, player) = signup(&app, "player", "declarer").await;
+    let (_, other) = signup(&app, "player", "bystander").await;
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let id = player_id(&pool, player_user).await;
+    let uri = format!("/api/players/{}/medications", id);
+
+    let declaration = json!({
+        "kind": "medication", "name": "Salbutamol inhaler", "dosage": "2 puffs",
+        "started_on": "2026-02-01", "ended_on": "2026-01-01",
+    });
+    let (status, _) = send(&app, "POST", &uri, Some(&player), declaration.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "ends before it starts");
+    let mut declaration = declaration;
+    declaration["ended_on"] = json!(null);
+    let (status, _) = send(&app, "POST", &uri, Some(&player), declaration.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    for (token, who) in [(&coach, "coach"), (&admin(), "admin"), (&other, "another player")] {
+        let (status, _) = send(&app, "GET", &uri, Some(token), json!({})).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} must not read declarations", who);
+    }
+    let (status, _) = send(&app, "GET", "/api/medical/declarations", Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, queue) = send(&app, "GET", "/api/medical/declarations?status=pending", Some(&officer), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let entry = queue.as_array().unwrap().iter().find(|d| d["player_id"] == id).unwrap().clone();
+    assert_eq!(entry["name"], "Salbutamol inhaler");
+    let review = format!("/api/medical/declarations/{}/review", entry["id"]);
+    let (status, _) = send(&app, "POST", &review, Some(&officer), json!({ "status": "approved" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "POST", &review, Some(&officer), json!({ "status": "flagged", "notes": "Apply for a TUE" })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, own) = send(&app, "GET", &uri, Some(&player), json!({})).await;
+    assert_eq!(own[0]["status"], "flagged");
+    assert_eq!(own[0]["review_notes"], "Apply for a TUE");
+    let notified: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND title = 'Declaration needs attention'")
+        .bind(player_user)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(notified, 1);
+
+    declaration["ended_on"] = json!("2026-03-01");
+    let (status, _) = send(&app, "PUT", &format!("/api/medications/{}", entry["id"]), Some(&player), declaration).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, own) = send(&app, "GET", &uri, Some(&player), json!({})).await;
+    assert_eq!(own[0]["status"], "pending", "changes are reviewed again");
+
+    let audited: Vec<Value> = sqlx::query_scalar(
+        "SELECT after_data FROM audit_log WHERE entity_type = 'medication_declaration' AND entity_id = $1",
+    )
+    .bind(entry["id"].as_i64().unwrap())
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(audited.len(), 3);
+    assert!(audited.iter().all(|row| !row.to_string().contains("Salbutamol")), "no health data in the audit log");
+}
+
+#[tokio::test]
+async fn test_tue_certificate_and_expiry_reminder() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let officer = medical_officer(&app).await;
+    let (player_user, player) = signup(&app, "player", "exempt").await;
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let id = player_id(&pool, player_user).await;
+    let uri = format!("/api/players/{}/tues", id);
+    let expires_on = (chrono::Utc::now().date_naive() + chrono::Duration::days(10)).to_string();
+
+    let (status, _) = send(&app, "POST", &uri, Some(&player), json!({ "substance": "Insulin", "expires_on": expires_on })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "needs a certificate or a reference");
+    let certificate = b"%PDF-1.4 TUE certificate";
+    let tue = json!({
+        "substance": "Insulin", "expires_on": expires_on,
+        "document": { "file_name": "tue.pdf", "content_type": "application/pdf", "data": STANDARD.encode(certificate) },
+    });
+    let (status, _) = send(&app, "POST", &uri, Some(&player), tue).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, tues) = send(&app, "GET", &uri, Some(&officer), json!({})).await;
+    assert_eq!(tues[0]["has_document"], true);
+    assert_eq!(tues[0]["days_left"], 10);
+    let document = format!("/api/tues/{}/document", tues[0]["id"]);
+    let (status, bytes) = send_raw(&app, "GET", &document, Some(&player), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bytes, certificate);
+    let (status, _) = send_raw(&app, "GET", &document, Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, expiring) = send(&app, "GET", "/api/medical/tues?within_days=30", Some(&officer), json!({})).await;
+    assert!(expiring.as_array().unwrap().iter().any(|t| t["player_id"] == id));
+
+    antidoping::send_tue_reminders(&pool, 30).await.unwrap();
+    antidoping::send_tue_reminders(&pool, 30).await.unwrap();
+    let reminders: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND title = 'TUE expiring soon'")
+        .bind(player_user)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(reminders, 1, "reminded once");
+}
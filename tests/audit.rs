@@ -0,0 +1,100 @@
+//! Tests for the audit log.
+
+use axum::body::Body;
+use axum::extract::FromRequestParts;
+use axum::http::{Request, StatusCode};
+use handball_team_app::auth::{create_token, Claims};
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::audit::{redact, AuditContext};
+use serde_json::json;
+use tower::util::ServiceExt;
+
+#[test]
+fn test_redact_removes_password_hash() {
+    let row = json!({ "id": 4, "email": "a@example.com", "password_hash": "$argon2id$..." });
+    assert_eq!(redact(row), json!({ "id": 4, "email": "a@example.com" }));
+    assert_eq!(redact(json!([1, 2])), json!([1, 2]), "non-objects are left alone");
+}
+
+#[tokio::test]
+async fn test_context_reads_actor_and_request_id() {
+    let token = create_token(7, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let claims = handball_team_app::auth::decode_token(&token).unwrap();
+    let mut req = Request::builder()
+        .header("x-request-id", "req-123")
+        .body(())
+        .unwrap();
+    req.extensions_mut().insert::<Claims>(claims);
+    let (mut parts, _) = req.into_parts();
+    let ctx = AuditContext::from_request_parts(&mut parts, &()).await.unwrap();
+    assert_eq!(ctx.actor_id, Some(7));
+    assert_eq!(ctx.request_id.as_deref(), Some("req-123"));
+}
+
+#[tokio::test]
+async fn test_coach_cannot_read_audit_log() {
+    let app = build_app_for_test().await;
+    let token = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let req = Request::builder()
+        .uri("/api/audit")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_bad_date_filter_rejected() {
+    let app = build_app_for_test().await;
+    let token = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let req = Request::builder()
+        .uri("/api/audit?from=2026/03/01")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_season_changes_are_recorded_with_actor() {
+    let app = build_app_for_test().await;
+    let actor_id = 990_001;
+    let token = create_token(actor_id, "auditor@example.com", "admin", "Auditor", 1).unwrap();
+    let name = format!("Audit season {}", chrono::Utc::now().timestamp_nanos_opt().unwrap());
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/seasons")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .header("x-request-id", "audit-test")
+        .body(Body::from(json!({ "name": name, "start_date": "2030-08-01", "end_date": "2031-06-30" }).to_string()))
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = Request::builder()
+        .uri(format!("/api/audit?actor_id={}&entity_type=season&limit=1", actor_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let entries: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let entry = &entries[0];
+    assert_eq!(entry["action"], "create");
+    assert_eq!(entry["request_id"], "audit-test");
+    assert_eq!(entry["after"]["name"], name.as_str());
+    assert!(entry["before"].is_null());
+
+    let req = Request::builder()
+        .method("DELETE")
+        .uri(format!("/api/seasons/{}", entry["entity_id"]))
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
@@ -0,0 +1,168 @@
+//! Tests for backup archives and restore validation.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::backup::{
+    build_archive, dependency_order, is_safe_identifier, read_archive, restore_sqlite, sqlite_create_table,
+    ColumnInfo, TableDump, SCHEMA_VERSION,
+};
+use std::io::{Cursor, Read, Write};
+use tower::util::ServiceExt;
+
+fn column(name: &str, data_type: &str, nullable: bool) -> ColumnInfo {
+    ColumnInfo { name: name.to_string(), data_type: data_type.to_string(), nullable }
+}
+
+fn sample_dumps() -> Vec<TableDump> {
+    vec![
+        TableDump {
+            name: "roles".to_string(),
+            columns: vec![column("id", "bigint", false), column("name", "character varying", false)],
+            json: r#"[{"id":1,"name":"player"},{"id":3,"name":"admin"}]"#.to_string(),
+        },
+        TableDump {
+            name: "users".to_string(),
+            columns: vec![
+                column("id", "bigint", false),
+                column("email", "character varying", false),
+                column("role_id", "bigint", false),
+                column("active", "boolean", false),
+                column("created_at", "timestamp without time zone", true),
+            ],
+            json: r#"[{"id":5,"email":"a@example.com","role_id":3,"active":true,"created_at":null}]"#.to_string(),
+        },
+    ]
+}
+
+/// Rewrite one file inside a zip archive.
+fn replace_file(archive: &[u8], name: &str, edit: impl Fn(String) -> String) -> Vec<u8> {
+    let mut reader = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for i in 0..reader.len() {
+        let mut file = reader.by_index(i).unwrap();
+        let file_name = file.name().to_string();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        if file_name == name {
+            contents = edit(contents);
+        }
+        writer.start_file(file_name, zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(contents.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_schema_version_matches_newest_migration() {
+    let newest = std::fs::read_dir("migrations")
+        .unwrap()
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .filter_map(|name| name.split('_').next().map(str::to_string))
+        .max()
+        .unwrap();
+    assert_eq!(SCHEMA_VERSION, newest, "bump SCHEMA_VERSION when adding a migration");
+}
+
+#[test]
+fn test_dependency_order_puts_parents_first() {
+    let tables: Vec<String> = ["match_events", "matches", "players", "users", "roles"].iter().map(|s| s.to_string()).collect();
+    let references: Vec<(String, String)> = [
+        ("match_events", "matches"),
+        ("match_events", "players"),
+        ("players", "users"),
+        ("users", "roles"),
+        ("users", "users"),
+    ]
+    .iter()
+    .map(|(a, b)| (a.to_string(), b.to_string()))
+    .collect();
+    let order = dependency_order(&tables, &references);
+    let position = |t: &str| order.iter().position(|o| o == t).unwrap();
+    assert!(position("roles") < position("users"));
+    assert!(position("users") < position("players"));
+    assert!(position("players") < position("match_events"));
+    assert!(position("matches") < position("match_events"));
+}
+
+#[test]
+fn test_identifiers_are_checked() {
+    assert!(is_safe_identifier("match_events"));
+    assert!(!is_safe_identifier("users; DROP TABLE users"));
+    assert!(!is_safe_identifier("Users"));
+    assert!(!is_safe_identifier(""));
+}
+
+#[test]
+fn test_archive_round_trip() {
+    let archive = build_archive(&sample_dumps(), "2026-03-01T00:00:00Z").unwrap();
+    let (manifest, tables) = read_archive(&archive).unwrap();
+    assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+    assert_eq!(manifest.tables.len(), 2);
+    assert_eq!(tables[0].entry.name, "roles");
+    assert_eq!(tables[0].rows.len(), 2);
+    assert_eq!(tables[1].rows[0]["email"], "a@example.com");
+}
+
+#[test]
+fn test_tampered_table_rejected() {
+    let archive = build_archive(&sample_dumps(), "2026-03-01T00:00:00Z").unwrap();
+    let tampered = replace_file(&archive, "tables/users.json", |json| json.replace("a@example.com", "b@example.com"));
+    let error = read_archive(&tampered).err().unwrap().to_string();
+    assert!(error.contains("checksum mismatch for table users"), "{}", error);
+}
+
+#[test]
+fn test_other_schema_version_rejected() {
+    let archive = build_archive(&sample_dumps(), "2026-03-01T00:00:00Z").unwrap();
+    let older = replace_file(&archive, "manifest.json", |json| json.replace(SCHEMA_VERSION, "20200101000000"));
+    let error = read_archive(&older).err().unwrap().to_string();
+    assert!(error.contains("schema 20200101000000"), "{}", error);
+}
+
+#[test]
+fn test_sqlite_table_definition() {
+    let archive = build_archive(&sample_dumps(), "2026-03-01T00:00:00Z").unwrap();
+    let (_, tables) = read_archive(&archive).unwrap();
+    assert_eq!(
+        sqlite_create_table(&tables[1].entry),
+        "CREATE TABLE \"users\" (\"id\" INTEGER PRIMARY KEY, \"email\" TEXT NOT NULL, \"role_id\" INTEGER NOT NULL, \
+         \"active\" INTEGER NOT NULL, \"created_at\" TEXT)"
+    );
+}
+
+#[tokio::test]
+async fn test_restore_into_empty_sqlite_then_refuse_second_restore() {
+    let archive = build_archive(&sample_dumps(), "2026-03-01T00:00:00Z").unwrap();
+    let (_, tables) = read_archive(&archive).unwrap();
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let summary = restore_sqlite(&pool, &tables).await.unwrap();
+    assert_eq!((summary.tables, summary.rows), (2, 3));
+    let (email, active): (String, bool) = sqlx::query_as("SELECT email, active FROM users WHERE id = 5")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((email.as_str(), active), ("a@example.com", true));
+
+    let error = restore_sqlite(&pool, &tables).await.err().unwrap().to_string();
+    assert!(error.contains("not empty"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_coach_cannot_download_backup() {
+    let app = build_app_for_test().await;
+    let token = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let req = Request::builder()
+        .uri("/api/admin/backup")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,164 @@
+//! Tests for knockout bracket seeding, winner decision and the bracket endpoint.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::services::bracket::{decide_winner, first_round_pairings, next_slot, round_name, seed_order, Side};
+use handball_team_app::{build_app_for_test, connect_for_test};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::util::ServiceExt;
+
+#[test]
+fn test_seed_order_keeps_top_seeds_apart() {
+    assert_eq!(seed_order(2), vec![1, 2]);
+    assert_eq!(seed_order(4), vec![1, 4, 2, 3]);
+    assert_eq!(seed_order(8), vec![1, 8, 4, 5, 2, 7, 3, 6]);
+}
+
+#[test]
+fn test_first_round_gives_byes_to_top_seeds() {
+    let teams: Vec<String> = ["A", "B", "C", "D", "E", "F"].iter().map(|t| t.to_string()).collect();
+    let pairings = first_round_pairings(&teams);
+    assert_eq!(pairings.len(), 4);
+    // Seeds 1 and 2 face the empty slots 8 and 7.
+    assert_eq!(pairings[0], (0, Some("A".to_string()), None));
+    assert_eq!(pairings[1], (1, Some("D".to_string()), Some("E".to_string())));
+    assert_eq!(pairings[2], (2, Some("B".to_string()), None));
+    assert_eq!(pairings[3], (3, Some("C".to_string()), Some("F".to_string())));
+}
+
+#[test]
+fn test_decide_winner_uses_shootout_for_draws() {
+    assert_eq!(decide_winner(Some(30), Some(28), None, None), Some(Side::Home));
+    assert_eq!(decide_winner(Some(27), Some(29), None, None), Some(Side::Away));
+    assert_eq!(decide_winner(Some(30), Some(30), None, None), None);
+    assert_eq!(decide_winner(Some(30), Some(30), Some(4), Some(5)), Some(Side::Away));
+    assert_eq!(decide_winner(Some(30), Some(30), Some(3), Some(3)), None);
+    assert_eq!(decide_winner(None, None, None, None), None);
+}
+
+#[test]
+fn test_winner_moves_to_half_position_in_next_round() {
+    assert_eq!(next_slot(1, 0), (2, 0, Side::Home));
+    assert_eq!(next_slot(1, 1), (2, 0, Side::Away));
+    assert_eq!(next_slot(1, 6), (2, 3, Side::Home));
+    assert_eq!(next_slot(2, 3), (3, 1, Side::Away));
+}
+
+#[test]
+fn test_round_names() {
+    assert_eq!(round_name(3, 3), "Final");
+    assert_eq!(round_name(2, 3), "Semi-finals");
+    assert_eq!(round_name(1, 3), "Quarter-finals");
+    assert_eq!(round_name(1, 4), "Round of 16");
+}
+
+#[tokio::test]
+async fn test_bracket_unknown_tournament() {
+    let app = build_app_for_test().await;
+    let req = Request::builder()
+        .uri("/api/tournaments/99999/bracket")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> StatusCode {
+    let token = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let req = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    app.clone().oneshot(req).await.unwrap().status()
+}
+
+/// (id, home team, away team) of the bracket match at `round`, `position`.
+async fn bracket_match(pool: &PgPool, stage_id: i64, round: i32, position: i32) -> (i64, String, String) {
+    sqlx::query_as("SELECT id, home_team, away_team FROM matches WHERE stage_id = $1 AND round = $2 AND bracket_position = $3")
+        .bind(stage_id)
+        .bind(round)
+        .bind(position)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_corrected_results_update_the_next_round() {
+    let app = build_app_for_test().await;
+    let pool = connect_for_test().await;
+    let season_id: i64 = sqlx::query_scalar(
+        "INSERT INTO seasons (name, start_date, end_date, club_id) VALUES ('Bracket corrections', CURRENT_DATE, CURRENT_DATE + 300, 1) RETURNING id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let tournament_id: i64 = sqlx::query_scalar(
+        "INSERT INTO tournaments (name, start_date, format, season_id, club_id) \
+         VALUES ('Bracket corrections', CURRENT_DATE, 'single_elimination', $1, 1) RETURNING id",
+    )
+    .bind(season_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let stage_id: i64 = sqlx::query_scalar(
+        "INSERT INTO tournament_stages (tournament_id, name, stage_type, position) VALUES ($1, 'Knockout', 'knockout', 1) RETURNING id",
+    )
+    .bind(tournament_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let uri = format!("/api/tournaments/{}/stages/{}/bracket", tournament_id, stage_id);
+    let twice = json!({ "teams": ["North", "South", "North", "West"], "match_date": "2026-06-01" });
+    assert_eq!(post(&app, &uri, twice).await, StatusCode::BAD_REQUEST);
+    // A stage of this tournament cannot be attached to a match of another one
+    let stray = json!({ "match_date": "2026-06-01", "home_team": "North", "away_team": "South", "location": "Arena", "stage_id": stage_id });
+    assert_eq!(post(&app, "/api/matches", stray).await, StatusCode::NOT_FOUND);
+
+    let draw = json!({ "teams": ["North", "South", "East", "West"], "match_date": "2026-06-01" });
+    let status = post(&app, &uri, draw).await;
+    assert_eq!(status, StatusCode::OK);
+    let (semi, home, _) = bracket_match(&pool, stage_id, 1, 0).await;
+    assert_eq!(home, "North");
+    let result = |home: i32, away: i32, shootout: Option<(i32, i32)>| {
+        let mut body = json!({ "id": semi, "home_score": home, "away_score": away });
+        if let Some((h, a)) = shootout {
+            body["home_shootout"] = json!(h);
+            body["away_shootout"] = json!(a);
+        }
+        body
+    };
+
+    assert_eq!(post(&app, "/api/matches/update", result(30, 28, None)).await, StatusCode::OK);
+    assert_eq!(bracket_match(&pool, stage_id, 2, 0).await.1, "North");
+
+    // A correction to an undecided draw takes the team back out of the final
+    assert_eq!(post(&app, "/api/matches/update", result(29, 29, None)).await, StatusCode::OK);
+    assert_eq!(bracket_match(&pool, stage_id, 2, 0).await.1, "TBD");
+
+    assert_eq!(post(&app, "/api/matches/update", result(29, 29, Some((3, 4)))).await, StatusCode::OK);
+    let (final_id, finalist, _) = bracket_match(&pool, stage_id, 2, 0).await;
+    assert_eq!(finalist, "West");
+
+    // Correcting the score without repeating the shootout keeps it
+    assert_eq!(post(&app, "/api/matches/update", result(28, 28, None)).await, StatusCode::OK);
+    assert_eq!(bracket_match(&pool, stage_id, 2, 0).await.1, "West");
+    let mut clear = result(28, 28, None);
+    clear["clear_shootout"] = json!(true);
+    assert_eq!(post(&app, "/api/matches/update", clear).await, StatusCode::OK);
+    assert_eq!(bracket_match(&pool, stage_id, 2, 0).await.1, "TBD");
+    assert_eq!(post(&app, "/api/matches/update", result(27, 30, None)).await, StatusCode::OK);
+
+    // Once the final is played its line-up is fixed
+    let final_result = json!({ "id": final_id, "home_score": 25, "away_score": 24 });
+    assert_eq!(post(&app, "/api/matches/update", final_result).await, StatusCode::OK);
+    assert_eq!(post(&app, "/api/matches/update", result(31, 30, None)).await, StatusCode::CONFLICT);
+    assert_eq!(post(&app, "/api/matches/update", result(27, 31, None)).await, StatusCode::OK, "same winner is fine");
+    assert_eq!(bracket_match(&pool, stage_id, 2, 0).await.1, "West");
+}
@@ -0,0 +1,62 @@
+//! Fixtures shared by the integration tests: requests, tokens, the database
+//! pool and accounts signed up through an admin invite.
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::connect_for_test;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::util::ServiceExt;
+
+pub const PASSWORD: &str = "Seven wings at dusk";
+
+pub async fn send_raw(app: &Router, method: &str, uri: &str, token: Option<&str>, body: Value) -> (StatusCode, Vec<u8>) {
+    let mut builder = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    let resp = app.clone().oneshot(builder.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let status = resp.status();
+    (status, axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap().to_vec())
+}
+
+pub async fn send(app: &Router, method: &str, uri: &str, token: Option<&str>, body: Value) -> (StatusCode, Value) {
+    let (status, bytes) = send_raw(app, method, uri, token, body).await;
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+pub fn admin() -> String {
+    create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap()
+}
+
+pub fn unique(prefix: &str) -> String {
+    format!("{}-{}", prefix, chrono::Utc::now().timestamp_nanos_opt().unwrap())
+}
+
+pub async fn pool() -> PgPool {
+    connect_for_test().await
+}
+
+/// Sign up through an admin invite so the account is active whatever the
+/// registration mode. Returns (user id, token).
+pub async fn signup(app: &Router, role: &str, prefix: &str) -> (i64, String) {
+    let email = format!("{}@example.com", unique(prefix));
+    let (_, invite) = send(app, "POST", "/api/admin/invites", Some(&admin()), json!({ "role": role, "email": email })).await;
+    let mut body = json!({
+        "email": email, "password": PASSWORD, "first_name": prefix, "last_name": "Test",
+        "role": role, "invite_code": invite["code"],
+    });
+    if role == "player" {
+        body["player_details"] = json!({ "date_of_birth": "2012-05-01", "position": "wing", "jersey_number": 7 });
+    }
+    let (status, body) = send(app, "POST", "/api/register", None, body).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    (body["user_id"].as_i64().unwrap(), body["token"].as_str().unwrap().to_string())
+}
+
+pub async fn player_id(pool: &PgPool, user_id: i64) -> i64 {
+    sqlx::query_scalar("SELECT id FROM players WHERE user_id = $1").bind(user_id).fetch_one(pool).await.unwrap()
+}
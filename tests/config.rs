@@ -0,0 +1,75 @@
+//! Tests for layered configuration and validation.
+
+use handball_team_app::config::{redact_url, Config, Environment, DEFAULT_JWT_SECRET};
+use std::collections::HashMap;
+
+fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+const DB: &str = "postgres://app:s3cret@db:5432/handball";
+
+#[test]
+fn test_layers_override_in_order() {
+    let defaults = Config::from_sources(None, &env(&[])).unwrap();
+    assert_eq!(defaults.server.port, 3000);
+    assert_eq!(defaults.database.max_connections, 5);
+    assert_eq!(defaults.auth.jwt_secret, DEFAULT_JWT_SECRET);
+
+    let file = "[server]\nport = 8080\nstatic_dir = \"public\"\n\n[database]\nmax_connections = 20\n";
+    let config = Config::from_sources(Some(file), &env(&[("PORT", "9090"), ("DATABASE_URL", DB)])).unwrap();
+    assert_eq!(config.server.port, 9090, "environment beats the file");
+    assert_eq!(config.server.static_dir.to_str(), Some("public"));
+    assert_eq!(config.database.max_connections, 20, "file beats the defaults");
+    assert_eq!(config.database.url, DB);
+}
+
+#[test]
+fn test_bad_sources_are_reported() {
+    let error = Config::from_sources(None, &env(&[("PORT", "eighty")])).unwrap_err();
+    assert!(error.contains("PORT"), "{}", error);
+    let error = Config::from_sources(Some("[server]\nprot = 1\n"), &env(&[])).unwrap_err();
+    assert!(error.contains("prot"), "unknown keys are rejected: {}", error);
+    assert!(Config::from_sources(None, &env(&[("APP_ENV", "staging")])).is_err());
+}
+
+#[test]
+fn test_production_refuses_default_secret() {
+    let config = Config::from_sources(None, &env(&[("APP_ENV", "production"), ("DATABASE_URL", DB)])).unwrap();
+    assert_eq!(config.environment, Environment::Production);
+    let errors = config.validate().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("production"), "{:?}", errors);
+
+    let config = Config::from_sources(
+        None,
+        &env(&[("APP_ENV", "production"), ("DATABASE_URL", DB), ("JWT_SECRET", "a-long-and-random-production-key")]),
+    )
+    .unwrap();
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_validation_collects_all_errors() {
+    let config = Config::from_sources(None, &env(&[("DATABASE_MAX_CONNECTIONS", "0"), ("JWT_SECRET", "short")])).unwrap();
+    let errors = config.validate().unwrap_err();
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+}
+
+#[test]
+fn test_secrets_are_redacted() {
+    assert_eq!(redact_url(DB), "postgres://app:********@db:5432/handball");
+    assert_eq!(redact_url("postgres://localhost/handball"), "postgres://localhost/handball");
+    let config = Config::from_sources(None, &env(&[("DATABASE_URL", DB), ("JWT_SECRET", "a-long-and-random-production-key")])).unwrap();
+    let printed = format!("{}\n{:?}", config.to_toml(), config.redacted());
+    assert!(!printed.contains("s3cret"));
+    assert!(!printed.contains("a-long-and-random-production-key"));
+}
+
+#[test]
+fn test_tenancy_is_validated() {
+    let config = Config::from_sources(None, &env(&[("TENANCY_BASE_DOMAIN", "https://example.com"), ("TENANCY_DEFAULT_CLUB", "Tornadoes")])).unwrap();
+    let errors = config.validate().unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("tenancy.base_domain")), "{:?}", errors);
+    assert!(errors.iter().any(|e| e.contains("tenancy.default_club")), "{:?}", errors);
+}
@@ -0,0 +1,141 @@
+//! Tests for card tallies, disciplinary rules and match bans.
+
+use axum::http::StatusCode;
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+mod common;
+use common::{admin, player_id, pool, send, signup};
+
+/// Sign up a player through an admin invite. Returns (player id, token).
+async fn signup_player(app: &Router, pool: &PgPool) -> (i64, String) {
+    let (user_id, token) = signup(app, "player", "carded").await;
+    (player_id(pool, user_id).await, token)
+}
+
+async fn insert_tournament(pool: &PgPool, name: &str) -> i64 {
+    let season_id: i64 = sqlx::query_scalar(
+        "INSERT INTO seasons (name, start_date, end_date, club_id) VALUES ($1, CURRENT_DATE - 60, CURRENT_DATE + 300, 1) RETURNING id",
+    )
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query_scalar("INSERT INTO tournaments (name, start_date, season_id, club_id) VALUES ($1, CURRENT_DATE - 30, $2, 1) RETURNING id")
+        .bind(name)
+        .bind(season_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn insert_match(pool: &PgPool, tournament_id: Option<i64>, days_from_now: i32) -> i64 {
+    sqlx::query_scalar(
+        "INSERT INTO matches (date, home_team, away_team, tournament_id, club_id) \
+         VALUES (CURRENT_DATE + $1, 'Tornadoes', 'Referees XI', $2, 1) RETURNING id",
+    )
+    .bind(days_from_now)
+    .bind(tournament_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn card(app: &Router, match_id: i64, player_id: i64, event_type: &str) {
+    let event = json!({
+        "player_id": player_id, "event_type": event_type, "minute": 40, "period": "second_half",
+        "is_fast_break": false, "is_penalty": false,
+    });
+    let (status, body) = send(app, "POST", &format!("/api/matches/{}/events", match_id), Some(&admin()), event).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+async fn select(app: &Router, match_id: i64, player_id: i64) -> (StatusCode, Value) {
+    let squad = json!({ "players": [{ "player_id": player_id, "shirt_number": 4, "is_goalkeeper": true }] });
+    send(app, "PUT", &format!("/api/matches/{}/squad", match_id), Some(&admin()), squad).await
+}
+
+#[tokio::test]
+async fn test_cards_lead_to_bans_served_by_later_fixtures() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let admin = admin();
+    let (player, player_token) = signup_player(&app, &pool).await;
+    let tournament = insert_tournament(&pool, "Discipline Cup").await;
+    let played = insert_match(&pool, Some(tournament), -7).await;
+    let next = insert_match(&pool, Some(tournament), 7).await;
+    let after_next = insert_match(&pool, Some(tournament), 14).await;
+    let third = insert_match(&pool, Some(tournament), 21).await;
+    let friendly = insert_match(&pool, None, 7).await;
+
+    let rules_uri = format!("/api/tournaments/{}/disciplinary/rules", tournament);
+    let (status, _) = send(&app, "PUT", &rules_uri, Some(&admin), json!({ "rules": [{ "event_type": "goal", "threshold": 1, "ban_matches": 1 }] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let rules = json!({ "rules": [
+        { "event_type": "blue_card", "threshold": 1, "ban_matches": 2 },
+        { "event_type": "yellow_card", "threshold": 2, "ban_matches": 1 },
+    ] });
+    let (status, _) = send(&app, "PUT", &rules_uri, Some(&coach), rules.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "PUT", &rules_uri, Some(&admin), rules).await;
+    assert_eq!(status, StatusCode::OK);
+
+    card(&app, played, player, "blue_card").await;
+    card(&app, played, player, "suspension").await;
+    let (status, body) = select(&app, next, player).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.to_string().contains("match 1 of 2"), "{}", body);
+    let (_, body) = select(&app, after_next, player).await;
+    assert!(body.to_string().contains("match 2 of 2"), "{}", body);
+    let (status, body) = select(&app, third, player).await;
+    assert_eq!(status, StatusCode::OK, "the ban is over: {}", body);
+    let (status, body) = select(&app, friendly, player).await;
+    assert_eq!(status, StatusCode::OK, "bans apply within the tournament: {}", body);
+
+    let report_uri = format!("/api/tournaments/{}/disciplinary", tournament);
+    let (_, report) = send(&app, "GET", &report_uri, Some(&admin), json!({})).await;
+    let tally = report["players"].as_array().unwrap().iter().find(|t| t["player_id"] == player).unwrap();
+    assert_eq!(tally["blue_cards"], 1);
+    assert_eq!(tally["two_minutes"], 1);
+    let ban = &report["bans"][0];
+    assert_eq!(ban["matches"], 2);
+    assert_eq!(ban["served"], 0);
+    assert_eq!(ban["remaining"], 2);
+
+    sqlx::query("UPDATE matches SET home_score = 25, away_score = 22 WHERE id = $1").bind(next).execute(&pool).await.unwrap();
+    let (_, report) = send(&app, "GET", &report_uri, Some(&admin), json!({})).await;
+    assert_eq!(report["bans"][0]["served"], 1);
+
+    // Every second yellow card bans for the next fixture
+    card(&app, third, player, "yellow_card").await;
+    let (_, record) = send(&app, "GET", &format!("/api/players/{}/disciplinary", player), Some(&player_token), json!({})).await;
+    assert_eq!(record["bans"].as_array().unwrap().len(), 1);
+    card(&app, third, player, "yellow_card").await;
+    let (status, record) = send(&app, "GET", &format!("/api/players/{}/disciplinary", player), Some(&player_token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(record["bans"].as_array().unwrap().len(), 2);
+    assert_eq!(record["bans"][0]["reason"], "2 yellow cards in the tournament");
+    assert_eq!(record["tournaments"][0]["yellow_cards"], 2);
+}
+
+#[tokio::test]
+async fn test_default_rule_bans_red_card_with_report() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let (player, _) = signup_player(&app, &pool).await;
+    let tournament = insert_tournament(&pool, "Default Rules Cup").await;
+    let first = insert_match(&pool, Some(tournament), 1).await;
+    let second = insert_match(&pool, Some(tournament), 8).await;
+
+    card(&app, first, player, "red_card").await;
+    let (status, _) = select(&app, second, player).await;
+    assert_eq!(status, StatusCode::OK, "a red card without report has no ban by default");
+    card(&app, first, player, "blue_card").await;
+    let (status, body) = select(&app, second, player).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.to_string().contains("red card with report"), "{}", body);
+}
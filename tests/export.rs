@@ -0,0 +1,87 @@
+//! Tests for the data export formats and endpoint.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::export::{csv_chunk, xlsx_workbook, ExportDataset, ExportFormat};
+use handball_team_app::services::import::read_xlsx;
+use serde_json::json;
+use tower::util::ServiceExt;
+
+#[test]
+fn test_format_negotiation() {
+    assert_eq!(ExportFormat::parse("JSONL"), Some(ExportFormat::JsonLines));
+    assert_eq!(ExportFormat::parse("pdf"), None);
+    assert_eq!(
+        ExportFormat::from_accept("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+        ExportFormat::Xlsx
+    );
+    assert_eq!(ExportFormat::from_accept("application/x-ndjson"), ExportFormat::JsonLines);
+    assert_eq!(ExportFormat::from_accept("*/*"), ExportFormat::Csv);
+}
+
+#[test]
+fn test_csv_chunk_orders_columns_and_blanks_nulls() {
+    let columns = ExportDataset::Attendance.columns();
+    let rows = vec![json!({
+        "attended": true, "date": "2026-09-05", "first_name": "Ann", "id": 1,
+        "last_name": "Berg, Jr.", "match_id": null, "player_id": 4
+    })];
+    let csv = String::from_utf8(csv_chunk(columns, &rows, true).unwrap()).unwrap();
+    assert_eq!(
+        csv,
+        "id,player_id,first_name,last_name,match_id,date,attended\n1,4,Ann,\"Berg, Jr.\",,2026-09-05,true\n"
+    );
+    let body_only = String::from_utf8(csv_chunk(columns, &rows, false).unwrap()).unwrap();
+    assert!(body_only.starts_with("1,4,"), "later chunks have no header");
+}
+
+#[test]
+fn test_xlsx_workbook_round_trips() {
+    let columns = ExportDataset::Matches.columns();
+    let rows = vec![json!({
+        "id": 7, "date": "2026-09-05", "season_id": 1, "tournament_id": null, "tournament": null,
+        "home_team": "Tornadoes", "away_team": "Lions", "location": "Main Hall",
+        "home_score": 28, "away_score": 25
+    })];
+    let bytes = xlsx_workbook("matches", columns, &rows).unwrap();
+    let read = read_xlsx(&bytes).unwrap();
+    assert_eq!(read.len(), 1);
+    assert_eq!(read[0].line, 2);
+    assert_eq!(read[0].fields["home_team"], "Tornadoes");
+    assert_eq!(read[0].fields["home_score"], "28");
+    assert_eq!(read[0].fields["tournament"], "");
+}
+
+#[tokio::test]
+async fn test_player_cannot_export() {
+    let app = build_app_for_test().await;
+    let token = create_token(3, "player@example.com", "player", "Player", 1).unwrap();
+    let req = Request::builder()
+        .uri("/api/export/events?format=csv")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_unknown_dataset_and_bad_dates_rejected() {
+    let app = build_app_for_test().await;
+    let token = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    for (uri, status) in [
+        ("/api/export/salaries", StatusCode::NOT_FOUND),
+        ("/api/export/matches?from=05.09.2026", StatusCode::BAD_REQUEST),
+        ("/api/export/matches?format=pdf", StatusCode::BAD_REQUEST),
+    ] {
+        let req = Request::builder()
+            .uri(uri)
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), status, "{}", uri);
+    }
+}
@@ -0,0 +1,207 @@
+//! Tests for membership fees: invoices, partial payments, balances, receipts and online payments.
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::audit::AuditContext;
+use handball_team_app::services::fees::{self, InvoiceFilter};
+use handball_team_app::services::payments::FakeProvider;
+use serde_json::json;
+use sqlx::PgPool;
+use tower::util::ServiceExt;
+
+mod common;
+use common::{admin, player_id, pool, send, signup};
+
+/// A coach account an admin made treasurer.
+async fn treasurer(app: &Router) -> String {
+    let (user_id, _) = signup(app, "coach", "treasurer").await;
+    let promote = json!({ "user_id": user_id, "role_name": "treasurer" });
+    let (status, _) = send(app, "POST", "/api/users/role", Some(&admin()), promote).await;
+    assert_eq!(status, StatusCode::OK);
+    create_token(user_id, "treasurer@example.com", "treasurer", "Treasurer", 1).unwrap()
+}
+
+/// A season of its own and a fee plan in it, due `due_in_days` from today. Returns (season id, plan id).
+async fn fee_plan(app: &Router, pool: &PgPool, amount_cents: i64, due_in_days: i64) -> (i64, i64) {
+    let season: i64 = sqlx::query_scalar(
+        "INSERT INTO seasons (name, start_date, end_date, club_id) VALUES ('Fees test', CURRENT_DATE - 30, CURRENT_DATE + 300, 1) RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let due = chrono::Utc::now().date_naive() + chrono::Duration::days(due_in_days);
+    let plan = json!({ "season_id": season, "category": "senior", "name": "Senior membership", "amount_cents": amount_cents, "due_date": due.to_string() });
+    let treasurer = treasurer(app).await;
+    let (status, body) = send(app, "POST", "/api/fees/plans", Some(&treasurer), plan).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, plans) = send(app, "GET", &format!("/api/fees/plans?season_id={}", season), Some(&treasurer), json!({})).await;
+    (season, plans[0]["id"].as_i64().unwrap())
+}
+
+#[tokio::test]
+async fn test_invoices_partial_payments_balances_and_receipts() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let treasurer = treasurer(&app).await;
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+
+    let plan_body = json!({ "season_id": 1, "category": "senior", "name": "Senior", "amount_cents": 100, "due_date": "2026-09-01" });
+    let (status, _) = send(&app, "POST", "/api/fees/plans", Some(&coach), plan_body).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (season, plan) = fee_plan(&app, &pool, 12000, -10).await;
+    let duplicate = json!({ "season_id": season, "category": "senior", "name": "Again", "amount_cents": 100, "due_date": "2026-09-01" });
+    let (status, _) = send(&app, "POST", "/api/fees/plans", Some(&treasurer), duplicate).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (adult_user, adult) = signup(&app, "player", "adult").await;
+    let (child_user, _) = signup(&app, "player", "child").await;
+    let (guardian_id, guardian) = signup(&app, "guardian", "parent").await;
+    let (adult_player, child_player) = (player_id(&pool, adult_user).await, player_id(&pool, child_user).await);
+    let link = json!({ "guardian_id": guardian_id, "player_id": child_player });
+    let (status, _) = send(&app, "POST", "/api/admin/guardians", Some(&admin()), link).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let issue = format!("/api/fees/plans/{}/invoices", plan);
+    let players = json!({ "player_ids": [adult_player, child_player] });
+    let (status, body) = send(&app, "POST", &issue, Some(&treasurer), players.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["message"], "2 invoice(s) issued, 0 player(s) already invoiced");
+    let (_, body) = send(&app, "POST", &issue, Some(&treasurer), players).await;
+    assert_eq!(body["message"], "0 invoice(s) issued, 2 player(s) already invoiced");
+
+    let (_, invoices) = send(&app, "GET", &format!("/api/invoices?season_id={}", season), Some(&treasurer), json!({})).await;
+    let invoices = invoices.as_array().unwrap();
+    assert_eq!(invoices.len(), 2);
+    let adult_invoice = invoices.iter().find(|i| i["player_id"] == adult_player).unwrap()["id"].as_i64().unwrap();
+    let child_invoice = invoices.iter().find(|i| i["player_id"] == child_player).unwrap();
+    assert_eq!(child_invoice["billed_to"], guardian_id, "minors are billed to their guardian");
+    let child_invoice = child_invoice["id"].as_i64().unwrap();
+
+    // Payers only see their own invoices
+    let (_, mine) = send(&app, "GET", &format!("/api/invoices?season_id={}", season), Some(&guardian), json!({})).await;
+    assert_eq!(mine.as_array().unwrap().len(), 1);
+    assert_eq!(mine[0]["id"], child_invoice);
+    let (status, _) = send(&app, "GET", &format!("/api/invoices/{}", child_invoice), Some(&adult), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let pay = format!("/api/invoices/{}/payments", adult_invoice);
+    let (status, _) = send(&app, "POST", &pay, Some(&adult), json!({ "amount_cents": 4000, "method": "cash" })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "POST", &pay, Some(&treasurer), json!({ "amount_cents": 12001, "method": "cash" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "more than the balance");
+    let (status, _) = send(&app, "POST", &pay, Some(&treasurer), json!({ "amount_cents": 4000, "method": "barter" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let partial = json!({ "amount_cents": 4000, "method": "mobile_money", "reference": "QK7H2M1X" });
+    let (status, _) = send(&app, "POST", &pay, Some(&treasurer), partial).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, detail) = send(&app, "GET", &format!("/api/invoices/{}", adult_invoice), Some(&adult), json!({})).await;
+    assert_eq!(detail["invoice"]["balance_cents"], 8000);
+    assert_eq!(detail["invoice"]["status"], "overdue", "partly paid after the due date");
+    assert_eq!(detail["invoice"]["days_overdue"], 10);
+
+    let (_, overdue) = send(&app, "GET", &format!("/api/fees/overdue?season_id={}", season), Some(&treasurer), json!({})).await;
+    assert_eq!(overdue.as_array().unwrap().len(), 2);
+    let (status, _) = send(&app, "GET", "/api/fees/overdue", Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, balances) = send(&app, "GET", &format!("/api/fees/balances?season_id={}", season), Some(&treasurer), json!({})).await;
+    let adult_balance = balances.as_array().unwrap().iter().find(|b| b["user_id"] == adult_user).unwrap();
+    assert_eq!(adult_balance["paid_cents"], 4000);
+    assert_eq!(adult_balance["balance_cents"], 8000);
+    assert_eq!(adult_balance["overdue_cents"], 8000);
+
+    let (status, _) = send(&app, "POST", &pay, Some(&treasurer), json!({ "amount_cents": 8000, "method": "bank_transfer" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "POST", &pay, Some(&treasurer), json!({ "amount_cents": 1, "method": "cash" })).await;
+    assert_eq!(status, StatusCode::CONFLICT, "already paid");
+    let (_, detail) = send(&app, "GET", &format!("/api/invoices/{}", adult_invoice), Some(&adult), json!({})).await;
+    assert_eq!(detail["invoice"]["status"], "paid");
+    assert_eq!(detail["payments"].as_array().unwrap().len(), 2);
+
+    let receipt = format!("/api/payments/{}/receipt.pdf", detail["payments"][0]["id"]);
+    let request = Request::builder().uri(&receipt).header("Authorization", format!("Bearer {}", adult)).body(Body::empty()).unwrap();
+    let resp = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/pdf");
+    let pdf = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+    let (status, _) = send(&app, "GET", &receipt, Some(&guardian), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(&app, "POST", &format!("/api/invoices/{}/cancel", adult_invoice), Some(&treasurer), json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT, "paid invoices stay");
+    let (status, _) = send(&app, "POST", &format!("/api/invoices/{}/cancel", child_invoice), Some(&treasurer), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, mine) = send(&app, "GET", &format!("/api/invoices?season_id={}", season), Some(&guardian), json!({})).await;
+    assert_eq!(mine[0]["status"], "cancelled");
+}
+
+#[tokio::test]
+async fn test_online_payment_is_recorded_when_the_provider_confirms() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let (season, plan) = fee_plan(&app, &pool, 9000, 30).await;
+    let (user, token) = signup(&app, "player", "online").await;
+    let player = player_id(&pool, user).await;
+    let (status, _) = send(&app, "POST", &format!("/api/fees/plans/{}/invoices", plan), Some(&treasurer(&app).await), json!({ "player_ids": [player] })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, invoices) = send(&app, "GET", &format!("/api/invoices?season_id={}", season), Some(&token), json!({})).await;
+    let invoice_id = invoices[0]["id"].as_i64().unwrap();
+    assert_eq!(invoices[0]["status"], "open");
+
+    let checkout = format!("/api/invoices/{}/checkout", invoice_id);
+    let (status, _) = send(&app, "POST", &checkout, Some(&token), json!({ "amount_cents": 9001 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, started) = send(&app, "POST", &checkout, Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", started);
+    assert_eq!(started["provider"], "fake");
+    let (status, _) = send(&app, "POST", &checkout, Some(&token), json!({ "amount_cents": 100 })).await;
+    assert_eq!(status, StatusCode::CONFLICT, "the pending payment covers the balance");
+    // The payer gives up on that checkout
+    sqlx::query("UPDATE payment_intents SET status = 'failed' WHERE reference = $1")
+        .bind(started["reference"].as_str().unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Settle a payment through a provider the test controls
+    let provider = FakeProvider::new();
+    let filter = InvoiceFilter { player_id: Some(player), ..Default::default() };
+    let invoice = fees::list(&pool, 1, None, &filter).await.unwrap().remove(0);
+    let mut conn = pool.acquire().await.unwrap();
+    let started = fees::start_checkout(&mut conn, &AuditContext::default(), &provider, &invoice, Some(5000)).await.unwrap();
+    drop(conn);
+    assert_eq!(fees::sync_payments(&pool, &provider).await.unwrap(), 0, "still pending");
+    assert!(provider.complete(&started.reference));
+    assert_eq!(fees::sync_payments(&pool, &provider).await.unwrap(), 1);
+    assert_eq!(fees::sync_payments(&pool, &provider).await.unwrap(), 0, "settled only once");
+
+    let (_, detail) = send(&app, "GET", &format!("/api/invoices/{}", invoice_id), Some(&token), json!({})).await;
+    assert_eq!(detail["invoice"]["status"], "partial");
+    assert_eq!(detail["invoice"]["balance_cents"], 4000);
+    assert_eq!(detail["payments"][0]["method"], "online");
+    assert_eq!(detail["payments"][0]["reference"], started.reference.as_str());
+
+    // Paid by hand while the online payment of the rest was pending: the
+    // provider's money is flagged for refund, not recorded on top
+    let mut conn = pool.acquire().await.unwrap();
+    let rest = fees::start_checkout(&mut conn, &AuditContext::default(), &provider, &invoice, None).await.unwrap();
+    drop(conn);
+    let cash = json!({ "amount_cents": 4000, "paid_on": "2026-04-02", "method": "cash" });
+    let (status, body) = send(&app, "POST", &format!("/api/invoices/{}/payments", invoice_id), Some(&treasurer(&app).await), cash).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(provider.complete(&rest.reference));
+    assert_eq!(fees::sync_payments(&pool, &provider).await.unwrap(), 1);
+    let (_, detail) = send(&app, "GET", &format!("/api/invoices/{}", invoice_id), Some(&token), json!({})).await;
+    assert_eq!(detail["invoice"]["balance_cents"], 0);
+    assert_eq!(detail["payments"].as_array().unwrap().len(), 2);
+    let refund: i64 = sqlx::query_scalar("SELECT refund_cents FROM payment_intents WHERE reference = $1")
+        .bind(&rest.reference)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(refund, 4000);
+}
@@ -0,0 +1,148 @@
+//! Tests for fitness testing: results, progression, team percentiles and export.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::util::ServiceExt;
+
+mod common;
+use common::{admin, player_id, pool, send, signup, unique};
+
+/// Sign up a player through an admin invite and add them to `team_id`.
+/// Returns (player id, token).
+async fn signup_player(app: &Router, pool: &PgPool, prefix: &str, team_id: i64) -> (i64, String) {
+    let (user_id, token) = signup(app, "player", prefix).await;
+    let (status, _) = send(app, "POST", &format!("/api/teams/{}/members", team_id), Some(&admin()), json!({ "user_id": user_id })).await;
+    assert_eq!(status, StatusCode::OK);
+    (player_id(pool, user_id).await, token)
+}
+
+async fn create_team(app: &Router) -> i64 {
+    let (status, team) = send(app, "POST", "/api/teams", Some(&admin()), json!({ "name": unique("Fitness"), "age_group": "U19" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", team);
+    team["id"].as_i64().unwrap()
+}
+
+/// Create a test type and return its id.
+async fn create_test(app: &Router, name: &str, unit: &str, higher_is_better: bool) -> i64 {
+    let body = json!({ "name": name, "unit": unit, "higher_is_better": higher_is_better });
+    let (status, body) = send(app, "POST", "/api/fitness/tests", Some(&admin()), body).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, tests) = send(app, "GET", "/api/fitness/tests", Some(&admin()), json!({})).await;
+    tests.as_array().unwrap().iter().find(|t| t["name"] == name).unwrap()["id"].as_i64().unwrap()
+}
+
+#[tokio::test]
+async fn test_progression_and_percentiles_rank_timed_tests_lowest_first() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let admin = admin();
+    let outsider = create_token(987_654, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let team = create_team(&app).await;
+    let (fast, fast_token) = signup_player(&app, &pool, "fast", team).await;
+    let (middle, _) = signup_player(&app, &pool, "middle", team).await;
+    let (slow, _) = signup_player(&app, &pool, "slow", team).await;
+
+    let name = unique("30 m sprint");
+    let (status, _) = send(&app, "POST", "/api/fitness/tests", Some(&admin), json!({ "name": name, "unit": "" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "unit is required");
+    let sprint = create_test(&app, &name, "s", false).await;
+    let (status, _) = send(&app, "POST", "/api/fitness/tests", Some(&admin), json!({ "name": name, "unit": "s" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let session = |date: &str, results: Value| json!({ "test_type_id": sprint, "tested_on": date, "results": results });
+    let first = session("2026-01-15", json!([
+        { "player_id": fast, "value": 4.6 },
+        { "player_id": middle, "value": 4.7 },
+        { "player_id": slow, "value": 5.2 },
+    ]));
+    let (status, body) = send(&app, "POST", "/api/fitness/results", Some(&outsider), first.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+    let (status, body) = send(&app, "POST", "/api/fitness/results", Some(&admin), first).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, _) = send(&app, "POST", "/api/fitness/results", Some(&admin), session("2099-01-01", json!([{ "player_id": fast, "value": 4.0 }]))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "future date");
+
+    // A second session; re-recording the same day replaces the value
+    let second = session("2026-03-15", json!([{ "player_id": fast, "value": 4.5 }, { "player_id": middle, "value": 5.0 }]));
+    send(&app, "POST", "/api/fitness/results", Some(&admin), second).await;
+    let fix = session("2026-03-15", json!([{ "player_id": middle, "value": 4.4, "notes": "timing gate fixed" }]));
+    let (status, _) = send(&app, "POST", "/api/fitness/results", Some(&admin), fix).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, history) = send(&app, "GET", &format!("/api/players/{}/fitness", middle), Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let sprint_history = history.as_array().unwrap().iter().find(|p| p["test_type_id"] == sprint).unwrap();
+    let results = sprint_history["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results[0]["change"].is_null());
+    assert!((results[1]["change"].as_f64().unwrap() + 0.3).abs() < 1e-9);
+    assert_eq!(results[1]["notes"], "timing gate fixed");
+    assert_eq!(sprint_history["best"], 4.4, "lower is better");
+
+    // Players only see their own results
+    let (status, _) = send(&app, "GET", &format!("/api/players/{}/fitness", fast), Some(&fast_token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", &format!("/api/players/{}/fitness", slow), Some(&fast_token), json!({})).await;
+    assert!(status == StatusCode::FORBIDDEN || status == StatusCode::NOT_FOUND, "{}", status);
+
+    let uri = format!("/api/fitness/tests/{}/percentiles?team_id={}", sprint, team);
+    let (status, _) = send(&app, "GET", &uri, Some(&outsider), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, report) = send(&app, "GET", &uri, Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", report);
+    assert_eq!(report["count"], 3);
+    let order: Vec<i64> = report["players"].as_array().unwrap().iter().map(|p| p["player_id"].as_i64().unwrap()).collect();
+    assert_eq!(order, vec![middle, fast, slow], "latest results, fastest first");
+    assert_eq!(report["players"][0]["percentile"], 100.0);
+    assert_eq!(report["players"][2]["percentile"], 0.0);
+    assert_eq!(report["median"], 4.5);
+
+    // Before the second session, the first results count
+    let (_, report) = send(&app, "GET", &format!("{}&date=2026-02-01", uri), Some(&admin), json!({})).await;
+    let order: Vec<i64> = report["players"].as_array().unwrap().iter().map(|p| p["player_id"].as_i64().unwrap()).collect();
+    assert_eq!(order, vec![fast, middle, slow]);
+}
+
+#[tokio::test]
+async fn test_fitness_results_export_as_csv() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let admin = admin();
+    let team = create_team(&app).await;
+    let (player, _) = signup_player(&app, &pool, "jumper", team).await;
+    let name = unique("Standing long jump");
+    let jump = create_test(&app, &name, "cm", true).await;
+
+    let (status, _) = send(&app, "POST", "/api/fitness/results", Some(&admin), json!({
+        "test_type_id": 999_999_999, "tested_on": "2026-02-10", "results": [{ "player_id": player, "value": 231.0 }],
+    }))
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    for (date, value) in [("2026-02-10", 231.0), ("2026-05-10", 244.5)] {
+        let (status, _) = send(&app, "POST", "/api/fitness/results", Some(&admin), json!({
+            "test_type_id": jump, "tested_on": date, "results": [{ "player_id": player, "value": value }],
+        }))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let request = Request::builder()
+        .uri(format!("/api/export/fitness?format=csv&team_id={}&from=2026-03-01", team))
+        .header("Authorization", format!("Bearer {}", admin))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let csv = String::from_utf8(bytes.to_vec()).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("id,tested_on,player_id,first_name,last_name,test,unit,value,notes"));
+    let rows: Vec<&str> = lines.collect();
+    assert_eq!(rows.len(), 1, "{}", csv);
+    assert!(rows[0].contains(&format!("2026-05-10,{},jumper,Test,{},cm,244.5,", player, name)), "{}", rows[0]);
+}
@@ -0,0 +1,108 @@
+//! Tests for guardian accounts: linking, scoped access and consents.
+
+use axum::http::StatusCode;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use serde_json::json;
+
+mod common;
+use common::{admin, player_id, pool, send, signup};
+
+#[tokio::test]
+async fn test_guardian_sees_only_linked_players() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let admin = admin();
+    let (child_user, child_token) = signup(&app, "player", "child").await;
+    let (other_user, _) = signup(&app, "player", "other").await;
+    let (guardian_id, guardian) = signup(&app, "guardian", "guardian").await;
+    let child = player_id(&pool, child_user).await;
+    let other = player_id(&pool, other_user).await;
+
+    let link = json!({ "guardian_id": guardian_id, "player_id": child, "relationship": "father" });
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (status, _) = send(&app, "POST", "/api/admin/guardians", Some(&coach), link.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "POST", "/api/admin/guardians", Some(&admin), link.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "POST", "/api/admin/guardians", Some(&admin), link).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let not_guardian = json!({ "guardian_id": other_user, "player_id": child });
+    let (status, _) = send(&app, "POST", "/api/admin/guardians", Some(&admin), not_guardian).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "only guardian accounts can be linked");
+
+    let (status, players) = send(&app, "GET", "/api/guardian/players", Some(&guardian), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let players = players.as_array().unwrap();
+    assert_eq!(players.len(), 1);
+    assert_eq!(players[0]["player_id"], child);
+    assert_eq!(players[0]["relationship"], "father");
+
+    for view in ["schedule", "attendance", "stats", "consents"] {
+        let (status, _) = send(&app, "GET", &format!("/api/players/{}/{}", child, view), Some(&guardian), json!({})).await;
+        assert_eq!(status, StatusCode::OK, "{} of a linked player", view);
+        let (status, _) = send(&app, "GET", &format!("/api/players/{}/{}", other, view), Some(&guardian), json!({})).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} of someone else's child", view);
+        let (status, _) = send(&app, "GET", &format!("/api/players/{}/{}", other, view), Some(&child_token), json!({})).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} of another player", view);
+    }
+    let (status, users) = send(&app, "GET", "/api/users", Some(&guardian), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "guardians do not get the club's user list");
+    assert!(!users.to_string().contains(&format!("\"id\":{}", other_user)), "an unrelated player is not visible");
+    let (status, guardians) = send(&app, "GET", &format!("/api/players/{}/guardians", child), Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(guardians[0]["guardian_id"], guardian_id);
+
+    let unlink = format!("/api/admin/guardians/{}/players/{}", guardian_id, child);
+    let (status, _) = send(&app, "DELETE", &unlink, Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", &format!("/api/players/{}/stats", child), Some(&guardian), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "access ends with the link");
+}
+
+#[tokio::test]
+async fn test_guardian_submits_availability_and_consent() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let admin = admin();
+    let (child_user, child_token) = signup(&app, "player", "minor").await;
+    let (other_user, _) = signup(&app, "player", "unrelated").await;
+    let (guardian_id, guardian) = signup(&app, "guardian", "parent").await;
+    let child = player_id(&pool, child_user).await;
+    let other = player_id(&pool, other_user).await;
+    let link = json!({ "guardian_id": guardian_id, "player_id": child });
+    send(&app, "POST", "/api/admin/guardians", Some(&admin), link).await;
+
+    let match_id: i64 = sqlx::query_scalar(
+        "INSERT INTO matches (date, home_team, away_team, club_id) VALUES (CURRENT_DATE + 7, 'Tornadoes', 'Guardians FC', 1) RETURNING id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let availability = format!("/api/matches/{}/availability", match_id);
+    let (status, _) = send(&app, "POST", &availability, Some(&guardian), json!({ "player_id": child, "available": false, "reason": "School trip" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "POST", &availability, Some(&guardian), json!({ "player_id": other, "available": true })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, schedule) = send(&app, "GET", &format!("/api/players/{}/schedule", child), Some(&guardian), json!({})).await;
+    let entry = schedule.as_array().unwrap().iter().find(|m| m["match_id"] == match_id).unwrap();
+    assert_eq!(entry["available"], false);
+    assert!(entry["in_squad"].is_null(), "no squad published yet");
+
+    let consent = format!("/api/players/{}/consents/photos", child);
+    let (status, _) = send(&app, "PUT", &consent, Some(&child_token), json!({ "granted": true })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "a minor's consent comes from the guardian");
+    let (status, _) = send(&app, "PUT", &consent, Some(&guardian), json!({ "granted": true, "notes": "Team photos only" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "PUT", &format!("/api/players/{}/consents/photos", other), Some(&guardian), json!({ "granted": true })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "PUT", &format!("/api/players/{}/consents/tattoos", child), Some(&guardian), json!({ "granted": true })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, consents) = send(&app, "GET", &format!("/api/players/{}/consents", child), Some(&child_token), json!({})).await;
+    let photos = consents.as_array().unwrap().iter().find(|c| c["consent_type"] == "photos").unwrap();
+    assert_eq!(photos["granted"], true);
+    assert_eq!(photos["given_by"], guardian_id);
+
+    sqlx::query("DELETE FROM matches WHERE id = $1").bind(match_id).execute(&pool).await.unwrap();
+}
@@ -0,0 +1,81 @@
+//! Tests for bulk CSV/XLSX import parsing and the import endpoint.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::auth::{create_token, validate_registration};
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::matches;
+use handball_team_app::services::import::{
+    check_columns, match_request, player_request, read_csv, ImportKind,
+};
+use tower::util::ServiceExt;
+
+const FIXTURES: &str = "Date,Home_Team,Away_Team,Location\n\
+2026-09-05,Tornadoes,Lions,Main Hall\n\
+\n\
+2026-09-12, Eagles , Tornadoes ,Away Arena\n";
+
+#[test]
+fn test_csv_rows_keep_source_line_numbers() {
+    let rows = read_csv(FIXTURES.as_bytes()).unwrap();
+    assert_eq!(rows.len(), 2, "blank lines are skipped");
+    assert_eq!(rows[0].line, 2);
+    assert_eq!(rows[1].line, 4);
+    assert_eq!(rows[1].fields["home_team"], "Eagles", "headers are lower-cased and values trimmed");
+}
+
+#[test]
+fn test_missing_columns_reported_on_header_line() {
+    let rows = read_csv(FIXTURES.as_bytes()).unwrap();
+    let errors = check_columns(ImportKind::Results, &rows).unwrap_err();
+    assert_eq!(errors[0].line, 1);
+    assert!(errors[0].message.contains("home_score"));
+    assert!(check_columns(ImportKind::Fixtures, &rows).is_ok());
+}
+
+#[test]
+fn test_fixture_rows_use_create_match_validation() {
+    let rows = read_csv(b"date,home_team,away_team,location\n05/09/2026,Tornadoes,Lions,Hall\n2026-09-05,Tornadoes,,Hall\n").unwrap();
+    let first = match_request(&rows[0], ImportKind::Fixtures).unwrap();
+    assert!(matches::validate(&first).is_err(), "dates must be YYYY-MM-DD");
+    let second = match_request(&rows[1], ImportKind::Fixtures).unwrap();
+    assert!(matches::validate(&second).is_err(), "away team is required");
+}
+
+#[test]
+fn test_results_require_scores() {
+    let rows = read_csv(b"date,home_team,away_team,location,home_score,away_score\n2025-03-01,A,B,Hall,,20\n2025-03-08,A,C,Hall,x,20\n").unwrap();
+    let missing = match_request(&rows[0], ImportKind::Results).err().unwrap();
+    assert!(missing.contains("home_score"));
+    let invalid = match_request(&rows[1], ImportKind::Results).err().unwrap();
+    assert!(invalid.contains("whole number"));
+}
+
+#[test]
+fn test_player_rows_use_registration_validation() {
+    let csv = "email,password,first_name,last_name,date_of_birth,position,jersey_number\n\
+a@example.com,Handb4ll-Wing-7,Ann,Berg,2008-04-02,Wing,7\n\
+b@example.com,123,Bo,Dahl,2008-05-02,Pivot,9\n\
+c@example.com,Handb4ll-Back-10,Cy,Ek,02.05.2008,Back,10\n";
+    let rows = read_csv(csv.as_bytes()).unwrap();
+    let valid = player_request(&rows[0]).unwrap();
+    assert_eq!(valid.role, "player");
+    assert!(validate_registration(&valid).is_ok());
+    assert!(validate_registration(&player_request(&rows[1]).unwrap()).is_err(), "password too short");
+    assert!(player_request(&rows[2]).err().unwrap().contains("date_of_birth"));
+}
+
+#[tokio::test]
+async fn test_coach_cannot_import() {
+    let app = build_app_for_test().await;
+    let token = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/import/fixtures?dry_run=true")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "text/csv")
+        .body(Body::from(FIXTURES))
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,151 @@
+//! Tests for injuries: medical clearance, access to the notes and squad flagging.
+
+use axum::http::StatusCode;
+use axum::Router;
+use handball_team_app::auth::{create_token, decode_token};
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::injuries::can_read_notes;
+use serde_json::json;
+use sqlx::PgPool;
+
+mod common;
+use common::{admin, player_id, pool, send, signup};
+
+/// Sign up a player through an admin invite. Returns (player id, token).
+async fn signup_player(app: &Router, pool: &PgPool, prefix: &str) -> (i64, String) {
+    let (user_id, token) = signup(app, "player", prefix).await;
+    (player_id(pool, user_id).await, token)
+}
+
+#[tokio::test]
+async fn test_uncleared_player_cannot_be_selected() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let admin = admin();
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (player, _) = signup_player(&app, &pool, "injured").await;
+    let match_id: i64 = sqlx::query_scalar(
+        "INSERT INTO matches (date, home_team, away_team, club_id) VALUES (CURRENT_DATE + 7, 'Tornadoes', 'Physio FC', 1) RETURNING id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let injury = json!({
+        "player_id": player, "injury_type": "sprain", "body_part": "left ankle",
+        "injured_on": "2025-01-10", "expected_return": "2025-01-03",
+    });
+    let (status, _) = send(&app, "POST", "/api/injuries", Some(&coach), injury.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "return before the injury");
+    let mut injury = injury;
+    injury["expected_return"] = json!(null);
+    injury["injury_type"] = json!("broken");
+    let (status, _) = send(&app, "POST", "/api/injuries", Some(&coach), injury.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "unknown injury type");
+    injury["injury_type"] = json!("sprain");
+    let (status, _) = send(&app, "POST", "/api/injuries", Some(&coach), injury).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, open) = send(&app, "GET", "/api/injuries", Some(&coach), json!({})).await;
+    let record = open.as_array().unwrap().iter().find(|i| i["player_id"] == player).unwrap().clone();
+    assert!(record["matches_affected"].as_i64().unwrap() >= 1, "{}", record);
+    let injury_id = record["id"].as_i64().unwrap();
+
+    let squad = json!({ "players": [{ "player_id": player, "shirt_number": 6, "is_goalkeeper": true }] });
+    let squad_uri = format!("/api/matches/{}/squad", match_id);
+    let (status, body) = send(&app, "PUT", &squad_uri, Some(&admin), squad.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.to_string().contains("not medically cleared"), "{}", body);
+
+    let availability = format!("/api/matches/{}/availability", match_id);
+    let (_, body) = send(&app, "POST", &availability, Some(&admin), json!({ "player_id": player, "available": true })).await;
+    assert!(body["message"].as_str().unwrap().contains("cannot be selected"), "{}", body);
+    let (_, list) = send(&app, "GET", &availability, Some(&admin), json!({})).await;
+    assert_eq!(list[0]["available"], false);
+    assert!(list[0]["injury"].as_str().unwrap().contains("left ankle sprain"));
+
+    let clear = format!("/api/injuries/{}/clear", injury_id);
+    let (status, _) = send(&app, "POST", &clear, Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "clearance is medical");
+    let (status, _) = send(&app, "POST", &clear, Some(&admin), json!({ "cleared_on": "2025-02-01" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "POST", &clear, Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = send(&app, "PUT", &squad_uri, Some(&admin), squad).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (_, list) = send(&app, "GET", &availability, Some(&admin), json!({})).await;
+    assert_eq!(list[0]["available"], true);
+    assert!(list[0]["injury"].is_null());
+}
+
+#[tokio::test]
+async fn test_injury_notes_are_restricted() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (player, player_token) = signup_player(&app, &pool, "concussed").await;
+    let (_, other_token) = signup_player(&app, &pool, "teammate").await;
+
+    let injury = json!({
+        "player_id": player, "injury_type": "concussion", "body_part": "head",
+        "injured_on": "2026-03-01", "expected_return": "2026-03-21", "notes": "Follow the return-to-play protocol",
+    });
+    let (status, _) = send(&app, "POST", "/api/injuries", Some(&player_token), injury.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "players do not record injuries");
+    let (status, _) = send(&app, "POST", "/api/injuries", Some(&coach), injury).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let uri = format!("/api/players/{}/injuries", player);
+    let (status, own) = send(&app, "GET", &uri, Some(&player_token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(own[0]["notes"], "Follow the return-to-play protocol");
+    assert_eq!(own[0]["expected_return"], "2026-03-21");
+    let (status, seen) = send(&app, "GET", &uri, Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(seen[0]["injury_type"], "concussion");
+    assert!(seen[0]["notes"].is_null(), "coaches see availability, not medical notes");
+    let (status, _) = send(&app, "GET", &uri, Some(&other_token), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let update = json!({
+        "player_id": player, "injury_type": "concussion", "body_part": "head",
+        "injured_on": "2026-03-01", "expected_return": "2026-03-28",
+    });
+    let injury_uri = format!("/api/injuries/{}", own[0]["id"]);
+    let (status, _) = send(&app, "PUT", &injury_uri, Some(&coach), update.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut overwrite = update.clone();
+    overwrite["notes"] = json!("Cleared to play");
+    let (status, _) = send(&app, "PUT", &injury_uri, Some(&coach), overwrite.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, history) = send(&app, "GET", &uri, Some(&admin()), json!({})).await;
+    assert_eq!(history[0]["expected_return"], "2026-03-28");
+    assert_eq!(history[0]["notes"], "Follow the return-to-play protocol", "coaches cannot blank or overwrite notes");
+    let (status, _) = send(&app, "PUT", &injury_uri, Some(&admin()), overwrite).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, history) = send(&app, "GET", &uri, Some(&player_token), json!({})).await;
+    assert_eq!(history[0]["notes"], "Cleared to play");
+    let treasurer = create_token(1, "treasurer@example.com", "treasurer", "Treasurer", 1).unwrap();
+    assert!(!can_read_notes(&decode_token(&treasurer).unwrap()), "roles other than admin, player, guardian and medical officer get no notes");
+
+    // The medical officer reads the notes and gives the clearance
+    let (officer_id, _) = signup(&app, "coach", "physio").await;
+    let promote = json!({ "user_id": officer_id, "role_name": "medical_officer" });
+    let (status, _) = send(&app, "POST", "/api/users/role", Some(&admin()), promote).await;
+    assert_eq!(status, StatusCode::OK);
+    let officer = create_token(officer_id, "physio@example.com", "medical_officer", "Physio", 1).unwrap();
+    let (status, history) = send(&app, "GET", &uri, Some(&officer), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history[0]["notes"], "Cleared to play");
+    let clear = format!("{}/clear", injury_uri);
+    let (status, _) = send(&app, "POST", &clear, Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "POST", &clear, Some(&officer), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "PUT", "/api/injuries/999999999", Some(&coach), json!({
+        "player_id": player, "injury_type": "other", "body_part": "knee", "injured_on": "2026-03-01",
+    }))
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,133 @@
+//! Tests for the background job scheduler.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::auth::create_token;
+use handball_team_app::{build_app_for_test, connect_for_test};
+use handball_team_app::services::jobs::{self, backoff, JobOutcome, Scheduler};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::time::Duration;
+use tower::util::ServiceExt;
+
+async fn pool() -> PgPool {
+    connect_for_test().await
+}
+
+fn unique(prefix: &str) -> String {
+    format!("{}.{}", prefix, chrono::Utc::now().timestamp_nanos_opt().unwrap())
+}
+
+/// A scheduler with one job that fails whenever its payload says so.
+fn scheduler(pool: &PgPool, name: &str) -> Scheduler {
+    let mut scheduler = Scheduler::new(pool.clone(), Duration::from_secs(1));
+    scheduler.register(name, |_pool, payload: Value| async move {
+        if payload["fail"] == true { Err("asked to fail".to_string()) } else { Ok(()) }
+    });
+    scheduler
+}
+
+async fn status(pool: &PgPool, id: i64) -> (String, i32, Option<String>) {
+    sqlx::query_as("SELECT status, attempts, last_error FROM jobs WHERE id = $1")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_backoff_doubles_and_is_capped() {
+    assert_eq!(backoff(1), Duration::from_secs(30));
+    assert_eq!(backoff(2), Duration::from_secs(60));
+    assert_eq!(backoff(4), Duration::from_secs(240));
+    assert_eq!(backoff(20), Duration::from_secs(3600));
+    assert_eq!(backoff(0), Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn test_job_succeeds_and_failures_are_retried_then_failed() {
+    let pool = pool().await;
+    let name = unique("test.job");
+    let scheduler = scheduler(&pool, &name);
+
+    let ok = jobs::enqueue(&pool, &name, json!({}), None).await.unwrap();
+    let run = scheduler.run_next().await.unwrap().unwrap();
+    assert_eq!((run.id, run.outcome), (ok, JobOutcome::Succeeded));
+    assert_eq!(status(&pool, ok).await, ("succeeded".to_string(), 1, None));
+    assert!(scheduler.run_next().await.unwrap().is_none(), "nothing else is due");
+
+    let flaky = jobs::enqueue(&pool, &name, json!({ "fail": true }), None).await.unwrap();
+    let run = scheduler.run_next().await.unwrap().unwrap();
+    assert!(matches!(run.outcome, JobOutcome::Retrying { .. }));
+    assert_eq!(status(&pool, flaky).await, ("pending".to_string(), 1, Some("asked to fail".to_string())));
+    assert!(scheduler.run_next().await.unwrap().is_none(), "the retry waits for its backoff");
+
+    // Last attempt: the job is given up on, and an admin retry revives it
+    sqlx::query("UPDATE jobs SET run_at = NOW(), max_attempts = 2 WHERE id = $1")
+        .bind(flaky)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let run = scheduler.run_next().await.unwrap().unwrap();
+    assert_eq!(run.outcome, JobOutcome::Failed { error: "asked to fail".to_string() });
+    assert_eq!(status(&pool, flaky).await.0, "failed");
+    assert!(jobs::retry(&pool, flaky).await.unwrap());
+    assert!(!jobs::retry(&pool, ok).await.unwrap(), "only failed jobs can be retried");
+    assert_eq!(status(&pool, flaky).await.0, "pending");
+}
+
+#[tokio::test]
+async fn test_recurring_job_is_scheduled_once_per_interval() {
+    let pool = pool().await;
+    let name = unique("test.recurring");
+    let mut scheduler = scheduler(&pool, &name);
+    scheduler.every(&name, Duration::from_secs(3600));
+
+    scheduler.schedule_recurring().await.unwrap();
+    scheduler.schedule_recurring().await.unwrap();
+    let count = |pool: PgPool, name: String| async move {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM jobs WHERE name = $1")
+            .bind(name)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    assert_eq!(count(pool.clone(), name.clone()).await, 1, "one pending run at a time");
+
+    assert_eq!(scheduler.run_next().await.unwrap().unwrap().outcome, JobOutcome::Succeeded);
+    scheduler.schedule_recurring().await.unwrap();
+    assert_eq!(count(pool.clone(), name.clone()).await, 2);
+    assert!(scheduler.run_next().await.unwrap().is_none(), "next run is an hour away");
+    let gap: f64 = sqlx::query_scalar(
+        "SELECT EXTRACT(EPOCH FROM MAX(run_at) - MAX(completed_at))::FLOAT8 FROM jobs WHERE name = $1",
+    )
+    .bind(&name)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!((gap - 3600.0).abs() < 1.0);
+}
+
+#[tokio::test]
+async fn test_jobs_endpoint_is_admin_only() {
+    let app = build_app_for_test().await;
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let get = |token: &str, uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let resp = app.clone().oneshot(get(&coach, "/api/admin/jobs")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp = app.clone().oneshot(get(&admin, "/api/admin/jobs?status=bogus")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = app.clone().oneshot(get(&admin, "/api/admin/jobs?status=failed&limit=5")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let jobs: Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(jobs.as_array().unwrap().iter().all(|j| j["status"] == "failed"));
+}
@@ -0,0 +1,130 @@
+//! Tests for the printable match report.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::match_report::{
+    build_player_lines, render_html, render_pdf, score_by_period, MatchReport, SquadMember,
+};
+use handball_team_app::services::match_statistics::{
+    MatchStatisticsResponse, TeamResult, TeamStatistics, TimelineEvent,
+};
+use tower::util::ServiceExt;
+
+fn event(id: i64, player_id: i64, team: &str, event_type: &str, period: &str, is_penalty: bool) -> TimelineEvent {
+    TimelineEvent {
+        id,
+        player_id,
+        first_name: format!("Player{}", player_id),
+        last_name: "Test".to_string(),
+        team: team.to_string(),
+        event_type: event_type.to_string(),
+        minute: Some(id as i32),
+        period: period.to_string(),
+        is_fast_break: false,
+        is_penalty,
+    }
+}
+
+fn sample_events() -> Vec<TimelineEvent> {
+    vec![
+        event(1, 1, "home", "goal", "first_half", false),
+        event(2, 1, "home", "goal", "first_half", true),
+        event(3, 2, "away", "goal", "first_half", false),
+        event(4, 1, "home", "shot_missed", "second_half", false),
+        event(5, 3, "home", "save", "second_half", false),
+        event(6, 1, "home", "suspension", "second_half", false),
+        event(7, 2, "away", "goal", "second_half", false),
+    ]
+}
+
+fn sample_report(home_team: &str) -> MatchReport {
+    let events = sample_events();
+    MatchReport {
+        match_id: 1,
+        date: "2026-03-01".to_string(),
+        location: Some("Sports Hall".to_string()),
+        tournament: None,
+        home_team: home_team.to_string(),
+        away_team: "Rivals".to_string(),
+        home_score: Some(2),
+        away_score: Some(2),
+        score_by_period: score_by_period(&events),
+        players: build_player_lines(&[], &events),
+        timeline: Vec::new(),
+        statistics: MatchStatisticsResponse {
+            match_id: 1,
+            tournament_id: None,
+            season_id: None,
+            home_team: home_team.to_string(),
+            away_team: "Rivals".to_string(),
+            result: TeamResult { home_team: "draw".to_string(), away_team: "draw".to_string() },
+            team_statistics: TeamStatistics {
+                home_team_goals: 2,
+                away_team_goals: 2,
+                home_team_fast_break_goals: 0,
+                away_team_fast_break_goals: 0,
+                home_team_penalty_goals: 1,
+                away_team_penalty_goals: 0,
+                home_team_goalkeeper_saves: 1,
+                away_team_goalkeeper_saves: 0,
+                home_team_defense_events: 0,
+                away_team_defense_events: 0,
+            },
+            players: Vec::new(),
+        },
+    }
+}
+
+#[test]
+fn test_score_by_half() {
+    let periods = score_by_period(&sample_events());
+    assert_eq!(periods.len(), 2, "extra time is omitted when not played");
+    assert_eq!((periods[0].home, periods[0].away), (2, 1));
+    assert_eq!((periods[1].home, periods[1].away), (0, 1));
+}
+
+#[test]
+fn test_player_lines_include_squad_without_events() {
+    let squad = vec![
+        SquadMember { player_id: 1, name: "Player1 Test".to_string(), shirt_number: 7, is_goalkeeper: false },
+        SquadMember { player_id: 3, name: "Player3 Test".to_string(), shirt_number: 1, is_goalkeeper: true },
+        SquadMember { player_id: 9, name: "Bench Player".to_string(), shirt_number: 22, is_goalkeeper: false },
+    ];
+    let lines = build_player_lines(&squad, &sample_events());
+    let numbers: Vec<Option<i32>> = lines.iter().map(|l| l.shirt_number).collect();
+    assert_eq!(numbers, vec![Some(1), Some(7), Some(22), None], "opponents without a shirt number come last");
+
+    let scorer = &lines[1];
+    assert_eq!((scorer.goals, scorer.shots, scorer.penalty_goals, scorer.suspensions), (2, 3, 1, 1));
+    assert_eq!(lines[0].saves, 1);
+    assert_eq!(lines[2].goals, 0);
+}
+
+#[test]
+fn test_html_escapes_names() {
+    let html = render_html(&sample_report("<b>Home</b> & Co"));
+    assert!(html.contains("&lt;b&gt;Home&lt;/b&gt; &amp; Co"));
+    assert!(!html.contains("<b>Home</b>"));
+}
+
+#[test]
+fn test_pdf_is_well_formed() {
+    let pdf = render_pdf(&sample_report("Home"));
+    assert!(pdf.starts_with(b"%PDF-"));
+    assert!(pdf.windows(5).any(|w| w == b"%%EOF"));
+}
+
+#[tokio::test]
+async fn test_player_cannot_download_report() {
+    let app = build_app_for_test().await;
+    let token = create_token(3, "player@example.com", "player", "Player", 1).unwrap();
+    let req = Request::builder()
+        .uri("/api/matches/1/report.pdf")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -9,7 +9,7 @@ use tower::util::ServiceExt;
 #[tokio::test]
 async fn test_statistics_admin_access() {
     let app = build_app_for_test().await;
-    let token = create_token(1, "admin@example.com", "admin", "Admin").unwrap();
+    let token = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
     let req = Request::builder()
         .uri("/api/matches/1/statistics")
         .header("Authorization", format!("Bearer {}", token))
@@ -22,7 +22,7 @@ async fn test_statistics_admin_access() {
 #[tokio::test]
 async fn test_statistics_coach_access() {
     let app = build_app_for_test().await;
-    let token = create_token(2, "coach@example.com", "coach", "Coach").unwrap();
+    let token = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
     let req = Request::builder()
         .uri("/api/matches/1/statistics")
         .header("Authorization", format!("Bearer {}", token))
@@ -35,7 +35,7 @@ async fn test_statistics_coach_access() {
 #[tokio::test]
 async fn test_statistics_player_forbidden() {
     let app = build_app_for_test().await;
-    let token = create_token(3, "player@example.com", "player", "Player").unwrap();
+    let token = create_token(3, "player@example.com", "player", "Player", 1).unwrap();
     let req = Request::builder()
         .uri("/api/matches/1/statistics")
         .header("Authorization", format!("Bearer {}", token))
@@ -48,7 +48,7 @@ async fn test_statistics_player_forbidden() {
 #[tokio::test]
 async fn test_statistics_not_found() {
     let app = build_app_for_test().await;
-    let token = create_token(1, "admin@example.com", "admin", "Admin").unwrap();
+    let token = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
     let req = Request::builder()
         .uri("/api/matches/99999/statistics")
         .header("Authorization", format!("Bearer {}", token))
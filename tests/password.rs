@@ -0,0 +1,127 @@
+//! Tests for the password policy, hashing parameters and password changes.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::{build_app_for_test, connect_for_test};
+use handball_team_app::config::PasswordConfig;
+use handball_team_app::services::password::{self, BloomFilter};
+use serde_json::{json, Value};
+use tower::util::ServiceExt;
+
+async fn post(app: &Router, uri: &str, token: Option<&str>, body: Value) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method("POST").uri(uri).header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    let resp = app.clone().oneshot(builder.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let status = resp.status();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// A cheap policy so hashing in tests stays fast.
+fn cheap_policy() -> PasswordConfig {
+    PasswordConfig { argon2_memory_kib: 1024, argon2_iterations: 1, ..PasswordConfig::default() }
+}
+
+#[test]
+fn test_bloom_filter_membership() {
+    let filter = BloomFilter::from_lists(&["# comment\nhunter2\n\n  Trustno1  \n", "letmein"]);
+    assert!(filter.contains("hunter2"));
+    assert!(filter.contains("trustno1"), "entries are trimmed and lowercased");
+    assert!(filter.contains("letmein"));
+    assert!(!filter.contains("# comment"));
+    assert!(!filter.contains("correct horse battery staple"));
+}
+
+#[test]
+fn test_policy_rules() {
+    let policy = PasswordConfig::default();
+    let breached = BloomFilter::from_lists(&["password123"]);
+    let check = |pw: &str| password::check(&policy, &breached, pw, "Anna.Berg@example.com");
+    assert!(check("short").unwrap_err().contains("at least 10"));
+    assert!(check(&"x".repeat(129)).unwrap_err().contains("at most 128"));
+    assert!(check("my-anna.berg-pass").unwrap_err().contains("email"));
+    assert!(check("Password123").unwrap_err().contains("breached"));
+    assert!(check("Seven wings at dusk").is_ok());
+}
+
+#[test]
+fn test_hash_parameters_and_rehash() {
+    let cheap = cheap_policy();
+    let hash = password::hash(&cheap, "Seven wings at dusk").unwrap();
+    assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+    assert!(password::verify("Seven wings at dusk", &hash).unwrap());
+    assert!(!password::verify("seven wings at dusk", &hash).unwrap());
+    assert!(!password::needs_rehash(&cheap, &hash));
+    assert!(password::needs_rehash(&PasswordConfig::default(), &hash), "cost changed");
+    assert!(password::needs_rehash(&cheap, "not a hash"));
+}
+
+#[tokio::test]
+async fn test_registration_rejects_weak_passwords() {
+    let app = build_app_for_test().await;
+    let email = format!("weak-{}@example.com", chrono::Utc::now().timestamp_nanos_opt().unwrap());
+    let register = |password: &str| json!({ "email": email, "password": password, "first_name": "We", "last_name": "Ak", "role": "coach" });
+    let (status, body) = post(&app, "/api/register", None, register("letmein123")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("breached"));
+    let (status, _) = post(&app, "/api/register", None, register("abc12")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_login_rehashes_and_password_can_be_changed() {
+    let app = build_app_for_test().await;
+    let email = format!("rehash-{}@example.com", chrono::Utc::now().timestamp_nanos_opt().unwrap());
+    let (status, body) = post(
+        &app,
+        "/api/register",
+        None,
+        json!({ "email": email, "password": "Seven wings at dusk", "first_name": "Re", "last_name": "Hash", "role": "coach" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let user_id = body["user_id"].as_i64().unwrap();
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let (status, _) = post(&app, &format!("/api/admin/users/{}/approve", user_id), Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Pretend the account was created under older, cheaper parameters
+    let pool = connect_for_test().await;
+    let stored_hash = |pool: sqlx::PgPool| async move {
+        sqlx::query_scalar::<_, String>("SELECT password_hash FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(password::hash(&cheap_policy(), "Seven wings at dusk").unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, body) = post(&app, "/api/login", None, json!({ "email": email, "password": "Seven wings at dusk" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let upgraded = stored_hash(pool.clone()).await;
+    assert!(!password::needs_rehash(&PasswordConfig::default(), &upgraded), "upgraded to the configured cost");
+    assert!(password::verify("Seven wings at dusk", &upgraded).unwrap());
+
+    let token = body["token"].as_str().unwrap().to_string();
+    let change = |current: &str, new: &str| json!({ "current_password": current, "new_password": new });
+    let (status, _) = post(&app, "/api/password/change", Some(&token), change("wrong", "Eight oars in fog")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = post(&app, "/api/password/change", Some(&token), change("Seven wings at dusk", "password123")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = post(&app, "/api/password/change", Some(&token), change("Seven wings at dusk", "Eight oars in fog")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = post(&app, "/api/login", None, json!({ "email": email, "password": "Seven wings at dusk" })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = post(&app, "/api/login", None, json!({ "email": email, "password": "Eight oars in fog" })).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,119 @@
+//! Tests for rate limiting and login lockouts.
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::response::IntoResponse;
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::errors::AppError;
+use handball_team_app::services::rate_limit::{lockout_duration, Limit};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tower::util::ServiceExt;
+
+async fn post(app: &Router, uri: &str, token: Option<&str>, body: Value) -> (StatusCode, Option<u64>, Value) {
+    let mut builder = Request::builder().method("POST").uri(uri).header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    let resp = app.clone().oneshot(builder.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let status = resp.status();
+    let retry_after = resp
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, retry_after, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn unique_email(prefix: &str) -> String {
+    format!("{}-{}@example.com", prefix, chrono::Utc::now().timestamp_nanos_opt().unwrap())
+}
+
+#[test]
+fn test_token_bucket_refills_over_time() {
+    let limit = Limit { burst: 2, per_minute: 60 };
+    let (tokens, wait) = limit.take(2.0, Duration::ZERO);
+    assert_eq!((tokens, wait), (1.0, None));
+    let (tokens, wait) = limit.take(tokens, Duration::ZERO);
+    assert_eq!((tokens, wait), (0.0, None));
+    let (tokens, wait) = limit.take(tokens, Duration::from_millis(500));
+    assert_eq!(wait, Some(Duration::from_millis(500)), "half a token short at one per second");
+    let (tokens, wait) = limit.take(tokens, Duration::from_millis(500));
+    assert!(wait.is_none());
+    assert!(tokens.abs() < 1e-9);
+    // A long pause never refills past the burst size
+    assert_eq!(limit.take(0.0, Duration::from_secs(3600)), (1.0, None));
+}
+
+#[test]
+fn test_lockout_grows_with_each_failure() {
+    let base = Duration::from_secs(60);
+    assert_eq!(lockout_duration(2, 3, base), None);
+    assert_eq!(lockout_duration(3, 3, base), Some(Duration::from_secs(60)));
+    assert_eq!(lockout_duration(4, 3, base), Some(Duration::from_secs(120)));
+    assert_eq!(lockout_duration(6, 3, base), Some(Duration::from_secs(480)));
+    assert_eq!(lockout_duration(100, 3, base), Some(Duration::from_secs(24 * 60 * 60)));
+}
+
+#[test]
+fn test_too_many_requests_sets_retry_after() {
+    let resp = AppError::TooManyRequests("Slow down".into(), 42).into_response();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "42");
+}
+
+#[tokio::test]
+async fn test_unknown_account_is_rate_limited() {
+    let app = build_app_for_test().await;
+    let login = json!({ "email": unique_email("nobody"), "password": "whatever" });
+    for _ in 0..10 {
+        let (status, _, body) = post(&app, "/api/login", None, login.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Invalid email or password");
+    }
+    let (status, retry_after, _) = post(&app, "/api/login", None, login).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after.unwrap() >= 1);
+}
+
+#[tokio::test]
+async fn test_repeated_failures_lock_account_until_admin_unlocks() {
+    let app = build_app_for_test().await;
+    let email = unique_email("lockout");
+    let (status, _, body) = post(
+        &app,
+        "/api/register",
+        None,
+        json!({ "email": email, "password": "correct-horse", "first_name": "Lock", "last_name": "Out", "role": "coach" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let user_id = body["user_id"].as_i64().unwrap();
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let (status, _, _) = post(&app, &format!("/api/admin/users/{}/approve", user_id), Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK, "coaches wait for approval");
+
+    for _ in 0..5 {
+        let (status, _, _) = post(&app, "/api/login", None, json!({ "email": email, "password": "wrong" })).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let correct = json!({ "email": email, "password": "correct-horse" });
+    let (status, retry_after, body) = post(&app, "/api/login", None, correct.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "locked even with the right password");
+    assert_eq!(body["message"], "Invalid email or password", "a lockout looks like a wrong password");
+    assert!(retry_after.is_none());
+
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let uri = format!("/api/admin/users/{}/unlock", user_id);
+    let (status, _, _) = post(&app, &uri, Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = post(&app, &uri, Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, body) = post(&app, "/api/login", None, correct).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user_id"], user_id);
+}
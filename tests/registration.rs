@@ -0,0 +1,194 @@
+//! Tests for registration modes, admin invites and the pending-approval queue.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::registration::{initial_status, RegistrationMode};
+use serde_json::{json, Value};
+use tower::util::ServiceExt;
+
+const PASSWORD: &str = "Seven wings at dusk";
+
+async fn send(app: &Router, method: &str, uri: &str, token: Option<&str>, body: Value) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    let resp = app.clone().oneshot(builder.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let status = resp.status();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn unique_email(prefix: &str) -> String {
+    format!("{}-{}@example.com", prefix, chrono::Utc::now().timestamp_nanos_opt().unwrap())
+}
+
+fn coach_signup(email: &str) -> Value {
+    json!({ "email": email, "password": PASSWORD, "first_name": "New", "last_name": "Coach", "role": "coach" })
+}
+
+fn admin() -> String {
+    create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap()
+}
+
+#[test]
+fn test_initial_status() {
+    assert_eq!(initial_status(RegistrationMode::Open, "player", false), "active");
+    assert_eq!(initial_status(RegistrationMode::Open, "coach", false), "pending");
+    assert_eq!(initial_status(RegistrationMode::Approval, "player", false), "pending");
+    assert_eq!(initial_status(RegistrationMode::Approval, "coach", true), "active");
+    assert_eq!(initial_status(RegistrationMode::InviteOnly, "coach", true), "active");
+}
+
+#[tokio::test]
+async fn test_coach_signup_waits_for_approval() {
+    let app = build_app_for_test().await;
+    let admin = admin();
+    let email = unique_email("pending");
+    let (status, body) = send(&app, "POST", "/api/register", None, coach_signup(&email)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_null(), "no token before approval");
+    let user_id = body["user_id"].as_i64().unwrap();
+
+    let login = json!({ "email": email, "password": PASSWORD });
+    let (status, _) = send(&app, "POST", "/api/login", None, json!({ "email": email, "password": "wrong" })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "status is not revealed without the password");
+    let (status, _) = send(&app, "POST", "/api/login", None, login.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (status, _) = send(&app, "GET", "/api/admin/pending-users", Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, pending) = send(&app, "GET", "/api/admin/pending-users", Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let entry = pending.as_array().unwrap().iter().find(|u| u["id"] == user_id).unwrap();
+    assert_eq!(entry["role"], "coach");
+
+    let approve = format!("/api/admin/users/{}/approve", user_id);
+    let (status, _) = send(&app, "POST", &approve, Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "POST", &approve, Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "no longer pending");
+    let (status, body) = send(&app, "POST", "/api/login", None, login).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].as_str().is_some());
+}
+
+#[tokio::test]
+async fn test_rejected_signup_cannot_log_in() {
+    let app = build_app_for_test().await;
+    let email = unique_email("rejected");
+    let (_, body) = send(&app, "POST", "/api/register", None, coach_signup(&email)).await;
+    let user_id = body["user_id"].as_i64().unwrap();
+
+    let (status, _) = send(&app, "POST", &format!("/api/admin/users/{}/reject", user_id), Some(&admin()), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = send(&app, "POST", "/api/login", None, json!({ "email": email, "password": PASSWORD })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(body["message"].as_str().unwrap().contains("not approved"));
+}
+
+#[tokio::test]
+async fn test_self_registered_player_cannot_promote_themselves() {
+    let app = build_app_for_test().await;
+    let email = unique_email("climber");
+    let signup = json!({
+        "email": email, "password": PASSWORD, "first_name": "Self", "last_name": "Made", "role": "player",
+        "player_details": { "date_of_birth": "2010-02-03", "position": "pivot", "jersey_number": 5 },
+    });
+    let (status, body) = send(&app, "POST", "/api/register", None, signup).await;
+    assert_eq!(status, StatusCode::OK);
+    let user_id = body["user_id"].as_i64().unwrap();
+    // Whatever the registration mode, the account ends up an active player
+    send(&app, "POST", &format!("/api/admin/users/{}/approve", user_id), Some(&admin()), json!({})).await;
+    let (status, body) = send(&app, "POST", "/api/login", None, json!({ "email": email, "password": PASSWORD })).await;
+    assert_eq!(status, StatusCode::OK);
+    let player = body["token"].as_str().unwrap().to_string();
+
+    let promote = json!({ "name": "Self Made", "email": email, "role": "coach" });
+    let (status, _) = send(&app, "PATCH", &format!("/api/users/{}", user_id), Some(&player), promote).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "approval cannot be skipped by editing the account");
+    let (status, body) = send(&app, "POST", "/api/login", None, json!({ "email": email, "password": PASSWORD })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["role"], "player");
+}
+
+#[tokio::test]
+async fn test_invite_sets_role_and_is_single_use() {
+    let app = build_app_for_test().await;
+    let admin = admin();
+    let email = unique_email("invited");
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (status, _) = send(&app, "POST", "/api/admin/invites", Some(&coach), json!({ "role": "coach" })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "POST", "/api/admin/invites", Some(&admin), json!({ "role": "admin" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, created) =
+        send(&app, "POST", "/api/admin/invites", Some(&admin), json!({ "role": "coach", "email": email, "expires_in_days": 3 })).await;
+    assert_eq!(status, StatusCode::OK);
+    let code = created["code"].as_str().unwrap().to_string();
+    assert!(created["link"].as_str().unwrap().ends_with(&code));
+
+    // Bound to one address
+    let mut other = coach_signup(&unique_email("other"));
+    other["invite_code"] = json!(code);
+    let (status, _) = send(&app, "POST", "/api/register", None, other).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The invite's role wins and the account is active at once
+    let mut signup = json!({ "email": email, "password": PASSWORD, "first_name": "In", "last_name": "Vited", "role": "player" });
+    signup["invite_code"] = json!(code.to_uppercase());
+    let (status, body) = send(&app, "POST", "/api/register", None, signup).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["role"], "coach");
+    assert!(body["token"].as_str().is_some());
+
+    let mut again = coach_signup(&email);
+    again["invite_code"] = json!(code);
+    let (status, _) = send(&app, "POST", "/api/register", None, again).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "used invites are refused");
+
+    let (_, invites) = send(&app, "GET", "/api/admin/invites", Some(&admin), json!({})).await;
+    let invite = invites.as_array().unwrap().iter().find(|i| i["id"] == created["invite"]["id"]).unwrap();
+    assert_eq!(invite["used_by"], body["user_id"]);
+    assert!(invite.get("code_hash").is_none());
+    let (status, _) = send(&app, "DELETE", &format!("/api/admin/invites/{}", invite["id"]), Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "used invites cannot be revoked");
+}
+
+#[tokio::test]
+async fn test_revoked_invite_is_refused() {
+    let app = build_app_for_test().await;
+    let admin = admin();
+    let (_, created) = send(&app, "POST", "/api/admin/invites", Some(&admin), json!({ "role": "coach" })).await;
+    let (status, _) = send(&app, "DELETE", &format!("/api/admin/invites/{}", created["invite"]["id"]), Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let mut signup = coach_signup(&unique_email("revoked"));
+    signup["invite_code"] = created["code"].clone();
+    let (status, _) = send(&app, "POST", "/api/register", None, signup).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_registration_mode_endpoints() {
+    let app = build_app_for_test().await;
+    let admin = admin();
+    let (status, current) = send(&app, "GET", "/api/admin/registration-mode", Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(["open", "approval", "invite_only"].contains(&current["mode"].as_str().unwrap()));
+
+    // Written back unchanged so concurrently running tests are unaffected
+    let (status, body) = send(&app, "PUT", "/api/admin/registration-mode", Some(&admin), current.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, current);
+    let (status, _) = send(&app, "PUT", "/api/admin/registration-mode", Some(&admin), json!({ "mode": "closed" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (status, _) = send(&app, "PUT", "/api/admin/registration-mode", Some(&coach), current).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,114 @@
+//! Tests for team sheet validation and squad endpoints.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::squad::{validate_squad, SquadEntry};
+use std::collections::HashMap;
+use tower::util::ServiceExt;
+
+fn entry(player_id: i64, shirt_number: i32, is_goalkeeper: bool, is_starting: bool) -> SquadEntry {
+    SquadEntry { player_id, shirt_number, is_goalkeeper, is_starting }
+}
+
+/// A valid 16-player sheet: two goalkeepers, one of whom starts with six court players.
+fn full_sheet() -> Vec<SquadEntry> {
+    (1..=16)
+        .map(|i| entry(i, i as i32, i <= 2, i == 1 || (3..=8).contains(&i)))
+        .collect()
+}
+
+#[test]
+fn test_valid_full_sheet() {
+    assert!(validate_squad(&full_sheet(), &HashMap::new(), true).is_ok());
+}
+
+#[test]
+fn test_more_than_sixteen_players_rejected() {
+    let mut sheet = full_sheet();
+    sheet.push(entry(17, 17, false, false));
+    let errors = validate_squad(&sheet, &HashMap::new(), false).unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("at most 16")));
+}
+
+#[test]
+fn test_duplicate_shirt_numbers_and_players_rejected() {
+    let mut sheet = full_sheet();
+    sheet[15].shirt_number = 3;
+    sheet.push(entry(1, 40, false, false));
+    let errors = validate_squad(&sheet, &HashMap::new(), false).unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("Shirt number 3")));
+    assert!(errors.iter().any(|e| e.contains("Player 1 is listed more than once")));
+}
+
+#[test]
+fn test_starting_seven_needs_one_goalkeeper() {
+    let mut sheet = full_sheet();
+    sheet[1].is_starting = true; // second goalkeeper starts too
+    sheet[2].is_starting = false;
+    let errors = validate_squad(&sheet, &HashMap::new(), false).unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("Only one goalkeeper")));
+
+    let mut draft = full_sheet();
+    draft[7].is_starting = false;
+    assert!(validate_squad(&draft, &HashMap::new(), false).is_ok(), "drafts may name fewer starters");
+    assert!(validate_squad(&draft, &HashMap::new(), true).is_err(), "publishing needs all seven");
+}
+
+#[test]
+fn test_ineligible_players_rejected() {
+    let mut ineligible = HashMap::new();
+    ineligible.insert(5, "suspended (red card)".to_string());
+    let errors = validate_squad(&full_sheet(), &ineligible, false).unwrap_err();
+    assert_eq!(errors, vec!["Player 5 is not eligible: suspended (red card)".to_string()]);
+}
+
+#[tokio::test]
+async fn test_player_cannot_edit_squad() {
+    let app = build_app_for_test().await;
+    let token = create_token(3, "player@example.com", "player", "Player", 1).unwrap();
+    let req = Request::builder()
+        .method("PUT")
+        .uri("/api/matches/1/squad")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .body(Body::from(r#"{"players":[]}"#))
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_squad_unknown_match() {
+    let app = build_app_for_test().await;
+    let token = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let req = Request::builder()
+        .uri("/api/matches/99999/squad")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_player_cannot_export_squad() {
+    let app = build_app_for_test().await;
+    let token = create_token(3, "player@example.com", "player", "Player", 1).unwrap();
+    let req = Request::builder()
+        .uri("/api/matches/1/squad/export")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_notifications_require_auth() {
+    let app = build_app_for_test().await;
+    let req = Request::builder().uri("/api/notifications").body(Body::empty()).unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
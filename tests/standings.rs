@@ -0,0 +1,106 @@
+//! Tests for tournament standings computation and endpoint.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::standings::{compute_standings, parse_tiebreakers, validate_points, MatchResult, StandingsRules, Tiebreaker};
+use tower::util::ServiceExt;
+
+fn result(home: &str, away: &str, home_score: i32, away_score: i32) -> MatchResult {
+    MatchResult {
+        home_team: home.to_string(),
+        away_team: away.to_string(),
+        home_score,
+        away_score,
+    }
+}
+
+fn teams(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn test_standings_counts_results_and_points() {
+    let results = vec![
+        result("Tornadoes", "Sharks", 30, 25),
+        result("Sharks", "Eagles", 22, 22),
+        result("Eagles", "Tornadoes", 28, 27),
+    ];
+    let table = compute_standings(&teams(&["Tornadoes", "Sharks", "Eagles", "Lions"]), &results, &StandingsRules::default());
+
+    assert_eq!(table.len(), 4);
+    let eagles = &table[0];
+    assert_eq!(eagles.team, "Eagles");
+    assert_eq!((eagles.played, eagles.won, eagles.drawn, eagles.lost), (2, 1, 1, 0));
+    assert_eq!((eagles.goals_for, eagles.goals_against, eagles.goal_difference, eagles.points), (50, 49, 1, 3));
+    assert_eq!(table[1].team, "Tornadoes");
+    assert_eq!(table[1].points, 2);
+    assert_eq!(table[2].team, "Sharks");
+    // Teams without results are still listed.
+    assert_eq!(table[3].team, "Lions");
+    assert_eq!(table[3].played, 0);
+    assert_eq!(table.iter().map(|r| r.position).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_head_to_head_beats_goal_difference() {
+    // A and B both finish on 2 points; B has the better goal difference but lost to A.
+    let results = vec![
+        result("A", "B", 21, 20),
+        result("B", "C", 35, 20),
+        result("C", "A", 30, 20),
+    ];
+    let table = compute_standings(&teams(&["A", "B", "C"]), &results, &StandingsRules::default());
+    let order: Vec<&str> = table.iter().map(|r| r.team.as_str()).collect();
+    // All three are level on points; the head-to-head mini table is also level,
+    // so head-to-head goal difference decides: B +14, A -9, C -5.
+    assert_eq!(order, vec!["B", "C", "A"]);
+
+    let two_way = vec![result("A", "B", 21, 20), result("B", "C", 35, 20), result("C", "D", 20, 30), result("D", "A", 30, 20)];
+    let table = compute_standings(&teams(&["A", "B", "C", "D"]), &two_way, &StandingsRules::default());
+    let a = table.iter().position(|r| r.team == "A").unwrap();
+    let b = table.iter().position(|r| r.team == "B").unwrap();
+    assert!(a < b, "A won the head-to-head against B");
+}
+
+#[test]
+fn test_custom_points_and_goal_difference_only() {
+    let rules = StandingsRules {
+        points_for_win: 3,
+        points_for_draw: 1,
+        points_for_loss: 0,
+        tiebreakers: vec![Tiebreaker::GoalDifference],
+    };
+    let results = vec![result("A", "B", 21, 20), result("B", "C", 35, 20), result("C", "A", 30, 20)];
+    let table = compute_standings(&teams(&["A", "B", "C"]), &results, &rules);
+    assert!(table.iter().all(|r| r.points == 3));
+    assert_eq!(table[0].team, "B");
+}
+
+#[test]
+fn test_parse_tiebreakers() {
+    assert_eq!(
+        parse_tiebreakers("head_to_head, goal_difference").unwrap(),
+        vec![Tiebreaker::HeadToHead, Tiebreaker::GoalDifference]
+    );
+    assert_eq!(parse_tiebreakers("head_to_head,coin_toss").unwrap_err(), "coin_toss");
+}
+
+#[test]
+fn test_validate_points() {
+    assert!(validate_points(2, 1, 0));
+    assert!(validate_points(3, 3, 0));
+    assert!(!validate_points(1, 2, 0));
+    assert!(!validate_points(2, 1, -1));
+}
+
+#[tokio::test]
+async fn test_standings_unknown_tournament() {
+    let app = build_app_for_test().await;
+    let req = Request::builder()
+        .uri("/api/tournaments/99999/standings")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,120 @@
+//! Tests for teams, team membership and team-scoped access.
+
+use axum::http::StatusCode;
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::build_app_for_test;
+use handball_team_app::services::teams::TeamScope;
+use serde_json::json;
+use sqlx::PgPool;
+
+mod common;
+use common::{admin, pool, send, signup, unique};
+
+async fn create_team(app: &Router, name: &str, age_group: &str) -> i64 {
+    let (status, team) = send(app, "POST", "/api/teams", Some(&admin()), json!({ "name": name, "age_group": age_group })).await;
+    assert_eq!(status, StatusCode::OK, "{}", team);
+    team["id"].as_i64().unwrap()
+}
+
+async fn only_match(app: &Router, team: i64) -> i64 {
+    let (_, list) = send(app, "GET", &format!("/api/matches?team_id={}", team), Some(&admin()), json!({})).await;
+    let list = list.as_array().unwrap();
+    assert_eq!(list.len(), 1);
+    list[0]["id"].as_i64().unwrap()
+}
+
+async fn player_id(pool: &PgPool, user_id: i64) -> i64 {
+    sqlx::query_scalar("SELECT id FROM players WHERE user_id = $1").bind(user_id).fetch_one(pool).await.unwrap()
+}
+
+#[test]
+fn test_scope_allows() {
+    assert!(TeamScope::All.allows(Some(3)));
+    assert!(TeamScope::Teams(vec![1, 2]).allows(Some(2)));
+    assert!(!TeamScope::Teams(vec![1, 2]).allows(Some(3)));
+    assert!(TeamScope::Teams(vec![]).allows(None), "club-wide items are visible to everyone");
+    assert_eq!(TeamScope::All.ids(), None);
+}
+
+#[tokio::test]
+async fn test_team_management_is_admin_only() {
+    let app = build_app_for_test().await;
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let name = unique("U15");
+    let (status, _) = send(&app, "POST", "/api/teams", Some(&coach), json!({ "name": name })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let id = create_team(&app, &name, "U15").await;
+    let (status, _) = send(&app, "POST", "/api/teams", Some(&admin()), json!({ "name": name })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = send(&app, "PUT", &format!("/api/teams/{}", id), Some(&admin()), json!({ "name": name, "gender": "robots" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, team) = send(&app, "PUT", &format!("/api/teams/{}", id), Some(&admin()), json!({ "name": name, "gender": "women" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(team["gender"], "women");
+
+    let (guardian, _) = signup(&app, "guardian", "member").await;
+    let (status, _) = send(&app, "POST", &format!("/api/teams/{}/members", id), Some(&admin()), json!({ "user_id": guardian })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "guardians do not join teams");
+    let (status, _) = send(&app, "DELETE", &format!("/api/teams/{}", id), Some(&admin()), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_coach_is_scoped_to_own_team() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let admin = admin();
+    let mine = create_team(&app, &unique("U17"), "U17").await;
+    let theirs = create_team(&app, &unique("Women"), "senior").await;
+    let (coach_id, coach) = signup(&app, "coach", "member").await;
+    let (my_player, _) = signup(&app, "player", "member").await;
+    let (their_player, _) = signup(&app, "player", "member").await;
+    for (team, user) in [(mine, coach_id), (mine, my_player), (theirs, their_player)] {
+        let (status, _) = send(&app, "POST", &format!("/api/teams/{}/members", team), Some(&admin), json!({ "user_id": user })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (_, teams) = send(&app, "GET", "/api/teams", Some(&coach), json!({})).await;
+    let ids: Vec<i64> = teams.as_array().unwrap().iter().map(|t| t["id"].as_i64().unwrap()).collect();
+    assert_eq!(ids, vec![mine]);
+    assert_eq!(teams[0]["players"], 1);
+    assert_eq!(teams[0]["coaches"], 1);
+    let (status, _) = send(&app, "GET", &format!("/api/teams/{}/members", theirs), Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // A coach's match lands in their only team; admins pick the team
+    let fixture = json!({ "match_date": "2031-02-01", "home_team": "Tornadoes", "away_team": "Hawks", "location": "Main Hall" });
+    let (status, _) = send(&app, "POST", "/api/matches", Some(&coach), fixture.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut other = fixture.clone();
+    other["team_id"] = json!(theirs);
+    let (status, _) = send(&app, "POST", "/api/matches", Some(&coach), other.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "POST", "/api/matches", Some(&admin), other).await;
+    assert_eq!(status, StatusCode::OK);
+    let my_match = only_match(&app, mine).await;
+    let their_match = only_match(&app, theirs).await;
+
+    let (status, _) = send(&app, "GET", &format!("/api/matches/{}/squad", my_match), Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", &format!("/api/matches/{}/squad", their_match), Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "DELETE", &format!("/api/matches/{}", their_match), Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let sheet = |player: i64| json!({ "players": [{ "player_id": player, "shirt_number": 1, "is_goalkeeper": true, "is_starting": true }] });
+    let squad = format!("/api/matches/{}/squad", my_match);
+    let (status, body) = send(&app, "PUT", &squad, Some(&coach), sheet(player_id(&pool, their_player).await)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("not in this match's team"));
+    let (status, _) = send(&app, "PUT", &squad, Some(&coach), sheet(player_id(&pool, my_player).await)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, users) = send(&app, "GET", "/api/users", Some(&coach), json!({})).await;
+    let users: Vec<i64> = users.as_array().unwrap().iter().map(|u| u["id"].as_i64().unwrap()).collect();
+    assert!(users.contains(&my_player));
+    assert!(!users.contains(&their_player));
+
+    sqlx::query("DELETE FROM matches WHERE id = ANY($1)").bind(vec![my_match, their_match]).execute(&pool).await.unwrap();
+}
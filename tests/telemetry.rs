@@ -0,0 +1,54 @@
+//! Tests for request ids, health checks and the metrics endpoint.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use handball_team_app::build_app_for_test;
+use tower::util::ServiceExt;
+
+async fn get(app: &axum::Router, uri: &str, request_id: Option<&str>) -> axum::response::Response {
+    let mut builder = Request::builder().uri(uri);
+    if let Some(id) = request_id {
+        builder = builder.header("x-request-id", id);
+    }
+    app.clone().oneshot(builder.body(Body::empty()).unwrap()).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_request_id_is_assigned_or_echoed() {
+    let app = build_app_for_test().await;
+    let resp = get(&app, "/healthz", None).await;
+    let assigned = resp.headers()["x-request-id"].to_str().unwrap().to_string();
+    assert_eq!(assigned.len(), 36, "a UUID is generated: {}", assigned);
+
+    let resp = get(&app, "/api/seasons", Some("client-42")).await;
+    assert_eq!(resp.headers()["x-request-id"], "client-42");
+}
+
+#[tokio::test]
+async fn test_health_endpoints_check_database() {
+    let app = build_app_for_test().await;
+    for uri in ["/healthz", "/readyz"] {
+        let resp = get(&app, uri, None).await;
+        assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["database"], "ok");
+    }
+}
+
+#[tokio::test]
+async fn test_metrics_count_requests_by_route_pattern() {
+    let app = build_app_for_test().await;
+    get(&app, "/api/tournaments/999999/standings", None).await;
+    let resp = get(&app, "/metrics", None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(
+        text.contains(r#"http_requests_total{method="GET",route="/api/tournaments/:id/standings",status="404"}"#),
+        "{}",
+        text
+    );
+    assert!(text.contains("http_request_duration_seconds_bucket"));
+    assert!(text.contains("db_pool_max_connections 1"));
+}
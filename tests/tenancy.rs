@@ -0,0 +1,234 @@
+//! Tests for clubs sharing one installation: tenant resolution, branding and isolation.
+
+use axum::body::Body;
+use axum::http::{HeaderMap, HeaderValue, Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::{build_app_for_test, connect_for_test};
+use handball_team_app::config::TenancyConfig;
+use handball_team_app::services::clubs::{requested_slug, slug_from_host};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::util::ServiceExt;
+
+const PASSWORD: &str = "Seven wings at dusk";
+
+async fn send(app: &Router, method: &str, uri: &str, club: Option<&str>, token: Option<&str>, body: Value) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+    if let Some(club) = club {
+        builder = builder.header("X-Club", club);
+    }
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    let resp = app.clone().oneshot(builder.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let status = resp.status();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn host_admin() -> String {
+    create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap()
+}
+
+fn unique(prefix: &str) -> String {
+    format!("{}-{}", prefix, chrono::Utc::now().timestamp_nanos_opt().unwrap())
+}
+
+async fn pool() -> PgPool {
+    connect_for_test().await
+}
+
+async fn player_id(pool: &PgPool, user_id: i64) -> i64 {
+    sqlx::query_scalar("SELECT id FROM players WHERE user_id = $1").bind(user_id).fetch_one(pool).await.unwrap()
+}
+
+fn ids(list: &Value) -> Vec<i64> {
+    list.as_array().unwrap().iter().map(|item| item["id"].as_i64().unwrap()).collect()
+}
+
+/// A player of the default club, signed up through an invite. Returns (user id, email).
+async fn host_player(app: &Router) -> (i64, String) {
+    let email = format!("{}@example.com", unique("member"));
+    let (_, invite) = send(app, "POST", "/api/admin/invites", None, Some(&host_admin()), json!({ "role": "player", "email": email })).await;
+    let body = json!({
+        "email": email, "password": PASSWORD, "first_name": "Host", "last_name": "Player", "role": "player",
+        "invite_code": invite["code"],
+        "player_details": { "date_of_birth": "2008-01-20", "position": "back", "jersey_number": 11 },
+    });
+    let (status, body) = send(app, "POST", "/api/register", None, None, body).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    (body["user_id"].as_i64().unwrap(), email)
+}
+
+/// A second club created by the host admin. Returns (slug, admin token).
+async fn other_club(app: &Router, admin_email: &str) -> (String, String) {
+    let slug = unique("hawks");
+    let (status, created) = send(
+        app,
+        "POST",
+        "/api/admin/clubs",
+        None,
+        Some(&host_admin()),
+        json!({ "slug": slug, "name": "Hawks", "admin_email": admin_email, "admin_name": "Hawk Admin" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+    let login = json!({ "email": admin_email, "password": created["temporary_password"] });
+    let (status, _) = send(app, "POST", "/api/login", None, None, login.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "the address belongs to another account in the default club");
+    let (status, body) = send(app, "POST", "/api/login", Some(&slug), None, login).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    (slug, body["token"].as_str().unwrap().to_string())
+}
+
+#[test]
+fn test_slug_from_host() {
+    assert_eq!(slug_from_host("hawks.example.com", "example.com").as_deref(), Some("hawks"));
+    assert_eq!(slug_from_host("Hawks.Example.com:8080", "example.com").as_deref(), Some("hawks"));
+    assert_eq!(slug_from_host("example.com", "example.com"), None);
+    assert_eq!(slug_from_host("www.example.com", "example.com"), None);
+    assert_eq!(slug_from_host("a.b.example.com", "example.com"), None);
+    assert_eq!(slug_from_host("hawks.example.org", "example.com"), None);
+    assert_eq!(slug_from_host("hawks.example.com", ""), None);
+}
+
+#[test]
+fn test_header_wins_over_subdomain() {
+    let tenancy = TenancyConfig { base_domain: "example.com".into(), default_club: "tornadoes".into() };
+    let mut headers = HeaderMap::new();
+    assert_eq!(requested_slug(&headers, &tenancy), "tornadoes");
+    headers.insert("host", HeaderValue::from_static("hawks.example.com"));
+    assert_eq!(requested_slug(&headers, &tenancy), "hawks");
+    headers.insert("x-club", HeaderValue::from_static("Eagles"));
+    assert_eq!(requested_slug(&headers, &tenancy), "eagles");
+}
+
+#[tokio::test]
+async fn test_unknown_club_and_foreign_tokens_are_refused() {
+    let app = build_app_for_test().await;
+    let (status, _) = send(&app, "GET", "/api/matches", Some("no-such-club"), None, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, email) = host_player(&app).await;
+    let (slug, hawks_admin) = other_club(&app, &email).await;
+    let (status, _) = send(&app, "GET", "/api/teams", Some(&slug), Some(&host_admin()), json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "a token only works on its own club");
+    let (status, _) = send(&app, "GET", "/api/teams", None, Some(&hawks_admin), json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Installation-wide data stays with the host club's admins
+    let (status, _) = send(&app, "GET", "/api/admin/backup", Some(&slug), Some(&hawks_admin), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "GET", "/api/admin/clubs", Some(&slug), Some(&hawks_admin), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, clubs) = send(&app, "GET", "/api/admin/clubs", None, Some(&host_admin()), json!({})).await;
+    assert!(clubs.as_array().unwrap().iter().any(|c| c["slug"] == slug.as_str()));
+}
+
+#[tokio::test]
+async fn test_clubs_never_see_each_others_data() {
+    let app = build_app_for_test().await;
+    let pool = pool().await;
+    let (host_user, email) = host_player(&app).await;
+    let (slug, hawks) = other_club(&app, &email).await;
+    let club = Some(slug.as_str());
+
+    // Data created by the other club
+    let (status, team) = send(&app, "POST", "/api/teams", club, Some(&hawks), json!({ "name": "First Team" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", team);
+    let team_id = team["id"].as_i64().unwrap();
+    let (status, _) = send(&app, "POST", "/api/seasons", club, Some(&hawks), json!({ "name": "Hawks 2031", "start_date": "2031-01-01", "end_date": "2031-12-31" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, seasons) = send(&app, "GET", "/api/seasons", club, None, json!({})).await;
+    let season_id = ids(&seasons)[0];
+    let tournament_id: i64 = sqlx::query_scalar(
+        "INSERT INTO tournaments (name, start_date, season_id, club_id) \
+         SELECT 'Hawks Cup', '2031-02-01', $1, id FROM clubs WHERE slug = $2 RETURNING id",
+    )
+    .bind(season_id)
+    .bind(&slug)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let (_, tournaments) = send(&app, "GET", "/api/tournaments", club, None, json!({})).await;
+    assert_eq!(ids(&tournaments), vec![tournament_id]);
+    let fixture = json!({ "match_date": "2031-03-01", "home_team": "Hawks", "away_team": "Eagles", "location": "Eyrie", "team_id": team_id });
+    let (status, _) = send(&app, "POST", "/api/matches", club, Some(&hawks), fixture).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, matches) = send(&app, "GET", "/api/matches", club, None, json!({})).await;
+    assert_eq!(matches.as_array().unwrap().len(), 1, "a new club starts empty");
+    let match_id = ids(&matches)[0];
+
+    // The default club's lists do not contain any of it
+    let (_, teams) = send(&app, "GET", "/api/teams", None, Some(&host_admin()), json!({})).await;
+    assert!(!ids(&teams).contains(&team_id));
+    let (_, seasons) = send(&app, "GET", "/api/seasons", None, None, json!({})).await;
+    assert!(!ids(&seasons).contains(&season_id));
+    let (_, tournaments) = send(&app, "GET", "/api/tournaments", None, None, json!({})).await;
+    assert!(!ids(&tournaments).contains(&tournament_id));
+    let (_, matches) = send(&app, "GET", "/api/matches", None, None, json!({})).await;
+    assert!(!ids(&matches).contains(&match_id));
+
+    // Ids of the other club behave as if they did not exist
+    let admin = host_admin();
+    for (method, uri) in [
+        ("GET", format!("/api/teams/{}/members", team_id)),
+        ("DELETE", format!("/api/teams/{}", team_id)),
+        ("DELETE", format!("/api/matches/{}", match_id)),
+        ("GET", format!("/api/matches/{}/squad", match_id)),
+        ("DELETE", format!("/api/seasons/{}", season_id)),
+        ("DELETE", format!("/api/tournaments/{}", tournament_id)),
+        ("GET", format!("/api/tournaments/{}/standings", tournament_id)),
+    ] {
+        let (status, _) = send(&app, method, &uri, None, Some(&admin), json!({})).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+    }
+    for (method, uri) in [
+        ("DELETE", format!("/api/users/{}", host_user)),
+        ("GET", format!("/api/players/{}/schedule", player_id(&pool, host_user).await)),
+        ("POST", format!("/api/admin/users/{}/unlock", host_user)),
+    ] {
+        let (status, _) = send(&app, method, &uri, club, Some(&hawks), json!({})).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+    }
+    let (_, users) = send(&app, "GET", "/api/users", club, Some(&hawks), json!({})).await;
+    assert_eq!(users.as_array().unwrap().len(), 1, "only the club's own admin: {}", users);
+
+    // Each club's audit log holds its own changes only
+    let (_, audit) = send(&app, "GET", "/api/audit", club, Some(&hawks), json!({})).await;
+    let audit = audit.as_array().unwrap();
+    assert!(audit.iter().any(|e| e["entity_type"] == "team" && e["entity_id"] == team_id));
+    assert!(audit.iter().all(|e| e["actor_id"] != 1 || e["entity_type"] == "club" || e["entity_type"] == "user"));
+    let (_, audit) = send(&app, "GET", "/api/audit?entity_type=team", None, Some(&admin), json!({})).await;
+    assert!(audit.as_array().unwrap().iter().all(|e| e["entity_id"] != team_id));
+}
+
+#[tokio::test]
+async fn test_each_club_has_its_own_branding() {
+    let app = build_app_for_test().await;
+    let (_, email) = host_player(&app).await;
+    let (slug, hawks) = other_club(&app, &email).await;
+    let club = Some(slug.as_str());
+
+    let (status, branding) = send(&app, "GET", "/api/club", club, None, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(branding["name"], "Hawks");
+    assert!(branding["logo_url"].is_null());
+
+    let (status, _) = send(&app, "PUT", "/api/club", club, Some(&hawks), json!({ "name": "Hawks HC", "primary_color": "orange" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "PUT", "/api/club", club, Some(&hawks), json!({ "name": "Hawks HC", "logo_url": "javascript:alert(1)" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let update = json!({ "name": "Hawks HC", "logo_url": "https://hawks.example.com/logo.svg", "primary_color": "#1d4ed8" });
+    let (status, branding) = send(&app, "PUT", "/api/club", club, Some(&hawks), update).await;
+    assert_eq!(status, StatusCode::OK, "{}", branding);
+    assert_eq!(branding["primary_color"], "#1d4ed8");
+
+    let (_, branding) = send(&app, "GET", "/api/club", club, None, json!({})).await;
+    assert_eq!(branding["name"], "Hawks HC");
+    assert_eq!(branding["logo_url"], "https://hawks.example.com/logo.svg");
+    let (_, host) = send(&app, "GET", "/api/club", None, None, json!({})).await;
+    assert_eq!(host["slug"], "tornadoes");
+    assert_eq!(host["logo_url"], "/static/FirstTeamLogo.svg");
+}
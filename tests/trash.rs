@@ -0,0 +1,164 @@
+//! Tests for soft deletion, the trash view and the purge job.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::{build_app_for_test, connect_for_test};
+use handball_team_app::services::trash::{self, TrashEntity};
+use serde_json::{json, Value};
+use tower::util::ServiceExt;
+
+async fn send(app: &Router, method: &str, uri: &str, token: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json");
+    let req = builder
+        .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    let status = resp.status();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Create a season through the API and return its id.
+async fn create_season(app: &Router, token: &str, name: &str) -> i64 {
+    let (status, _) = send(
+        app,
+        "POST",
+        "/api/seasons",
+        token,
+        Some(json!({ "name": name, "start_date": "2031-08-01", "end_date": "2032-06-30" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, seasons) = send(app, "GET", "/api/seasons", token, None).await;
+    seasons.as_array().unwrap().iter().find(|s| s["name"] == name).unwrap()["id"].as_i64().unwrap()
+}
+
+fn unique(prefix: &str) -> String {
+    format!("{} {}", prefix, chrono::Utc::now().timestamp_nanos_opt().unwrap())
+}
+
+#[test]
+fn test_entity_names() {
+    assert_eq!(TrashEntity::parse("matches"), Some(TrashEntity::Match));
+    assert_eq!(TrashEntity::parse("players"), None);
+    assert_eq!(TrashEntity::User.table(), "users");
+    assert_eq!(TrashEntity::Season.entity_type(), "season");
+    // Matches reference tournaments and seasons, so they are purged first.
+    assert_eq!(TrashEntity::ALL[0], TrashEntity::Match);
+    assert_eq!(TrashEntity::ALL[3], TrashEntity::User);
+}
+
+#[tokio::test]
+async fn test_coach_cannot_view_trash() {
+    let app = build_app_for_test().await;
+    let token = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (status, _) = send(&app, "GET", "/api/admin/trash", &token, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, "POST", "/api/admin/trash/seasons/1/restore", &token, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_only_admins_delete_seasons_and_users() {
+    let app = build_app_for_test().await;
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let id = create_season(&app, &admin, "Kept season").await;
+    for token in [
+        create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap(),
+        create_token(3, "player@example.com", "player", "Player", 1).unwrap(),
+    ] {
+        let (status, _) = send(&app, "DELETE", &format!("/api/seasons/{}", id), &token, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(&app, "DELETE", "/api/users/2", &token, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+    let (status, _) = send(&app, "DELETE", &format!("/api/seasons/{}", id), &admin, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_deleted_season_is_hidden_then_restored() {
+    let app = build_app_for_test().await;
+    let token = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let name = unique("Trash season");
+    let id = create_season(&app, &token, &name).await;
+
+    let (status, _) = send(&app, "DELETE", &format!("/api/seasons/{}", id), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, seasons) = send(&app, "GET", "/api/seasons", &token, None).await;
+    assert!(seasons.as_array().unwrap().iter().all(|s| s["id"] != id), "deleted season is not listed");
+    let (status, _) = send(&app, "DELETE", &format!("/api/seasons/{}", id), &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "already in the trash");
+
+    let (_, items) = send(&app, "GET", "/api/admin/trash?entity=seasons", &token, None).await;
+    let item = items.as_array().unwrap().iter().find(|i| i["id"] == id).unwrap();
+    assert_eq!(item["entity"], "seasons");
+    assert_eq!(item["label"], name.as_str());
+
+    let uri = format!("/api/admin/trash/seasons/{}/restore", id);
+    let (status, _) = send(&app, "POST", &uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, seasons) = send(&app, "GET", "/api/seasons", &token, None).await;
+    assert!(seasons.as_array().unwrap().iter().any(|s| s["id"] == id));
+    let (status, _) = send(&app, "POST", &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "no longer in the trash");
+
+    send(&app, "DELETE", &format!("/api/seasons/{}", id), &token, None).await;
+}
+
+#[tokio::test]
+async fn test_purge_removes_only_expired_rows() {
+    let app = build_app_for_test().await;
+    let token = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let expired = create_season(&app, &token, &unique("Expired season")).await;
+    let recent = create_season(&app, &token, &unique("Recent season")).await;
+    for id in [expired, recent] {
+        send(&app, "DELETE", &format!("/api/seasons/{}", id), &token, None).await;
+    }
+
+    let pool = connect_for_test().await;
+    sqlx::query("UPDATE seasons SET deleted_at = NOW() - INTERVAL '40 days' WHERE id = $1")
+        .bind(expired)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let summary = trash::purge(&pool, 30).await.unwrap();
+    assert!(summary.purged >= 1);
+    let remaining: Vec<i64> = sqlx::query_scalar("SELECT id FROM seasons WHERE id = ANY($1)")
+        .bind(vec![expired, recent])
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, vec![recent]);
+}
+
+#[tokio::test]
+async fn test_deleted_user_token_stops_working() {
+    let app = build_app_for_test().await;
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let pool = connect_for_test().await;
+    let email = format!("{}@example.com", unique("deleted").replace(' ', "-"));
+    let user_id: i64 = sqlx::query_scalar(
+        "INSERT INTO users (email, password_hash, name, role_id, club_id) \
+         VALUES ($1, 'x', 'Deleted', (SELECT id FROM roles WHERE name = 'coach'), 1) RETURNING id",
+    )
+    .bind(&email)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let token = create_token(user_id, &email, "coach", "Deleted", 1).unwrap();
+    let (status, _) = send(&app, "GET", "/api/notifications", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(&app, "DELETE", &format!("/api/users/{}", user_id), &admin, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", "/api/notifications", &token, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "a deleted user's token is revoked");
+}
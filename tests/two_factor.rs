@@ -0,0 +1,227 @@
+//! Tests for TOTP codes, 2FA enrolment, the two-step login and the 2FA policy.
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use handball_team_app::auth::create_token;
+use handball_team_app::{build_app_for_test, connect_for_test};
+use handball_team_app::services::totp;
+use serde_json::{json, Value};
+use tower::util::ServiceExt;
+
+async fn send(app: &Router, method: &str, uri: &str, token: Option<&str>, body: Value) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    let resp = app.clone().oneshot(builder.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+    let status = resp.status();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Register and approve a coach and return (email, user id).
+async fn register_coach(app: &Router, prefix: &str) -> (String, i64) {
+    let email = format!("{}-{}@example.com", prefix, chrono::Utc::now().timestamp_nanos_opt().unwrap());
+    let (status, body) = send(
+        app,
+        "POST",
+        "/api/register",
+        None,
+        json!({ "email": email, "password": "Seven wings at dusk", "first_name": "Two", "last_name": "Factor", "role": "coach" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let user_id = body["user_id"].as_i64().unwrap();
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let (status, _) = send(app, "POST", &format!("/api/admin/users/{}/approve", user_id), Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    (email, user_id)
+}
+
+/// The code `offset` steps from now.
+fn code(secret: &str, offset: i64) -> String {
+    let step = chrono::Utc::now().timestamp().div_euclid(totp::STEP_SECONDS) + offset;
+    totp::code_at(&totp::base32_decode(secret).unwrap(), step)
+}
+
+#[test]
+fn test_rfc6238_vectors() {
+    let secret = b"12345678901234567890";
+    assert_eq!(totp::code_at(secret, 59 / 30), "287082");
+    assert_eq!(totp::code_at(secret, 1111111109 / 30), "081804");
+    assert_eq!(totp::code_at(secret, 1234567890 / 30), "005924");
+    assert_eq!(totp::code_at(secret, 2000000000 / 30), "279037");
+}
+
+#[test]
+fn test_base32_and_otpauth_uri() {
+    assert_eq!(totp::base32_encode(b"foobar"), "MZXW6YTBOI");
+    assert_eq!(totp::base32_decode("mzxw 6ytb oi==").unwrap(), b"foobar");
+    assert!(totp::base32_decode("not base32!").is_none());
+    let secret = totp::generate_secret();
+    assert_eq!(totp::base32_decode(&secret).unwrap().len(), 20);
+    assert_eq!(
+        totp::otpauth_uri("coach@example.com", "ABC"),
+        "otpauth://totp/Tornadoes%20Handball:coach@example.com?secret=ABC&issuer=Tornadoes%20Handball&algorithm=SHA1&digits=6&period=30"
+    );
+}
+
+#[test]
+fn test_verify_window_and_replay() {
+    let secret = totp::generate_secret();
+    let now = chrono::Utc::now().timestamp();
+    let step = now.div_euclid(totp::STEP_SECONDS);
+    let current = totp::code_at(&totp::base32_decode(&secret).unwrap(), step);
+    assert_eq!(totp::verify(&secret, &current, now, None), Some(step));
+    assert_eq!(totp::verify(&secret, &current, now, Some(step)), None, "a code is accepted once");
+    let old = totp::code_at(&totp::base32_decode(&secret).unwrap(), step - 3);
+    assert_eq!(totp::verify(&secret, &old, now, None), None, "outside the drift window");
+}
+
+#[test]
+fn test_recovery_codes() {
+    let codes = totp::generate_recovery_codes(10);
+    assert_eq!(codes.len(), 10);
+    assert!(codes.iter().all(|c| c.len() == 9 && c.as_bytes()[4] == b'-'));
+    assert_eq!(totp::hash_recovery_code("ABCD-efgh"), totp::hash_recovery_code(" abcdefgh "));
+}
+
+#[tokio::test]
+async fn test_enrol_then_login_with_code_and_recovery_code() {
+    let app = build_app_for_test().await;
+    let (email, user_id) = register_coach(&app, "totp").await;
+    let login = json!({ "email": email, "password": "Seven wings at dusk" });
+    let (_, body) = send(&app, "POST", "/api/login", None, login.clone()).await;
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let (status, setup) = send(&app, "POST", "/api/2fa/setup", Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let secret = setup["secret"].as_str().unwrap().to_string();
+    assert!(setup["otpauth_uri"].as_str().unwrap().contains(&secret));
+    let (status, _) = send(&app, "POST", "/api/2fa/enable", Some(&token), json!({ "code": "000000x" })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let enrolment_code = code(&secret, 0);
+    let (status, enabled) = send(&app, "POST", "/api/2fa/enable", Some(&token), json!({ "code": enrolment_code })).await;
+    assert_eq!(status, StatusCode::OK);
+    let recovery: Vec<String> = serde_json::from_value(enabled["recovery_codes"].clone()).unwrap();
+    assert_eq!(recovery.len(), 10);
+
+    // Password alone now yields a challenge, not a token
+    let (status, body) = send(&app, "POST", "/api/login", None, login.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_null());
+    assert_eq!(body["two_factor"], "verify");
+    let challenge = body["challenge"].as_str().unwrap().to_string();
+
+    let verify = |code: String| json!({ "challenge": challenge, "code": code });
+    let (status, _) = send(&app, "POST", "/api/login/2fa", None, json!({ "challenge": token, "code": code(&secret, 1) })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "an access token is not a challenge");
+    let (status, _) = send(&app, "POST", "/api/login/2fa", None, verify(enrolment_code)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "the enrolment code cannot be replayed");
+    let (status, body) = send(&app, "POST", "/api/login/2fa", None, verify(code(&secret, 1))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user_id"], user_id);
+    assert!(body["token"].as_str().is_some());
+
+    let (status, _) = send(&app, "POST", "/api/login/2fa", None, verify(recovery[0].to_uppercase())).await;
+    assert_eq!(status, StatusCode::OK, "recovery codes work too");
+    let (status, _) = send(&app, "POST", "/api/login/2fa", None, verify(recovery[0].clone())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "but only once");
+
+    // An admin can reset a lost device
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let (status, _) = send(&app, "POST", &format!("/api/admin/users/{}/2fa/reset", user_id), Some(&admin), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(&app, "POST", "/api/login", None, login).await;
+    assert!(body["token"].as_str().is_some());
+}
+
+#[tokio::test]
+async fn test_policy_forces_enrolment_at_login() {
+    let app = build_app_for_test().await;
+    let admin = create_token(1, "admin@example.com", "admin", "Admin", 1).unwrap();
+    let coach = create_token(2, "coach@example.com", "coach", "Coach", 1).unwrap();
+    let (status, _) = send(&app, "GET", "/api/admin/security-policy", Some(&coach), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) =
+        send(&app, "PUT", "/api/admin/security-policy", Some(&admin), json!({ "require_2fa_roles": ["player"] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, previous) = send(&app, "GET", "/api/admin/security-policy", Some(&admin), json!({})).await;
+
+    // Promote a fresh account to admin so real accounts are not affected
+    let (email, user_id) = register_coach(&app, "policy").await;
+    let pool = connect_for_test().await;
+    let session = create_token(user_id, &email, "coach", "Two Factor", 1).unwrap();
+    let promote = json!({ "user_id": user_id, "role_name": "admin" });
+    let (status, _) = send(&app, "POST", "/api/users/role", Some(&admin), promote).await;
+    assert_eq!(status, StatusCode::OK);
+    // The old session ends, so the new role's policy applies at the next login
+    let (status, _) = send(&app, "GET", "/api/users", Some(&session), json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, policy) =
+        send(&app, "PUT", "/api/admin/security-policy", Some(&admin), json!({ "require_2fa_roles": ["admin", "admin"] })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(policy["require_2fa_roles"], json!(["admin"]));
+
+    let (_, body) = send(&app, "POST", "/api/login", None, json!({ "email": email, "password": "Seven wings at dusk" })).await;
+    assert_eq!(body["two_factor"], "setup");
+    let challenge = body["challenge"].as_str().unwrap().to_string();
+    let (status, setup) = send(&app, "POST", "/api/login/2fa/setup", None, json!({ "challenge": challenge })).await;
+    assert_eq!(status, StatusCode::OK);
+    let secret = setup["secret"].as_str().unwrap();
+    let (status, body) =
+        send(&app, "POST", "/api/login/2fa", None, json!({ "challenge": challenge, "code": code(secret, 0) })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["role"], "admin");
+    assert_eq!(body["recovery_codes"].as_array().unwrap().len(), 10);
+
+    // Required 2FA cannot be switched off by the user
+    let token = body["token"].as_str().unwrap();
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/2fa/disable",
+        Some(token),
+        json!({ "password": "Seven wings at dusk", "code": code(secret, 1) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    send(&app, "PUT", "/api/admin/security-policy", Some(&admin), previous).await;
+    sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_enrolment_and_new_recovery_codes_are_audited() {
+    let app = build_app_for_test().await;
+    let (email, user_id) = register_coach(&app, "audited").await;
+    let (_, body) = send(&app, "POST", "/api/login", None, json!({ "email": email, "password": "Seven wings at dusk" })).await;
+    let token = body["token"].as_str().unwrap().to_string();
+    let (_, setup) = send(&app, "POST", "/api/2fa/setup", Some(&token), json!({})).await;
+    let secret = setup["secret"].as_str().unwrap().to_string();
+    let (status, _) = send(&app, "POST", "/api/2fa/enable", Some(&token), json!({ "code": code(&secret, 0) })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = send(&app, "POST", "/api/2fa/recovery-codes", Some(&token), json!({ "code": code(&secret, 1) })).await;
+    assert_eq!(status, StatusCode::OK);
+    let recovery = body["recovery_codes"][0].as_str().unwrap().to_string();
+
+    let pool = connect_for_test().await;
+    #[allow(clippy::type_complexity)]
+    let entries: Vec<(Option<i64>, String, Option<Value>, Option<Value>)> = sqlx::query_as(
+        "SELECT actor_id, action, before_data, after_data FROM audit_log WHERE entity_type = 'user' AND entity_id = $1 ORDER BY id",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let actions: Vec<&str> = entries.iter().map(|(_, action, _, _)| action.as_str()).collect();
+    assert_eq!(&actions[actions.len() - 3..], ["begin_2fa_enrolment", "enable_2fa", "regenerate_recovery_codes"]);
+    let (actor, _, before, after) = entries.last().unwrap();
+    assert_eq!(*actor, Some(user_id));
+    assert_eq!(before.as_ref().unwrap()["unused_recovery_codes"], 10);
+    assert_eq!(after.as_ref().unwrap()["unused_recovery_codes"], 10);
+    let logged = serde_json::to_string(&entries.iter().map(|(_, _, b, a)| (b, a)).collect::<Vec<_>>()).unwrap();
+    assert!(!logged.contains(&secret) && !logged.contains(&recovery), "secrets stay out of the log");
+}